@@ -64,16 +64,11 @@ fn main() {
             } else {
                 println!("roles: any");
             }
-            if let Some(selected_level) = dialog.selected_level() {
-                println!("level: {:?}", selected_level);
-            } else {
-                println!("level: {}", dialog.show_name());
-            }
-            match dialog.selected_site(){
-                Some(site) => println!(
-                    "site:  {}", site
-                ),
-                None => println!("site:  Any"),
+            println!("levels: {:?}", dialog.selected_levels());
+            println!("sites: {:?}", dialog.selected_sites());
+            println!("platforms: {:?}", dialog.selected_platforms());
+            for combination in dialog.selected_combinations() {
+                println!("pin: {}", combination.label());
             }
             dialog.accept();
         }});
@@ -1,17 +1,19 @@
-use crate::serialize::{Log, LogItem};
+use crate::search::{LogSearch, SearchMode, SearchScope};
+use crate::serialize::{HtmlColors, Log, LogFormat, LogItem};
 use chrono::{DateTime, Local};
-use log::Level;
-use qt_core::{GlobalColor, QString};
+use log::{Level, LevelFilter};
+use qt_core::{GlobalColor, QSortFilterProxyModel, QString};
 use qt_gui::{QBrush, QColor, QStandardItem, QStandardItemModel};
 use qt_widgets::{
     cpp_core::{CastInto, CppBox, MutPtr, Ref as QRef},
     q_abstract_item_view::SelectionBehavior,
     q_header_view::ResizeMode,
-    QCheckBox, QFileDialog, QFrame, QGridLayout, QGroupBox, QHBoxLayout, QPushButton, QTableView,
-    QWidget,
+    QCheckBox, QComboBox, QFileDialog, QFrame, QGridLayout, QGroupBox, QHBoxLayout, QLineEdit,
+    QPushButton, QTableView, QWidget,
 };
 use rustqt_utils::{create_hlayout, create_vlayout, qs, set_stylesheet_from_str};
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::str::FromStr;
 
 const STYLE_STR: &'static str = include_str!("../resources/pbgui_logger.qss");
 const COL_0_WIDTH: i32 = 60;
@@ -19,6 +21,13 @@ const COL_1_WIDTH: i32 = 180;
 const COL_2_WIDTH: i32 = 270;
 const COL_3_WIDTH: i32 = 120;
 const COL_4_WIDTH: i32 = 60;
+// hidden column consulted by the filter proxy to decide whether a row is shown.
+// it reflects both the active log levels and the "show only matches" toggle.
+const COL_FILTER: i32 = 6;
+const ROW_VISIBLE: &str = "1";
+const ROW_HIDDEN: &str = "0";
+// the levels presented by the log level selector, from least to most verbose
+const LOG_LEVELS: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
 
 pub struct LogData<'a> {
     pub target: &'a str,
@@ -83,6 +92,16 @@ impl LogColor {
             }
         }
     }
+
+    /// Retrieve the color as an html hex string (eg #ff0000)
+    pub fn to_html(&self) -> String {
+        unsafe {
+            match self {
+                Self::GlobalColor(gc) => QColor::from_global_color(*gc).name_0a().to_std_string(),
+                Self::QColor(color) => color.name_0a().to_std_string(),
+            }
+        }
+    }
 }
 
 pub struct LogLevelColorsConfig {
@@ -117,6 +136,17 @@ impl LogLevelColorsConfig {
             Level::Error => &self.error,
         }
     }
+
+    /// Retrieve the colors as html, for use when exporting the log as html
+    pub fn html_colors(&self) -> HtmlColors {
+        HtmlColors {
+            trace: self.trace.to_html(),
+            debug: self.debug.to_html(),
+            info: self.info.to_html(),
+            warn: self.warn.to_html(),
+            error: self.error.to_html(),
+        }
+    }
 }
 /// Configuration for the log metadata in the log ui's controls, dictating
 /// the state of the checkboxes for log metadata
@@ -176,6 +206,7 @@ pub struct InnerLogWin {
     view_ctrls_qframe: MutPtr<QFrame>,
     clear_button: MutPtr<QPushButton>,
     save_button: MutPtr<QPushButton>,
+    open_button: MutPtr<QPushButton>,
     trace_cb: MutPtr<QCheckBox>,
     debug_cb: MutPtr<QCheckBox>,
     info_cb: MutPtr<QCheckBox>,
@@ -186,9 +217,25 @@ pub struct InnerLogWin {
     target_md_cb: MutPtr<QCheckBox>,
     file_md_cb: MutPtr<QCheckBox>,
     line_md_cb: MutPtr<QCheckBox>,
+    search_le: MutPtr<QLineEdit>,
+    search_mode_cb: MutPtr<QComboBox>,
+    search_scope_cb: MutPtr<QComboBox>,
+    prev_match_button: MutPtr<QPushButton>,
+    next_match_button: MutPtr<QPushButton>,
+    matches_only_cb: MutPtr<QCheckBox>,
+    log_level_cb: MutPtr<QComboBox>,
+    log_overrides_le: MutPtr<QLineEdit>,
+    apply_log_level_button: MutPtr<QPushButton>,
     model: MutPtr<QStandardItemModel>,
+    proxy_model: MutPtr<QSortFilterProxyModel>,
     visible_levels: LogLevelState,
     level_colors: LogLevelColorsConfig,
+    search: RefCell<Option<LogSearch>>,
+    // model rows matching the current search, in ascending order
+    matches: RefCell<Vec<i32>>,
+    current_match: Cell<Option<usize>>,
+    // maximum number of rows retained. 0 retains every row
+    max_rows: Cell<usize>,
 }
 
 impl InnerLogWin {
@@ -217,9 +264,16 @@ impl InnerLogWin {
         let view_ptr = view.as_mut_ptr();
 
         let mut model = QStandardItemModel::new_0a();
-        model.set_column_count(6);
+        model.set_column_count(7);
         let model_ptr = model.as_mut_ptr();
-        view.set_model(model.into_ptr());
+
+        let mut proxy_model = QSortFilterProxyModel::new_0a();
+        proxy_model.set_source_model(model.into_ptr());
+        proxy_model.set_filter_key_column(COL_FILTER);
+        proxy_model.set_filter_fixed_string(&qs(ROW_VISIBLE));
+        let proxy_model_ptr = proxy_model.as_mut_ptr();
+        view.set_model(proxy_model.into_ptr());
+        view.set_column_hidden(COL_FILTER, true);
 
         let mut header = view_ptr.vertical_header();
         header.set_section_resize_mode_1a(ResizeMode::Fixed);
@@ -241,6 +295,7 @@ impl InnerLogWin {
             view_ctrls_qframe,
             clear_button,
             save_button,
+            open_button,
             trace_cb,
             debug_cb,
             info_cb,
@@ -251,6 +306,15 @@ impl InnerLogWin {
             target_md_cb,
             file_md_cb,
             line_md_cb,
+            search_le,
+            search_mode_cb,
+            search_scope_cb,
+            prev_match_button,
+            next_match_button,
+            matches_only_cb,
+            log_level_cb,
+            log_overrides_le,
+            apply_log_level_button,
         } = Self::add_ctrls(main_layout.as_mut_ptr(), levelconfig, metadataconfig);
         // set the main layout
         main_frame.set_layout(main_layout.into_ptr());
@@ -266,6 +330,7 @@ impl InnerLogWin {
             view_ctrls_qframe,
             clear_button,
             save_button,
+            open_button,
             trace_cb,
             debug_cb,
             info_cb,
@@ -276,9 +341,23 @@ impl InnerLogWin {
             target_md_cb,
             file_md_cb,
             line_md_cb,
+            search_le,
+            search_mode_cb,
+            search_scope_cb,
+            prev_match_button,
+            next_match_button,
+            matches_only_cb,
+            log_level_cb,
+            log_overrides_le,
+            apply_log_level_button,
             model: model_ptr,
+            proxy_model: proxy_model_ptr,
             visible_levels: LogLevelState::new(levelconfig),
             level_colors: level_colors_config,
+            search: RefCell::new(None),
+            matches: RefCell::new(Vec::new()),
+            current_match: Cell::new(None),
+            max_rows: Cell::new(0),
         }
     }
 
@@ -312,16 +391,21 @@ impl InnerLogWin {
             let save_button_ptr = save_button.as_mut_ptr();
             ctrls_layout.add_widget_5a(save_button.into_ptr(), 0, 1, 1, 1);
 
+            // add Open button
+            let mut open_button = QPushButton::from_q_string(&QString::from_std_str("Open"));
+            let open_button_ptr = open_button.as_mut_ptr();
+            ctrls_layout.add_widget_5a(open_button.into_ptr(), 1, 0, 1, 1);
+
             // spacer
             let mut spacer = QFrame::new_0a();
             spacer.set_object_name(&qs("Spacer"));
             spacer.set_minimum_width(20);
             spacer.set_minimum_height(20);
-            ctrls_layout.add_widget_3a(spacer.into_ptr(), 1, 0);
+            ctrls_layout.add_widget_3a(spacer.into_ptr(), 2, 0);
 
             let mut loglevel_grp_box = QGroupBox::new();
             let mut loglevel_grp_box_ptr = loglevel_grp_box.as_mut_ptr();
-            ctrls_layout.add_widget_3a(loglevel_grp_box.into_ptr(), 2, 0);
+            ctrls_layout.add_widget_3a(loglevel_grp_box.into_ptr(), 3, 0);
 
             let name = qs("Active Log Levels");
             loglevel_grp_box_ptr.set_title(&name);
@@ -370,7 +454,7 @@ impl InnerLogWin {
 
             let mut metadata_grp_box = QGroupBox::new();
             let mut metadata_grp_box_ptr = metadata_grp_box.as_mut_ptr();
-            ctrls_layout.add_widget_3a(metadata_grp_box.into_ptr(), 2, 1);
+            ctrls_layout.add_widget_3a(metadata_grp_box.into_ptr(), 3, 1);
             let name = qs("Log Metadata");
 
             metadata_grp_box_ptr.set_title(&name);
@@ -409,6 +493,82 @@ impl InnerLogWin {
 
             metadata_grp_box_ptr.set_layout(metadata_grp_box_layout.into_ptr());
 
+            // search group
+            let mut search_grp_box = QGroupBox::new();
+            let mut search_grp_box_ptr = search_grp_box.as_mut_ptr();
+            ctrls_layout.add_widget_5a(search_grp_box.into_ptr(), 4, 0, 1, 2);
+            let name = qs("Search");
+            search_grp_box_ptr.set_title(&name);
+            search_grp_box_ptr.set_object_name(&name);
+            let mut search_grp_box_layout = create_vlayout();
+
+            let mut search_le = QLineEdit::new();
+            let search_le_ptr = search_le.as_mut_ptr();
+            search_le.set_placeholder_text(&qs("Search Log"));
+            search_grp_box_layout.add_widget(search_le.into_ptr());
+
+            let mut search_opts_layout = create_hlayout();
+            let mut search_mode_cb = QComboBox::new_0a();
+            let search_mode_cb_ptr = search_mode_cb.as_mut_ptr();
+            for mode in SearchMode::ALL.iter() {
+                search_mode_cb.add_item_q_string(&qs(mode.label()));
+            }
+            search_opts_layout.add_widget(search_mode_cb.into_ptr());
+
+            let mut search_scope_cb = QComboBox::new_0a();
+            let search_scope_cb_ptr = search_scope_cb.as_mut_ptr();
+            for scope in SearchScope::ALL.iter() {
+                search_scope_cb.add_item_q_string(&qs(scope.label()));
+            }
+            search_opts_layout.add_widget(search_scope_cb.into_ptr());
+            search_grp_box_layout.add_layout_1a(search_opts_layout.into_ptr());
+
+            let mut search_nav_layout = create_hlayout();
+            let mut prev_match_button = QPushButton::from_q_string(&qs("Prev"));
+            let prev_match_button_ptr = prev_match_button.as_mut_ptr();
+            search_nav_layout.add_widget(prev_match_button.into_ptr());
+
+            let mut next_match_button = QPushButton::from_q_string(&qs("Next"));
+            let next_match_button_ptr = next_match_button.as_mut_ptr();
+            search_nav_layout.add_widget(next_match_button.into_ptr());
+            search_grp_box_layout.add_layout_1a(search_nav_layout.into_ptr());
+
+            let matches_only_cb = QCheckBox::from_q_string(&qs("Show Only Matches"));
+            let matches_only_cb_ptr = matches_only_cb.as_mut_ptr();
+            search_grp_box_layout.add_widget(matches_only_cb.into_ptr());
+
+            search_grp_box_ptr.set_layout(search_grp_box_layout.into_ptr());
+
+            // log level group. changes the level records are emitted at
+            let mut log_level_grp_box = QGroupBox::new();
+            let mut log_level_grp_box_ptr = log_level_grp_box.as_mut_ptr();
+            ctrls_layout.add_widget_5a(log_level_grp_box.into_ptr(), 5, 0, 1, 2);
+            let name = qs("Emitted Log Level");
+            log_level_grp_box_ptr.set_title(&name);
+            log_level_grp_box_ptr.set_object_name(&name);
+            let mut log_level_grp_box_layout = create_vlayout();
+
+            let mut log_level_cb = QComboBox::new_0a();
+            let log_level_cb_ptr = log_level_cb.as_mut_ptr();
+            for level in LOG_LEVELS.iter() {
+                log_level_cb.add_item_q_string(&qs(level));
+            }
+            log_level_grp_box_layout.add_widget(log_level_cb.into_ptr());
+
+            let mut log_overrides_le = QLineEdit::new();
+            let log_overrides_le_ptr = log_overrides_le.as_mut_ptr();
+            log_overrides_le.set_placeholder_text(&qs("pbgui::messaging=debug,packybara=off"));
+            log_overrides_le.set_tool_tip(&qs(
+                "Per target overrides. A comma separated list of target=level",
+            ));
+            log_level_grp_box_layout.add_widget(log_overrides_le.into_ptr());
+
+            let mut apply_log_level_button = QPushButton::from_q_string(&qs("Apply"));
+            let apply_log_level_button_ptr = apply_log_level_button.as_mut_ptr();
+            log_level_grp_box_layout.add_widget(apply_log_level_button.into_ptr());
+
+            log_level_grp_box_ptr.set_layout(log_level_grp_box_layout.into_ptr());
+
             //ctrls_layout.add_stretch_0a();
 
             // spacer
@@ -416,7 +576,7 @@ impl InnerLogWin {
             spacer.set_object_name(&qs("Spacer"));
             spacer.set_minimum_width(20);
             spacer.set_minimum_height(20);
-            ctrls_layout.add_widget_5a(spacer.into_ptr(), 4, 0, 1, 2);
+            ctrls_layout.add_widget_5a(spacer.into_ptr(), 6, 0, 1, 2);
 
             //view_ctrls_qframe.set_layout(ctrls_layout.into_ptr());
             parent_layout.add_widget(view_ctrls_qframe.into_ptr());
@@ -424,6 +584,7 @@ impl InnerLogWin {
                 view_ctrls_qframe: view_ctrls_qframe_ptr,
                 clear_button: clear_button_ptr,
                 save_button: save_button_ptr,
+                open_button: open_button_ptr,
                 trace_cb: trace_cb_ptr,
                 debug_cb: debug_cb_ptr,
                 info_cb: info_cb_ptr,
@@ -434,6 +595,15 @@ impl InnerLogWin {
                 target_md_cb: target_cb_ptr,
                 file_md_cb: file_cb_ptr,
                 line_md_cb: line_cb_ptr,
+                search_le: search_le_ptr,
+                search_mode_cb: search_mode_cb_ptr,
+                search_scope_cb: search_scope_cb_ptr,
+                prev_match_button: prev_match_button_ptr,
+                next_match_button: next_match_button_ptr,
+                matches_only_cb: matches_only_cb_ptr,
+                log_level_cb: log_level_cb_ptr,
+                log_overrides_le: log_overrides_le_ptr,
+                apply_log_level_button: apply_log_level_button_ptr,
             }
         }
     }
@@ -463,11 +633,125 @@ impl InnerLogWin {
         self.save_button
     }
 
+    /// Return a mutable pointer to the open button
+    pub fn open_button(&self) -> MutPtr<QPushButton> {
+        self.open_button
+    }
+
     /// Retrieve a mutable pointer to the model
     pub fn model(&self) -> MutPtr<QStandardItemModel> {
         self.model
     }
 
+    /// Retrieve a mutable pointer to the proxy model presented by the view
+    pub fn proxy_model(&self) -> MutPtr<QSortFilterProxyModel> {
+        self.proxy_model
+    }
+
+    /// Return a mutable pointer to the search lineedit
+    pub fn search_le(&self) -> MutPtr<QLineEdit> {
+        self.search_le
+    }
+
+    /// Return a mutable pointer to the search mode combobox
+    pub fn search_mode_cb(&self) -> MutPtr<QComboBox> {
+        self.search_mode_cb
+    }
+
+    /// Return a mutable pointer to the search scope combobox
+    pub fn search_scope_cb(&self) -> MutPtr<QComboBox> {
+        self.search_scope_cb
+    }
+
+    /// Return a mutable pointer to the previous match button
+    pub fn prev_match_button(&self) -> MutPtr<QPushButton> {
+        self.prev_match_button
+    }
+
+    /// Return a mutable pointer to the next match button
+    pub fn next_match_button(&self) -> MutPtr<QPushButton> {
+        self.next_match_button
+    }
+
+    /// Return a mutable pointer to the show only matches checkbox
+    pub fn matches_only_cb(&self) -> MutPtr<QCheckBox> {
+        self.matches_only_cb
+    }
+
+    /// Return a mutable pointer to the emitted log level combobox
+    pub fn log_level_cb(&self) -> MutPtr<QComboBox> {
+        self.log_level_cb
+    }
+
+    /// Return a mutable pointer to the per target log level overrides lineedit
+    pub fn log_overrides_le(&self) -> MutPtr<QLineEdit> {
+        self.log_overrides_le
+    }
+
+    /// Return a mutable pointer to the button which applies the log level
+    pub fn apply_log_level_button(&self) -> MutPtr<QPushButton> {
+        self.apply_log_level_button
+    }
+
+    /// Set the state of the log level selector
+    ///
+    /// # Arguments
+    /// * `level` - The default level (eg debug)
+    /// * `overrides` - The per target overrides (eg pbgui::messaging=trace)
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_log_level_selection(&self, level: &str, overrides: &str) {
+        unsafe {
+            let level = level.to_lowercase();
+            if let Some(idx) = LOG_LEVELS.iter().position(|l| *l == level.as_str()) {
+                let mut log_level_cb = self.log_level_cb;
+                log_level_cb.set_current_index(idx as i32);
+            }
+            let mut log_overrides_le = self.log_overrides_le;
+            log_overrides_le.set_text(&qs(overrides));
+        }
+    }
+
+    /// Retrieve the log level selection as a filter spec (eg `info,pbgui=debug`)
+    pub fn log_level_spec(&self) -> String {
+        unsafe {
+            let level = self.log_level_cb.current_text().to_std_string();
+            let overrides = self.log_overrides_le.text().to_std_string();
+            let overrides = overrides.trim();
+            if overrides.is_empty() {
+                level
+            } else {
+                format!("{},{}", level, overrides)
+            }
+        }
+    }
+
+    /// Check the level checkboxes of every level up to and including the supplied
+    /// level, so that newly emitted records are not hidden.
+    ///
+    /// # Arguments
+    /// * `max_level` - The most verbose level being emitted
+    ///
+    /// # Returns
+    /// * None
+    pub fn show_levels_up_to(&self, max_level: LevelFilter) {
+        unsafe {
+            let checkboxes = [
+                (Level::Error, self.error_cb),
+                (Level::Warn, self.warn_cb),
+                (Level::Info, self.info_cb),
+                (Level::Debug, self.debug_cb),
+                (Level::Trace, self.trace_cb),
+            ];
+            for (level, mut checkbox) in checkboxes.iter().copied() {
+                if level <= max_level {
+                    checkbox.set_checked(true);
+                }
+            }
+        }
+    }
+
     /// Return the trace level checkbox from the log controlx
     pub fn trace_cb(&self) -> MutPtr<QCheckBox> {
         self.trace_cb
@@ -475,19 +759,9 @@ impl InnerLogWin {
 
     /// hide or unhide the file metadata checkbox
     pub fn hide_trace_cb(&self, hide: bool) {
-        unsafe {
-            // let mut view = self.table_view();
-            //view.set_column_hidden(0, hide);
-            if self.visible_levels.trace.get() == hide {
-                let trace = QString::from_std_str("TRACE");
-                self.visible_levels.trace.set(!hide);
-                let mut view = self.table_view();
-                for row in 0..self.model.row_count_0a() {
-                    if self.model.item_2a(row, 0).text().compare_q_string(&trace) == 0 {
-                        view.set_row_hidden(row, hide);
-                    }
-                }
-            }
+        if self.visible_levels.trace.get() == hide {
+            self.visible_levels.trace.set(!hide);
+            self.refresh_visibility();
         }
     }
     /// Return the debug level checkbox from the log controlx
@@ -497,19 +771,11 @@ impl InnerLogWin {
 
     /// hide or unhide the file metadata checkbox
     pub fn hide_debug_cb(&self, hide: bool) {
-        unsafe {
-            // this looks backwards becasue we set the
-            // value to !hide
-            if self.visible_levels.debug.get() == hide {
-                let debug = QString::from_std_str("DEBUG");
-                self.visible_levels.debug.set(!hide);
-                let mut view = self.table_view();
-                for row in 0..self.model.row_count_0a() {
-                    if self.model.item_2a(row, 0).text().compare_q_string(&debug) == 0 {
-                        view.set_row_hidden(row, hide);
-                    }
-                }
-            }
+        // this looks backwards becasue we set the
+        // value to !hide
+        if self.visible_levels.debug.get() == hide {
+            self.visible_levels.debug.set(!hide);
+            self.refresh_visibility();
         }
     }
     /// Return the info level checkbox from the log controlx
@@ -519,17 +785,9 @@ impl InnerLogWin {
 
     /// hide or unhide the file metadata checkbox
     pub fn hide_info_cb(&self, hide: bool) {
-        unsafe {
-            if self.visible_levels.info.get() == hide {
-                let info = QString::from_std_str("INFO");
-                self.visible_levels.info.set(!hide);
-                let mut view = self.table_view();
-                for row in 0..self.model.row_count_0a() {
-                    if self.model.item_2a(row, 0).text().compare_q_string(&info) == 0 {
-                        view.set_row_hidden(row, hide);
-                    }
-                }
-            }
+        if self.visible_levels.info.get() == hide {
+            self.visible_levels.info.set(!hide);
+            self.refresh_visibility();
         }
     }
 
@@ -540,17 +798,9 @@ impl InnerLogWin {
 
     /// hide or unhide the file metadata checkbox
     pub fn hide_warn_cb(&self, hide: bool) {
-        unsafe {
-            if self.visible_levels.warn.get() == hide {
-                let warn = QString::from_std_str("WARN");
-                self.visible_levels.warn.set(!hide);
-                let mut view = self.table_view();
-                for row in 0..self.model.row_count_0a() {
-                    if self.model.item_2a(row, 0).text().compare_q_string(&warn) == 0 {
-                        view.set_row_hidden(row, hide);
-                    }
-                }
-            }
+        if self.visible_levels.warn.get() == hide {
+            self.visible_levels.warn.set(!hide);
+            self.refresh_visibility();
         }
     }
 
@@ -561,17 +811,9 @@ impl InnerLogWin {
 
     /// hide or unhide the file metadata checkbox
     pub fn hide_error_cb(&self, hide: bool) {
-        unsafe {
-            if self.visible_levels.trace.get() == hide {
-                let error = QString::from_std_str("ERROR");
-                self.visible_levels.error.set(!hide);
-                let mut view = self.table_view();
-                for row in 0..self.model.row_count_0a() {
-                    if self.model.item_2a(row, 0).text().compare_q_string(&error) == 0 {
-                        view.set_row_hidden(row, hide);
-                    }
-                }
-            }
+        if self.visible_levels.error.get() == hide {
+            self.visible_levels.error.set(!hide);
+            self.refresh_visibility();
         }
     }
 
@@ -661,63 +903,346 @@ impl InnerLogWin {
             let mut model = self.model;
             model.remove_rows_2a(0, cnt);
         }
+        self.matches.borrow_mut().clear();
+        self.current_match.set(None);
+    }
+
+    /// Set the maximum number of rows retained by the log. Once exceeded, the
+    /// oldest rows are dropped. A value of 0 retains every row.
+    ///
+    /// # Arguments
+    /// * `max_rows` - The maximum number of rows to retain
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_max_rows(&self, max_rows: usize) {
+        self.max_rows.set(max_rows);
+        unsafe {
+            self.enforce_max_rows();
+        }
     }
-    /// save teh log
+
+    /// Retrieve the maximum number of rows retained by the log
+    pub fn max_rows(&self) -> usize {
+        self.max_rows.get()
+    }
+
+    // drop the oldest rows once the log exceeds max_rows. Continuation rows
+    // are dropped along with the row they continue.
+    unsafe fn enforce_max_rows(&self) {
+        let max_rows = self.max_rows.get() as i32;
+        let cnt = self.model.row_count_0a();
+        if max_rows == 0 || cnt <= max_rows {
+            return;
+        }
+        let mut remove = cnt - max_rows;
+        while remove < cnt {
+            let item = self.model.item_2a(remove, 0);
+            if item.is_null() || !item.text().is_empty() {
+                break;
+            }
+            remove += 1;
+        }
+        let mut model = self.model;
+        model.remove_rows_2a(0, remove);
+        // the match indexes refer to model rows, and must be shifted
+        let mut matches = self.matches.borrow_mut();
+        let dropped = matches.iter().take_while(|row| **row < remove).count();
+        matches.drain(0..dropped);
+        for row in matches.iter_mut() {
+            *row -= remove;
+        }
+        let current = self.current_match.get();
+        self.current_match.set(current.and_then(|idx| idx.checked_sub(dropped)));
+    }
+
+    /// Search the log using the term, mode and scope set in the search controls,
+    /// highlighting the matches and selecting the first of them. An invalid
+    /// regular expression is flagged on the search lineedit.
+    pub fn search(&self) {
+        unsafe {
+            let term = self.search_le.text().to_std_string();
+            let mode = SearchMode::from_index(self.search_mode_cb.current_index());
+            let scope = SearchScope::from_index(self.search_scope_cb.current_index());
+            let mut search_le = self.search_le;
+            let search = if term.is_empty() {
+                None
+            } else {
+                match LogSearch::new(term.as_str(), mode, scope) {
+                    Ok(search) => Some(search),
+                    Err(e) => {
+                        search_le.set_tool_tip(&qs(e.to_string()));
+                        search_le.set_style_sheet(&qs("border: 1px solid red;"));
+                        return;
+                    }
+                }
+            };
+            search_le.set_tool_tip(&qs(""));
+            search_le.set_style_sheet(&qs(""));
+            *self.search.borrow_mut() = search;
+
+            let clear = QBrush::new();
+            let mut matches = Vec::new();
+            for row in 0..self.model.row_count_0a() {
+                for col in 0..COL_FILTER {
+                    let mut item = self.model.item_2a(row, col);
+                    if !item.is_null() {
+                        item.set_background(clear.as_ref());
+                    }
+                }
+                if self.highlight_row(row) {
+                    matches.push(row);
+                }
+            }
+            *self.matches.borrow_mut() = matches;
+            self.current_match.set(None);
+            self.refresh_visibility();
+            self.next_match();
+        }
+    }
+
+    /// Select the next match, wrapping around at the end of the log
+    pub fn next_match(&self) {
+        self.step_match(true);
+    }
+
+    /// Select the previous match, wrapping around at the start of the log
+    pub fn prev_match(&self) {
+        self.step_match(false);
+    }
+
+    /// Reapply the level and "show only matches" filters to every row
+    pub fn refresh_visibility(&self) {
+        unsafe {
+            for row in 0..self.model.row_count_0a() {
+                self.update_row_visibility(row);
+            }
+        }
+    }
+
+    fn step_match(&self, forward: bool) {
+        let cnt = self.matches.borrow().len();
+        if cnt == 0 {
+            return;
+        }
+        let idx = match (self.current_match.get(), forward) {
+            (None, true) => 0,
+            (None, false) => cnt - 1,
+            (Some(idx), true) => (idx + 1) % cnt,
+            (Some(idx), false) => (idx + cnt - 1) % cnt,
+        };
+        self.current_match.set(Some(idx));
+        let row = self.matches.borrow()[idx];
+        unsafe {
+            let index = self
+                .proxy_model
+                .map_from_source(self.model.index_2a(row, 0).as_ref());
+            // the match may be hidden by the level filters
+            if !index.is_valid() {
+                return;
+            }
+            let mut view = self.table_view();
+            view.select_row(index.row());
+            view.scroll_to_1a(index.as_ref());
+        }
+    }
+
+    // highlight the searched column of the row if it matches the current search
+    unsafe fn highlight_row(&self, row: i32) -> bool {
+        let search = self.search.borrow();
+        let search = match search.as_ref() {
+            Some(search) => search,
+            None => return false,
+        };
+        let mut item = self.model.item_2a(row, search.scope().column());
+        if item.is_null() || !search.is_match(item.text().to_std_string().as_str()) {
+            return false;
+        }
+        let brush = QBrush::from_q_color(QColor::from_rgb_3a(110, 90, 20).as_ref());
+        item.set_background(brush.as_ref());
+        true
+    }
+
+    // Continuation rows do not carry a level. They share the visibility of
+    // the row they continue.
+    unsafe fn is_level_visible(&self, row: i32) -> bool {
+        for row in (0..=row).rev() {
+            let item = self.model.item_2a(row, 0);
+            if item.is_null() {
+                continue;
+            }
+            let level = item.text().to_std_string();
+            if level.is_empty() {
+                continue;
+            }
+            return Level::from_str(level.as_str())
+                .map(|level| self.visible_levels.is_visible(&level))
+                .unwrap_or(true);
+        }
+        true
+    }
+
+    unsafe fn update_row_visibility(&self, row: i32) {
+        let matches_only = self.matches_only_cb.is_checked() && self.search.borrow().is_some();
+        let visible = self.is_level_visible(row)
+            && (!matches_only || self.matches.borrow().binary_search(&row).is_ok());
+        let value = qs(if visible { ROW_VISIBLE } else { ROW_HIDDEN });
+        let mut item = self.model.item_2a(row, COL_FILTER);
+        if item.is_null() {
+            let mut model = self.model;
+            model.set_item_3a(row, COL_FILTER, QStandardItem::from_q_string(&value).into_ptr());
+        } else {
+            item.set_text(&value);
+        }
+    }
+
+    // apply the current search and filters to a newly added row
+    unsafe fn filter_new_row(&self, row: i32) {
+        if self.highlight_row(row) {
+            self.matches.borrow_mut().push(row);
+        }
+        self.update_row_visibility(row);
+    }
+    /// save the log. The format is chosen by the extension of the file
+    /// (json, txt/log, csv or html), defaulting to json.
     pub fn save_log(&self, trim_space: bool, trim_return: bool) {
         unsafe {
             let output_path = QFileDialog::get_save_file_name_4a(
                 self.main(),
                 &qs("save log"),
                 &qs(""),
-                &qs("*.json"),
+                &qs("JSON (*.json);;Text (*.txt *.log);;CSV (*.csv);;HTML (*.html)"),
             );
-            if output_path.is_null() {
+            if output_path.is_null() || output_path.is_empty() {
                 log::debug!("log save cancelled by user");
                 return;
             }
-            let output_path = output_path.to_std_string();
-            log::info!("saving to {}", &output_path);
-            let mut items: Vec<LogItem> = Vec::new();
-            for row in 0..self.model.row_count_0a() {
-                let level = &self
-                    .model
-                    .item_2a(row, 0)
-                    .text()
-                    .as_mut_ptr()
-                    .to_std_string();
-                let msg = self
-                    .model
-                    .item_2a(row, 5)
-                    .text()
-                    .as_mut_ptr()
-                    .to_std_string();
-                if level.as_str() == "" {
-                    let msg = if trim_space == true { msg.trim() } else { &msg };
-                    let join = if trim_return == true { " " } else { "\n" };
-                    let txt = format!("{}{}", join, msg);
-                    match items.last_mut() {
-                        Some(item) => item.append_msg(txt.as_str()),
-                        None => (),
-                    };
-                    continue;
+            let mut output_path = output_path.to_std_string();
+            let format = match LogFormat::from_path(output_path.as_str()) {
+                Some(format) => format,
+                None => {
+                    output_path.push_str(".");
+                    output_path.push_str(LogFormat::Json.extension());
+                    LogFormat::Json
                 }
-
-                items.push(LogItem::new(
-                    &self.model.item_2a(row, 0).text().as_mut_ptr(),
-                    &self.model.item_2a(row, 1).text().as_mut_ptr(),
-                    &self.model.item_2a(row, 2).text().as_mut_ptr(),
-                    &self.model.item_2a(row, 3).text().as_mut_ptr(),
-                    &self.model.item_2a(row, 4).text().as_mut_ptr(),
-                    msg,
-                ));
-            }
-            let log = Log::new(items);
-            match log.write(&output_path) {
+            };
+            log::info!("saving to {}", &output_path);
+            let log = self.to_log(trim_space, trim_return);
+            let colors = self.level_colors.html_colors();
+            match log.write_as(&output_path, format, &colors) {
                 Ok(_) => (),
                 Err(e) => log::error!("{:?}", e),
             };
         }
     }
+
+    // gather the rows of the model into a Log. Rows without a level are
+    // continuations of the previous message.
+    unsafe fn to_log(&self, trim_space: bool, trim_return: bool) -> Log {
+        let mut items: Vec<LogItem> = Vec::new();
+        for row in 0..self.model.row_count_0a() {
+            let level = &self
+                .model
+                .item_2a(row, 0)
+                .text()
+                .as_mut_ptr()
+                .to_std_string();
+            let msg = self
+                .model
+                .item_2a(row, 5)
+                .text()
+                .as_mut_ptr()
+                .to_std_string();
+            if level.as_str() == "" {
+                let msg = if trim_space == true { msg.trim() } else { &msg };
+                let join = if trim_return == true { " " } else { "\n" };
+                let txt = format!("{}{}", join, msg);
+                match items.last_mut() {
+                    Some(item) => item.append_msg(txt.as_str()),
+                    None => (),
+                };
+                continue;
+            }
+
+            items.push(LogItem::new(
+                &self.model.item_2a(row, 0).text().as_mut_ptr(),
+                &self.model.item_2a(row, 1).text().as_mut_ptr(),
+                &self.model.item_2a(row, 2).text().as_mut_ptr(),
+                &self.model.item_2a(row, 3).text().as_mut_ptr(),
+                &self.model.item_2a(row, 4).text().as_mut_ptr(),
+                msg,
+            ));
+        }
+        Log::new(items)
+    }
+
+    /// Prompt the user for a log previously saved as json and replace the
+    /// contents of the log with it.
+    pub fn open_log(&self) {
+        unsafe {
+            let input_path = QFileDialog::get_open_file_name_4a(
+                self.main(),
+                &qs("open log"),
+                &qs(""),
+                &qs("JSON (*.json)"),
+            );
+            if input_path.is_null() || input_path.is_empty() {
+                log::debug!("log open cancelled by user");
+                return;
+            }
+            let input_path = input_path.to_std_string();
+            match Log::read(&input_path) {
+                Ok(log) => {
+                    self.clear_log();
+                    self.load_log(&log);
+                }
+                Err(e) => log::error!("unable to open log {}: {:?}", input_path, e),
+            }
+        }
+    }
+
+    /// Append the items of a saved Log to the log, presenting them as they
+    /// were originally logged. The active log level filters apply.
+    ///
+    /// # Arguments
+    /// * `log` - The Log to load
+    ///
+    /// # Returns
+    /// * None
+    pub fn load_log(&self, log: &Log) {
+        unsafe {
+            let mut model = self.model();
+            for log_item in log.items() {
+                let level = Level::from_str(log_item.level()).ok();
+                let brush = level.map(|level| self.level_colors.get(&level).get_brush());
+                let fields = [
+                    log_item.level(),
+                    log_item.datetime(),
+                    log_item.target(),
+                    log_item.file(),
+                    log_item.line(),
+                    log_item.msg(),
+                ];
+                let rc = model.row_count_0a();
+                model.set_row_count(rc + 1);
+                for (col, field) in fields.iter().enumerate() {
+                    let mut item = QStandardItem::new();
+                    item.set_editable(false);
+                    item.set_text(&qs(field));
+                    // the message column is not colored, matching log()
+                    match brush {
+                        Some(ref brush) if col < 5 => item.set_foreground(brush.as_ref()),
+                        _ => (),
+                    }
+                    model.set_item_3a(rc, col as i32, item.into_ptr());
+                }
+                self.filter_new_row(rc);
+            }
+            self.enforce_max_rows();
+            self.table_view().scroll_to_bottom();
+        }
+    }
+
     /// Turn the controls on and off
     pub fn set_ctrls_visible(&self, visible: bool) {
         unsafe {
@@ -795,9 +1320,7 @@ impl InnerLogWin {
                     model.set_item_3a(rc, 3, file_item.into_ptr());
                     model.set_item_3a(rc, 4, line_item.into_ptr());
                     model.set_item_3a(rc, 5, item.into_ptr());
-                    if !self.visible_levels.is_visible(&level) {
-                        self.table_view().set_row_hidden(rc, true);
-                    }
+                    self.filter_new_row(rc);
                 }
                 &None => {
                     loglevel.set_text(&qs(""));
@@ -813,11 +1336,10 @@ impl InnerLogWin {
                     model.set_item_3a(rc, 3, file_item.into_ptr());
                     model.set_item_3a(rc, 4, line_item.into_ptr());
                     model.set_item_3a(rc, 5, item.into_ptr());
-                    if !self.visible_levels.is_visible(&level) {
-                        self.table_view().set_row_hidden(rc, true);
-                    }
+                    self.filter_new_row(rc);
                 }
             }
+            self.enforce_max_rows();
             self.table_view().scroll_to_bottom();
         }
     }
@@ -840,6 +1362,8 @@ impl InnerLogWin {
 
         model.set_item_3a(rc, 0, loglevel.into_ptr());
         model.set_item_3a(rc, 1, item.into_ptr());
+        self.filter_new_row(rc);
+        self.enforce_max_rows();
     }
 
     pub unsafe fn log_items(&self, item: CppBox<QStandardItem>, loglevel: CppBox<QStandardItem>) {
@@ -848,6 +1372,8 @@ impl InnerLogWin {
         model.set_row_count(rc + 1);
         model.set_item_3a(rc, 0, loglevel.into_ptr());
         model.set_item_3a(rc, 1, item.into_ptr());
+        self.filter_new_row(rc);
+        self.enforce_max_rows();
     }
 }
 
@@ -857,6 +1383,7 @@ pub struct AddCtrlsReturn {
     view_ctrls_qframe: MutPtr<QFrame>,
     clear_button: MutPtr<QPushButton>,
     save_button: MutPtr<QPushButton>,
+    open_button: MutPtr<QPushButton>,
     trace_cb: MutPtr<QCheckBox>,
     debug_cb: MutPtr<QCheckBox>,
     info_cb: MutPtr<QCheckBox>,
@@ -867,4 +1394,13 @@ pub struct AddCtrlsReturn {
     target_md_cb: MutPtr<QCheckBox>,
     file_md_cb: MutPtr<QCheckBox>,
     line_md_cb: MutPtr<QCheckBox>,
+    search_le: MutPtr<QLineEdit>,
+    search_mode_cb: MutPtr<QComboBox>,
+    search_scope_cb: MutPtr<QComboBox>,
+    prev_match_button: MutPtr<QPushButton>,
+    next_match_button: MutPtr<QPushButton>,
+    matches_only_cb: MutPtr<QCheckBox>,
+    log_level_cb: MutPtr<QComboBox>,
+    log_overrides_le: MutPtr<QLineEdit>,
+    apply_log_level_button: MutPtr<QPushButton>,
 }
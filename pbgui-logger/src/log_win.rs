@@ -3,10 +3,10 @@ use crate::inner_log_win::{
 };
 use crate::InnerLogWin;
 use log::Level;
-use qt_core::{Slot, SlotOfInt};
+use qt_core::{QString, Slot, SlotOfInt, SlotOfQString};
 use qt_gui::QStandardItemModel;
 use qt_widgets::{
-    cpp_core::{CastInto, MutPtr},
+    cpp_core::{CastInto, MutPtr, Ref},
     QTableView, QWidget,
 };
 use rustqt_utils::enclose;
@@ -16,6 +16,7 @@ pub struct LogWin<'a> {
     inner_log_win: Rc<InnerLogWin>,
     clear_log: Slot<'a>,
     save_log: Slot<'a>,
+    open_log: Slot<'a>,
     toggle_trace_cb: SlotOfInt<'a>,
     toggle_debug_cb: SlotOfInt<'a>,
     toggle_info_cb: SlotOfInt<'a>,
@@ -26,6 +27,11 @@ pub struct LogWin<'a> {
     toggle_target_cb: SlotOfInt<'a>,
     toggle_file_cb: SlotOfInt<'a>,
     toggle_line_cb: SlotOfInt<'a>,
+    search: SlotOfQString<'a>,
+    search_option_changed: SlotOfInt<'a>,
+    next_match: Slot<'a>,
+    prev_match: Slot<'a>,
+    toggle_matches_only_cb: SlotOfInt<'a>,
 }
 
 impl<'a> LogWin<'a> {
@@ -50,6 +56,9 @@ impl<'a> LogWin<'a> {
                 // trim_space? trim_return?
                inner.save_log(true, true);
             }}),
+            open_log: Slot::new(enclose! { (inner) move || {
+               inner.open_log();
+            }}),
             toggle_trace_cb: SlotOfInt::new(enclose! {(inner) move |checked: i32| {
                 inner.hide_trace_cb(checked<1)
             }}),
@@ -81,6 +90,21 @@ impl<'a> LogWin<'a> {
             toggle_line_cb: SlotOfInt::new(enclose! {(inner) move |checked: i32| {
                 inner.hide_line_md_cb(checked<1)
             }}),
+            search: SlotOfQString::new(enclose! {(inner) move |_text: Ref<QString>| {
+                inner.search()
+            }}),
+            search_option_changed: SlotOfInt::new(enclose! {(inner) move |_idx: i32| {
+                inner.search()
+            }}),
+            next_match: Slot::new(enclose! { (inner) move || {
+               inner.next_match();
+            }}),
+            prev_match: Slot::new(enclose! { (inner) move || {
+               inner.prev_match();
+            }}),
+            toggle_matches_only_cb: SlotOfInt::new(enclose! {(inner) move |_checked: i32| {
+                inner.refresh_visibility()
+            }}),
         };
 
         log_win
@@ -93,6 +117,11 @@ impl<'a> LogWin<'a> {
             .save_button()
             .clicked()
             .connect(&log_win.save_log);
+        log_win
+            .inner()
+            .open_button()
+            .clicked()
+            .connect(&log_win.open_log);
         //configure
         let inner = log_win.inner();
         inner.set_ctrls_visible(false);
@@ -137,6 +166,29 @@ impl<'a> LogWin<'a> {
             .line_md_cb()
             .state_changed()
             .connect(&log_win.toggle_line_cb);
+        inner.search_le().text_changed().connect(&log_win.search);
+        // return steps through the matches of the current search
+        inner.search_le().return_pressed().connect(&log_win.next_match);
+        inner
+            .search_mode_cb()
+            .current_index_changed()
+            .connect(&log_win.search_option_changed);
+        inner
+            .search_scope_cb()
+            .current_index_changed()
+            .connect(&log_win.search_option_changed);
+        inner
+            .next_match_button()
+            .clicked()
+            .connect(&log_win.next_match);
+        inner
+            .prev_match_button()
+            .clicked()
+            .connect(&log_win.prev_match);
+        inner
+            .matches_only_cb()
+            .state_changed()
+            .connect(&log_win.toggle_matches_only_cb);
         log_win
     }
     /// Retrieve the reference counted pointer to the InnerLogWin
@@ -156,6 +208,10 @@ impl<'a> LogWin<'a> {
     pub unsafe fn table_view(&self) -> MutPtr<QTableView> {
         self.inner_log_win.table_view()
     }
+    /// Set the maximum number of rows retained by the log. 0 retains every row
+    pub fn set_max_rows(&self, max_rows: usize) {
+        self.inner_log_win.set_max_rows(max_rows);
+    }
     /// inject log data onto the end of the log
     pub unsafe fn log(&self, level: Level, log_data: Option<LogData>, msg: &str) {
         self.inner_log_win.log(level, log_data, msg);
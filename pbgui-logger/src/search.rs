@@ -0,0 +1,142 @@
+//! Matching of log rows against the search term entered in the log controls.
+//! Kept free of qt so that it may be tested on its own.
+use regex::{Regex, RegexBuilder};
+
+/// The column of the model holding the message
+pub const COL_MSG: i32 = 5;
+/// The column of the model holding the target
+pub const COL_TARGET: i32 = 2;
+/// The column of the model holding the file
+pub const COL_FILE: i32 = 3;
+
+/// How the search term is interpreted
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SearchMode {
+    Plain,
+    CaseInsensitive,
+    Regex,
+}
+
+impl SearchMode {
+    /// The modes, in the order they are presented in the mode combobox
+    pub const ALL: [SearchMode; 3] = [Self::Plain, Self::CaseInsensitive, Self::Regex];
+
+    /// The label presented to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Plain => "Plain",
+            Self::CaseInsensitive => "Ignore Case",
+            Self::Regex => "Regex",
+        }
+    }
+
+    /// Retrieve the mode at the supplied combobox index, defaulting to Plain
+    pub fn from_index(idx: i32) -> Self {
+        Self::ALL.get(idx as usize).copied().unwrap_or(Self::Plain)
+    }
+}
+
+/// The field of each row which is searched
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SearchScope {
+    Message,
+    Target,
+    File,
+}
+
+impl SearchScope {
+    /// The scopes, in the order they are presented in the scope combobox
+    pub const ALL: [SearchScope; 3] = [Self::Message, Self::Target, Self::File];
+
+    /// The label presented to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Message => "Message",
+            Self::Target => "Target",
+            Self::File => "File",
+        }
+    }
+
+    /// Retrieve the scope at the supplied combobox index, defaulting to Message
+    pub fn from_index(idx: i32) -> Self {
+        Self::ALL.get(idx as usize).copied().unwrap_or(Self::Message)
+    }
+
+    /// The column of the model searched by the scope
+    pub fn column(&self) -> i32 {
+        match self {
+            Self::Message => COL_MSG,
+            Self::Target => COL_TARGET,
+            Self::File => COL_FILE,
+        }
+    }
+}
+
+enum Matcher {
+    Plain(String),
+    CaseInsensitive(String),
+    Regex(Regex),
+}
+
+/// A compiled search term
+pub struct LogSearch {
+    matcher: Matcher,
+    scope: SearchScope,
+}
+
+impl LogSearch {
+    /// New up a LogSearch
+    ///
+    /// # Arguments
+    /// * `term` - The term to search for
+    /// * `mode` - How the term is interpreted
+    /// * `scope` - The field to search
+    ///
+    /// # Returns
+    /// * Ok(LogSearch) if successful
+    /// * Err(regex::Error) if the term is not a valid regular expression
+    pub fn new(term: &str, mode: SearchMode, scope: SearchScope) -> Result<Self, regex::Error> {
+        let matcher = match mode {
+            SearchMode::Plain => Matcher::Plain(term.to_string()),
+            SearchMode::CaseInsensitive => Matcher::CaseInsensitive(term.to_lowercase()),
+            SearchMode::Regex => Matcher::Regex(RegexBuilder::new(term).build()?),
+        };
+        Ok(Self { matcher, scope })
+    }
+
+    /// The field searched
+    pub fn scope(&self) -> SearchScope {
+        self.scope
+    }
+
+    /// Determine whether the supplied value matches the search term
+    pub fn is_match(&self, value: &str) -> bool {
+        match &self.matcher {
+            Matcher::Plain(term) => value.contains(term.as_str()),
+            Matcher::CaseInsensitive(term) => value.to_lowercase().contains(term.as_str()),
+            Matcher::Regex(re) => re.is_match(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_match_as_expected() {
+        let plain = LogSearch::new("Pin", SearchMode::Plain, SearchScope::Message).unwrap();
+        assert!(plain.is_match("Pin saved"));
+        assert!(!plain.is_match("pin saved"));
+        let ci = LogSearch::new("Pin", SearchMode::CaseInsensitive, SearchScope::Message).unwrap();
+        assert!(ci.is_match("pin saved"));
+        let re = LogSearch::new(r"^pbgui::\w+$", SearchMode::Regex, SearchScope::Target).unwrap();
+        assert!(re.is_match("pbgui::main_window"));
+        assert!(!re.is_match("packybara::db"));
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        assert!(LogSearch::new("(", SearchMode::Regex, SearchScope::Message).is_err());
+    }
+}
@@ -1,10 +1,87 @@
 use qt_core::QString;
 use qt_widgets::cpp_core::MutPtr;
 use serde::{Deserialize, Serialize};
-use std::fs::OpenOptions;
-use std::io;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The formats a Log may be written in. Only json may be read back.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LogFormat {
+    Json,
+    Text,
+    Csv,
+    Html,
+}
+
+impl LogFormat {
+    /// Determine the format from the extension of the supplied path
+    ///
+    /// # Arguments
+    /// * `path` - The path of the log file
+    ///
+    /// # Returns
+    /// * Some(LogFormat) if the extension is recognized
+    /// * None otherwise
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "json" => Some(Self::Json),
+            "txt" | "log" => Some(Self::Text),
+            "csv" => Some(Self::Csv),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// The default extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Text => "txt",
+            Self::Csv => "csv",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// The css colors used to present each log level in an html export
+#[derive(Debug, Clone)]
+pub struct HtmlColors {
+    pub trace: String,
+    pub debug: String,
+    pub info: String,
+    pub warn: String,
+    pub error: String,
+}
+
+impl Default for HtmlColors {
+    fn default() -> Self {
+        Self {
+            trace: "#008080".to_string(),
+            debug: "#00ffff".to_string(),
+            info: "#50d2ff".to_string(),
+            warn: "#ffff00".to_string(),
+            error: "#ff0000".to_string(),
+        }
+    }
+}
+
+impl HtmlColors {
+    /// Retrieve the color for the supplied level name (eg TRACE)
+    pub fn get(&self, level: &str) -> Option<&str> {
+        match level {
+            "TRACE" => Some(&self.trace),
+            "DEBUG" => Some(&self.debug),
+            "INFO" => Some(&self.info),
+            "WARN" => Some(&self.warn),
+            "ERROR" => Some(&self.error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct LogItem {
     level: String,
     datetime: String,
@@ -37,9 +114,39 @@ impl LogItem {
     pub fn append_msg(&mut self, msg: &str) {
         self.msg.push_str(msg);
     }
+
+    /// The log level (eg INFO)
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    /// The time the item was logged
+    pub fn datetime(&self) -> &str {
+        &self.datetime
+    }
+
+    /// The target (module path) which logged the item
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// The file which logged the item
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The line of the file which logged the item
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// The logged message
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Log {
     items: Vec<LogItem>,
 }
@@ -55,16 +162,132 @@ impl Log {
         self.items.push(item);
     }
 
-    /// write log out to disk
+    /// Retrieve the items in the log
+    pub fn items(&self) -> &[LogItem] {
+        &self.items
+    }
+
+    /// write log out to disk as json
     pub fn write(&self, path: &str) -> io::Result<()> {
+        self.write_as(path, LogFormat::Json, &HtmlColors::default())
+    }
+
+    /// Write the log out to disk in the requested format
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file to write
+    /// * `format` - The LogFormat to write
+    /// * `colors` - The colors of each level, used by the html format
+    ///
+    /// # Returns
+    /// * io::Result<()>
+    pub fn write_as(&self, path: &str, format: LogFormat, colors: &HtmlColors) -> io::Result<()> {
         let file = OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
             .open(path)?;
-        serde_json::to_writer_pretty(file, &self)?;
+        let mut writer = BufWriter::new(file);
+        self.write_to(&mut writer, format, colors)?;
+        writer.flush()
+    }
+
+    /// Write the log to the supplied writer in the requested format
+    ///
+    /// # Arguments
+    /// * `writer` - The destination of the log
+    /// * `format` - The LogFormat to write
+    /// * `colors` - The colors of each level, used by the html format
+    ///
+    /// # Returns
+    /// * io::Result<()>
+    pub fn write_to<W: Write>(
+        &self,
+        mut writer: W,
+        format: LogFormat,
+        colors: &HtmlColors,
+    ) -> io::Result<()> {
+        match format {
+            LogFormat::Json => {
+                serde_json::to_writer_pretty(writer, &self)?;
+                Ok(())
+            }
+            LogFormat::Text => self.write_text(&mut writer),
+            LogFormat::Csv => self.write_csv(writer),
+            LogFormat::Html => self.write_html(&mut writer, colors),
+        }
+    }
+
+    /// Read a log previously written as json
+    ///
+    /// # Arguments
+    /// * `path` - The path of the log to read
+    ///
+    /// # Returns
+    /// * io::Result<Log>
+    pub fn read(path: &str) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let log = serde_json::from_reader(reader)?;
+        Ok(log)
+    }
+
+    fn write_text<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for item in &self.items {
+            let location = if item.line.is_empty() {
+                item.file.clone()
+            } else {
+                format!("{}:{}", item.file, item.line)
+            };
+            writeln!(
+                writer,
+                "{:<5} {} {} {} {}",
+                item.level, item.datetime, item.target, location, item.msg
+            )?;
+        }
         Ok(())
     }
+
+    fn write_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for item in &self.items {
+            writer.serialize(item).map_err(csv_to_io)?;
+        }
+        writer.flush()
+    }
+
+    fn write_html<W: Write>(&self, writer: &mut W, colors: &HtmlColors) -> io::Result<()> {
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, "<html>\n<head>\n<meta charset=\"utf-8\">\n<title>pbgui log</title>")?;
+        writeln!(
+            writer,
+            "<style>\nbody {{ background: #2b2b2b; color: #dcdcdc; font-family: monospace; }}\n\
+             td {{ padding: 0 8px; vertical-align: top; }}\n\
+             td.msg {{ white-space: pre-wrap; }}\n</style>"
+        )?;
+        writeln!(writer, "</head>\n<body>\n<table>")?;
+        writeln!(
+            writer,
+            "<tr><th>level</th><th>datetime</th><th>target</th><th>file</th><th>line</th><th>msg</th></tr>"
+        )?;
+        for item in &self.items {
+            let style = colors
+                .get(item.level.as_str())
+                .map(|color| format!(" style=\"color: {}\"", escape_html(color)))
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"msg\">{}</td></tr>",
+                style,
+                escape_html(&item.level),
+                escape_html(&item.datetime),
+                escape_html(&item.target),
+                escape_html(&item.file),
+                escape_html(&item.line),
+                escape_html(&item.msg)
+            )?;
+        }
+        writeln!(writer, "</table>\n</body>\n</html>")
+    }
 }
 
 impl Default for Log {
@@ -72,3 +295,59 @@ impl Default for Log {
         Self { items: Vec::new() }
     }
 }
+
+fn csv_to_io(err: csv::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+// escape the characters with special meaning in html
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log() -> Log {
+        Log::new(vec![LogItem {
+            level: "WARN".to_string(),
+            datetime: "Mon Jan  6 10:00:00 2020".to_string(),
+            target: "pbgui::main_window".to_string(),
+            file: "main_window.rs".to_string(),
+            line: "42".to_string(),
+            msg: "unable to find <pin>".to_string(),
+        }])
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let mut out = Vec::new();
+        log()
+            .write_to(&mut out, LogFormat::Json, &HtmlColors::default())
+            .unwrap();
+        let read: Log = serde_json::from_slice(&out).unwrap();
+        assert_eq!(read, log());
+    }
+
+    #[test]
+    fn html_is_colored_and_escaped() {
+        let mut out = Vec::new();
+        log()
+            .write_to(&mut out, LogFormat::Html, &HtmlColors::default())
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("<tr style=\"color: #ffff00\"><td>WARN</td>"));
+        assert!(out.contains("unable to find &lt;pin&gt;"));
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(LogFormat::from_path("/tmp/out.LOG"), Some(LogFormat::Text));
+        assert_eq!(LogFormat::from_path("/tmp/out"), None);
+    }
+}
@@ -11,6 +11,9 @@ pub struct InnerMenuBar {
     pub edit_menu: MutPtr<QMenu>,
     pub view_menu: MutPtr<QMenu>,
     pub save_packages_action: MutPtr<QAction>,
+    pub import_manifest_action: MutPtr<QAction>,
+    pub export_pins_action: MutPtr<QAction>,
+    pub apply_template_action: MutPtr<QAction>,
     pub clear_edits_action: MutPtr<QAction>,
     // pub toggle_distributions_action: MutPtr<QAction>,
     // pub toggle_withs_action: MutPtr<QAction>,
@@ -25,6 +28,9 @@ impl InnerMenuBar {
             let mut menubar: MutPtr<QMenuBar> = main_window.menu_bar();
             let mut file_menu = menubar.add_menu_q_string(&qs("File"));
             let save_packages_action = file_menu.add_action_q_string(&qs("save packages.xml"));
+            let import_manifest_action = file_menu.add_action_q_string(&qs("import manifest..."));
+            let export_pins_action = file_menu.add_action_q_string(&qs("export pins..."));
+            let apply_template_action = file_menu.add_action_q_string(&qs("apply template..."));
 
             let mut edit_menu = menubar.add_menu_q_string(&qs("Edit"));
             let clear_edits_action = edit_menu.add_action_q_string(&qs("clear edits"));
@@ -49,6 +55,9 @@ impl InnerMenuBar {
                 view_menu,
                 help_menu,
                 save_packages_action,
+                import_manifest_action,
+                export_pins_action,
+                apply_template_action,
                 clear_edits_action,
                 // toggle_distributions_action,
                 // toggle_withs_action,
@@ -70,6 +79,18 @@ impl InnerMenuBar {
         self.save_packages_action
     }
 
+    pub fn import_manifest_action(&self) -> MutPtr<QAction> {
+        self.import_manifest_action
+    }
+
+    pub fn export_pins_action(&self) -> MutPtr<QAction> {
+        self.export_pins_action
+    }
+
+    pub fn apply_template_action(&self) -> MutPtr<QAction> {
+        self.apply_template_action
+    }
+
     pub fn view_action_at_idx(&self, idx: i32) -> Option<MutPtr<QAction>> {
         unsafe {
             let mut actions = self.view_menu.actions();
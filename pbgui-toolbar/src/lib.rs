@@ -2,4 +2,5 @@ pub(crate) mod combo_boxes;
 pub(crate) mod line_edit;
 //pub(crate) mod menu_bar;
 pub(crate) mod query_button;
+pub(crate) mod time_travel;
 pub mod toolbar;
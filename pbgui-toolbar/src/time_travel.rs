@@ -0,0 +1,53 @@
+use qt_core::WidgetAttribute;
+use qt_widgets::{cpp_core::MutPtr, QFrame, QLineEdit, QPushButton, QToolBar};
+use rustqt_utils::{create_hlayout, qs};
+
+/// Create the time travel controls, with which the user views the versionpins as of
+/// a past revision.
+///
+/// # Arguments
+/// * `toolbar` - The toolbar to add the controls to
+///
+/// # Returns
+/// * Tuple of
+///   * the checkable button toggling time travel
+///   * the line edit taking the revision id or timestamp
+///   * the button restoring the past state
+pub fn create(
+    mut toolbar: MutPtr<QToolBar>,
+) -> (MutPtr<QPushButton>, MutPtr<QLineEdit>, MutPtr<QPushButton>) {
+    unsafe {
+        let mut as_of_btn = QPushButton::from_q_string(&qs("As Of"));
+        as_of_btn.set_object_name(&qs("AsOfButton"));
+        as_of_btn.set_checkable(true);
+        as_of_btn.set_tool_tip(&qs(
+            "View the versionpins as they stood at a past revision (read-only)",
+        ));
+        let as_of_btn_ptr = as_of_btn.as_mut_ptr();
+
+        let mut as_of_line_edit = QLineEdit::new();
+        as_of_line_edit.set_attribute_2a(WidgetAttribute::WAMacShowFocusRect, false);
+        as_of_line_edit.set_object_name(&qs("AsOfLineEdit"));
+        as_of_line_edit.set_placeholder_text(&qs("revision or YYYY-MM-DD HH:MM"));
+        let as_of_line_edit_ptr = as_of_line_edit.as_mut_ptr();
+
+        let mut restore_btn = QPushButton::from_q_string(&qs("Restore"));
+        restore_btn.set_object_name(&qs("RestoreButton"));
+        restore_btn.set_tool_tip(&qs(
+            "Stage the changes which return the versionpins to the revision shown",
+        ));
+        restore_btn.set_enabled(false);
+        let restore_btn_ptr = restore_btn.as_mut_ptr();
+
+        let mut frame = QFrame::new_0a();
+        frame.set_object_name(&qs("ComboFrame"));
+        let mut layout = create_hlayout();
+        layout.add_widget(as_of_btn.into_ptr());
+        layout.add_widget(as_of_line_edit.into_ptr());
+        layout.add_widget(restore_btn.into_ptr());
+        frame.set_layout(layout.into_ptr());
+        toolbar.add_widget(frame.into_ptr());
+
+        (as_of_btn_ptr, as_of_line_edit_ptr, restore_btn_ptr)
+    }
+}
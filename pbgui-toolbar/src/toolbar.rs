@@ -1,4 +1,4 @@
-use crate::{combo_boxes::*, line_edit, query_button};
+use crate::{combo_boxes::*, line_edit, query_button, time_travel};
 use qt_core::{AlignmentFlag, QFlags, QString};
 use qt_gui::QIcon;
 use qt_widgets::{
@@ -24,6 +24,9 @@ pub struct MainToolbar {
     line_edit: MutPtr<QLineEdit>,
     menu: CppBox<QMenu>,
     clear_line_edit_action: MutPtr<QAction>,
+    as_of_btn: MutPtr<QPushButton>,
+    as_of_line_edit: MutPtr<QLineEdit>,
+    restore_btn: MutPtr<QPushButton>,
 }
 
 /// load style at compile time
@@ -49,6 +52,8 @@ pub fn create(main_window: MutPtr<QMainWindow>) -> MainToolbar {
         let (dir, dir_icon) = setup_directions_cb(&mut top_toolbar.clone());
 
         let (line_edit, menu, clear_line_edit_action) = line_edit::create(top_toolbar.clone());
+        // Time travel
+        let (as_of_btn, as_of_line_edit, restore_btn) = time_travel::create(top_toolbar.clone());
 
         let _align: QFlags<AlignmentFlag> = AlignmentFlag::AlignCenter.into();
         MainToolbar {
@@ -67,6 +72,9 @@ pub fn create(main_window: MutPtr<QMainWindow>) -> MainToolbar {
             line_edit,
             menu,
             clear_line_edit_action,
+            as_of_btn,
+            as_of_line_edit,
+            restore_btn,
         }
     }
 }
@@ -292,6 +300,42 @@ impl MainToolbar {
         self.clear_line_edit_action
     }
 
+    /// Retrieve a MutPtr to the checkable button toggling time travel
+    pub fn as_of_btn(&self) -> MutPtr<QPushButton> {
+        self.as_of_btn
+    }
+
+    /// Retrieve a MutPtr to the QLineEdit taking the revision id or timestamp to
+    /// travel to
+    pub fn as_of_line_edit(&self) -> MutPtr<QLineEdit> {
+        self.as_of_line_edit
+    }
+
+    /// Retrieve a MutPtr to the button restoring the past state shown
+    pub fn restore_btn(&self) -> MutPtr<QPushButton> {
+        self.restore_btn
+    }
+
+    /// Retrieve the revision id or timestamp to view the versionpins at
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Some(String) if time travel is toggled on and a revision or timestamp is set
+    /// * None otherwise
+    pub unsafe fn as_of_string(&self) -> Option<String> {
+        if !self.as_of_btn.is_checked() {
+            return None;
+        }
+        let as_of = self.as_of_line_edit.text().trimmed().to_std_string();
+        if as_of.is_empty() {
+            None
+        } else {
+            Some(as_of)
+        }
+    }
+
     /// Set the stylesheet to the internal stylesheet
     pub fn set_default_stylesheet(&self) {
         set_stylesheet_from_str(STYLE_STR, self.toolbar);
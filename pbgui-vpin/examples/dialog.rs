@@ -34,16 +34,11 @@ fn main() {
             } else {
                 println!("roles: any");
             }
-            if let Some(selected_level) = dialog.selected_level() {
-                println!("level: {:?}", selected_level);
-            } else {
-                println!("level: {}", dialog.show_name());
-            }
-            match dialog.selected_site(){
-                Some(site) => println!(
-                    "site:  {}", site
-                ),
-                None => println!("site:  Any"),
+            println!("levels: {:?}", dialog.selected_levels());
+            println!("sites: {:?}", dialog.selected_sites());
+            println!("platforms: {:?}", dialog.selected_platforms());
+            for combination in dialog.selected_combinations() {
+                println!("pin: {}", combination.label());
             }
             dialog.accept();
         }});
@@ -75,6 +70,7 @@ unsafe fn create_dialog<'a, I: Into<String>>(
     dialog.set_levels(levelmap);
 
     dialog.set_sites(vec!["hyderabad", "montreal", "playa", "vancouver"]);
+    dialog.set_platforms(vec!["cent6_64", "cent7_64", "win10_64"]);
     dialog
 }
 
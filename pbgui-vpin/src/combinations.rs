@@ -0,0 +1,114 @@
+//! Expands the levels, roles, sites and platforms selected in the dialog into the
+//! individual pins they describe.
+
+/// The level, role, site and platform of a single pin requested by the dialog
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct PinCombination {
+    pub level: String,
+    pub role: String,
+    pub site: String,
+    pub platform: String,
+}
+
+impl PinCombination {
+    /// New up a PinCombination
+    ///
+    /// # Arguments
+    /// * `level` - The level of the pin
+    /// * `role` - The role of the pin
+    /// * `site` - The site of the pin
+    /// * `platform` - The platform of the pin
+    ///
+    /// # Returns
+    /// * PinCombination instance
+    pub fn new<L, R, S, P>(level: L, role: R, site: S, platform: P) -> Self
+    where
+        L: Into<String>,
+        R: Into<String>,
+        S: Into<String>,
+        P: Into<String>,
+    {
+        Self {
+            level: level.into(),
+            role: role.into(),
+            site: site.into(),
+            platform: platform.into(),
+        }
+    }
+
+    /// The text presented for the combination in the preview
+    pub fn label(&self) -> String {
+        format!(
+            "level: {}  role: {}  site: {}  platform: {}",
+            self.level, self.role, self.site, self.platform
+        )
+    }
+}
+
+/// Expand the cartesian product of levels, roles, sites and platforms, ordered by level,
+/// then role, then site, then platform.
+///
+/// # Arguments
+/// * `levels` - The selected levels
+/// * `roles` - The selected roles
+/// * `sites` - The selected sites
+/// * `platforms` - The selected platforms
+///
+/// # Returns
+/// * Vec of PinCombination
+pub fn expand(
+    levels: &[String],
+    roles: &[String],
+    sites: &[String],
+    platforms: &[String],
+) -> Vec<PinCombination> {
+    let mut combinations =
+        Vec::with_capacity(levels.len() * roles.len() * sites.len() * platforms.len());
+    for level in levels {
+        for role in roles {
+            for site in sites {
+                for platform in platforms {
+                    combinations.push(PinCombination::new(
+                        level.as_str(),
+                        role.as_str(),
+                        site.as_str(),
+                        platform.as_str(),
+                    ));
+                }
+            }
+        }
+    }
+    combinations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn expands_cartesian_product() {
+        let combinations = expand(
+            &strings(&["dev01.RD", "dev01.AA.0100"]),
+            &strings(&["model", "anim"]),
+            &strings(&["portland", "playa"]),
+            &strings(&["cent7_64"]),
+        );
+        assert_eq!(combinations.len(), 8);
+        assert_eq!(
+            combinations[1],
+            PinCombination::new("dev01.RD", "model", "playa", "cent7_64")
+        );
+        assert_eq!(combinations[4].level, "dev01.AA.0100");
+        assert!(expand(
+            &strings(&["dev01"]),
+            &strings(&["model"]),
+            &[],
+            &strings(&["any"])
+        )
+        .is_empty());
+    }
+}
@@ -1,40 +1,43 @@
 #![allow(dead_code)]
 //! The DistributionDialog allows the user to generate one or more pins for a distribution
-use qt_core::{FocusPolicy, QChar, QString, Signal, SlotOfInt};
+use crate::combinations::{expand, PinCombination};
+pub use crate::levels::LevelMap;
+use crate::levels::{expand_shot_list, selection_levels, shot_label, LevelExpansion};
+use qt_core::{CheckState, FocusPolicy, ItemFlag, QString, Signal, Slot, SlotOfInt, SlotOfQString};
 use qt_widgets::{
-    cpp_core::{CastInto, CppBox, MutPtr, Ptr},
+    cpp_core::{CastInto, CppBox, MutPtr, Ptr, Ref},
     q_abstract_item_view::SelectionMode,
     q_dialog_button_box::StandardButton,
-    QCheckBox, QComboBox, QDialog, QDialogButtonBox, QFrame, QGroupBox, QHBoxLayout, QLabel,
-    QLayout, QLineEdit, QListWidget, QVBoxLayout, QWidget,
+    QCheckBox, QDialog, QDialogButtonBox, QFrame, QGroupBox, QHBoxLayout, QLabel, QLayout,
+    QLineEdit, QListWidget, QListWidgetItem, QVBoxLayout, QWidget,
 };
 use std::cell::RefCell;
-use std::collections::HashMap;
-
-/// LevelMap maps a sequence to a list of shots
-pub type LevelMap = HashMap<String, Vec<String>>;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 pub use rustqt_utils::{create_hlayout, create_vlayout, qs, set_stylesheet_from_str};
 
 const STYLE_STR: &'static str = include_str!("../resources/pbgui_vpin.qss");
-const DEFAULT_SEQ: &'static str = "All Sequences";
-const DEFAULT_SHOT: &'static str = "All Shots";
 pub const DEFAULT_SITE: &'static str = "any";
+pub const DEFAULT_PLATFORM: &str = "any";
+const DEFAULT_ROLE: &str = "any";
 pub struct InnerVpinDialog<'a> {
     dialog: CppBox<QDialog>,
-    show: RefCell<String>,
     distribution: MutPtr<QLabel>,
     roles_checkbox: MutPtr<QCheckBox>,
     roles_filter: MutPtr<QLineEdit>,
     roles_list: MutPtr<QListWidget>,
-    seqs_cbox: MutPtr<QComboBox>,
-    seq_shot_checkbox: MutPtr<QCheckBox>,
-    shots_cbox: MutPtr<QComboBox>,
-    sites_cbox: MutPtr<QComboBox>,
+    level_picker: LevelPicker,
+    sites_list: MutPtr<QListWidget>,
+    platforms_list: MutPtr<QListWidget>,
+    preview: Preview,
     buttons: MutPtr<QDialogButtonBox>,
-    levels: LevelMap,
+    preset: RefCell<Option<PinCombination>>,
     seq_shot_cb_slot: SlotOfInt<'a>,
     roles_cb_slot: SlotOfInt<'a>,
+    selection_changed_slot: Slot<'a>,
+    shot_list_changed_slot: SlotOfQString<'a>,
+    preview_changed_slot: Slot<'a>,
 }
 
 impl<'a> InnerVpinDialog<'a> {
@@ -71,20 +74,29 @@ impl<'a> InnerVpinDialog<'a> {
             // right side controls
             let mut right_layout = Self::add_right_layout(hlayout_ptr);
             //
-            let seq_shot_checkbox = Self::add_seq_shot_checkbox(right_layout);
-            let seq_shot_group_box = Self::add_select_level_groupbox(right_layout);
-            let seqs_cbox = Self::add_seq_cbox(seq_shot_group_box.layout());
-            let mut seqs_cbox_ref = seqs_cbox
-                .as_mut_ref()
-                .expect("unable to get mut ref to seq combobox from mutptr");
-            let shots_cbox = Self::add_shot_cbox(seq_shot_group_box.layout());
-            let mut shots_cbox_ref = shots_cbox
-                .as_mut_ref()
-                .expect("unable to get mut ref to shot combobox from mutptr");
+            let level_picker = LevelPicker::create(show.into(), right_layout);
 
             let sel_sites_group_box = Self::add_select_site_groupbox(right_layout);
-            let sites_cbox = Self::add_site_cbox(sel_sites_group_box.layout());
+            let sites_list = Self::add_multiselect_list(
+                sel_sites_group_box.layout(),
+                "SelectSitesListWidget",
+            );
+            let sel_platforms_group_box = Self::add_select_platform_groupbox(right_layout);
+            let platforms_list = Self::add_multiselect_list(
+                sel_platforms_group_box.layout(),
+                "SelectPlatformsListWidget",
+            );
             right_layout.add_stretch_1a(1);
+
+            // the pins which will be created, one per level, role, site and platform
+            let preview = Preview::create(
+                layout_ptr,
+                level_picker.clone(),
+                roles_checkbox,
+                roles_list,
+                sites_list,
+                platforms_list,
+            );
             layout.add_stretch_1a(1);
             let mut strut = QFrame::new_0a();
             strut.set_fixed_size_2a(100, 50);
@@ -107,33 +119,32 @@ impl<'a> InnerVpinDialog<'a> {
                 .as_mut_ref()
                 .expect("unable to get ref to roles_filter");
             // default to disabled
-            seqs_cbox_ref.set_enabled(false);
-            shots_cbox_ref.set_enabled(false);
+            level_picker.set_enabled(false);
 
             roles_list_ref.set_enabled(false);
             roles_filter_ref.set_enabled(false);
+            let levels_picker = level_picker.clone();
+            let levels_preview = preview.clone();
+            let roles_preview = preview.clone();
+            let selection_preview = preview.clone();
+            let shot_list_preview = preview.clone();
+            let changed_preview = preview.clone();
             // create the dialog
             let mut dialog = InnerVpinDialog {
                 dialog,
-                show: RefCell::new(show.into()),
                 distribution: distribution_label,
                 roles_checkbox,
                 roles_filter,
                 roles_list,
-                seq_shot_checkbox,
-                seqs_cbox,
-                shots_cbox,
-                sites_cbox,
+                level_picker,
+                sites_list,
+                platforms_list,
+                preview,
                 buttons,
-                levels: LevelMap::new(),
+                preset: RefCell::new(None),
                 seq_shot_cb_slot: SlotOfInt::new(move |active: std::os::raw::c_int| {
-                    if active > 0 {
-                        seqs_cbox_ref.set_enabled(true);
-                        shots_cbox_ref.set_enabled(true);
-                    } else {
-                        seqs_cbox_ref.set_enabled(false);
-                        shots_cbox_ref.set_enabled(false);
-                    }
+                    levels_picker.set_enabled(active > 0);
+                    levels_preview.update();
                 }),
                 roles_cb_slot: SlotOfInt::new(move |active: std::os::raw::c_int| {
                     if active > 0 {
@@ -146,18 +157,49 @@ impl<'a> InnerVpinDialog<'a> {
                         group_box.set_enabled(false);
                         roles_filter_ref.set_enabled(false);
                     }
+                    roles_preview.update();
+                }),
+                selection_changed_slot: Slot::new(move || {
+                    selection_preview.update();
+                }),
+                shot_list_changed_slot: SlotOfQString::new(move |_text: Ref<QString>| {
+                    shot_list_preview.update();
+                }),
+                preview_changed_slot: Slot::new(move || {
+                    changed_preview.update_count();
                 }),
             };
             // set up internal signals and slots
             // Enable / Disable roles list and filter
             dialog
-                .seq_shot_checkbox
+                .level_picker
+                .checkbox
                 .state_changed()
                 .connect(&dialog.seq_shot_cb_slot);
             dialog
                 .roles_checkbox
                 .state_changed()
                 .connect(&dialog.roles_cb_slot);
+            // rebuild the preview whenever the selected levels, roles, sites or platforms change
+            for list in &[
+                dialog.level_picker.seqs_list,
+                dialog.level_picker.shots_list,
+                dialog.roles_list,
+                dialog.sites_list,
+                dialog.platforms_list,
+            ] {
+                list.item_selection_changed().connect(&dialog.selection_changed_slot);
+            }
+            dialog
+                .level_picker
+                .shot_list
+                .text_changed()
+                .connect(&dialog.shot_list_changed_slot);
+            dialog
+                .preview
+                .list
+                .item_changed()
+                .connect(&dialog.preview_changed_slot);
             // connect the Cancel button to a slot that dismisses the dialog
             buttons.rejected().connect(dialog.dialog.slot_reject());
             // set teh roles_lsit focus
@@ -171,11 +213,11 @@ impl<'a> InnerVpinDialog<'a> {
     }
     /// retrieve the show_name
     pub fn show_name(&self) -> String {
-        self.show.borrow().clone()
+        self.level_picker.show.borrow().clone()
     }
     /// set the show name
     pub fn set_show_name(&self, new_name: String) {
-        self.show.replace(new_name);
+        self.level_picker.show.replace(new_name);
     }
     /// retrieve the current distribution as a string
     pub unsafe fn distribution(&self) -> String {
@@ -190,18 +232,6 @@ impl<'a> InnerVpinDialog<'a> {
         let mut distribution_mut = self.distribution;
         distribution_mut.set_text(&qs(distribution));
     }
-    /// Retrieve the package name as a String
-    pub unsafe fn package(&self) -> String {
-        self.package_qs().to_std_string()
-    }
-    ///retrieve the package name as a qstring
-    pub unsafe fn package_qs(&self) -> CppBox<QString> {
-        let dash = QChar::from_char(45); // 45 is ascii code for dash
-        let part = self.distribution.text().split_q_char(dash.as_ref());
-        let mut qstr = QString::new();
-        qstr.append_q_string(part.first());
-        qstr
-    }
     /// Return the accepted signal from the button. This is provided as a convenience
     /// for hooking up a slot from this struct.
     pub unsafe fn accepted(&self) -> Signal<()> {
@@ -249,9 +279,21 @@ impl<'a> InnerVpinDialog<'a> {
         Some(results)
     }
 
-    /// Retrieve the current site
-    pub unsafe fn selected_site(&self) -> String {
-        self.sites_cbox.current_text().to_std_string()
+    /// Retrieve the selected sites. If none are selected, the default site is returned
+    pub unsafe fn selected_sites(&self) -> Vec<String> {
+        selected_or_default(self.sites_list, DEFAULT_SITE)
+    }
+
+    /// Retrieve the selected platforms. If none are selected, the default platform
+    /// is returned
+    pub unsafe fn selected_platforms(&self) -> Vec<String> {
+        selected_or_default(self.platforms_list, DEFAULT_PLATFORM)
+    }
+
+    /// Retrieve the combinations of level, role, site and platform which remain checked
+    /// in the preview
+    pub unsafe fn selected_combinations(&self) -> Vec<PinCombination> {
+        self.preview.checked()
     }
 
     /// Load the stylesheet
@@ -259,14 +301,30 @@ impl<'a> InnerVpinDialog<'a> {
         set_stylesheet_from_str(STYLE_STR, self.dialog.as_mut_ptr());
     }
 
-    /// Set the sites
+    /// Set the level, role, site and platform to select as the dialog is populated,
+    /// or None to fall back to the defaults
+    pub fn set_preset(&self, preset: Option<PinCombination>) {
+        self.preset.replace(preset);
+    }
+
+    /// Set the sites. The default site is listed first, and selected, unless the
+    /// preset names another site
     pub fn set_sites(&self, sites: Vec<&str>) {
         unsafe {
-            let mut sites_cbox = self.sites_cbox;
-            sites_cbox.clear();
-            sites_cbox.add_item_q_string(&qs(DEFAULT_SITE));
-            for site in sites {
-                sites_cbox.add_item_q_string(&qs(site));
+            set_list_items(self.sites_list, DEFAULT_SITE, sites);
+            if let Some(preset) = self.preset.borrow().as_ref() {
+                select_only(self.sites_list, &preset.site);
+            }
+        }
+    }
+
+    /// Set the platforms. The default platform is listed first, and selected, unless
+    /// the preset names another platform
+    pub fn set_platforms(&self, platforms: Vec<&str>) {
+        unsafe {
+            set_list_items(self.platforms_list, DEFAULT_PLATFORM, platforms);
+            if let Some(preset) = self.preset.borrow().as_ref() {
+                select_only(self.platforms_list, &preset.platform);
             }
         }
     }
@@ -281,104 +339,51 @@ impl<'a> InnerVpinDialog<'a> {
             }
             roles_list.select_all();
             roles_list.set_focus_policy(FocusPolicy::StrongFocus);
+            // a preset role other than the default is specified explicitly
+            if let Some(preset) = self.preset.borrow().as_ref() {
+                if preset.role != DEFAULT_ROLE && select_only(roles_list, &preset.role) {
+                    let mut roles_checkbox = self.roles_checkbox;
+                    roles_checkbox.set_checked(true);
+                }
+            }
         }
     }
 
     /// provide a vector of strings representing the sequences stored in the map
     pub fn seqs(&self) -> Vec<String> {
-        self.levels
+        self.level_picker
+            .levels
+            .borrow()
             .keys()
             .map(|x| x.to_string())
             .collect::<Vec<_>>()
     }
 
-    pub unsafe fn selected_seq(&self) -> Option<String> {
-        if !self.seqs_cbox.is_enabled() {
-            return None;
-        }
-        let result = self.seqs_cbox.current_text().to_std_string();
-        if result == DEFAULT_SEQ {
-            None
-        } else {
-            Some(result)
-        }
+    /// Return the levels chosen via the sequence and shot lists and the shot list
+    /// line edit. If no sequences or shots are specified, the show is returned.
+    pub unsafe fn selected_levels(&self) -> Vec<String> {
+        self.level_picker.selected().levels
     }
 
-    pub unsafe fn selected_shot(&self) -> Option<String> {
-        if !self.shots_cbox.is_enabled() {
-            return None;
-        }
-        let result = self.shots_cbox.current_text().to_std_string();
-        if result == DEFAULT_SHOT {
-            None
-        } else {
-            Some(result)
-        }
-    }
-
-    /// Return the selected Sequence/shot if applicable
-    pub unsafe fn selected_level(&self) -> Option<String> {
-        if !self.seq_shot_checkbox.is_checked() {
-            None
-        } else {
-            if let Some(seq) = self.selected_seq() {
-                if let Some(shot) = self.selected_shot() {
-                    Some(format!("{}.{}.{}", self.show.borrow(), seq, shot))
-                } else {
-                    Some(format!("{}.{}", self.show.borrow(), seq))
-                }
-            } else {
-                None
-            }
-        }
-    }
     /// Given a new LevelMap, repalace the existing one
-    pub fn set_levels_map(&mut self, levels: LevelMap) {
-        std::mem::replace(&mut self.levels, levels);
-    }
-
-    // pub fn set_levels(&self, levels: Vec<String>) {
-    //     unsafe {
-    //         let mut seqs_cbox = self.seqs_cbox;
-    //         let mut shots_cbox = self.shots_cbox;
-    //         seqs_cbox.clear();
-    //         seqs_cbox.add_item_q_string(&qs(DEFAULT_SEQ));
-    //         for seq in levels {
-    //             seqs_cbox.add_item_q_string(&qs(seq));
-    //         }
-    //         shots_cbox.clear();
-    //         shots_cbox.add_item_q_string(&qs(DEFAULT_SHOT));
-    //     }
-    // }
+    pub fn set_levels_map(&self, levels: LevelMap) {
+        self.level_picker.levels.replace(levels);
+    }
 
+    /// Populate the sequence list from the LevelMap. A preset level below the show
+    /// is entered in the shot list
     pub fn set_levels_from_map(&self) {
         unsafe {
-            let mut seqs_cbox = self.seqs_cbox;
-            let mut shots_cbox = self.shots_cbox;
-            seqs_cbox.clear();
-            seqs_cbox.add_item_q_string(&qs(DEFAULT_SEQ));
-            for seq in self.levels.keys() {
-                seqs_cbox.add_item_q_string(&qs(seq));
+            self.level_picker.set_seqs_from_map();
+            if let Some(preset) = self.preset.borrow().as_ref() {
+                self.level_picker.set_level(&preset.level);
             }
-            shots_cbox.clear();
-            shots_cbox.add_item_q_string(&qs(DEFAULT_SHOT));
         }
     }
 
-    pub unsafe fn clear_shots(&self) {
-        let mut shots_cbox = self.shots_cbox;
-        shots_cbox.clear();
-    }
-    /// Given a sequence from a selection, populate the shot combobox
-    pub unsafe fn set_shots_for_seq(&self, sequence: &str) {
-        let mut shots_cbox = self.shots_cbox;
-        shots_cbox.clear();
-        shots_cbox.add_item_q_string(&qs(DEFAULT_SHOT));
-        if let Some(shots) = self.levels.get(sequence) {
-            for shot in shots {
-                shots_cbox.add_item_q_string(&qs(shot));
-            }
-        }
+    /// Populate the shot list with the shots of the selected sequences
+    pub unsafe fn set_shots_for_selected_seqs(&self) {
+        self.level_picker.set_shots_for_selected_seqs();
     }
 
     pub unsafe fn set_roles_focus(&mut self) {
@@ -411,20 +416,22 @@ impl<'a> InnerVpinDialog<'a> {
     pub fn finished(&self) -> qt_core::Signal<(std::os::raw::c_int,)> {
         self.dialog.finished()
     }
-    pub fn seqs_cb(&self) -> MutPtr<QComboBox> {
-        self.seqs_cbox
+    pub fn seqs_list(&self) -> MutPtr<QListWidget> {
+        self.level_picker.seqs_list
     }
 
-    unsafe fn add_site_cbox(mut parent: MutPtr<QLayout>) -> MutPtr<QComboBox> {
-        let mut sites_cbox = QComboBox::new_0a();
-        sites_cbox.set_object_name(&qs("SelectLocationComboBox"));
-        let sites_cbox_ptr = sites_cbox.as_mut_ptr();
-        parent.add_widget(sites_cbox.into_ptr());
-        sites_cbox_ptr
+    unsafe fn add_multiselect_list(mut parent: MutPtr<QLayout>, name: &str) -> MutPtr<QListWidget> {
+        let mut list_widget = QListWidget::new_0a();
+        list_widget.set_object_name(&qs(name));
+        list_widget.set_selection_mode(SelectionMode::ExtendedSelection);
+        list_widget.set_maximum_height(120);
+        let list_widget_ptr = list_widget.as_mut_ptr();
+        parent.add_widget(list_widget.into_ptr());
+        list_widget_ptr
     }
 
     unsafe fn add_select_site_groupbox(mut parent: MutPtr<QVBoxLayout>) -> MutPtr<QGroupBox> {
-        let mut label = QLabel::from_q_string(&qs("Select Site"));
+        let mut label = QLabel::from_q_string(&qs("Select Site(s)"));
         label.set_object_name(&qs("SelectSiteLabel"));
         parent.add_widget(label.into_ptr());
         let mut group_box = QGroupBox::new();
@@ -436,36 +443,13 @@ impl<'a> InnerVpinDialog<'a> {
         group_box_ptr
     }
 
-    unsafe fn add_seq_shot_checkbox(mut parent: MutPtr<QVBoxLayout>) -> MutPtr<QCheckBox> {
-        let mut cb = QCheckBox::from_q_string(&qs("Specify Seq(s) / Shot(s)"));
-        cb.set_object_name(&qs("SeqShotCheckBox"));
-        let cb_ptr = cb.as_mut_ptr();
-        parent.add_widget(cb.into_ptr());
-        cb_ptr
-    }
-
-    unsafe fn add_seq_cbox(mut parent: MutPtr<QLayout>) -> MutPtr<QComboBox> {
-        let mut seqs_cbox = QComboBox::new_0a();
-        seqs_cbox.set_object_name(&qs("AddSeqsComboBox"));
-        seqs_cbox.add_item_q_string(&qs("All Sequences"));
-        let seqs_cbox_ptr = seqs_cbox.as_mut_ptr();
-        parent.add_widget(seqs_cbox.into_ptr());
-        seqs_cbox_ptr
-    }
-
-    unsafe fn add_shot_cbox(mut parent: MutPtr<QLayout>) -> MutPtr<QComboBox> {
-        let mut shots_cbox = QComboBox::new_0a();
-        shots_cbox.set_object_name(&qs("AddShotsComboBox"));
-        shots_cbox.add_item_q_string(&qs("All Shots"));
-        let shots_cbox_ptr = shots_cbox.as_mut_ptr();
-        parent.add_widget(shots_cbox.into_ptr());
-        shots_cbox_ptr
-    }
-
-    unsafe fn add_select_level_groupbox(mut parent: MutPtr<QVBoxLayout>) -> MutPtr<QGroupBox> {
+    unsafe fn add_select_platform_groupbox(mut parent: MutPtr<QVBoxLayout>) -> MutPtr<QGroupBox> {
+        let mut label = QLabel::from_q_string(&qs("Select Platform(s)"));
+        label.set_object_name(&qs("SelectPlatformLabel"));
+        parent.add_widget(label.into_ptr());
         let mut group_box = QGroupBox::new();
         let group_box_ptr = group_box.as_mut_ptr();
-        group_box.set_object_name(&qs("SelectLevelsGroupBox"));
+        group_box.set_object_name(&qs("SelectPlatformGroupBox"));
         let layout = create_vlayout();
         group_box.set_layout(layout.into_ptr());
         parent.add_widget(group_box.into_ptr());
@@ -557,3 +541,313 @@ impl<'a> InnerVpinDialog<'a> {
         distribution_ptr
     }
 }
+
+/// Chooses the levels to pin, by selecting any number of sequences and shots from
+/// their lists, and by typing a shot list, which may contain glob patterns.
+#[derive(Clone)]
+struct LevelPicker {
+    show: Rc<RefCell<String>>,
+    levels: Rc<RefCell<LevelMap>>,
+    checkbox: MutPtr<QCheckBox>,
+    group_box: MutPtr<QGroupBox>,
+    seqs_list: MutPtr<QListWidget>,
+    shots_list: MutPtr<QListWidget>,
+    shot_list: MutPtr<QLineEdit>,
+}
+
+impl LevelPicker {
+    unsafe fn create(show: String, mut parent: MutPtr<QVBoxLayout>) -> Self {
+        let mut checkbox = QCheckBox::from_q_string(&qs("Specify Seq(s) / Shot(s)"));
+        checkbox.set_object_name(&qs("SeqShotCheckBox"));
+        let checkbox_ptr = checkbox.as_mut_ptr();
+        parent.add_widget(checkbox.into_ptr());
+
+        let mut group_box = QGroupBox::new();
+        let group_box_ptr = group_box.as_mut_ptr();
+        group_box.set_object_name(&qs("SelectLevelsGroupBox"));
+        let mut layout = create_vlayout();
+        let mut lists_layout = create_hlayout();
+        let mut lists_layout_ptr = lists_layout.as_mut_ptr();
+        layout.add_layout_1a(lists_layout.into_ptr());
+        let seqs_list = Self::add_list(lists_layout_ptr, "SelectSeqsListWidget");
+        let shots_list = Self::add_list(lists_layout_ptr, "SelectShotsListWidget");
+        lists_layout_ptr.set_stretch(1, 2);
+        let mut shot_list = QLineEdit::new();
+        shot_list.set_object_name(&qs("ShotListLineEdit"));
+        shot_list.set_placeholder_text(&qs("Shot list. eg RD_0[12]*, AA.0100"));
+        shot_list.set_clear_button_enabled(true);
+        let shot_list_ptr = shot_list.as_mut_ptr();
+        layout.add_widget(shot_list.into_ptr());
+        group_box.set_layout(layout.into_ptr());
+        parent.add_widget(group_box.into_ptr());
+        Self {
+            show: Rc::new(RefCell::new(show)),
+            levels: Rc::new(RefCell::new(LevelMap::new())),
+            checkbox: checkbox_ptr,
+            group_box: group_box_ptr,
+            seqs_list,
+            shots_list,
+            shot_list: shot_list_ptr,
+        }
+    }
+
+    unsafe fn add_list(mut parent: MutPtr<QHBoxLayout>, name: &str) -> MutPtr<QListWidget> {
+        let mut list_widget = QListWidget::new_0a();
+        list_widget.set_object_name(&qs(name));
+        list_widget.set_selection_mode(SelectionMode::ExtendedSelection);
+        list_widget.set_maximum_height(160);
+        let list_widget_ptr = list_widget.as_mut_ptr();
+        parent.add_widget(list_widget.into_ptr());
+        list_widget_ptr
+    }
+
+    unsafe fn set_enabled(&self, enabled: bool) {
+        let mut group_box = self.group_box;
+        group_box.set_enabled(enabled);
+    }
+
+    // The levels chosen by the user. If the checkbox is unchecked, or nothing has been
+    // chosen, the show is returned.
+    unsafe fn selected(&self) -> LevelExpansion {
+        let show = self.show.borrow();
+        if !self.checkbox.is_checked() {
+            return LevelExpansion {
+                levels: vec![show.clone()],
+                unmatched: Vec::new(),
+            };
+        }
+        let shot_list = self.shot_list.text().to_std_string();
+        let mut expansion = expand_shot_list(&show, &shot_list, &self.levels.borrow());
+        let selected = selection_levels(
+            &show,
+            &selected_items(self.seqs_list),
+            &selected_items(self.shots_list),
+        );
+        if selected.is_empty() && shot_list.trim().is_empty() {
+            expansion.levels.push(show.clone());
+        } else {
+            expansion.levels.extend(selected);
+            expansion.levels.sort();
+            expansion.levels.dedup();
+        }
+        expansion
+    }
+
+    // enter a level of the show, as `seq` or `seq.shot`, in the shot list. The show
+    // itself is the default, and is left as is
+    unsafe fn set_level(&self, level: &str) {
+        let show = format!("{}.", self.show.borrow());
+        if !level.starts_with(show.as_str()) {
+            return;
+        }
+        let mut checkbox = self.checkbox;
+        checkbox.set_checked(true);
+        let mut shot_list = self.shot_list;
+        shot_list.set_text(&qs(&level[show.len()..]));
+    }
+
+    unsafe fn set_seqs_from_map(&self) {
+        let mut seqs = self.levels.borrow().keys().cloned().collect::<Vec<_>>();
+        seqs.sort();
+        let mut seqs_list = self.seqs_list;
+        let mut shots_list = self.shots_list;
+        shots_list.clear();
+        seqs_list.clear();
+        for seq in seqs {
+            seqs_list.add_item_q_string(&qs(seq));
+        }
+    }
+
+    // Rebuild the shot list from the selected sequences, retaining the selected shots
+    // whose sequence remains selected
+    unsafe fn set_shots_for_selected_seqs(&self) {
+        let selected = selected_items(self.shots_list)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let mut shots_list = self.shots_list;
+        shots_list.clear();
+        let levels = self.levels.borrow();
+        for seq in selected_items(self.seqs_list) {
+            let shots = match levels.get(&seq) {
+                Some(shots) => shots,
+                None => continue,
+            };
+            for shot in shots {
+                let label = shot_label(&seq, shot);
+                shots_list.add_item_q_string(&qs(&label));
+                if selected.contains(&label) {
+                    let mut item = shots_list.item(shots_list.count() - 1);
+                    item.set_selected(true);
+                }
+            }
+        }
+    }
+}
+
+/// Lists each combination of the selected levels, roles, sites and platforms as a
+/// checkable item, allowing the user to deselect individual pins before accepting
+/// the dialog.
+#[derive(Clone)]
+struct Preview {
+    list: MutPtr<QListWidget>,
+    count: MutPtr<QLabel>,
+    level_picker: LevelPicker,
+    roles_checkbox: MutPtr<QCheckBox>,
+    roles_list: MutPtr<QListWidget>,
+    sites_list: MutPtr<QListWidget>,
+    platforms_list: MutPtr<QListWidget>,
+    combinations: Rc<RefCell<Vec<PinCombination>>>,
+    unmatched: Rc<RefCell<Vec<String>>>,
+}
+
+impl Preview {
+    unsafe fn create(
+        mut parent: MutPtr<QVBoxLayout>,
+        level_picker: LevelPicker,
+        roles_checkbox: MutPtr<QCheckBox>,
+        roles_list: MutPtr<QListWidget>,
+        sites_list: MutPtr<QListWidget>,
+        platforms_list: MutPtr<QListWidget>,
+    ) -> Self {
+        let mut label = QLabel::from_q_string(&qs("Pins To Create"));
+        label.set_object_name(&qs("PreviewLabel"));
+        parent.add_widget(label.into_ptr());
+        let mut list = QListWidget::new_0a();
+        list.set_object_name(&qs("PreviewListWidget"));
+        list.set_selection_mode(SelectionMode::NoSelection);
+        let list_ptr = list.as_mut_ptr();
+        parent.add_widget(list.into_ptr());
+        let mut count = QLabel::new();
+        count.set_object_name(&qs("PreviewCountLabel"));
+        let count_ptr = count.as_mut_ptr();
+        parent.add_widget(count.into_ptr());
+        Self {
+            list: list_ptr,
+            count: count_ptr,
+            level_picker,
+            roles_checkbox,
+            roles_list,
+            sites_list,
+            platforms_list,
+            combinations: Rc::new(RefCell::new(Vec::new())),
+            unmatched: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    // Rebuild the preview from the current selection. Combinations the user
+    // previously unchecked remain unchecked.
+    unsafe fn update(&self) {
+        let levels = self.level_picker.selected();
+        let roles = if self.roles_checkbox.is_checked() {
+            selected_items(self.roles_list)
+        } else {
+            vec![DEFAULT_ROLE.to_string()]
+        };
+        let sites = selected_or_default(self.sites_list, DEFAULT_SITE);
+        let platforms = selected_or_default(self.platforms_list, DEFAULT_PLATFORM);
+        let unchecked = self.unchecked();
+        let combinations = expand(&levels.levels, &roles, &sites, &platforms);
+        let mut list = self.list;
+        list.block_signals(true);
+        list.clear();
+        for combination in &combinations {
+            let mut item = QListWidgetItem::from_q_string(&qs(combination.label()));
+            item.set_flags(ItemFlag::ItemIsEnabled | ItemFlag::ItemIsUserCheckable);
+            item.set_check_state(if unchecked.contains(combination) {
+                CheckState::Unchecked
+            } else {
+                CheckState::Checked
+            });
+            list.add_item_q_list_widget_item(item.into_ptr());
+        }
+        list.block_signals(false);
+        self.combinations.replace(combinations);
+        self.unmatched.replace(levels.unmatched);
+        self.update_count();
+    }
+
+    // Report the number of pins which will be created, along with any entries of the
+    // shot list which do not match a level
+    unsafe fn update_count(&self) {
+        let mut text = format!(
+            "{} of {} pin(s) selected",
+            self.checked().len(),
+            self.combinations.borrow().len()
+        );
+        let unmatched = self.unmatched.borrow();
+        if !unmatched.is_empty() {
+            text.push_str(&format!(". No levels match: {}", unmatched.join(", ")));
+        }
+        let mut count = self.count;
+        count.set_text(&qs(text));
+    }
+
+    unsafe fn checked(&self) -> Vec<PinCombination> {
+        self.with_state(CheckState::Checked).into_iter().collect()
+    }
+
+    unsafe fn unchecked(&self) -> HashSet<PinCombination> {
+        self.with_state(CheckState::Unchecked).into_iter().collect()
+    }
+
+    unsafe fn with_state(&self, state: CheckState) -> Vec<PinCombination> {
+        self.combinations
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(row, _)| {
+                let item = self.list.item(*row as i32);
+                !item.is_null() && item.check_state() == state
+            })
+            .map(|(_, combination)| combination.clone())
+            .collect()
+    }
+}
+
+// Retrieve the text of the selected items of the list, in list order
+unsafe fn selected_items(list: MutPtr<QListWidget>) -> Vec<String> {
+    (0..list.count())
+        .map(|row| list.item(row))
+        .filter(|item| !item.is_null() && item.is_selected())
+        .map(|item| item.text().to_std_string())
+        .collect()
+}
+
+// Retrieve the text of the selected items of the list, or the default if none are selected
+unsafe fn selected_or_default(list: MutPtr<QListWidget>, default: &str) -> Vec<String> {
+    let selected = selected_items(list);
+    if selected.is_empty() {
+        vec![default.to_string()]
+    } else {
+        selected
+    }
+}
+
+// Select the items of the list named `name`, and only those. The selection is left
+// as is if there are none.
+unsafe fn select_only(mut list: MutPtr<QListWidget>, name: &str) -> bool {
+    let matches = (0..list.count())
+        .map(|row| list.item(row))
+        .filter(|item| item.text().to_std_string() == name)
+        .collect::<Vec<_>>();
+    if matches.is_empty() {
+        return false;
+    }
+    list.clear_selection();
+    for mut item in matches {
+        item.set_selected(true);
+    }
+    true
+}
+
+// Replace the items of the list with the default followed by the supplied items,
+// selecting the default
+unsafe fn set_list_items(mut list: MutPtr<QListWidget>, default: &str, items: Vec<&str>) {
+    list.clear();
+    list.add_item_q_string(&qs(default));
+    for item in items {
+        list.add_item_q_string(&qs(item));
+    }
+    let mut default_item = list.item(0);
+    default_item.set_selected(true);
+}
@@ -0,0 +1,254 @@
+//! Expands the sequences, shots and shot lists chosen in the dialog into the levels
+//! they describe.
+//!
+//! A shot list is a comma or whitespace separated list of entries. Each entry is either
+//! a name or a glob pattern (`*`, `?` and `[...]` are supported), matched case insensitively
+//! against the sequences and shots of the show. Shots may be written as `SHOT`, `SEQ.SHOT`
+//! or `SEQ_SHOT`. An entry matching a sequence selects the sequence, rather than each of
+//! its shots.
+use std::collections::{BTreeSet, HashMap};
+
+/// LevelMap maps a sequence to a list of shots
+pub type LevelMap = HashMap<String, Vec<String>>;
+
+/// The levels described by a shot list
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LevelExpansion {
+    /// The matching levels, sorted
+    pub levels: Vec<String>,
+    /// The entries which did not match any sequence or shot
+    pub unmatched: Vec<String>,
+}
+
+/// Build the level of a sequence
+///
+/// # Arguments
+/// * `show` - The name of the show
+/// * `seq` - The name of the sequence
+///
+/// # Returns
+/// * The level, as `show.seq`
+pub fn seq_level(show: &str, seq: &str) -> String {
+    format!("{}.{}", show, seq)
+}
+
+/// Build the level of a shot
+///
+/// # Arguments
+/// * `show` - The name of the show
+/// * `seq` - The name of the sequence
+/// * `shot` - The name of the shot
+///
+/// # Returns
+/// * The level, as `show.seq.shot`
+pub fn shot_level(show: &str, seq: &str, shot: &str) -> String {
+    format!("{}.{}.{}", show, seq, shot)
+}
+
+/// The text presented for a shot in the dialog's shot list
+///
+/// # Arguments
+/// * `seq` - The name of the sequence
+/// * `shot` - The name of the shot
+///
+/// # Returns
+/// * The label, as `seq.shot`
+pub fn shot_label(seq: &str, shot: &str) -> String {
+    format!("{}.{}", seq, shot)
+}
+
+/// Convert the sequences and shots selected in the dialog into levels. A selected
+/// sequence contributes its selected shots, or the sequence itself if none of its
+/// shots are selected.
+///
+/// # Arguments
+/// * `show` - The name of the show
+/// * `seqs` - The selected sequences
+/// * `shots` - The selected shots, labelled as per `shot_label`
+///
+/// # Returns
+/// * Vec of levels, sorted
+pub fn selection_levels(show: &str, seqs: &[String], shots: &[String]) -> Vec<String> {
+    let mut levels = BTreeSet::new();
+    let mut seqs_with_shots = BTreeSet::new();
+    for label in shots {
+        let mut pieces = label.splitn(2, '.');
+        if let (Some(seq), Some(shot)) = (pieces.next(), pieces.next()) {
+            levels.insert(shot_level(show, seq, shot));
+            seqs_with_shots.insert(seq);
+        }
+    }
+    for seq in seqs {
+        if !seqs_with_shots.contains(seq.as_str()) {
+            levels.insert(seq_level(show, seq));
+        }
+    }
+    levels.into_iter().collect()
+}
+
+/// Expand a shot list against the sequences and shots of the show
+///
+/// # Arguments
+/// * `show` - The name of the show
+/// * `shot_list` - The shot list typed by the user
+/// * `levels` - The sequences and shots of the show
+///
+/// # Returns
+/// * LevelExpansion instance
+pub fn expand_shot_list(show: &str, shot_list: &str, levels: &LevelMap) -> LevelExpansion {
+    let mut matched = BTreeSet::new();
+    let mut unmatched = Vec::new();
+    for entry in shot_list
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+    {
+        let found = expand_entry(show, entry, levels);
+        if found.is_empty() {
+            unmatched.push(entry.to_string());
+        }
+        matched.extend(found);
+    }
+    LevelExpansion {
+        levels: matched.into_iter().collect(),
+        unmatched,
+    }
+}
+
+/// Determine whether the text matches the glob pattern, ignoring case
+///
+/// # Arguments
+/// * `pattern` - The pattern, which may use `*`, `?` and `[...]`
+/// * `text` - The text to test
+///
+/// # Returns
+/// * bool
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    match_from(&pattern, &text)
+}
+
+// the levels matched by a single entry of a shot list
+fn expand_entry(show: &str, entry: &str, levels: &LevelMap) -> Vec<String> {
+    let mut found = Vec::new();
+    for (seq, shots) in levels {
+        if glob_match(entry, seq) {
+            found.push(seq_level(show, seq));
+            continue;
+        }
+        for shot in shots {
+            let names = [
+                shot.clone(),
+                shot_label(seq, shot),
+                format!("{}_{}", seq, shot),
+            ];
+            if names.iter().any(|name| glob_match(entry, name)) {
+                found.push(shot_level(show, seq, shot));
+            }
+        }
+    }
+    found
+}
+
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len()).any(|idx| match_from(&pattern[1..], &text[idx..])),
+        Some('?') => !text.is_empty() && match_from(&pattern[1..], &text[1..]),
+        Some('[') => {
+            let c = match text.first() {
+                Some(c) => *c,
+                None => return false,
+            };
+            match match_class(&pattern[1..], c) {
+                Some((true, len)) => match_from(&pattern[len + 1..], &text[1..]),
+                Some((false, _)) => false,
+                // an unterminated class is a literal `[`
+                None => c == '[' && match_from(&pattern[1..], &text[1..]),
+            }
+        }
+        Some(p) => text.first() == Some(p) && match_from(&pattern[1..], &text[1..]),
+    }
+}
+
+// match `c` against the character class following an opening `[`. Returns whether the
+// class matched, and the length of the class including its closing `]`, or None if the
+// class is never closed. A leading `!` or `^` negates the class.
+fn match_class(class: &[char], c: char) -> Option<(bool, usize)> {
+    let negated = class.first() == Some(&'!') || class.first() == Some(&'^');
+    let mut idx = if negated { 1 } else { 0 };
+    let start = idx;
+    let mut matched = false;
+    while idx < class.len() {
+        let first = class[idx];
+        // a `]` immediately after the opening bracket is a member of the class
+        if first == ']' && idx > start {
+            return Some((matched != negated, idx + 1));
+        }
+        if idx + 2 < class.len() && class[idx + 1] == '-' && class[idx + 2] != ']' {
+            matched |= first <= c && c <= class[idx + 2];
+            idx += 3;
+        } else {
+            matched |= first == c;
+            idx += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level_map() -> LevelMap {
+        let mut levels = LevelMap::new();
+        levels.insert(
+            "RD".to_string(),
+            vec!["0100", "0150", "0200", "0300"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        );
+        levels.insert("AA".to_string(), vec!["0100".to_string()]);
+        levels
+    }
+
+    #[test]
+    fn glob_supports_wildcards_and_classes() {
+        assert!(glob_match("RD_0[12]*", "rd_0150"));
+        assert!(!glob_match("RD_0[12]*", "RD_0300"));
+        assert!(glob_match("RD.0?00", "RD.0200"));
+        assert!(glob_match("[!A]*", "RD"));
+        assert!(!glob_match("[!A]*", "AA"));
+        assert!(glob_match("a[", "A["));
+    }
+
+    #[test]
+    fn shot_list_expands_against_level_map() {
+        let expansion = expand_shot_list("dev01", "RD_0[12]*, AA 0300,missing", &level_map());
+        assert_eq!(
+            expansion.levels,
+            vec![
+                "dev01.AA".to_string(),
+                "dev01.RD.0100".to_string(),
+                "dev01.RD.0150".to_string(),
+                "dev01.RD.0200".to_string(),
+                "dev01.RD.0300".to_string(),
+            ]
+        );
+        assert_eq!(expansion.unmatched, vec!["missing".to_string()]);
+    }
+
+    #[test]
+    fn selected_seqs_without_shots_select_the_seq() {
+        let levels = selection_levels(
+            "dev01",
+            &["AA".to_string(), "RD".to_string()],
+            &["RD.0100".to_string()],
+        );
+        assert_eq!(
+            levels,
+            vec!["dev01.AA".to_string(), "dev01.RD.0100".to_string()]
+        );
+    }
+}
@@ -1,2 +1,4 @@
+pub mod combinations;
 pub(crate) mod inner_vpin_dialog;
+pub mod levels;
 pub mod vpin_dialog;
@@ -1,10 +1,10 @@
+pub use crate::combinations::PinCombination;
 use crate::inner_vpin_dialog::InnerVpinDialog;
 pub use crate::inner_vpin_dialog::LevelMap;
-use crate::inner_vpin_dialog::DEFAULT_SITE;
-use qt_core::{QString, Signal, SlotOfQString};
+use qt_core::{Signal, Slot};
 use qt_widgets::{
-    cpp_core::{CastInto, CppBox, MutPtr, Ptr, Ref},
-    QComboBox, QDialog, QWidget,
+    cpp_core::{CastInto, MutPtr, Ptr},
+    QDialog, QListWidget, QWidget,
 };
 use std::cell::RefCell;
 use std::os::raw::c_int;
@@ -43,16 +43,11 @@ use std::rc::Rc;
 ///         } else {
 ///             println!("roles: any");
 ///         }
-///         if let Some(selected_level) = dialog.selected_level() {
-///             println!("level: {:?}", selected_level);
-///         } else {
-///             println!("level: {}", dialog.show_name());
-///         }
-///         match dialog.selected_site(){
-///             Some(site) => println!(
-///                 "site:  {}", site
-///             ),
-///             None => println!("site:  Any"),
+///         println!("levels: {:?}", dialog.selected_levels());
+///         println!("sites: {:?}", dialog.selected_sites());
+///         println!("platforms: {:?}", dialog.selected_platforms());
+///         for combination in dialog.selected_combinations() {
+///             println!("pin: {}", combination.label());
 ///         }
 ///         dialog.accept();
 ///      }});
@@ -69,7 +64,7 @@ use std::rc::Rc;
 /// ```
 pub struct VpinDialog<'a> {
     dialog: Rc<RefCell<InnerVpinDialog<'a>>>,
-    seq_changed: SlotOfQString<'a>,
+    seqs_changed: Slot<'a>,
 }
 
 impl<'a> VpinDialog<'a> {
@@ -94,18 +89,17 @@ impl<'a> VpinDialog<'a> {
                 parent,
             )));
             let ivd = inner_vpin_dialog.clone();
-            let seq_changed = SlotOfQString::new(move |idx: Ref<QString>| {
-                let sequence = idx.to_std_string();
-                ivd.borrow().set_shots_for_seq(sequence.as_str());
+            let seqs_changed = Slot::new(move || {
+                ivd.borrow().set_shots_for_selected_seqs();
             });
             let dialog = VpinDialog {
                 dialog: inner_vpin_dialog,
-                seq_changed,
+                seqs_changed,
             };
             dialog
-                .seqs_cb()
-                .current_index_changed2()
-                .connect(&dialog.seq_changed);
+                .seqs_list()
+                .item_selection_changed()
+                .connect(&dialog.seqs_changed);
             dialog
         }
     }
@@ -192,22 +186,38 @@ impl<'a> VpinDialog<'a> {
         unsafe { self.dialog.borrow().selected_roles() }
     }
 
-    /// Retrieve an Option wrapped current site, if specified
+    /// Retrieve the selected sites
     ///
     /// # Arguments
     /// * None
     ///
     /// # Returns
-    /// * Some wrapped site name, if specified
-    /// * None, if `any` site specified
-    pub fn selected_site(&self) -> Option<String> {
-        unsafe {
-            let sel_site = self.dialog.borrow().selected_site();
-            if sel_site == DEFAULT_SITE {
-                return None;
-            }
-            Some(sel_site)
-        }
+    /// * Vec of site names. `any` if no site is selected
+    pub fn selected_sites(&self) -> Vec<String> {
+        unsafe { self.dialog.borrow().selected_sites() }
+    }
+
+    /// Retrieve the selected platforms
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Vec of platform names. `any` if no platform is selected
+    pub fn selected_platforms(&self) -> Vec<String> {
+        unsafe { self.dialog.borrow().selected_platforms() }
+    }
+
+    /// Retrieve the combinations of level, role, site and platform the user has left
+    /// checked in the preview. Each combination describes a single pin.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Vec of PinCombination
+    pub fn selected_combinations(&self) -> Vec<PinCombination> {
+        unsafe { self.dialog.borrow().selected_combinations() }
     }
     /// Return the show's name. Unfortunately, we have to disambiguate between
     /// the `show` widget name, and the model
@@ -228,27 +238,29 @@ impl<'a> VpinDialog<'a> {
     {
         self.dialog.borrow().set_show_name(new_name.into());
     }
-    /// Return the a Some wrapped Sequence/shot if the user has activated
-    /// the checkbox and selected a sequence or shot. Otherwise, it returns
-    /// None
+    /// Return the levels the user has chosen, by selecting sequences and shots or
+    /// by typing a shot list. A selected sequence contributes its selected shots, or
+    /// itself if none of its shots are selected. If the user has not activated the
+    /// checkbox, or has not chosen any sequences or shots, the show is returned.
     ///
     /// # Arguments
     /// * None
     ///
     /// # Returns
-    /// * Option<String>
-    pub fn selected_level(&self) -> Option<String> {
-        unsafe { self.dialog.borrow().selected_level() }
+    /// * Vec of levels
+    pub fn selected_levels(&self) -> Vec<String> {
+        unsafe { self.dialog.borrow().selected_levels() }
     }
 
     /// Return the distribution as a String
     pub fn distribution(&self) -> String {
         unsafe { self.dialog.borrow().distribution() }
     }
-    /// Set the distribution name
+    /// Set the distribution presented by the dialog. The dialog only presents the
+    /// distribution; its package and version are tracked by the caller.
     ///
     /// # Arguments
-    /// * distribution - The distribution name
+    /// * distribution - The distribution, as `package-version`
     ///
     /// # Returns
     /// * None
@@ -257,14 +269,6 @@ impl<'a> VpinDialog<'a> {
             self.dialog.borrow().set_distribution(distribution);
         }
     }
-    /// Retrieve the package name as a QString
-    pub fn package_qs(&self) -> CppBox<QString> {
-        unsafe { self.dialog.borrow().package_qs() }
-    }
-    /// Retrieve the package name as a String
-    pub fn package(&self) -> String {
-        unsafe { self.dialog.borrow().package() }
-    }
     /// Load the default stylesheet
     ///
     /// # Arguments
@@ -278,6 +282,19 @@ impl<'a> VpinDialog<'a> {
         }
     }
 
+    /// Set the level, role, site and platform selected as the dialog is populated,
+    /// for instance from the context the user is working in. None restores the
+    /// defaults.
+    ///
+    /// # Arguments
+    /// * `preset` - The PinCombination to select, or None
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_preset(&self, preset: Option<PinCombination>) {
+        self.dialog.borrow().set_preset(preset);
+    }
+
     /// Set the sites. This should be done before calling exec on
     /// the inner dialog.
     ///
@@ -290,6 +307,18 @@ impl<'a> VpinDialog<'a> {
         self.dialog.borrow().set_sites(sites);
     }
 
+    /// Set the platforms. This should be done before calling exec on
+    /// the inner dialog.
+    ///
+    /// # Arguments
+    /// * The platform names as a vector of &str
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_platforms(&self, platforms: Vec<&str>) {
+        self.dialog.borrow().set_platforms(platforms);
+    }
+
     /// set the list of roles. This should before calling exec
     /// on the inner dialog.
     ///
@@ -302,15 +331,15 @@ impl<'a> VpinDialog<'a> {
         self.dialog.borrow().set_roles(roles);
     }
 
-    /// Retrieve a mutable pointer to the sequences QComboBox
+    /// Retrieve a mutable pointer to the sequences QListWidget
     ///
     /// # Arguments
     /// * None
     ///
     /// # Returns
-    /// * MutPtr wrapping the QComboBox for the sequences
-    pub fn seqs_cb(&self) -> MutPtr<QComboBox> {
-        self.dialog.borrow().seqs_cb()
+    /// * MutPtr wrapping the QListWidget for the sequences
+    pub fn seqs_list(&self) -> MutPtr<QListWidget> {
+        self.dialog.borrow().seqs_list()
     }
 
     // /// Given a vector of Strings, set levels
@@ -338,7 +367,7 @@ impl<'a> VpinDialog<'a> {
     // * `levels` - A LevelMap whose keys are Sequences and whose shots are
     //              vectors of shots
     fn set_levels_map(&self, levels: LevelMap) {
-        self.dialog.borrow_mut().set_levels_map(levels);
+        self.dialog.borrow().set_levels_map(levels);
     }
 
     // Helper method that initializes the qt widgets once the level map has been
@@ -1,4 +1,5 @@
 use pbgui_withs::{
+    order::order_diff,
     utility::{create_vlayout, qs},
     WithsList, WithsListConfig,
 };
@@ -93,9 +94,11 @@ fn main() {
         main_ref.layout().add_widget(print_button.into_ptr());
 
         let print_slot: Slot<'static> = Slot::new(move || {
-            for x in wl_c3.borrow().items() {
-                println!("{}", x);
-            }
+            let withs_list = wl_c3.borrow();
+            println!(
+                "{}",
+                order_diff(&withs_list.original_items(), &withs_list.items())
+            );
         });
         bp.pressed().connect(&print_slot);
 
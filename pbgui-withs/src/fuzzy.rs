@@ -0,0 +1,48 @@
+//! Fuzzy matching used to filter the packages offered by the package picker.
+//!
+//! A pattern matches a package if its characters appear in the package name in the
+//! same order, ignoring case. `hpip` matches `houdinipipeline`, for instance.
+
+/// Score the text against the pattern. Consecutive characters, and characters
+/// matched at the start of the text, score higher.
+///
+/// # Arguments
+/// * `pattern` - The pattern typed by the user
+/// * `text` - The text to match against
+///
+/// # Returns
+/// * Some(score) if the pattern matches. An empty pattern matches with a score of 0
+/// * None otherwise
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i32> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for p in pattern.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let idx = next + text[next..].iter().position(|c| *c == p)?;
+        score += 1;
+        if idx == 0 {
+            score += 3;
+        }
+        if previous.map(|prev| prev + 1 == idx).unwrap_or(false) {
+            score += 2;
+        }
+        previous = Some(idx);
+        next = idx + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_characters_in_order() {
+        assert!(fuzzy_score("hpip", "houdinipipeline").is_some());
+        assert!(fuzzy_score("HOU", "houdini").is_some());
+        assert_eq!(fuzzy_score("", "maya"), Some(0));
+        assert_eq!(fuzzy_score("pih", "houdinipipeline"), None);
+        assert!(fuzzy_score("mod", "modelpublish") > fuzzy_score("mod", "layoutpipelne_mod"));
+    }
+}
@@ -1,10 +1,14 @@
 use super::utility::qs;
+use crate::order::{diff_withs, WithState};
 use crate::toolbar::ItemListModeToolbar;
 use crate::traits::*;
 use crate::utility::load_stylesheet;
 use log;
-use qt_core::{q_item_selection_model::SelectionFlag, MatchFlag, QModelIndex, QString};
-use qt_gui::{QStandardItem, QStandardItemModel};
+use qt_core::{
+    q_item_selection_model::SelectionFlag, DropAction, ItemDataRole, MatchFlag, QModelIndex,
+    QString, QVariant,
+};
+use qt_gui::{QBrush, QColor, QStandardItem, QStandardItemModel};
 use qt_widgets::{
     cpp_core::Ref as QRef,
     cpp_core::{CppBox, MutPtr},
@@ -12,10 +16,12 @@ use qt_widgets::{
     q_abstract_item_view::SelectionMode,
     q_action::ActionEvent,
     QAction, QComboBox, QFrame, QHBoxLayout, QLabel, QLayout, QListView, QPushButton, QToolBar,
-    QWidget,
+    QToolButton, QWidget,
 };
 //use rustqt_utils::{as_mut_ref, as_ref, enclose, enclose_all};
 use rustqt_utils::set_stylesheet_from_str;
+use std::cell::RefCell;
+use std::collections::HashMap;
 
 const STYLE_STR: &'static str = include_str!("../resources/withlist.qss");
 
@@ -32,9 +38,15 @@ pub(crate) struct InnerWithsList {
     mode_toolbar: ItemListModeToolbar,
     add_label: MutPtr<QLabel>,
     add_combobox: MutPtr<QComboBox>,
+    picker_button: MutPtr<QToolButton>,
     model: CppBox<QStandardItemModel>,
     view: MutPtr<QListView>,
+    diff_label: MutPtr<QLabel>,
     save_button: MutPtr<QPushButton>,
+    // the items as they were last set, before the user edited them
+    original: RefCell<Vec<String>>,
+    // warnings about individual items, keyed by item
+    warnings: RefCell<HashMap<String, String>>,
 }
 
 impl InnerWithsList {
@@ -54,9 +66,11 @@ impl InnerWithsList {
 
             let mode_toolbar = ItemListModeToolbar::new(&mut main_ptr);
 
-            let (cblabel, cbox) = Self::setup_combobox("ItemCombo", &mut main_ptr);
+            let (cblabel, cbox, picker_button) =
+                Self::setup_combobox("ItemCombo", &mut main_ptr);
 
             let listview_ptr = Self::setup_listview(model.as_mut_ptr(), &mut main_ptr.layout());
+            let diff_label = Self::setup_diff_label(&mut main_ptr.layout());
             //buttons
             let save_button = Self::setup_button("Save", &mut main_ptr.layout());
             /*
@@ -87,8 +101,12 @@ impl InnerWithsList {
                 mode_toolbar,
                 add_label: cblabel,
                 add_combobox: cbox,
+                picker_button,
                 view: listview_ptr,
+                diff_label,
                 save_button,
+                original: RefCell::new(Vec::new()),
+                warnings: RefCell::new(HashMap::new()),
             };
 
             f
@@ -140,6 +158,18 @@ impl InnerWithsList {
         self.add_combobox
     }
 
+    /// Returns a MutPtr to the button which opens the package picker, used to add
+    /// several with packages at once
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * MutPtr<QToolButton>
+    pub(crate) fn picker_button(&self) -> MutPtr<QToolButton> {
+        self.picker_button
+    }
+
     /// Returns a MutPtr to the backging QStandardItemModel
     ///
     /// # Arguments
@@ -211,6 +241,8 @@ impl InnerWithsList {
             let mut model = self.model();
             model.clear();
         }
+        self.original.borrow_mut().clear();
+        self.refresh_diff();
     }
 
     #[allow(dead_code)]
@@ -232,6 +264,111 @@ impl InnerWithsList {
                 self.add_item(item.as_ref());
             }
         }
+        self.set_original_items(self.items());
+    }
+
+    /// Record the items as they were before the user edited them. Ordinarily these
+    /// are the items most recently set.
+    ///
+    /// # Arguments
+    /// * `items` - Vector of the original items
+    ///
+    /// # Returns
+    /// * None
+    pub(crate) fn set_original_items(&self, items: Vec<String>) {
+        *self.original.borrow_mut() = items;
+        self.refresh_diff();
+    }
+
+    /// Set warnings about individual items, replacing any existing warnings. Items
+    /// with a warning are marked in the list, with the warning as their tooltip.
+    ///
+    /// # Arguments
+    /// * `warnings` - Map of item to warning
+    ///
+    /// # Returns
+    /// * None
+    pub(crate) fn set_warnings(&self, warnings: HashMap<String, String>) {
+        *self.warnings.borrow_mut() = warnings;
+        self.refresh_diff();
+    }
+
+    /// Remove any warnings about individual items
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * None
+    pub(crate) fn clear_warnings(&self) {
+        if self.warnings.borrow().is_empty() {
+            return;
+        }
+        self.warnings.borrow_mut().clear();
+        self.refresh_diff();
+    }
+
+    /// Mark the items which were added or moved since the original items were set,
+    /// as well as those with warnings, and summarize the changes, including removed
+    /// items, beneath the list.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * None
+    pub(crate) fn refresh_diff(&self) {
+        unsafe {
+            let diff = diff_withs(&self.original.borrow(), &self.items());
+            let model = self.model();
+            let warnings = self.warnings.borrow();
+            for (row, state) in diff.states.iter().enumerate() {
+                let mut item = model.item_1a(row as i32);
+                if item.is_null() {
+                    continue;
+                }
+                if let Some(warning) = warnings.get(&item.text().to_std_string()) {
+                    let color = QColor::from_rgb_3a(255, 120, 120);
+                    item.set_foreground(&QBrush::from_q_color(&color));
+                    item.set_tool_tip(&qs(warning));
+                    continue;
+                }
+                let (color, tip) = match state {
+                    WithState::Unchanged => {
+                        item.set_data_2a(&QVariant::new(), ItemDataRole::ForegroundRole.to_int());
+                        item.set_tool_tip(&qs(""));
+                        continue;
+                    }
+                    WithState::Added => (QColor::from_rgb_3a(200, 255, 200), "added"),
+                    WithState::Moved => (QColor::from_rgb_3a(255, 220, 130), "moved"),
+                };
+                item.set_foreground(&QBrush::from_q_color(&color));
+                item.set_tool_tip(&qs(tip));
+            }
+            let mut summary = diff.summary();
+            if !warnings.is_empty() {
+                let count = format!("{} warning(s)", warnings.len());
+                summary = if summary.is_empty() {
+                    count
+                } else {
+                    format!("{}, {}", summary, count)
+                };
+            }
+            let mut label = self.diff_label;
+            label.set_text(&qs(summary.as_str()));
+            label.set_visible(!summary.is_empty());
+        }
+    }
+
+    /// Retrieve the items as they were before the user edited them
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Vec<String>
+    pub(crate) fn original_items(&self) -> Vec<String> {
+        self.original.borrow().clone()
     }
 
     /// Add an item to self.
@@ -250,7 +387,105 @@ impl InnerWithsList {
         }
     }
 
-    /// Retrieve a vector of Strings for items
+    /// Insert an item into self at the supplied row. A row past the end of the
+    /// list appends the item.
+    ///
+    /// # Arguments
+    /// * `row` - The row the item will occupy
+    /// * `item` - The name of the item
+    ///
+    /// # Returns
+    /// * The row of the new item
+    pub(crate) fn insert_item_at(&self, row: i32, item: &str) -> i32 {
+        unsafe {
+            let mut model = self.model();
+            let row = row.max(0).min(model.row_count_0a());
+            let mut si = QStandardItem::new();
+            si.set_text(&qs(item));
+            si.set_drop_enabled(false);
+            model.insert_row_int_q_standard_item(row, si.into_ptr());
+            row
+        }
+    }
+
+    /// The row at which an item added by the user should be inserted: just after
+    /// the last selected item, or at the end of the list if nothing is selected.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * The row
+    pub(crate) fn insertion_row(&self) -> i32 {
+        let rows = self.selected_rows();
+        match rows.last() {
+            Some(row) => row + 1,
+            None => unsafe { self.model().row_count_0a() },
+        }
+    }
+
+    /// Move the selected items up or down by one row, keeping them selected.
+    /// Nothing moves if any selected item is already at the edge of the list.
+    ///
+    /// # Arguments
+    /// * `up` - Whether to move the items up (towards the front of the list) or down
+    ///
+    /// # Returns
+    /// * bool indicating whether the items were moved
+    pub(crate) fn move_sel_items(&self, up: bool) -> bool {
+        unsafe {
+            let mut rows = self.selected_rows();
+            let mut model = self.model();
+            let count = model.row_count_0a();
+            let at_edge = match (up, rows.first(), rows.last()) {
+                (_, None, _) | (_, _, None) => true,
+                (true, Some(first), _) => *first == 0,
+                (false, _, Some(last)) => *last == count - 1,
+            };
+            if at_edge {
+                return false;
+            }
+            // move the rows nearest the destination first, so that each row
+            // moves past an unselected neighbor
+            if !up {
+                rows.reverse();
+            }
+            let offset = if up { -1 } else { 1 };
+            for row in &rows {
+                let taken = model.take_row(*row);
+                model.insert_row_int_q_list_of_q_standard_item(row + offset, taken.as_ref());
+            }
+            let mut view = self.view;
+            let mut selection = view.selection_model();
+            selection.clear();
+            for row in &rows {
+                let idx = model.index_2a(row + offset, 0);
+                selection.select_q_model_index_q_flags_selection_flag(
+                    idx.as_ref(),
+                    SelectionFlag::Select.into(),
+                );
+            }
+            let current = model.index_2a(rows[0] + offset, 0);
+            selection.set_current_index(current.as_ref(), SelectionFlag::NoUpdate.into());
+            view.scroll_to_1a(&current);
+            true
+        }
+    }
+
+    // the selected rows, sorted
+    fn selected_rows(&self) -> Vec<i32> {
+        unsafe {
+            let selected = self.view.selection_model().selected_indexes();
+            let mut rows = (0..selected.size())
+                .map(|x| selected.at(x).row())
+                .collect::<Vec<_>>();
+            rows.sort();
+            rows.dedup();
+            rows
+        }
+    }
+
+    /// Retrieve a vector of Strings for items, in the order presented by the view
     ///
     /// # Arguments
     /// * None
@@ -497,18 +732,19 @@ impl InnerWithsList {
         }
     }
 
-    // Given a name and a parent, construct a QComboBox and return it
+    // Given a name and a parent, construct a QComboBox, along with its label and
+    // the button opening the package picker, and return them
     //
     // #Arguments
     // * `name` - Name of the combobox
     // * `parent` - mut reference to the parent widget. Will be used to fetch the layout
     //
     // # Returns
-    // * A MutPtr wrapping the QComboBox
+    // * A tuple of MutPtrs wrapping the QLabel, QComboBox and QToolButton
     fn setup_combobox(
         name: &str,
         mut parent: &mut MutPtr<QWidget>,
-    ) -> (MutPtr<QLabel>, MutPtr<QComboBox>) {
+    ) -> (MutPtr<QLabel>, MutPtr<QComboBox>, MutPtr<QToolButton>) {
         unsafe {
             let mut cb_widget = QFrame::create(&mut parent);
             cb_widget.add_layout(LayoutType::HBoxLayout);
@@ -525,14 +761,21 @@ impl InnerWithsList {
             let cbox_ptr = cbox.as_mut_ptr();
             cb_widget.layout().add_widget(cbox.into_ptr());
 
+            let mut picker_button = QToolButton::new_0a();
+            picker_button.set_text(&qs("..."));
+            picker_button.set_tool_tip(&qs("Choose several packages to add"));
+            picker_button.set_object_name(&qs("WithsPickerButton"));
+            let picker_button_ptr = picker_button.as_mut_ptr();
+            cb_widget.layout().add_widget(picker_button.into_ptr());
+
             let mut layout = cb_widget.layout().dynamic_cast_mut::<QHBoxLayout>();
             if layout.is_null() {
                 log::error!("unable to cast layout to QHBoxLayout");
-                return (cb_label_ptr, cbox_ptr);
+                return (cb_label_ptr, cbox_ptr, picker_button_ptr);
             }
             layout.set_stretch(1, 1);
 
-            (cb_label_ptr, cbox_ptr)
+            (cb_label_ptr, cbox_ptr, picker_button_ptr)
         }
     }
 
@@ -557,6 +800,8 @@ impl InnerWithsList {
             qlv.set_selection_mode(SelectionMode::ExtendedSelection);
             qlv.set_drag_drop_overwrite_mode(false);
             qlv.set_drag_drop_mode(DragDropMode::InternalMove);
+            qlv.set_default_drop_action(DropAction::MoveAction);
+            qlv.set_drop_indicator_shown(true);
             let qlv_ptr = qlv.as_mut_ptr();
             layout.add_widget(qlv.into_ptr());
 
@@ -564,6 +809,18 @@ impl InnerWithsList {
         }
     }
 
+    // set up the label summarizing the changes to the items, hidden until there
+    // are changes
+    unsafe fn setup_diff_label(layout: &mut MutPtr<QLayout>) -> MutPtr<QLabel> {
+        let mut label = QLabel::new();
+        label.set_object_name(&qs("WithsDiffLabel"));
+        label.set_word_wrap(true);
+        label.set_visible(false);
+        let label_ptr = label.as_mut_ptr();
+        layout.add_widget(label.into_ptr());
+        label_ptr
+    }
+
     unsafe fn setup_button(name: &str, layout: &mut MutPtr<QLayout>) -> MutPtr<QPushButton> {
         let mut button = QPushButton::from_q_string(&qs(name));
         let button_ptr = button.as_mut_ptr();
@@ -1,7 +1,10 @@
 pub mod withs_list;
 pub use withs_list::WithsList;
 pub use withs_list::WithsListConfig;
+pub mod fuzzy;
 pub(crate) mod inner_withs_list;
+pub mod order;
+pub(crate) mod package_picker;
 pub(crate) mod toolbar;
 pub mod traits;
 pub mod utility;
@@ -0,0 +1,240 @@
+//! Describes how a list of with packages has changed: which packages were added,
+//! removed, or moved.
+//!
+//! The order of with packages matters for resolution, so a change which only moves
+//! packages around is as significant as one which adds or removes them. A package is
+//! considered moved if its position relative to the other retained packages changed.
+
+const NONE: &str = "none";
+const ARROW: &str = " \u{2192} ";
+
+/// Determine whether the lists hold the same packages in a different order
+///
+/// # Arguments
+/// * `old` - The with packages before the edit
+/// * `new` - The with packages after the edit
+///
+/// # Returns
+/// * bool
+pub fn is_reordered<S: AsRef<str>>(old: &[S], new: &[S]) -> bool {
+    if old.len() != new.len() || old.iter().zip(new).all(|(o, n)| o.as_ref() == n.as_ref()) {
+        return false;
+    }
+    let mut old = old.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let mut new = new.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    old.sort();
+    new.sort();
+    old == new
+}
+
+/// Describe the change from the old to the new list of with packages, as
+/// `old, withs -> new, withs`. An empty list is presented as `none`.
+///
+/// # Arguments
+/// * `old` - The with packages before the edit
+/// * `new` - The with packages after the edit
+///
+/// # Returns
+/// * The description. If the lists are identical, the new list alone
+pub fn order_diff<S: AsRef<str>>(old: &[S], new: &[S]) -> String {
+    let new_str = join(new);
+    if old.len() == new.len() && old.iter().zip(new).all(|(o, n)| o.as_ref() == n.as_ref()) {
+        return new_str;
+    }
+    format!("{}{}{}", join(old), ARROW, new_str)
+}
+
+/// Describe the new list of with packages, followed by how it differs from the old
+/// list, as `ocio, maya, mtoa (1 moved)`. Where the old list is presented alongside,
+/// this conveys the same change as `order_diff` without repeating it.
+///
+/// # Arguments
+/// * `old` - The with packages before the edit
+/// * `new` - The with packages after the edit
+///
+/// # Returns
+/// * The description. If the lists are identical, the new list alone
+pub fn annotated_diff<S: AsRef<str>>(old: &[S], new: &[S]) -> String {
+    let diff = diff_withs(old, new);
+    if !diff.is_changed() {
+        return join(new);
+    }
+    format!("{} ({})", join(new), diff.summary())
+}
+
+/// The state of a with package in the edited list, relative to the original list
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WithState {
+    Unchanged,
+    Added,
+    Moved,
+}
+
+/// The difference between the original and edited lists of with packages
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct WithsDiff {
+    /// The state of each package of the edited list, in order
+    pub states: Vec<WithState>,
+    /// The packages of the original list which are no longer present
+    pub removed: Vec<String>,
+}
+
+impl WithsDiff {
+    /// Determine whether the edited list differs from the original
+    pub fn is_changed(&self) -> bool {
+        !self.removed.is_empty() || self.states.iter().any(|s| *s != WithState::Unchanged)
+    }
+
+    /// Summarize the difference, eg `1 added, 2 moved, removed: ocio`
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * The summary. Empty if the lists do not differ
+    pub fn summary(&self) -> String {
+        let count = |state| self.states.iter().filter(|s| **s == state).count();
+        let mut pieces = Vec::new();
+        for (state, label) in &[(WithState::Added, "added"), (WithState::Moved, "moved")] {
+            let cnt = count(*state);
+            if cnt > 0 {
+                pieces.push(format!("{} {}", cnt, label));
+            }
+        }
+        if !self.removed.is_empty() {
+            pieces.push(format!("removed: {}", self.removed.join(", ")));
+        }
+        pieces.join(", ")
+    }
+}
+
+/// Compare the edited list of with packages against the original
+///
+/// # Arguments
+/// * `old` - The with packages before the edit
+/// * `new` - The with packages after the edit
+///
+/// # Returns
+/// * WithsDiff instance
+pub fn diff_withs<S: AsRef<str>>(old: &[S], new: &[S]) -> WithsDiff {
+    let old = old.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let new = new.iter().map(|x| x.as_ref()).collect::<Vec<_>>();
+    let removed = old
+        .iter()
+        .filter(|x| !new.contains(x))
+        .map(|x| x.to_string())
+        .collect();
+    // the retained packages, in their original and edited order
+    let old_kept = old.iter().filter(|x| new.contains(x)).collect::<Vec<_>>();
+    let new_kept = new.iter().filter(|x| old.contains(x)).collect::<Vec<_>>();
+    let in_order = longest_common_subsequence(&old_kept, &new_kept);
+    let mut kept_idx = 0;
+    let states = new
+        .iter()
+        .map(|x| {
+            if !old.contains(x) {
+                return WithState::Added;
+            }
+            let state = if in_order[kept_idx] {
+                WithState::Unchanged
+            } else {
+                WithState::Moved
+            };
+            kept_idx += 1;
+            state
+        })
+        .collect();
+    WithsDiff { states, removed }
+}
+
+// flag the members of `new` which belong to a longest common subsequence of `old`
+// and `new`. These are the packages which kept their relative order.
+fn longest_common_subsequence<T: PartialEq>(old: &[T], new: &[T]) -> Vec<bool> {
+    let (rows, cols) = (old.len(), new.len());
+    let mut lengths = vec![vec![0usize; cols + 1]; rows + 1];
+    for i in (0..rows).rev() {
+        for j in (0..cols).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut flags = vec![false; cols];
+    let (mut i, mut j) = (0, 0);
+    while i < rows && j < cols {
+        if old[i] == new[j] {
+            flags[j] = true;
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    flags
+}
+
+/// Join the with packages for presentation. An empty list is presented as `none`.
+///
+/// # Arguments
+/// * `items` - The with packages
+///
+/// # Returns
+/// * The packages, separated by commas
+pub fn join<S: AsRef<str>>(items: &[S]) -> String {
+    if items.is_empty() {
+        return NONE.to_string();
+    }
+    items.iter().map(|x| x.as_ref()).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_reordering_and_edits() {
+        let old = ["maya", "mtoa", "ocio"];
+        assert!(is_reordered(&old, &["ocio", "maya", "mtoa"]));
+        assert!(!is_reordered(&old, &["maya", "mtoa", "gizmos"]));
+        assert!(!is_reordered(&old, &old));
+        assert_eq!(
+            order_diff(&old, &["ocio", "maya", "mtoa"]),
+            "maya, mtoa, ocio \u{2192} ocio, maya, mtoa"
+        );
+        assert_eq!(order_diff(&old, &old), "maya, mtoa, ocio");
+        assert_eq!(order_diff::<&str>(&[], &["ocio"]), "none \u{2192} ocio");
+    }
+
+    #[test]
+    fn diff_flags_added_moved_and_removed() {
+        let old = ["maya", "mtoa", "ocio", "gizmos"];
+        let diff = diff_withs(&old, &["ocio", "maya", "mtoa", "vray"]);
+        assert_eq!(
+            diff.states,
+            vec![
+                WithState::Moved,
+                WithState::Unchanged,
+                WithState::Unchanged,
+                WithState::Added
+            ]
+        );
+        assert_eq!(diff.removed, vec!["gizmos".to_string()]);
+        assert_eq!(diff.summary(), "1 added, 1 moved, removed: gizmos");
+        assert!(!diff_withs(&["maya"], &["maya"]).is_changed());
+    }
+
+    #[test]
+    fn annotated_diff_describes_the_reordering() {
+        let old = ["maya", "mtoa", "ocio"];
+        assert_eq!(
+            annotated_diff(&old, &["ocio", "maya", "mtoa"]),
+            "ocio, maya, mtoa (1 moved)"
+        );
+        assert_eq!(annotated_diff(&old, &old), "maya, mtoa, ocio");
+        assert_eq!(annotated_diff::<&str>(&["ocio"], &[]), "none (removed: ocio)");
+    }
+}
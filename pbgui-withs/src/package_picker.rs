@@ -0,0 +1,190 @@
+//! The PackagePicker is a popup dialog presenting the known packages as a checkable
+//! list, so that several with packages may be added in one step. The list may be
+//! narrowed with a fuzzy filter, and reloaded from the database via the reload button,
+//! whose `clicked` signal is left to the client to connect.
+use crate::fuzzy::fuzzy_score;
+use crate::utility::{create_hlayout, create_vlayout, qs};
+use qt_core::{CheckState, ItemFlag, QString, SlotOfQString};
+use qt_widgets::{
+    cpp_core::{MutPtr, Ref as QRef},
+    q_dialog::DialogCode,
+    q_dialog_button_box::StandardButton,
+    QDialog, QDialogButtonBox, QLabel, QLineEdit, QListWidget, QListWidgetItem, QPushButton,
+    QWidget,
+};
+use std::collections::HashSet;
+
+/// Dialog used to choose several packages at once
+pub(crate) struct PackagePicker<'l> {
+    dialog: MutPtr<QDialog>,
+    filter: MutPtr<QLineEdit>,
+    list: MutPtr<QListWidget>,
+    reload_button: MutPtr<QPushButton>,
+    filter_changed: SlotOfQString<'l>,
+}
+
+impl<'l> PackagePicker<'l> {
+    /// Create the dialog, parented to the supplied widget
+    ///
+    /// # Arguments
+    /// * `parent` - The parent widget, which assumes ownership of the dialog
+    ///
+    /// # Returns
+    /// * PackagePicker instance
+    pub(crate) fn create(parent: MutPtr<QWidget>) -> Self {
+        unsafe {
+            let mut dialog = QDialog::new_1a(parent);
+            dialog.set_object_name(&qs("PackagePickerDialog"));
+            dialog.set_window_title(&qs("Add With Packages"));
+            dialog.set_modal(true);
+            dialog.resize_2a(320, 480);
+            let mut layout = create_vlayout();
+            layout.set_spacing(6);
+            layout.set_contents_margins_4a(10, 10, 10, 10);
+
+            let mut filter_layout = create_hlayout();
+            filter_layout.set_spacing(6);
+            filter_layout.add_widget(QLabel::from_q_string(&qs("Filter")).into_ptr());
+            let mut filter = QLineEdit::new();
+            filter.set_object_name(&qs("PackagePickerFilter"));
+            filter.set_placeholder_text(&qs("eg hpip for houdinipipeline"));
+            filter.set_clear_button_enabled(true);
+            let filter_ptr = filter.as_mut_ptr();
+            filter_layout.add_widget(filter.into_ptr());
+            let mut reload_button = QPushButton::from_q_string(&qs("Reload"));
+            reload_button.set_object_name(&qs("PackagePickerReloadButton"));
+            reload_button.set_tool_tip(&qs("Reload the packages from the database"));
+            let reload_button_ptr = reload_button.as_mut_ptr();
+            filter_layout.add_widget(reload_button.into_ptr());
+            layout.add_layout_1a(filter_layout.into_ptr());
+
+            let mut list = QListWidget::new_0a();
+            list.set_object_name(&qs("PackagePickerList"));
+            let list_ptr = list.as_mut_ptr();
+            layout.add_widget(list.into_ptr());
+
+            let mut button_box = QDialogButtonBox::from_q_flags_standard_button(
+                StandardButton::Ok | StandardButton::Cancel,
+            );
+            let buttons = button_box.as_mut_ptr();
+            layout.add_widget(button_box.into_ptr());
+            dialog.set_layout(layout.into_ptr());
+
+            let dialog_ptr = dialog.into_ptr();
+            let picker = Self {
+                dialog: dialog_ptr,
+                filter: filter_ptr,
+                list: list_ptr,
+                reload_button: reload_button_ptr,
+                filter_changed: SlotOfQString::new(move |text: QRef<QString>| {
+                    apply_filter(list_ptr, &text.to_std_string());
+                }),
+            };
+            filter_ptr.text_changed().connect(&picker.filter_changed);
+            buttons.accepted().connect(dialog_ptr.slot_accept());
+            buttons.rejected().connect(dialog_ptr.slot_reject());
+            picker
+        }
+    }
+
+    /// Retrieve a MutPtr to the reload button
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * MutPtr to QPushButton
+    pub(crate) fn reload_button(&self) -> MutPtr<QPushButton> {
+        self.reload_button
+    }
+
+    /// Replace the packages offered by the picker. Packages which were checked, and
+    /// remain available, stay checked. The filter is reapplied.
+    ///
+    /// # Arguments
+    /// * `packages` - Vector of package names
+    ///
+    /// # Returns
+    /// * None
+    pub(crate) fn set_packages<I>(&self, packages: &[I])
+    where
+        I: AsRef<str>,
+    {
+        unsafe {
+            let checked = self.checked().into_iter().collect::<HashSet<_>>();
+            let mut list = self.list;
+            list.clear();
+            for package in packages {
+                let package = package.as_ref();
+                if package.is_empty() {
+                    continue;
+                }
+                let mut item = QListWidgetItem::new();
+                item.set_text(&qs(package));
+                item.set_flags(ItemFlag::ItemIsEnabled | ItemFlag::ItemIsUserCheckable);
+                item.set_check_state(if checked.contains(package) {
+                    CheckState::Checked
+                } else {
+                    CheckState::Unchecked
+                });
+                list.add_item_q_list_widget_item(item.into_ptr());
+            }
+            apply_filter(list, &self.filter.text().to_std_string());
+        }
+    }
+
+    /// Execute the dialog, with every package unchecked and the filter cleared
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * The packages checked by the user, in the order they are listed. Empty if
+    /// the dialog was cancelled
+    pub(crate) fn exec(&self) -> Vec<String> {
+        unsafe {
+            let mut list = self.list;
+            for row in 0..list.count() {
+                list.item(row).set_check_state(CheckState::Unchecked);
+            }
+            let mut filter = self.filter;
+            filter.clear();
+            filter.set_focus_0a();
+            let mut dialog = self.dialog;
+            if dialog.exec() != DialogCode::Accepted.to_int() {
+                return Vec::new();
+            }
+            self.checked()
+        }
+    }
+
+    // the names of the checked packages, in list order
+    fn checked(&self) -> Vec<String> {
+        unsafe {
+            (0..self.list.count())
+                .map(|row| self.list.item(row))
+                .filter(|item| item.check_state() == CheckState::Checked)
+                .map(|item| item.text().to_std_string())
+                .collect()
+        }
+    }
+}
+
+// hide the packages which do not match the filter, and make the best match current,
+// so that the space bar checks it
+unsafe fn apply_filter(mut list: MutPtr<QListWidget>, text: &str) {
+    let mut best: Option<(i32, i32)> = None;
+    for row in 0..list.count() {
+        let mut item = list.item(row);
+        let score = fuzzy_score(text, &item.text().to_std_string());
+        item.set_hidden(score.is_none());
+        if let Some(score) = score {
+            if best.map(|(_, best)| score > best).unwrap_or(true) {
+                best = Some((row, score));
+            }
+        }
+    }
+    if let Some((row, _)) = best {
+        list.set_current_row_1a(row);
+    }
+}
@@ -1,14 +1,16 @@
 use super::utility::qs;
 use crate::inner_withs_list::InnerWithsList;
+use crate::package_picker::PackagePicker;
 pub use crate::traits::*;
 use log;
-use qt_core::{Key, QModelIndex, QString, Slot};
+use qt_core::{Key, QModelIndex, QString, Slot, SlotOfQModelIndexIntInt};
 use qt_gui::{q_key_sequence::StandardKey, QKeySequence, QStandardItem, QStandardItemModel};
 use qt_widgets::{
     cpp_core::CastInto, cpp_core::MutPtr, cpp_core::Ref as QRef, QListView, QPushButton, QShortcut,
     QWidget,
 };
 pub use rustqt_utils::{as_mut_ref, as_ref, enclose, enclose_all};
+use std::collections::HashMap;
 use std::rc::Rc;
 
 /// Struct which holds configuration for the WithsList
@@ -18,6 +20,10 @@ pub struct WithsListConfig {
     find_shortcut: String,
     /// The add shortcut as a string
     add_shortcut: String,
+    /// The shortcut moving the selected items up, as a string
+    move_up_shortcut: String,
+    /// The shortcut moving the selected items down, as a string
+    move_down_shortcut: String,
 }
 
 impl Default for WithsListConfig {
@@ -25,6 +31,8 @@ impl Default for WithsListConfig {
         Self {
             find_shortcut: "Ctrl+f".to_string(),
             add_shortcut: "Ctrl+a".to_string(),
+            move_up_shortcut: "Ctrl+Up".to_string(),
+            move_down_shortcut: "Ctrl+Down".to_string(),
         }
     }
 }
@@ -35,17 +43,24 @@ impl Default for WithsListConfig {
 /// the actual items backing data, and various slots
 pub struct WithsList<'l> {
     inner: Rc<InnerWithsList>,
+    picker: Rc<PackagePicker<'l>>,
     enter_shortcut: MutPtr<QShortcut>,
     delete_shortcut: MutPtr<QShortcut>,
     cut_shortcut: MutPtr<QShortcut>,
     find_shortcut: MutPtr<QShortcut>,
     add_shortcut: MutPtr<QShortcut>,
+    move_up_shortcut: MutPtr<QShortcut>,
+    move_down_shortcut: MutPtr<QShortcut>,
     rm: Slot<'l>,
     find_mode: Slot<'l>,
     add_mode: Slot<'l>,
     enter_sc: Slot<'l>,
     find_shortcut_slot: Slot<'l>,
     add_shortcut_slot: Slot<'l>,
+    move_up_slot: Slot<'l>,
+    move_down_slot: Slot<'l>,
+    show_picker: Slot<'l>,
+    rows_changed: SlotOfQModelIndexIntInt<'l>,
 }
 
 impl<'l> WithsList<'l> {
@@ -60,6 +75,7 @@ impl<'l> WithsList<'l> {
     pub fn new(parent: impl CastInto<MutPtr<QWidget>>, config: WithsListConfig) -> WithsList<'l> {
         unsafe {
             let inner = Rc::new(InnerWithsList::new(parent.cast_into()));
+            let picker = Rc::new(PackagePicker::create(inner.main()));
 
             // shortcuts
             let enter_key_seq = QKeySequence::from_int(Key::KeyReturn.to_int());
@@ -77,6 +93,12 @@ impl<'l> WithsList<'l> {
             let key_seq = QKeySequence::from_q_string(&qs(&config.add_shortcut));
             let add_shortcut = QShortcut::new_2a(key_seq.as_ref(), inner.main());
 
+            let key_seq = QKeySequence::from_q_string(&qs(&config.move_up_shortcut));
+            let move_up_shortcut = QShortcut::new_2a(key_seq.as_ref(), inner.main());
+
+            let key_seq = QKeySequence::from_q_string(&qs(&config.move_down_shortcut));
+            let move_down_shortcut = QShortcut::new_2a(key_seq.as_ref(), inner.main());
+
             // Slots
             let inner_view = inner.view();
             let rm_slot = Slot::new(enclose_all! { () (mut inner_view) move || {
@@ -129,9 +151,16 @@ impl<'l> WithsList<'l> {
                         return;
                     }
 
-                    inner.add_item_to(text.to_std_string().as_str());
+                    // insert the new item after the selection, so that the user may
+                    // choose where it lands in the order
+                    let row = inner.insert_item_at(
+                        inner.insertion_row(),
+                        text.to_std_string().as_str()
+                    );
                     cbox_ptr.clear_edit_text();
-                    listview_ptr.scroll_to_bottom();
+                    let idx = model_ptr.index_2a(row, 0);
+                    inner.select_item(idx.as_ref());
+                    listview_ptr.scroll_to_1a(&idx);
 
                 }},
             );
@@ -141,31 +170,69 @@ impl<'l> WithsList<'l> {
             let add_shortcut_slot = Slot::new(enclose! { (inner) move || {
                 inner.set_add_mode();
             }});
+            let move_up_slot = Slot::new(enclose! { (inner) move || {
+                inner.move_sel_items(true);
+            }});
+            let move_down_slot = Slot::new(enclose! { (inner) move || {
+                inner.move_sel_items(false);
+            }});
+            // add the packages chosen in the picker after the selection, in one step,
+            // skipping those already in the list
+            let show_picker = Slot::new(enclose! { (inner, picker) move || {
+                let existing = inner.items();
+                let mut row = inner.insertion_row();
+                for package in picker.exec() {
+                    if existing.contains(&package) {
+                        continue;
+                    }
+                    inner.insert_item_at(row, package.as_str());
+                    row += 1;
+                }
+            }});
+            // dragging, moving, adding and removing items all insert or remove rows.
+            // warnings about the items no longer apply once the items change
+            let rows_changed = SlotOfQModelIndexIntInt::new(enclose! { (inner)
+            move |_parent: QRef<QModelIndex>, _first: i32, _last: i32| {
+                inner.clear_warnings();
+                inner.refresh_diff();
+            }});
+            let picker_button = inner.picker_button();
             let cblabel = inner.add_label();
             let f = Self {
                 inner,
+                picker,
                 enter_shortcut: enter_shortcut.into_ptr(),
                 delete_shortcut: delete_shortcut.into_ptr(),
                 cut_shortcut: cut_shortcut.into_ptr(),
                 find_shortcut: find_shortcut.into_ptr(),
                 add_shortcut: add_shortcut.into_ptr(),
+                move_up_shortcut: move_up_shortcut.into_ptr(),
+                move_down_shortcut: move_down_shortcut.into_ptr(),
                 rm: rm_slot,
 
                 find_mode: Slot::new(as_mut_ref! { (cblabel) enclose! { (cbox_ptr) move || {
                     let mut cbox_ptr = cbox_ptr;
+                    let mut picker_button = picker_button;
                     cbox_ptr.set_enabled(true);
+                    picker_button.set_enabled(false);
                     if let Some(mut cblabel) = cblabel {cblabel.set_text(&qs("Find Item"))};
                 }}}),
 
                 add_mode: Slot::new(as_mut_ref! {(cblabel) enclose! { ( cbox_ptr) move || {
                     let mut cbox_ptr = cbox_ptr;
+                    let mut picker_button = picker_button;
                     cbox_ptr.set_enabled(true);
+                    picker_button.set_enabled(true);
                     if let Some(mut cblabel) = cblabel {cblabel.set_text(&qs("Add Item"))};
                 }}}),
 
                 enter_sc,
                 find_shortcut_slot,
                 add_shortcut_slot,
+                move_up_slot,
+                move_down_slot,
+                show_picker,
+                rows_changed,
             };
             // Wire up signals and slots
             f.inner()
@@ -179,6 +246,11 @@ impl<'l> WithsList<'l> {
             f.cut_shortcut.activated().connect(&f.rm);
             f.find_shortcut.activated().connect(&f.find_shortcut_slot);
             f.add_shortcut.activated().connect(&f.add_shortcut_slot);
+            f.move_up_shortcut.activated().connect(&f.move_up_slot);
+            f.move_down_shortcut.activated().connect(&f.move_down_slot);
+            picker_button.clicked().connect(&f.show_picker);
+            model_ptr.rows_inserted().connect(&f.rows_changed);
+            model_ptr.rows_removed().connect(&f.rows_changed);
             f
         }
     }
@@ -223,7 +295,7 @@ impl<'l> WithsList<'l> {
 
     #[allow(dead_code)]
     /// Sets the contents to items, removing any pre-existing
-    /// items.
+    /// items. The items are recorded as the original items.
     ///
     /// # Arguments
     /// * `items` - a Vector of &str or String
@@ -239,6 +311,71 @@ impl<'l> WithsList<'l> {
         for item in items {
             inner.add_item(item.as_ref());
         }
+        inner.set_original_items(inner.items());
+    }
+
+    /// Replace the original items, against which the user's edits are compared,
+    /// without changing the items presented. Used when the items presented have
+    /// already been edited.
+    ///
+    /// # Arguments
+    /// * `items` - Vector of the original items
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_original_items<I>(&self, items: Vec<I>)
+    where
+        I: AsRef<str>,
+    {
+        self.inner()
+            .set_original_items(items.iter().map(|x| x.as_ref().to_string()).collect());
+    }
+
+    /// Get the items as they were last set, before the user added, removed or
+    /// reordered any of them.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Vector of String
+    pub fn original_items(&self) -> Vec<String> {
+        self.inner().original_items()
+    }
+
+    /// Set warnings about individual items, replacing any existing warnings. Items
+    /// with a warning are marked in the list, with the warning as their tooltip.
+    /// Warnings are cleared as soon as the items change.
+    ///
+    /// # Arguments
+    /// * `warnings` - Map of item to warning
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_warnings(&self, warnings: HashMap<String, String>) {
+        self.inner().set_warnings(warnings);
+    }
+
+    /// Move the selected items up by one row, towards the front of the list
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * bool indicating whether the items were moved
+    pub fn move_sel_items_up(&self) -> bool {
+        self.inner().move_sel_items(true)
+    }
+
+    /// Move the selected items down by one row, towards the back of the list
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * bool indicating whether the items were moved
+    pub fn move_sel_items_down(&self) -> bool {
+        self.inner().move_sel_items(false)
     }
 
     /// Set the stylesheet to the internal stylesheet
@@ -284,6 +421,19 @@ impl<'l> WithsList<'l> {
     pub fn save_button(&self) -> MutPtr<QPushButton> {
         self.inner().save_button()
     }
+
+    /// Retrieve a MutPtr to the package picker's reload button. Clients connect
+    /// its `clicked` signal in order to reload the packages, which are then set
+    /// via `set_cb_items`.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * MutPtr to QPushButton
+    pub fn reload_button(&self) -> MutPtr<QPushButton> {
+        self.picker.reload_button()
+    }
     /// add an item to the pulldown
     ///
     /// # Arguments
@@ -352,7 +502,8 @@ impl<'l> WithsList<'l> {
         }
     }
 
-    /// Get the items as a vector of Strings.
+    /// Get the items as a vector of Strings, in the order presented by the list,
+    /// which the user may have changed by dragging or moving items.
     ///
     /// # Arguments
     /// * None
@@ -364,7 +515,8 @@ impl<'l> WithsList<'l> {
     }
 
     #[allow(dead_code)]
-    /// Set comboboc items, replacing any extant items
+    /// Set comboboc items, replacing any extant items. The package picker
+    /// offers the same items.
     ///
     /// # Arguments
     /// * `items` - Vector of items
@@ -375,6 +527,7 @@ impl<'l> WithsList<'l> {
     where
         I: AsRef<str>,
     {
+        self.picker.set_packages(&items);
         self.inner().set_cb_items(items);
     }
 
@@ -8,7 +8,7 @@
 //! user applies the stored changes via the save button found between the versionpin
 //! table and the versionpin changes table below it (ultimately triggering the
 //! save_versionpin_changes slot_function ).
-use crate::change_type::{Change, ChangeType};
+use crate::change_type::{Change, ChangeType, PinCoord};
 use packybara::types::IdType;
 use std::cell::Cell;
 use std::cell::RefCell;
@@ -25,6 +25,16 @@ pub struct PinChangesCache {
     /// that we are tracking in the versionpin_changes table. We use this value to identify, for example,
     /// if a suggested change ultimately matches the original value (we don't have to change the db in this case)
     original_version: RefCell<HashMap<IdType, String>>,
+    /// A mapping of versionpin_id to the original with packages of the versionpin, in order.
+    /// Used to present the withs before and after a ChangeWiths.
+    original_withs: RefCell<HashMap<IdType, Vec<String>>>,
+    /// A mapping of versionpin_id to the level of the versionpin. Changes which only reference
+    /// an existing versionpin by id rely on this to determine the level they affect.
+    level: RefCell<HashMap<IdType, String>>,
+    /// A mapping of versionpin_id to the package and coordinate of the versionpin. The withs
+    /// changes of the pins at a coordinate are checked for with-cycles against one another,
+    /// whether or not the pins are presented in the versionpin table.
+    pin_coord: RefCell<HashMap<IdType, PinCoord>>,
     /// A vector of Change instances - These are the core change requests, with enough information to
     /// construct a change db request.
     change_vec: RefCell<Vec<Change>>,
@@ -59,6 +69,9 @@ impl PinChangesCache {
         Self {
             pkgcoord_index: RefCell::new(HashMap::new()),
             original_version: RefCell::new(HashMap::new()),
+            original_withs: RefCell::new(HashMap::new()),
+            level: RefCell::new(HashMap::new()),
+            pin_coord: RefCell::new(HashMap::new()),
             change_vec: RefCell::new(Vec::new()),
             changes: RefCell::new(HashMap::new()),
             changes_row: RefCell::new(HashMap::new()),
@@ -70,6 +83,9 @@ impl PinChangesCache {
     pub fn reset(&self) {
         self.pkgcoord_index.borrow_mut().clear();
         self.original_version.borrow_mut().clear();
+        self.original_withs.borrow_mut().clear();
+        self.level.borrow_mut().clear();
+        self.pin_coord.borrow_mut().clear();
         self.change_vec.borrow_mut().clear();
         self.changes.borrow_mut().clear();
         self.changes_row.borrow_mut().clear();
@@ -147,6 +163,105 @@ impl PinChangesCache {
         }
     }
 
+    /// Cache the original with packages of a versionpin
+    ///
+    /// # Argument
+    ///
+    /// * `vpin_id` - The verionpin id to use as a key
+    /// * `withs` - The with packages to cache, in order
+    pub fn cache_original_withs(&self, vpin_id: IdType, withs: Vec<String>) {
+        self.original_withs.borrow_mut().insert(vpin_id, withs);
+    }
+
+    /// Get the original with packages of the versionpin at the given id
+    ///
+    /// # Arguments
+    ///
+    /// * `vpin_id` - The versionpin id we want to use to look up the original
+    ///               withs for
+    ///
+    /// # Returns
+    ///
+    /// * Some of the withs if vpin_id exists
+    /// * None otherwise
+    pub fn orig_withs_for(&self, vpin_id: IdType) -> Option<Vec<String>> {
+        self.original_withs.borrow().get(&vpin_id).cloned()
+    }
+
+    /// Cache the level of a versionpin
+    ///
+    /// # Arguments
+    ///
+    /// * `vpin_id` - The versionpin id to use as a key
+    /// * `level` - The level of the versionpin
+    pub fn cache_level<S>(&self, vpin_id: IdType, level: S)
+    where
+        S: Into<String>,
+    {
+        self.level.borrow_mut().insert(vpin_id, level.into());
+    }
+
+    /// Get the level of the versionpin with the given id
+    ///
+    /// # Arguments
+    ///
+    /// * `vpin_id` - The versionpin id
+    ///
+    /// # Returns
+    ///
+    /// * Some of level string if vpin_id has been cached
+    /// * None otherwise
+    pub fn level_for(&self, vpin_id: IdType) -> Option<String> {
+        self.level.borrow().get(&vpin_id).cloned()
+    }
+
+    /// Cache the package and coordinate of a versionpin
+    ///
+    /// # Arguments
+    ///
+    /// * `vpin_id` - The versionpin id to use as a key
+    /// * `coord` - The package and coordinate of the versionpin
+    pub fn cache_pin_coord(&self, vpin_id: IdType, coord: PinCoord) {
+        self.pin_coord.borrow_mut().insert(vpin_id, coord);
+    }
+
+    /// Get the package and coordinate of the versionpin with the given id
+    ///
+    /// # Arguments
+    ///
+    /// * `vpin_id` - The versionpin id
+    ///
+    /// # Returns
+    ///
+    /// * Some of PinCoord if vpin_id has been cached
+    /// * None otherwise
+    pub fn pin_coord_for(&self, vpin_id: IdType) -> Option<PinCoord> {
+        self.pin_coord.borrow().get(&vpin_id).cloned()
+    }
+
+    /// Get the level affected by the supplied change. Changes that carry their
+    /// level report it directly; the rest are looked up via `level_for`.
+    ///
+    /// # Arguments
+    ///
+    /// * `change` - Reference to the Change
+    ///
+    /// # Returns
+    ///
+    /// * Some of level string if known
+    /// * None otherwise
+    pub fn level_for_change(&self, change: &Change) -> Option<String> {
+        match change {
+            Change::AddDistribution { level, .. } | Change::ChangePkgCoord { level, .. } => {
+                Some(level.clone())
+            }
+            Change::ChangeDistribution { vpin_id, .. } | Change::ChangeWiths { vpin_id, .. } => {
+                self.level_for(*vpin_id)
+            }
+            Change::Unknown => None,
+        }
+    }
+
     /// Retrieve the change instance stored at the vpinchange table row.
     /// Note that this has to clone under the hood.
     ///
@@ -285,6 +400,63 @@ impl PinChangesCache {
         self.changes_row.borrow_mut().insert(change_idx, idx);
     }
 
+    /// Look up the row in the pinchanges table of a pending new pin at the supplied
+    /// coordinate. The coordinates are read from the changes currently in the table,
+    /// rather than indexed separately, so that they cannot fall out of step with it.
+    ///
+    /// # Arguments
+    ///
+    /// * `coord` - The PinCoord to look up
+    ///
+    /// # Returns
+    ///
+    /// * Some row if a new pin is pending at the coordinate
+    /// * None otherwise
+    pub fn pending_pin_at(&self, coord: &PinCoord) -> Option<i32> {
+        let change_vec = self.change_vec.borrow();
+        self.changes
+            .borrow()
+            .iter()
+            .find(|(_, idx)| change_vec[**idx].pin_coord().as_ref() == Some(coord))
+            .map(|(row, _)| *row)
+    }
+
+    /// Look up the fake id of a pending new pin at the supplied coordinate, so that
+    /// changes to the new pin, such as its withs, may be staged against it.
+    ///
+    /// # Arguments
+    ///
+    /// * `coord` - The PinCoord to look up
+    ///
+    /// # Returns
+    ///
+    /// * Some fake id if a new pin is pending at the coordinate
+    /// * None otherwise
+    pub fn pending_pin_id(&self, coord: &PinCoord) -> Option<IdType> {
+        let row = self.pending_pin_at(coord)?;
+        self.pkgcoord_index
+            .borrow()
+            .iter()
+            .find(|(id, idx)| **id < 0 && **idx == row)
+            .map(|(id, _)| *id)
+    }
+
+    /// Look up the coordinate of the pending new pin with the supplied fake id
+    ///
+    /// # Arguments
+    ///
+    /// * `vpin_id` - The fake id of the new pin
+    ///
+    /// # Returns
+    ///
+    /// * Some PinCoord if the new pin is pending
+    /// * None otherwise
+    pub fn pending_pin_coord(&self, vpin_id: IdType) -> Option<PinCoord> {
+        self.index(vpin_id)
+            .and_then(|row| self.change_at(row))
+            .and_then(|change| change.pin_coord())
+    }
+
     /// Inserts a distribution's id and index into the cache
     ///
     /// # Argument
@@ -315,6 +487,7 @@ impl PinChangesCache {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::distribution::Distribution;
     #[test]
     fn cache_change_adds_key() {
         let change = Change::ChangeDistribution {
@@ -388,6 +561,64 @@ mod tests {
         assert_eq!(cache.row_count(), 1);
     }
     #[test]
+    fn pending_pin_is_indexed_by_coordinate() {
+        let cache = PinChangesCache::new();
+        cache.cache_change(Change::AddDistribution {
+            distribution: Distribution::new("maya", "2020.1").unwrap(),
+            level: "dev01".to_string(),
+            role: "model".to_string(),
+            platform: "any".to_string(),
+            site: "any".to_string(),
+        });
+        let coord = PinCoord::new("maya", "dev01", "model", "any", "any");
+        assert_eq!(cache.pending_pin_at(&coord), Some(0));
+        let other = PinCoord::new("maya", "dev01", "anim", "any", "any");
+        assert_eq!(cache.pending_pin_at(&other), None);
+        // replacing the change at the row drops its coordinate
+        cache.cache_change_at(
+            Change::ChangeDistribution {
+                vpin_id: 1,
+                new_dist_id: 2,
+            },
+            0,
+        );
+        assert_eq!(cache.pending_pin_at(&coord), None);
+        cache.cache_change(Change::AddDistribution {
+            distribution: Distribution::new("maya", "2020.1").unwrap(),
+            level: "dev01".to_string(),
+            role: "model".to_string(),
+            platform: "any".to_string(),
+            site: "any".to_string(),
+        });
+        assert_eq!(cache.pending_pin_at(&coord), Some(1));
+        cache.reset();
+        assert_eq!(cache.pending_pin_at(&coord), None);
+    }
+    #[test]
+    fn pending_pin_is_found_by_fake_id() {
+        let cache = PinChangesCache::new();
+        let id = cache.next_fake_row_id();
+        cache.cache_dist(id, cache.row_count());
+        cache.cache_change(Change::AddDistribution {
+            distribution: Distribution::new("maya", "2020.1").unwrap(),
+            level: "dev01.rd".to_string(),
+            role: "light".to_string(),
+            platform: "any".to_string(),
+            site: "any".to_string(),
+        });
+        let coord = PinCoord::new("maya", "dev01.rd", "light", "any", "any");
+        assert_eq!(cache.pending_pin_id(&coord), Some(id));
+        assert_eq!(cache.pending_pin_coord(id), Some(coord));
+        // withs staged against the new pin do not hide it
+        cache.cache_change(Change::ChangeWiths {
+            vpin_id: id,
+            withs: vec!["mtoa".to_string()],
+        });
+        assert_eq!(cache.pending_pin_coord(id).map(|c| c.package), Some("maya".to_string()));
+        let other = PinCoord::new("maya", "dev01.rd", "anim", "any", "any");
+        assert_eq!(cache.pending_pin_id(&other), None);
+    }
+    #[test]
     fn fake_row_works() {
         let cache = PinChangesCache::new();
         let row = cache.next_fake_row_id();
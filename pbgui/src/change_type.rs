@@ -1,6 +1,7 @@
 //! Provides the Change enum which models proposed versionpin changes, as
 //! well as the ChangeType enum, which provides a companion enum that simplifies
 //! Change to an enum of descriminants
+use crate::distribution::Distribution;
 use crate::utility::qs;
 pub use num_enum::{IntoPrimitive, UnsafeFromPrimitive};
 use packybara::types::IdType;
@@ -26,7 +27,7 @@ pub enum Change {
         new_dist_id: IdType,
     },
     AddDistribution {
-        distribution: String,
+        distribution: Distribution,
         level: String,
         role: String,
         platform: String,
@@ -47,6 +48,37 @@ pub enum Change {
     Unknown,
 }
 
+/// The package coordinate of a pin, along with its package. No two pins may share
+/// the same PinCoord, regardless of the version they pin.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct PinCoord {
+    pub package: String,
+    pub level: String,
+    pub role: String,
+    pub platform: String,
+    pub site: String,
+}
+
+impl PinCoord {
+    /// New up a PinCoord
+    pub fn new<P, L, R, PL, S>(package: P, level: L, role: R, platform: PL, site: S) -> Self
+    where
+        P: Into<String>,
+        L: Into<String>,
+        R: Into<String>,
+        PL: Into<String>,
+        S: Into<String>,
+    {
+        Self {
+            package: package.into(),
+            level: level.into(),
+            role: role.into(),
+            platform: platform.into(),
+            site: site.into(),
+        }
+    }
+}
+
 fn calculate_hash<T: Hash>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
     t.hash(&mut s);
@@ -66,19 +98,45 @@ impl Change {
     pub fn id(&self) -> u64 {
         match self {
             Change::ChangeDistribution { vpin_id, .. } => *vpin_id as u64,
+            // the version is deliberately left out, as two pins of the same
+            // package at the same coordinate collide
+            Change::AddDistribution { .. } => calculate_hash(
+                &self
+                    .pin_coord()
+                    .expect("unable to get pin coordinate of AddDistribution"),
+            ),
+            Change::ChangePkgCoord { vpin_id, .. } => *vpin_id as u64,
+            Change::ChangeWiths { vpin_id, .. } => *vpin_id as u64,
+            Change::Unknown => panic!("unable to retrieve id for unknown type"),
+        }
+    }
+
+    /// Retrieve the PinCoord of a change which adds a new pin
+    ///
+    /// # Arguments
+    ///
+    /// * None
+    ///
+    /// # Returns
+    ///
+    /// * Some(PinCoord) for AddDistribution
+    /// * None for all other variants
+    pub fn pin_coord(&self) -> Option<PinCoord> {
+        match self {
             Change::AddDistribution {
                 distribution,
                 level,
                 role,
                 platform,
                 site,
-            } => {
-                let val = format!("{}-{}-{}-{}-{}", distribution, level, role, platform, site);
-                calculate_hash(&val)
-            }
-            Change::ChangePkgCoord { vpin_id, .. } => *vpin_id as u64,
-            Change::ChangeWiths { vpin_id, .. } => *vpin_id as u64,
-            Change::Unknown => panic!("unable to retrieve id for unknown type"),
+            } => Some(PinCoord::new(
+                distribution.package(),
+                level.as_str(),
+                role.as_str(),
+                platform.as_str(),
+                site.as_str(),
+            )),
+            _ => None,
         }
     }
 
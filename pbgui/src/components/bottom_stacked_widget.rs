@@ -1,5 +1,7 @@
 use super::bottom_context_widget;
+use super::pin_history::PinHistory;
 use super::revision_changes_table;
+use super::revisions_filter::RevisionsFilter;
 use super::revisions_table;
 use super::versionpin_changes_table;
 use crate::utility::{create_hlayout, create_vlayout, qs};
@@ -28,6 +30,7 @@ pub fn create_bottom_stacked_widget<'a>(
 ) -> (
     MutPtr<QTableWidget>,
     MutPtr<QTableWidget>,
+    RevisionsFilter,
     MutPtr<QTableWidget>,
     LogWin<'a>,
     MutPtr<QPushButton>,
@@ -35,6 +38,8 @@ pub fn create_bottom_stacked_widget<'a>(
     MutPtr<QToolButton>,
     MutPtr<QToolButton>,
     MutPtr<QToolButton>,
+    MutPtr<QToolButton>,
+    PinHistory,
     MutPtr<QPushButton>,
     MutPtr<QStackedWidget>,
     CppBox<QIcon>,
@@ -81,6 +86,14 @@ pub fn create_bottom_stacked_widget<'a>(
 
         let log_button_ptr = log_button.as_mut_ptr();
         top_hlayout.add_widget(log_button.into_ptr());
+
+        // pin history button
+        let action = menubar
+            .view_action_at_idx(10)
+            .expect("unable to get action from menubar");
+        let mut pin_history_button = create_toolbutton(action, false);
+        let pin_history_button_ptr = pin_history_button.as_mut_ptr();
+        top_hlayout.add_widget(pin_history_button.into_ptr());
         top_hlayout.add_stretch_0a();
 
         pc_vlayout_ptr.add_layout_1a(top_hlayout.into_ptr());
@@ -139,8 +152,9 @@ pub fn create_bottom_stacked_widget<'a>(
         //nothing in it
         controls.push(pg2_context_widget);
         //
-        // Add revisions table
+        // Add the filter bar and revisions table
         //
+        let revisions_filter = RevisionsFilter::create(&mut pg2_layout_ptr);
         let mut revisions_widget = QWidget::new_0a();
         let mut rsplitter = QSplitter::new();
         rsplitter.set_orientation(Orientation::Horizontal);
@@ -198,6 +212,10 @@ pub fn create_bottom_stacked_widget<'a>(
         log_ctrls_button.set_checkable(true);
         log_layout_ptr.add_widget(log_ctrls_button.into_ptr());
         controls.push(log_widget);
+        //
+        // add the history of the selected versionpin
+        //
+        let pin_history = PinHistory::create(&mut stacked_ptr, &mut controls);
 
         // add the bottom_context_widget which gives us the ablitity
         // to add controls per page
@@ -205,6 +223,7 @@ pub fn create_bottom_stacked_widget<'a>(
         (
             pinchanges_ptr,
             revisions_table_ptr,
+            revisions_filter,
             changes_table_ptr,
             log_win,
             save_button_ptr,
@@ -212,6 +231,8 @@ pub fn create_bottom_stacked_widget<'a>(
             pinchanges_button_ptr,
             history_button_ptr,
             log_button_ptr,
+            pin_history_button_ptr,
+            pin_history,
             log_ctrls_button_ptr,
             controls_widget_ptr,
             mode_icon,
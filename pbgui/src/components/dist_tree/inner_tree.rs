@@ -1,16 +1,29 @@
-use qt_core::{QAbstractItemModel, QModelIndex, QSize, QString, WidgetAttribute};
+use crate::constants::{DIST_PACKAGE_ROLE, DIST_VERSION_ROLE};
+use crate::tree_search::TreeSearch;
+use qt_core::{
+    DropAction, ItemDataRole, QAbstractItemModel, QModelIndex, QSize, QString, QVariant,
+    WidgetAttribute,
+};
 use qt_gui::q_icon::{Mode, State};
 use qt_gui::QIcon;
-use qt_gui::{QStandardItem, QStandardItemModel};
+use qt_gui::{QBrush, QColor, QStandardItem, QStandardItemModel};
 use qt_widgets::{
     cpp_core::{CastInto, CppBox, DynamicCast, MutPtr, StaticUpcast},
-    q_abstract_item_view::EditTrigger,
+    q_abstract_item_view::{DragDropMode, EditTrigger},
     q_header_view::ResizeMode,
     QComboBox, QFrame, QLabel, QLayout, QLineEdit, QPushButton, QTreeView, QWidget,
 };
 use rustqt_utils::{create_hlayout, create_vlayout, qs, set_stylesheet_from_str, ToQStringOwned};
+use std::cell::RefCell;
+use std::collections::HashSet;
 
 const STYLE_STR: &str = include_str!("../../../resources/tree.qss");
+// the maximum number of packages expanded by a search for versions. Each expansion
+// loads the distributions of the package from the database
+const MAX_AUTO_EXPAND: usize = 20;
+/// The tooltip of the package filter, describing its syntax
+pub(crate) const FILTER_TOOLTIP: &str = "Filter packages by name, glob (hou*) or regex \
+    (/^nuke/). Append @versions (maya@>=2020,<2022) to expand the matching distributions";
 
 /// A struct holding the QTreeView and providing a simple Api, mirrored
 /// by the parent.
@@ -21,6 +34,8 @@ pub(crate) struct InnerTreeView {
     filter_frame: MutPtr<QFrame>,
     filter: MutPtr<QLineEdit>,
     view: MutPtr<QTreeView>,
+    search: RefCell<Option<TreeSearch>>,
+    auto_expanded: RefCell<HashSet<i32>>,
 }
 
 impl InnerTreeView {
@@ -58,6 +73,11 @@ impl InnerTreeView {
             treeview_ptr.set_items_expandable(true);
             treeview_ptr.set_uniform_row_heights(true);
             treeview_ptr.set_header_hidden(true);
+            // distributions may be dragged onto the versionpin table. They are copied,
+            // never moved out of the tree
+            treeview_ptr.set_drag_enabled(true);
+            treeview_ptr.set_drag_drop_mode(DragDropMode::DragOnly);
+            treeview_ptr.set_default_drop_action(DropAction::CopyAction);
 
             //parent_widget.layout().add_widget(treeview.into_ptr());
             layout_ptr.add_widget(treeview.into_ptr());
@@ -79,6 +99,8 @@ impl InnerTreeView {
                 filter_frame: filter_frame_ptr,
                 filter,
                 view: treeview_ptr,
+                search: RefCell::new(None),
+                auto_expanded: RefCell::new(HashSet::new()),
             }
         }
     }
@@ -156,6 +178,7 @@ impl InnerTreeView {
             item.set_text(&input.to_qstring());
             item.set_icon(&icon);
             item.set_editable(false);
+            item.set_drag_enabled(false);
             parent.append_row_q_standard_item(item.into_ptr());
             model.set_row_count(row_count + 1);
         }
@@ -174,6 +197,7 @@ impl InnerTreeView {
 
     /// Given a vector of a type that implements ToQstringOwned, append a distribution
     pub(crate) fn set_packages<T: ToQStringOwned>(&self, inputs: Vec<T>) {
+        self.auto_expanded.borrow_mut().clear();
         unsafe {
             let mut model = self.model();
             let mut parent = model.invisible_root_item();
@@ -187,10 +211,12 @@ impl InnerTreeView {
                 item.set_text(&txt);
                 item.set_icon(&icon);
                 item.set_editable(false);
+                item.set_drag_enabled(false);
                 // add one fake item to force qt to draw a
                 let mut child = QStandardItem::new();
                 child.set_text(&qs(""));
                 child.set_editable(false);
+                child.set_drag_enabled(false);
                 item.append_row_q_standard_item(child.into_ptr());
                 parent.append_row_q_standard_item(item.into_ptr());
             }
@@ -215,6 +241,7 @@ impl InnerTreeView {
             let txt = child.to_qstring();
             item.set_text(&txt);
             item.set_editable(false);
+            item.set_drag_enabled(false);
             let mut parent = parent;
             parent.append_row_q_standard_item(item.into_ptr());
         }
@@ -286,15 +313,18 @@ impl InnerTreeView {
                 let txt = child.to_qstring();
                 item.set_text(&txt);
                 item.set_editable(false);
+                item.set_drag_enabled(false);
                 // now we set a single child
                 if add_empty_gchild {
                     let mut child_item = QStandardItem::new();
                     child_item.set_text(&qs(""));
                     child_item.set_editable(false);
+                    child_item.set_drag_enabled(false);
                     item.append_row_q_standard_item(child_item.into_ptr());
                 }
                 let mut icon_item = QStandardItem::new();
                 icon_item.set_editable(false);
+                icon_item.set_drag_enabled(false);
                 parent.append_row_q_standard_item(item.into_ptr());
                 parent.set_child_3a(cnt as i32, 1, icon_item.into_ptr());
                 //cnt += 1;
@@ -302,6 +332,34 @@ impl InnerTreeView {
         }
     }
 
+    /// Allow the distributions of a package to be dragged onto the versionpin table,
+    /// recording the package and version of each in the DIST_PACKAGE_ROLE and
+    /// DIST_VERSION_ROLE
+    ///
+    /// # Arguments
+    /// * `package` - The item of the package, whose children are its distributions
+    ///
+    /// # Returns
+    /// * None
+    pub(crate) fn set_dists_draggable(&self, package: MutPtr<QStandardItem>) {
+        unsafe {
+            let package_name = package.text().to_std_string();
+            for row in 0..package.row_count() {
+                let mut child = package.child_2a(row, 0);
+                let version = child.text().to_std_string();
+                if version.is_empty() {
+                    continue;
+                }
+                let package_var = QVariant::from_q_string(&qs(package_name.as_str()));
+                child.set_data_2a(&package_var, DIST_PACKAGE_ROLE);
+                let version_var = QVariant::from_q_string(&qs(version));
+                child.set_data_2a(&version_var, DIST_VERSION_ROLE);
+                child.set_drag_enabled(true);
+                child.set_tool_tip(&qs("Drag onto the versionpin table to pin"));
+            }
+        }
+    }
+
     /// Clear the current selection
     ///
     /// # Arguments
@@ -365,6 +423,106 @@ impl InnerTreeView {
         }
     }
 
+    /// Apply the text of the package filter to the tree, hiding the packages which
+    /// do not match and highlighting those which do. When the filter includes
+    /// versions, the matching packages are expanded, loading their distributions
+    /// as needed, and the matching distributions are highlighted in turn.
+    ///
+    /// # Arguments
+    /// * `text` - The text of the package filter
+    ///
+    /// # Returns
+    /// * Ok(()) if the filter was applied
+    /// * Err(regex::Error) if the filter is not a valid regular expression. The tree
+    /// is left as is
+    pub(crate) fn apply_search(&self, text: &str) -> Result<(), regex::Error> {
+        let search = TreeSearch::parse(text)?;
+        let expand_versions = search.as_ref().map(|s| s.has_versions()).unwrap_or(false);
+        self.search.replace(search);
+        let mut expanded = HashSet::new();
+        let mut matched_cnt = 0;
+        unsafe {
+            let model = self.model();
+            let mut view = self.view;
+            for row in 0..model.row_count_0a() {
+                let item = model.item_2a(row, 0);
+                let matched = match self.search.borrow().as_ref() {
+                    Some(search) => search.matches_package(&item.text().to_std_string()),
+                    None => true,
+                };
+                self.set_row_hidden(row, !matched);
+                Self::set_highlighted(item, matched && self.search.borrow().is_some());
+                self.apply_version_search(item);
+                if matched && expand_versions {
+                    matched_cnt += 1;
+                    if expanded.len() < MAX_AUTO_EXPAND {
+                        expanded.insert(row);
+                    }
+                }
+            }
+            if matched_cnt > MAX_AUTO_EXPAND {
+                log::info!(
+                    "Expanded the first {} of {} matching packages",
+                    MAX_AUTO_EXPAND,
+                    matched_cnt
+                );
+            }
+            // collapse the packages expanded by the previous search which no longer match
+            for row in self.auto_expanded.borrow().difference(&expanded) {
+                let idx = model.index_2a(*row, 0);
+                view.collapse(idx.as_ref());
+            }
+            // expanding a package whose distributions are not loaded yet requests them
+            for row in &expanded {
+                let idx = model.index_2a(*row, 0);
+                view.expand(idx.as_ref());
+            }
+        }
+        self.auto_expanded.replace(expanded);
+        Ok(())
+    }
+
+    /// Apply the versions of the current filter to the distributions of a package,
+    /// hiding the distributions which do not match and highlighting those which do.
+    /// This is a no-op for a package whose distributions are not loaded.
+    ///
+    /// # Arguments
+    /// * `package` - The item of the package
+    ///
+    /// # Returns
+    /// * None
+    pub(crate) fn apply_version_search(&self, package: MutPtr<QStandardItem>) {
+        unsafe {
+            let search = self.search.borrow();
+            let search = search.as_ref().filter(|search| {
+                search.has_versions() && search.matches_package(&package.text().to_std_string())
+            });
+            let parent = package.index();
+            let mut view = self.view;
+            for row in 0..package.row_count() {
+                let child = package.child_2a(row, 0);
+                let version = child.text().to_std_string();
+                // the placeholder of a package whose distributions are not loaded yet
+                if version.is_empty() {
+                    continue;
+                }
+                let matched = search.map(|s| s.matches_version(&version)).unwrap_or(true);
+                view.set_row_hidden(row, parent.as_ref(), !matched);
+                Self::set_highlighted(child, matched && search.is_some());
+            }
+        }
+    }
+
+    // highlight an item matched by the filter, or restore its default color
+    unsafe fn set_highlighted(mut item: MutPtr<QStandardItem>, highlighted: bool) {
+        if highlighted {
+            let color = QColor::from_rgb_3a(255, 200, 90);
+            item.set_foreground(&QBrush::from_q_color(&color));
+        } else {
+            item.set_data_2a(&QVariant::new(), ItemDataRole::ForegroundRole.to_int());
+        }
+    }
+
     // Create a new `CppBox`'ed QFrame instance
     unsafe fn new_qframe() -> CppBox<QFrame> {
         let mut qf = QFrame::new_0a();
@@ -380,6 +538,8 @@ impl InnerTreeView {
         let mut qle = QLineEdit::new();
         qle.set_attribute_2a(WidgetAttribute::WAMacShowFocusRect, false);
         qle.set_object_name(&qs("PackageFilter"));
+        qle.set_placeholder_text(&qs("eg maya@>=2020, hou*, /^nuke/"));
+        qle.set_tool_tip(&qs(FILTER_TOOLTIP));
         let qle_ptr = qle.as_mut_ptr();
         parent.layout().add_widget(qle.into_ptr());
         qle_ptr
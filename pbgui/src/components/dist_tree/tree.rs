@@ -1,4 +1,4 @@
-use super::inner_tree::InnerTreeView;
+use super::inner_tree::{InnerTreeView, FILTER_TOOLTIP};
 use crate::messaging::outgoing::opackages_tree::OPackagesTree;
 use crate::messaging::OMsg;
 use crate::messaging::Sender;
@@ -8,7 +8,7 @@ use qt_widgets::{
     cpp_core::{MutPtr, Ref, StaticUpcast},
     QPushButton, QWidget,
 };
-use rustqt_utils::{enclose, ToQStringOwned};
+use rustqt_utils::{enclose, qs, ToQStringOwned};
 use std::rc::Rc;
 
 /// DistributionTreeView provides a tree of packages -> versions -> platforms
@@ -40,23 +40,18 @@ impl<'a> DistributionTreeView<'a> {
         unsafe {
             let treeview = Rc::new(InnerTreeView::create(parent_widget));
 
-            //let tv = treeview.clone();
             let filter_slot =
                 SlotOfQString::new(enclose! { (treeview) move |new_str: Ref<QString>| {
-                    let model_ptr = treeview.model();
-                    if new_str.to_std_string() == "" {
-                        for cnt in (0..model_ptr.row_count_0a()).rev() {
-                            treeview.set_row_hidden(cnt,  false)
+                    let mut filter = treeview.filter();
+                    match treeview.apply_search(&new_str.to_std_string()) {
+                        Ok(_) => {
+                            filter.set_style_sheet(&qs(""));
+                            filter.set_tool_tip(&qs(FILTER_TOOLTIP));
                         }
-                    } else {
-                        for cnt in (0..model_ptr.row_count_0a()).rev() {
-                            let item = model_ptr.item_2a(cnt, 0);
-                            let txt = item.text();
-                            if txt.contains_q_string(new_str) {
-                                treeview.set_row_hidden(cnt, false)
-                            } else {
-                                treeview.set_row_hidden(cnt, true)
-                            }
+                        Err(err) => {
+                            filter.set_style_sheet(&qs("color: rgb(255,120,120);"));
+                            let tip = format!("Invalid regular expression: {}", err);
+                            filter.set_tool_tip(&qs(tip));
                         }
                     }
                 }});
@@ -30,6 +30,7 @@ pub struct LeftToolBarActions {
     _view_vpin_changes_icon: CppBox<QIcon>,
     pub log_changes: MutPtr<QAction>,
     _log_changes_icon: CppBox<QIcon>,
+    pub view_pin_history: MutPtr<QAction>,
 }
 
 impl LeftToolBarActions {
@@ -52,6 +53,7 @@ impl LeftToolBarActions {
         view_vpin_changes_icon: CppBox<QIcon>,
         log_changes: CppBox<QAction>,
         log_changes_icon: CppBox<QIcon>,
+        view_pin_history: CppBox<QAction>,
     ) -> Self {
         unsafe {
             Self {
@@ -73,6 +75,7 @@ impl LeftToolBarActions {
                 _view_vpin_changes_icon: view_vpin_changes_icon,
                 log_changes: log_changes.into_ptr(),
                 _log_changes_icon: log_changes_icon,
+                view_pin_history: view_pin_history.into_ptr(),
             }
         }
     }
@@ -309,6 +312,13 @@ pub fn create(
         left_toolbar.add_action(log_action.as_mut_ptr());
         view_menu.add_action(log_action.as_mut_ptr());
 
+        // pin history. only offered by the view menu and the bottom pane
+        let mut view_pin_history_action =
+            QAction::from_q_string_q_object(&qs("pin history"), bottom_mode_action_group_ptr);
+        view_pin_history_action.set_tool_tip(&qs("Show the history of the selected versionpin"));
+        view_pin_history_action.set_checkable(true);
+        view_menu.add_action(view_pin_history_action.as_mut_ptr());
+
         main_window.add_tool_bar_tool_bar_area_q_tool_bar(
             ToolBarArea::LeftToolBarArea,
             left_toolbar.into_ptr(),
@@ -333,6 +343,7 @@ pub fn create(
             view_vpin_changes_icon,
             log_action,
             log_icon,
+            view_pin_history_action,
         )
     }
 }
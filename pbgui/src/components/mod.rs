@@ -17,14 +17,24 @@ pub(crate) mod dist_tree;
 pub(crate) mod left_toolbar;
 /// Creates the WithsList struct, which provides the withs list widget
 pub(crate) mod package_withs_list;
+/// The PackagesXmlDialog previews a packages.xml, and its changes, before it is written
+pub(crate) mod packages_xml_dialog;
 /// Creates the DistributionTreeView
 pub(crate) mod packages_tree;
+/// The PinHistory page lists the changes made to the selected versionpin
+pub(crate) mod pin_history;
 /// Part of the history widget that shows changes for a particular revision
 pub(crate) mod revision_changes_table;
+/// The RevisionsFilter bar narrows the revisions presented in the history
+pub(crate) mod revisions_filter;
 /// Creates the Revisions QTableWdiget
 pub(crate) mod revisions_table;
+/// The SaveReviewDialog presents pending changes, grouped by level, for confirmation prior to saving
+pub(crate) mod save_review_dialog;
 /// Utility function to help set up table headers. Should be moved to utilities
 pub(crate) mod table_headers;
+/// The VersionPickerDialog presents the versions of a package, newest first, along with their metadata
+pub(crate) mod version_picker_dialog;
 /// Models a row of versionpin change data. Should eb moved up
 pub(crate) mod versionpin_changes_row;
 /// Creates the table tracking staged versionpin changes
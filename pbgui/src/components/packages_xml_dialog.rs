@@ -0,0 +1,187 @@
+//! The PackagesXmlDialog previews a packages.xml before it is written, presenting the
+//! lines it changes in the file it replaces. The file is only written once the user
+//! accepts the dialog.
+use crate::packages_xml::{condense, diff_lines, diff_stats, DiffLine};
+use crate::utility::{create_vlayout, qs};
+use qt_core::Signal;
+use qt_gui::q_font_database::SystemFont;
+use qt_gui::QFontDatabase;
+use qt_widgets::{
+    cpp_core::MutPtr, q_dialog_button_box::StandardButton, q_text_edit::LineWrapMode, QDialog,
+    QDialogButtonBox, QLabel, QTextEdit, QWidget,
+};
+use std::cell::RefCell;
+
+/// The number of unchanged lines presented around each change
+const CONTEXT_LINES: usize = 3;
+
+/// A packages.xml awaiting confirmation
+#[derive(Debug, PartialEq, Clone)]
+pub struct PendingPackagesXml {
+    /// The show the file is exported for
+    pub show: String,
+    /// The path the file is to be written to
+    pub output: String,
+    /// The contents of the file
+    pub xml: String,
+}
+
+/// Dialog used to preview a packages.xml prior to writing it
+pub struct PackagesXmlDialog {
+    dialog: MutPtr<QDialog>,
+    summary: MutPtr<QLabel>,
+    diff_view: MutPtr<QTextEdit>,
+    buttons: MutPtr<QDialogButtonBox>,
+    pending: RefCell<Option<PendingPackagesXml>>,
+}
+
+impl PackagesXmlDialog {
+    /// Create the dialog, parented to the supplied widget
+    ///
+    /// # Arguments
+    /// * `parent` - The parent widget, which assumes ownership of the dialog
+    ///
+    /// # Returns
+    /// * PackagesXmlDialog instance
+    pub fn create(parent: MutPtr<QWidget>) -> Self {
+        unsafe {
+            let mut dialog = QDialog::new_1a(parent);
+            dialog.set_object_name(&qs("PackagesXmlDialog"));
+            dialog.set_window_title(&qs("Preview packages.xml"));
+            dialog.set_modal(true);
+            dialog.resize_2a(800, 600);
+            let mut layout = create_vlayout();
+            layout.set_spacing(6);
+            layout.set_contents_margins_4a(10, 10, 10, 10);
+
+            let mut summary = QLabel::new();
+            summary.set_object_name(&qs("PackagesXmlSummary"));
+            summary.set_word_wrap(true);
+            let summary_ptr = summary.as_mut_ptr();
+            layout.add_widget(summary.into_ptr());
+
+            let mut diff_view = QTextEdit::new();
+            diff_view.set_object_name(&qs("PackagesXmlDiff"));
+            diff_view.set_read_only(true);
+            diff_view.set_line_wrap_mode(LineWrapMode::NoWrap);
+            diff_view.set_font(&QFontDatabase::system_font(SystemFont::FixedFont));
+            let diff_view_ptr = diff_view.as_mut_ptr();
+            layout.add_widget(diff_view.into_ptr());
+
+            let mut button_box = QDialogButtonBox::from_q_flags_standard_button(
+                StandardButton::Save | StandardButton::Cancel,
+            );
+            let buttons = button_box.as_mut_ptr();
+            layout.add_widget(button_box.into_ptr());
+            dialog.set_layout(layout.into_ptr());
+
+            let dialog_ptr = dialog.into_ptr();
+            buttons.accepted().connect(dialog_ptr.slot_accept());
+            buttons.rejected().connect(dialog_ptr.slot_reject());
+            Self {
+                dialog: dialog_ptr,
+                summary: summary_ptr,
+                diff_view: diff_view_ptr,
+                buttons,
+                pending: RefCell::new(None),
+            }
+        }
+    }
+
+    /// Return the dialog's accepted signal, emitted once the user has confirmed
+    /// that the packages.xml should be written.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Signal that sends `()`
+    pub fn accepted(&self) -> Signal<()> {
+        unsafe { self.dialog.accepted() }
+    }
+
+    /// Take the packages.xml awaiting confirmation
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Some(PendingPackagesXml) if the dialog was populated
+    /// * None otherwise
+    pub fn take_pending(&self) -> Option<PendingPackagesXml> {
+        self.pending.borrow_mut().take()
+    }
+
+    /// Present the changes the packages.xml makes to the file on disk, if any, and
+    /// execute the dialog.
+    ///
+    /// # Arguments
+    /// * `pending` - The exported packages.xml
+    /// * `existing` - The contents of the file it would replace, if there is one
+    ///
+    /// # Returns
+    /// * None
+    pub fn exec(&self, pending: PendingPackagesXml, existing: Option<String>) {
+        unsafe {
+            let mut summary = self.summary;
+            let diff = match existing {
+                Some(existing) => {
+                    let diff = diff_lines(existing.as_str(), pending.xml.as_str());
+                    let (added, removed) = diff_stats(&diff);
+                    summary.set_text(&qs(format!(
+                        "Replace {} with the packages.xml for {}: {} line(s) added, {} removed.",
+                        pending.output, pending.show, added, removed
+                    )));
+                    condense(diff, CONTEXT_LINES)
+                }
+                None => {
+                    summary.set_text(&qs(format!(
+                        "Create {} with the packages.xml for {}.",
+                        pending.output, pending.show
+                    )));
+                    diff_lines("", pending.xml.as_str())
+                }
+            };
+            let mut diff_view = self.diff_view;
+            diff_view.set_html(&qs(diff_html(&diff)));
+            let mut save_button = self.buttons.button(StandardButton::Save);
+            save_button.set_focus_0a();
+            self.pending.replace(Some(pending));
+            let mut dialog = self.dialog;
+            dialog.exec();
+        }
+    }
+}
+
+// Present the difference as html, coloring added and removed lines
+fn diff_html(diff: &[DiffLine]) -> String {
+    let line = |prefix: &str, text: &str, color: &str| {
+        format!(
+            "<span style=\"color:{}\">{} {}</span>",
+            color,
+            prefix,
+            escape_html(text)
+        )
+    };
+    let lines = diff
+        .iter()
+        .map(|diff_line| match diff_line {
+            DiffLine::Same(text) => line(" ", text, "#b0b0b0"),
+            DiffLine::Added(text) => line("+", text, "#60c060"),
+            DiffLine::Removed(text) => line("-", text, "#e06060"),
+            DiffLine::Skipped(count) => line(
+                " ",
+                format!("... {} unchanged line(s) ...", count).as_str(),
+                "#707070",
+            ),
+        })
+        .collect::<Vec<_>>();
+    format!("<pre>{}</pre>", lines.join("\n"))
+}
+
+// escape the characters of xml which would otherwise be read as html
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,195 @@
+use crate::constants::*;
+use crate::history::PinHistoryEntry;
+use crate::table_headers;
+use crate::utility::{create_hlayout, create_vlayout, qs};
+use packybara::types::IdType;
+use qt_core::{AlignmentFlag, QFlags, QString, QVariant};
+use qt_widgets::{
+    cpp_core::{CppBox, MutPtr},
+    q_abstract_item_view::{EditTrigger, SelectionBehavior, SelectionMode},
+    q_header_view::ResizeMode,
+    QLabel, QPushButton, QStackedWidget, QTableWidget, QTableWidgetItem, QWidget,
+};
+use std::cell::Cell;
+
+/// The page of the bottom stacked widget presenting the changes made to the selected
+/// versionpin, along with the revisions making them.
+pub struct PinHistory {
+    label: MutPtr<QLabel>,
+    table: MutPtr<QTableWidget>,
+    goto_revision_btn: MutPtr<QPushButton>,
+    vpin_id: Cell<Option<IdType>>,
+}
+
+impl PinHistory {
+    /// Create the pin history page, adding it to the stacked widget, and its
+    /// controls to the supplied controls
+    ///
+    /// # Arguments
+    /// * `stacked` - The bottom stacked widget
+    /// * `controls` - The context controls of each page of the stacked widget
+    ///
+    /// # Returns
+    /// * PinHistory instance
+    pub fn create(
+        stacked: &mut MutPtr<QStackedWidget>,
+        controls: &mut Vec<CppBox<QWidget>>,
+    ) -> Self {
+        unsafe {
+            let mut page = QWidget::new_0a();
+            page.set_object_name(&qs("PinHistoryWidget"));
+            let mut layout = create_vlayout();
+            let mut layout_ptr = layout.as_mut_ptr();
+            page.set_layout(layout.into_ptr());
+            stacked.add_widget(page.into_ptr());
+
+            let mut label = QLabel::new();
+            label.set_object_name(&qs("PinHistoryLabel"));
+            label.set_contents_margins_4a(10, 4, 10, 4);
+            let label_ptr = label.as_mut_ptr();
+            layout_ptr.add_widget(label.into_ptr());
+
+            let mut table = QTableWidget::new_2a(0, PH_HEADERS.len() as i32);
+            let mut table_ptr = table.as_mut_ptr();
+            table.vertical_header().hide();
+            table.set_selection_behavior(SelectionBehavior::SelectRows);
+            table.set_edit_triggers(QFlags::from(EditTrigger::NoEditTriggers));
+            table.set_selection_mode(SelectionMode::SingleSelection);
+            table.horizontal_header().set_stretch_last_section(true);
+            table
+                .horizontal_header()
+                .set_default_alignment(QFlags::from(AlignmentFlag::AlignLeft));
+            table
+                .horizontal_header()
+                .set_section_resize_mode_1a(ResizeMode::ResizeToContents);
+            table.set_show_grid(false);
+            table.vertical_header().set_maximum_section_size(20);
+            table
+                .vertical_header()
+                .set_section_resize_mode_1a(ResizeMode::ResizeToContents);
+            table_headers::setup(&mut table_ptr, &PH_HEADERS);
+            layout_ptr.add_widget(table.into_ptr());
+
+            // the context controls
+            let mut controls_widget = QWidget::new_0a();
+            let mut controls_layout = create_hlayout();
+            controls_layout.insert_stretch_2a(0, 1);
+            let mut goto_revision_btn = QPushButton::from_q_string(&qs("Go To Revision"));
+            goto_revision_btn.set_tool_tip(&qs(
+                "Show the revision of the selected change in the history",
+            ));
+            let goto_revision_btn_ptr = goto_revision_btn.as_mut_ptr();
+            controls_layout.add_widget(goto_revision_btn.into_ptr());
+            controls_widget.set_layout(controls_layout.into_ptr());
+            controls.push(controls_widget);
+
+            let pin_history = Self {
+                label: label_ptr,
+                table: table_ptr,
+                goto_revision_btn: goto_revision_btn_ptr,
+                vpin_id: Cell::new(None),
+            };
+            pin_history.clear();
+            pin_history
+        }
+    }
+
+    /// Retrieve a MutPtr to the table of changes
+    pub fn table(&self) -> MutPtr<QTableWidget> {
+        self.table
+    }
+
+    /// Retrieve a MutPtr to the button which shows the revision of the selected
+    /// change in the revisions table
+    pub fn goto_revision_btn(&self) -> MutPtr<QPushButton> {
+        self.goto_revision_btn
+    }
+
+    /// Retrieve the id of the versionpin whose history is presented, or requested
+    pub fn vpin_id(&self) -> Option<IdType> {
+        self.vpin_id.get()
+    }
+
+    /// Clear the page, for want of a selected versionpin
+    pub fn clear(&self) {
+        self.vpin_id.set(None);
+        unsafe {
+            let mut table = self.table;
+            table.set_row_count(0);
+            let mut label = self.label;
+            label.set_text(&qs("Select a versionpin to view its history"));
+        }
+    }
+
+    /// Clear the page in anticipation of the history of a versionpin
+    ///
+    /// # Arguments
+    /// * `vpin_id` - The id of the versionpin
+    /// * `name` - The description of the versionpin, shown above the table
+    ///
+    /// # Returns
+    /// * None
+    pub fn request(&self, vpin_id: IdType, name: &str) {
+        self.vpin_id.set(Some(vpin_id));
+        unsafe {
+            let mut table = self.table;
+            table.set_row_count(0);
+            let mut label = self.label;
+            label.set_text(&qs(format!("History of {}", name)));
+        }
+    }
+
+    /// Present the history of the versionpin, newest change first
+    ///
+    /// # Arguments
+    /// * `entries` - The changes made to the versionpin
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_entries(&self, entries: Vec<PinHistoryEntry>) {
+        unsafe {
+            let mut table = self.table;
+            table.set_row_count(entries.len() as i32);
+            for (row, entry) in entries.iter().enumerate() {
+                let row = row as i32;
+                let mut item = QTableWidgetItem::new();
+                let variant = QVariant::from_i64(entry.transaction_id);
+                item.set_data(
+                    2, // EditRole
+                    variant.as_ref(),
+                );
+                table.set_item(row, COL_PH_TXID, item.into_ptr());
+                let mut set_text = |column: i32, text: &str| {
+                    let item = QTableWidgetItem::from_q_string(&QString::from_std_str(text));
+                    table.set_item(row, column, item.into_ptr());
+                };
+                set_text(COL_PH_CHANGE, entry.describe().as_str());
+                set_text(COL_PH_AUTHOR, entry.author.as_str());
+                set_text(COL_PH_DATETIME, entry.datetime.as_str());
+                set_text(COL_PH_COMMENT, entry.comment.as_str());
+            }
+        }
+    }
+
+    /// Retrieve the transaction id of the selected change
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Some(transaction id) if a change is selected
+    /// * None otherwise
+    pub fn selected_transaction(&self) -> Option<i64> {
+        unsafe {
+            let row = self.table.current_row();
+            if row < 0 {
+                return None;
+            }
+            let item = self.table.item(row, COL_PH_TXID);
+            if item.is_null() {
+                return None;
+            }
+            Some(item.data(2).to_long_long_0a())
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::history::HistoryFilter;
+use crate::utility::{create_hlayout, qs};
+use qt_widgets::{cpp_core::MutPtr, QFrame, QLabel, QLineEdit, QPushButton, QVBoxLayout};
+
+/// The bar above the revisions table, which filters the revisions by author, date
+/// range, comment, show or level, package, and revision.
+pub struct RevisionsFilter {
+    author: MutPtr<QLineEdit>,
+    since: MutPtr<QLineEdit>,
+    until: MutPtr<QLineEdit>,
+    comment: MutPtr<QLineEdit>,
+    level: MutPtr<QLineEdit>,
+    package: MutPtr<QLineEdit>,
+    revision: MutPtr<QLineEdit>,
+    search_btn: MutPtr<QPushButton>,
+    status: MutPtr<QLabel>,
+}
+
+impl RevisionsFilter {
+    /// Create the filter bar, adding it to the supplied layout
+    ///
+    /// # Arguments
+    /// * `layout` - The layout of the history page
+    ///
+    /// # Returns
+    /// * RevisionsFilter instance
+    pub fn create(layout: &mut MutPtr<QVBoxLayout>) -> Self {
+        unsafe {
+            let mut frame = QFrame::new_0a();
+            frame.set_object_name(&qs("RevisionsFilterFrame"));
+            let mut hlayout = create_hlayout();
+            hlayout.set_spacing(6);
+            hlayout.set_contents_margins_4a(10, 4, 10, 4);
+            let mut hlayout_ptr = hlayout.as_mut_ptr();
+            frame.set_layout(hlayout.into_ptr());
+
+            let mut add_field = |placeholder: &str, tooltip: &str| {
+                let mut line_edit = QLineEdit::new();
+                line_edit.set_placeholder_text(&qs(placeholder));
+                line_edit.set_tool_tip(&qs(tooltip));
+                line_edit.set_clear_button_enabled(true);
+                let line_edit_ptr = line_edit.as_mut_ptr();
+                hlayout_ptr.add_widget(line_edit.into_ptr());
+                line_edit_ptr
+            };
+            let author = add_field("Author", "Revisions whose author contains the text");
+            let since = add_field("Since", "Revisions made on or after YYYY-MM-DD [HH:MM]");
+            let until = add_field("Until", "Revisions made on or before YYYY-MM-DD [HH:MM]");
+            let comment = add_field("Comment", "Revisions whose comment contains the text");
+            let level = add_field(
+                "Show / Level",
+                "Revisions changing pins at or below the level",
+            );
+            let package = add_field("Package", "Revisions changing pins of the package");
+            let revision = add_field("Revision", "The revision and those preceding it");
+
+            let mut search_btn = QPushButton::from_q_string(&qs("Search"));
+            let search_btn_ptr = search_btn.as_mut_ptr();
+            hlayout_ptr.add_widget(search_btn.into_ptr());
+
+            let mut status = QLabel::new();
+            status.set_object_name(&qs("RevisionsFilterStatus"));
+            let status_ptr = status.as_mut_ptr();
+            hlayout_ptr.add_widget(status.into_ptr());
+
+            layout.add_widget(frame.into_ptr());
+            Self {
+                author,
+                since,
+                until,
+                comment,
+                level,
+                package,
+                revision,
+                search_btn: search_btn_ptr,
+                status: status_ptr,
+            }
+        }
+    }
+
+    /// Retrieve the HistoryFilter described by the bar
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Ok(HistoryFilter) if the dates and revision are valid
+    /// * Err(String) otherwise
+    pub fn filter(&self) -> Result<HistoryFilter, String> {
+        unsafe {
+            HistoryFilter::from_fields(
+                self.author.text().to_std_string().as_str(),
+                self.since.text().to_std_string().as_str(),
+                self.until.text().to_std_string().as_str(),
+                self.comment.text().to_std_string().as_str(),
+                self.level.text().to_std_string().as_str(),
+                self.package.text().to_std_string().as_str(),
+                self.revision.text().to_std_string().as_str(),
+            )
+        }
+    }
+
+    /// Retrieve the line edits of the bar, each of which searches when return is
+    /// pressed
+    pub fn line_edits(&self) -> Vec<MutPtr<QLineEdit>> {
+        vec![
+            self.author,
+            self.since,
+            self.until,
+            self.comment,
+            self.level,
+            self.package,
+            self.revision,
+        ]
+    }
+
+    /// Clear the bar, save for the revision, so that a search presents the revision
+    /// at the top of the revisions table
+    ///
+    /// # Arguments
+    /// * `revision` - The transaction id of the revision
+    ///
+    /// # Returns
+    /// * None
+    pub fn show_revision(&self, revision: i64) {
+        unsafe {
+            for mut line_edit in self.line_edits() {
+                line_edit.clear();
+            }
+            let mut line_edit = self.revision;
+            line_edit.set_text(&qs(revision.to_string()));
+        }
+    }
+
+    /// Retrieve a MutPtr to the search button
+    pub fn search_btn(&self) -> MutPtr<QPushButton> {
+        self.search_btn
+    }
+
+    /// Report on the revisions loaded, or on a problem with the filter
+    ///
+    /// # Arguments
+    /// * `status` - The text to display
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_status(&self, status: &str) {
+        unsafe {
+            let mut label = self.status;
+            label.set_text(&qs(status));
+        }
+    }
+}
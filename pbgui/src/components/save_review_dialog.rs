@@ -0,0 +1,299 @@
+//! The SaveReviewDialog is presented before pending versionpin changes are written to
+//! the database. It groups the changes by level, reports how many descendant levels
+//! inherit each level's pins, highlights facility-wide changes, and, when the impact
+//! is large, requires the user to type the name of the show (or `facility`) before
+//! the save may proceed.
+use crate::utility::{create_hlayout, create_vlayout, qs};
+use qt_core::{QString, Signal, SlotOfQString};
+use qt_gui::{QBrush, QColor};
+use qt_widgets::{
+    cpp_core::{MutPtr, Ref},
+    q_dialog_button_box::StandardButton,
+    QDialog, QDialogButtonBox, QFrame, QLabel, QLineEdit, QTreeWidget, QTreeWidgetItem, QWidget,
+};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// The level name which denotes the facility
+const FACILITY: &str = "facility";
+/// Default number of inheriting levels above which the user must confirm by typing
+pub const DEFAULT_CONFIRM_THRESHOLD: usize = 50;
+
+/// The pending changes at a single level, along with the number of descendant
+/// levels which inherit from it.
+#[derive(Debug, PartialEq)]
+pub struct SaveReviewGroup {
+    /// The level the changes apply to
+    pub level: String,
+    /// The number of descendant levels which inherit pins from the level
+    pub inheriting: usize,
+    /// Human readable descriptions of the changes
+    pub changes: Vec<String>,
+}
+
+impl SaveReviewGroup {
+    /// Determine whether the group applies to the facility
+    pub fn is_facility(&self) -> bool {
+        self.level == FACILITY
+    }
+
+    /// The show the group applies to. For the facility this is `facility`.
+    pub fn show(&self) -> &str {
+        self.level.split('.').next().unwrap_or(FACILITY)
+    }
+}
+
+/// Dialog used to review pending changes prior to saving them
+pub struct SaveReviewDialog<'a> {
+    dialog: MutPtr<QDialog>,
+    summary: MutPtr<QLabel>,
+    changes_tree: MutPtr<QTreeWidget>,
+    confirm_frame: MutPtr<QFrame>,
+    confirm_label: MutPtr<QLabel>,
+    confirm_edit: MutPtr<QLineEdit>,
+    buttons: MutPtr<QDialogButtonBox>,
+    confirm_word: Rc<RefCell<String>>,
+    confirm_threshold: Cell<usize>,
+    confirm_changed: SlotOfQString<'a>,
+}
+
+impl<'a> SaveReviewDialog<'a> {
+    /// Create the dialog, parented to the supplied widget
+    ///
+    /// # Arguments
+    /// * `parent` - The parent widget, which assumes ownership of the dialog
+    ///
+    /// # Returns
+    /// * SaveReviewDialog instance
+    pub fn create(parent: MutPtr<QWidget>) -> Self {
+        unsafe {
+            let mut dialog = QDialog::new_1a(parent);
+            dialog.set_object_name(&qs("SaveReviewDialog"));
+            dialog.set_window_title(&qs("Review Changes"));
+            dialog.set_modal(true);
+            dialog.resize_2a(700, 500);
+            let mut layout = create_vlayout();
+            layout.set_spacing(6);
+            layout.set_contents_margins_4a(10, 10, 10, 10);
+
+            let mut summary = QLabel::new();
+            summary.set_object_name(&qs("SaveReviewSummary"));
+            summary.set_word_wrap(true);
+            let summary_ptr = summary.as_mut_ptr();
+            layout.add_widget(summary.into_ptr());
+
+            let mut changes_tree = QTreeWidget::new_0a();
+            changes_tree.set_object_name(&qs("SaveReviewTree"));
+            changes_tree.set_column_count(2);
+            let mut headers = qt_core::QStringList::new();
+            headers.append_q_string(&qs("Level / Change"));
+            headers.append_q_string(&qs("Inheriting Levels"));
+            changes_tree.set_header_labels(&headers);
+            let changes_tree_ptr = changes_tree.as_mut_ptr();
+            layout.add_widget(changes_tree.into_ptr());
+
+            // confirmation controls. only shown when the impact exceeds the threshold
+            let mut confirm_frame = QFrame::new_0a();
+            confirm_frame.set_object_name(&qs("SaveReviewConfirmFrame"));
+            let mut confirm_layout = create_hlayout();
+            let mut confirm_label = QLabel::new();
+            let confirm_label_ptr = confirm_label.as_mut_ptr();
+            confirm_layout.add_widget(confirm_label.into_ptr());
+            let mut confirm_edit = QLineEdit::new();
+            confirm_edit.set_object_name(&qs("SaveReviewConfirmEdit"));
+            let confirm_edit_ptr = confirm_edit.as_mut_ptr();
+            confirm_layout.add_widget(confirm_edit.into_ptr());
+            confirm_frame.set_layout(confirm_layout.into_ptr());
+            let confirm_frame_ptr = confirm_frame.as_mut_ptr();
+            layout.add_widget(confirm_frame.into_ptr());
+
+            let mut button_box = QDialogButtonBox::from_q_flags_standard_button(
+                StandardButton::Ok | StandardButton::Cancel,
+            );
+            let buttons = button_box.as_mut_ptr();
+            layout.add_widget(button_box.into_ptr());
+            dialog.set_layout(layout.into_ptr());
+
+            let dialog_ptr = dialog.into_ptr();
+            let confirm_word = Rc::new(RefCell::new(String::new()));
+            let word = confirm_word.clone();
+            let review = Self {
+                dialog: dialog_ptr,
+                summary: summary_ptr,
+                changes_tree: changes_tree_ptr,
+                confirm_frame: confirm_frame_ptr,
+                confirm_label: confirm_label_ptr,
+                confirm_edit: confirm_edit_ptr,
+                buttons,
+                confirm_word,
+                confirm_threshold: Cell::new(DEFAULT_CONFIRM_THRESHOLD),
+                // only enable the Ok button once the user has typed the confirmation word
+                confirm_changed: SlotOfQString::new(move |text: Ref<QString>| {
+                    let mut ok_button = buttons.button(StandardButton::Ok);
+                    ok_button.set_enabled(text.to_std_string() == *word.borrow());
+                }),
+            };
+            confirm_edit_ptr
+                .text_changed()
+                .connect(&review.confirm_changed);
+            buttons.accepted().connect(dialog_ptr.slot_accept());
+            buttons.rejected().connect(dialog_ptr.slot_reject());
+            review
+        }
+    }
+
+    /// Return the dialog's accepted signal, emitted once the user has reviewed
+    /// and confirmed the changes.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Signal that sends `()`
+    pub fn accepted(&self) -> Signal<()> {
+        unsafe { self.dialog.accepted() }
+    }
+
+    /// Set the number of inheriting levels above which the user must
+    /// type the show name in order to confirm the save.
+    ///
+    /// # Arguments
+    /// * `threshold` - The number of inheriting levels
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_confirm_threshold(&self, threshold: usize) {
+        self.confirm_threshold.set(threshold);
+    }
+
+    /// Populate the dialog with the supplied groups and execute it.
+    ///
+    /// # Arguments
+    /// * `groups` - The pending changes, grouped by level
+    ///
+    /// # Returns
+    /// * None
+    pub fn exec(&self, mut groups: Vec<SaveReviewGroup>) {
+        unsafe {
+            // facility first, then by impact
+            groups.sort_by(|a, b| {
+                b.is_facility()
+                    .cmp(&a.is_facility())
+                    .then(b.inheriting.cmp(&a.inheriting))
+                    .then(a.level.cmp(&b.level))
+            });
+            self.populate_tree(&groups);
+
+            let change_cnt: usize = groups.iter().map(|g| g.changes.len()).sum();
+            let inheriting: usize = groups.iter().map(|g| g.inheriting).sum();
+            let has_facility = groups.iter().any(|g| g.is_facility());
+            let mut summary = self.summary;
+            summary.set_text(&qs(format!(
+                "{} change(s) at {} level(s), inherited by up to {} descendant level(s).{}",
+                change_cnt,
+                groups.len(),
+                inheriting,
+                if has_facility {
+                    " Facility-wide pins are affected."
+                } else {
+                    ""
+                }
+            )));
+
+            let mut ok_button = self.buttons.button(StandardButton::Ok);
+            let mut confirm_frame = self.confirm_frame;
+            let mut confirm_edit = self.confirm_edit;
+            confirm_edit.clear();
+            match confirmation_word(&groups, self.confirm_threshold.get()) {
+                Some(word) => {
+                    let mut confirm_label = self.confirm_label;
+                    confirm_label.set_text(&qs(format!("Type \"{}\" to confirm:", word)));
+                    self.confirm_word.replace(word);
+                    confirm_frame.set_visible(true);
+                    ok_button.set_enabled(false);
+                    confirm_edit.set_focus_0a();
+                }
+                None => {
+                    confirm_frame.set_visible(false);
+                    ok_button.set_enabled(true);
+                }
+            }
+            let mut dialog = self.dialog;
+            dialog.exec();
+        }
+    }
+
+    unsafe fn populate_tree(&self, groups: &[SaveReviewGroup]) {
+        let mut tree = self.changes_tree;
+        tree.clear();
+        let highlight = QBrush::from_q_color(QColor::from_rgb_3a(220, 70, 70).as_ref());
+        for group in groups {
+            let mut level_item = QTreeWidgetItem::new();
+            level_item.set_text(0, &qs(&group.level));
+            level_item.set_text(1, &qs(group.inheriting.to_string()));
+            if group.is_facility() {
+                level_item.set_foreground(0, highlight.as_ref());
+                level_item.set_foreground(1, highlight.as_ref());
+                let mut font = level_item.font(0);
+                font.set_bold(true);
+                level_item.set_font(0, font.as_ref());
+            }
+            for change in &group.changes {
+                let mut change_item = QTreeWidgetItem::new();
+                change_item.set_text(0, &qs(change));
+                level_item.add_child(change_item.into_ptr());
+            }
+            let mut level_item_ptr = level_item.into_ptr();
+            tree.add_top_level_item(level_item_ptr);
+            level_item_ptr.set_expanded(true);
+        }
+        tree.resize_column_to_contents(0);
+    }
+}
+
+// Determine what, if anything, the user must type to confirm the save. Facility
+// changes always require confirmation. Otherwise, confirmation is required once the
+// total number of inheriting levels reaches the threshold, in which case the user
+// types the name of the most affected show.
+fn confirmation_word(groups: &[SaveReviewGroup], threshold: usize) -> Option<String> {
+    if groups.iter().any(|g| g.is_facility()) {
+        return Some(FACILITY.to_string());
+    }
+    let inheriting: usize = groups.iter().map(|g| g.inheriting).sum();
+    if inheriting < threshold {
+        return None;
+    }
+    groups
+        .iter()
+        .max_by_key(|g| g.inheriting)
+        .map(|g| g.show().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(level: &str, inheriting: usize) -> SaveReviewGroup {
+        SaveReviewGroup {
+            level: level.to_string(),
+            inheriting,
+            changes: vec!["change".to_string()],
+        }
+    }
+
+    #[test]
+    fn facility_always_requires_confirmation() {
+        let groups = vec![group("dev01.rd", 2), group("facility", 0)];
+        assert_eq!(
+            confirmation_word(&groups, 1000),
+            Some("facility".to_string())
+        );
+    }
+
+    #[test]
+    fn threshold_requires_show_name() {
+        let groups = vec![group("dev01.rd", 30), group("dev02", 40)];
+        assert_eq!(confirmation_word(&groups, 100), None);
+        assert_eq!(confirmation_word(&groups, 70), Some("dev02".to_string()));
+    }
+}
@@ -0,0 +1,226 @@
+//! The VersionPickerDialog lets the user choose an alternative version of a pinned
+//! distribution. Versions are listed newest first, using semver ordering, along with
+//! when they were installed at the current site, if they are, and the number of pins
+//! using them. The currently pinned and newest versions are marked, and the
+//! list may be narrowed with a filter (eg `1.2`, or `>=2.0`).
+use crate::utility::{create_hlayout, create_vlayout, qs};
+use crate::versions::{matches_filter, sort_newest_first, VersionInfo};
+use qt_core::{QString, SlotOfQString};
+use qt_gui::{QBrush, QColor};
+use qt_widgets::{
+    cpp_core::{MutPtr, Ref},
+    q_abstract_item_view::{EditTrigger, SelectionBehavior, SelectionMode},
+    q_dialog::DialogCode,
+    q_dialog_button_box::StandardButton,
+    q_header_view::ResizeMode,
+    QDialog, QDialogButtonBox, QLabel, QLineEdit, QTableWidget, QTableWidgetItem, QWidget,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const COL_VERSION: i32 = 0;
+const COL_INSTALLED: i32 = 1;
+const COL_PINS: i32 = 2;
+const COL_NOTES: i32 = 3;
+const HEADERS: [&str; 4] = ["Version", "Installed", "Pins", ""];
+
+/// Dialog used to choose a version of a package
+pub struct VersionPickerDialog<'a> {
+    dialog: MutPtr<QDialog>,
+    label: MutPtr<QLabel>,
+    filter: MutPtr<QLineEdit>,
+    table: MutPtr<QTableWidget>,
+    versions: Rc<RefCell<Vec<VersionInfo>>>,
+    filter_changed: SlotOfQString<'a>,
+}
+
+impl<'a> VersionPickerDialog<'a> {
+    /// Create the dialog, parented to the supplied widget
+    ///
+    /// # Arguments
+    /// * `parent` - The parent widget, which assumes ownership of the dialog
+    ///
+    /// # Returns
+    /// * VersionPickerDialog instance
+    pub fn create(parent: MutPtr<QWidget>) -> Self {
+        unsafe {
+            let mut dialog = QDialog::new_1a(parent);
+            dialog.set_object_name(&qs("VersionPickerDialog"));
+            dialog.set_window_title(&qs("Pick Version"));
+            dialog.set_modal(true);
+            dialog.resize_2a(560, 420);
+            let mut layout = create_vlayout();
+            layout.set_spacing(6);
+            layout.set_contents_margins_4a(10, 10, 10, 10);
+
+            let mut label = QLabel::new();
+            label.set_object_name(&qs("VersionPickerLabel"));
+            let label_ptr = label.as_mut_ptr();
+            layout.add_widget(label.into_ptr());
+
+            let mut filter_layout = create_hlayout();
+            filter_layout.add_widget(QLabel::from_q_string(&qs("Filter")).into_ptr());
+            let mut filter = QLineEdit::new();
+            filter.set_object_name(&qs("VersionPickerFilter"));
+            filter.set_placeholder_text(&qs("eg 1.2 or >=2.0"));
+            filter.set_clear_button_enabled(true);
+            let filter_ptr = filter.as_mut_ptr();
+            filter_layout.add_widget(filter.into_ptr());
+            layout.add_layout_1a(filter_layout.into_ptr());
+
+            let mut table = QTableWidget::new_0a();
+            table.set_object_name(&qs("VersionPickerTable"));
+            table.set_column_count(HEADERS.len() as i32);
+            let mut headers = qt_core::QStringList::new();
+            for header in HEADERS.iter() {
+                headers.append_q_string(&qs(header));
+            }
+            table.set_horizontal_header_labels(&headers);
+            table.vertical_header().set_visible(false);
+            table
+                .horizontal_header()
+                .set_section_resize_mode_1a(ResizeMode::ResizeToContents);
+            table.horizontal_header().set_stretch_last_section(true);
+            table.set_selection_behavior(SelectionBehavior::SelectRows);
+            table.set_selection_mode(SelectionMode::SingleSelection);
+            table.set_edit_triggers(EditTrigger::NoEditTriggers.into());
+            let table_ptr = table.as_mut_ptr();
+            layout.add_widget(table.into_ptr());
+
+            let mut button_box = QDialogButtonBox::from_q_flags_standard_button(
+                StandardButton::Ok | StandardButton::Cancel,
+            );
+            let buttons = button_box.as_mut_ptr();
+            layout.add_widget(button_box.into_ptr());
+            dialog.set_layout(layout.into_ptr());
+
+            let dialog_ptr = dialog.into_ptr();
+            let versions = Rc::new(RefCell::new(Vec::new()));
+            let filtered = versions.clone();
+            let picker = Self {
+                dialog: dialog_ptr,
+                label: label_ptr,
+                filter: filter_ptr,
+                table: table_ptr,
+                versions,
+                // hide the versions which do not match the filter
+                filter_changed: SlotOfQString::new(move |text: Ref<QString>| {
+                    let mut table = table_ptr;
+                    let text = text.to_std_string();
+                    for (row, version) in filtered.borrow().iter().enumerate() {
+                        let hidden = !matches_filter(&version.version, &text);
+                        table.set_row_hidden(row as i32, hidden);
+                    }
+                }),
+            };
+            filter_ptr.text_changed().connect(&picker.filter_changed);
+            table_ptr
+                .double_clicked()
+                .connect(dialog_ptr.slot_accept());
+            buttons.accepted().connect(dialog_ptr.slot_accept());
+            buttons.rejected().connect(dialog_ptr.slot_reject());
+            picker
+        }
+    }
+
+    /// Populate the dialog with the versions of the package and execute it.
+    ///
+    /// # Arguments
+    /// * `package` - The name of the package
+    /// * `current` - The currently pinned version
+    /// * `versions` - The versions of the package, along with their install metadata
+    ///
+    /// # Returns
+    /// * Some(VersionInfo) if the user accepted the dialog with a version selected
+    /// * None otherwise
+    pub fn exec(
+        &self,
+        package: &str,
+        current: &str,
+        mut versions: Vec<VersionInfo>,
+    ) -> Option<VersionInfo> {
+        unsafe {
+            sort_newest_first(&mut versions);
+            let mut label = self.label;
+            label.set_text(&qs(format!(
+                "{} version(s) of {}, currently pinned at {}",
+                versions.len(),
+                package,
+                current
+            )));
+            self.versions.replace(versions);
+            self.populate_table(current);
+
+            let mut filter = self.filter;
+            filter.clear();
+            filter.set_focus_0a();
+            let mut dialog = self.dialog;
+            if dialog.exec() != DialogCode::Accepted.to_int() {
+                return None;
+            }
+            let row = self.table.current_row();
+            if row < 0 || self.table.is_row_hidden(row) {
+                return None;
+            }
+            self.versions.borrow().get(row as usize).cloned()
+        }
+    }
+
+    unsafe fn populate_table(&self, current: &str) {
+        let mut table = self.table;
+        table.clear_contents();
+        let versions = self.versions.borrow();
+        table.set_row_count(versions.len() as i32);
+        // sorted newest first, so the newest release is the first without a pre-release
+        let newest = versions
+            .iter()
+            .find(|v| !v.version.contains('-'))
+            .or_else(|| versions.first())
+            .map(|v| v.version.clone());
+        let current_brush = QBrush::from_q_color(QColor::from_rgb_3a(100, 150, 255).as_ref());
+        let newest_brush = QBrush::from_q_color(QColor::from_rgb_3a(80, 200, 120).as_ref());
+        for (row, version) in versions.iter().enumerate() {
+            let row = row as i32;
+            let is_current = version.version == current;
+            let is_newest = newest.as_ref() == Some(&version.version);
+            let mut notes = Vec::new();
+            if is_current {
+                notes.push("current");
+            }
+            if is_newest {
+                notes.push("newest");
+            }
+            let values = [
+                version.version.clone(),
+                match (version.installed, &version.installed_on) {
+                    (Some(true), Some(date)) => date.clone(),
+                    (Some(true), None) => "yes".to_string(),
+                    (Some(false), _) => "no".to_string(),
+                    (None, _) => "-".to_string(),
+                },
+                version.pin_count.to_string(),
+                notes.join(", "),
+            ];
+            for (col, value) in [COL_VERSION, COL_INSTALLED, COL_PINS, COL_NOTES]
+                .iter()
+                .zip(values.iter())
+            {
+                let mut item = QTableWidgetItem::new();
+                item.set_text(&qs(value));
+                if is_current {
+                    let mut font = item.font();
+                    font.set_bold(true);
+                    item.set_font(font.as_ref());
+                    item.set_foreground(current_brush.as_ref());
+                } else if is_newest {
+                    item.set_foreground(newest_brush.as_ref());
+                }
+                table.set_item(row, *col, item.into_ptr());
+            }
+            table.set_row_hidden(row, false);
+            if is_current {
+                table.select_row(row);
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use crate::change_type::PinCoord;
 use crate::constants::*;
+use crate::distribution::{Distribution, DistributionError};
 use crate::utility::qs;
 use crate::{RowSetterTrait, RowTrait};
 use packybara::db::find_all::versionpins::FindAllVersionPinsRow;
@@ -8,6 +10,7 @@ use qt_widgets::{
     cpp_core::{CppBox, MutPtr},
     QTableWidget, QTableWidgetItem,
 };
+use std::convert::TryFrom;
 use std::fmt;
 
 /// A row of versionpin data, parameterized by the type of data returned
@@ -20,8 +23,8 @@ pub struct VersionPinRow<T> {
     pub dist_id: IdType,
     /// The package coord id of the versionpin
     pub pkgcoord_id: IdType,
-    /// The distribution, as type T
-    pub distribution: T,
+    /// The distribution, carrying the distribution id when known
+    pub distribution: Distribution,
     /// The level, as type T
     pub level: T,
     /// The role, as type T
@@ -58,7 +61,7 @@ impl VersionPinRow<String> {
         id: IdType,
         dist_id: IdType,
         pkgcoord_id: IdType,
-        distribution: CppBox<QString>,
+        distribution: Distribution,
         level: CppBox<QString>,
         role: CppBox<QString>,
         platform: CppBox<QString>,
@@ -69,7 +72,7 @@ impl VersionPinRow<String> {
             id,
             dist_id,
             pkgcoord_id,
-            distribution: distribution.to_std_string(),
+            distribution,
             level: level.to_std_string(),
             role: role.to_std_string(),
             platform: platform.to_std_string(),
@@ -79,6 +82,27 @@ impl VersionPinRow<String> {
     }
 }
 
+impl TryFrom<&FindAllVersionPinsRow> for VersionPinRow<String> {
+    type Error = DistributionError;
+
+    fn try_from(pin: &FindAllVersionPinsRow) -> Result<Self, Self::Error> {
+        let distribution =
+            Distribution::new(pin.distribution.package(), pin.distribution.version())?
+                .with_id(pin.distribution_id);
+        Ok(Self {
+            id: pin.versionpin_id,
+            dist_id: pin.distribution_id,
+            pkgcoord_id: pin.pkgcoord_id,
+            distribution,
+            level: pin.coords.level.to_string(),
+            role: pin.coords.role.to_string(),
+            platform: pin.coords.platform.to_string(),
+            site: pin.coords.site.to_string(),
+            withs: pin.withs.as_ref().map_or(0, |withs| withs.len() as i32),
+        })
+    }
+}
+
 impl RowTrait for VersionPinRow<String> {
     type ReturnType = VersionPinRow<String>;
     type SourceTableType = MutPtr<QTableWidget>;
@@ -103,7 +127,10 @@ impl RowTrait for VersionPinRow<String> {
                 .data(2)
                 .to_int_0a();
             let pkgcoord_id = source_table.item(row, COL_PKGCOORD_ID).data(2).to_int_0a();
-            let distribtution = source_table.item(row, COL_DISTRIBUTION).text();
+            let distribution = match table_distribution(source_table, row, dist_id) {
+                Some(distribution) => distribution,
+                None => return None,
+            };
             let level = source_table.item(row, COL_LEVEL).text();
             let role = source_table.item(row, COL_ROLE).text();
             let platform = source_table.item(row, COL_PLATFORM).text();
@@ -113,7 +140,7 @@ impl RowTrait for VersionPinRow<String> {
                 vpin_id,
                 dist_id,
                 pkgcoord_id,
-                distribtution,
+                distribution,
                 level,
                 role,
                 platform,
@@ -137,7 +164,7 @@ impl RowSetterTrait for VersionPinRow<String> {
             target_table.set_item(row, COL_ID, vpin_table_widget_item.into_ptr());
             // DISTRIBUTION
             let mut vpin_table_widget_item = QTableWidgetItem::new();
-            vpin_table_widget_item.set_text(&qs(&self.distribution));
+            set_distribution_item(&mut vpin_table_widget_item, &self.distribution);
             target_table.set_item(row, COL_DISTRIBUTION, vpin_table_widget_item.into_ptr());
             // LEVEL
             let mut vpin_table_widget_item = QTableWidgetItem::new();
@@ -194,7 +221,7 @@ impl fmt::Debug for VersionPinRow<CppBox<QString>> {
             .field("id", &self.id)
             .field("dist_id", &self.dist_id)
             .field("pkgcoord_id", &self.pkgcoord_id) //&format_args!("{}", self.addr))
-            .field("distribution", &self.distribution.to_std_string())
+            .field("distribution", &self.distribution)
             .field("level", &self.level.to_std_string())
             .field("role", &self.role.to_std_string())
             .field("platform", &self.platform.to_std_string())
@@ -209,7 +236,7 @@ impl VersionPinRow<CppBox<QString>> {
         id: IdType,
         dist_id: IdType,
         pkgcoord_id: IdType,
-        distribution: CppBox<QString>,
+        distribution: Distribution,
         level: CppBox<QString>,
         role: CppBox<QString>,
         platform: CppBox<QString>,
@@ -229,16 +256,34 @@ impl VersionPinRow<CppBox<QString>> {
         }
     }
 
+    /// Return the package of the row's distribution
+    pub fn package(&self) -> String {
+        self.distribution.package().to_string()
+    }
+
+    /// Return the PinCoord of the row
+    pub fn pin_coord(&self) -> PinCoord {
+        unsafe {
+            PinCoord::new(
+                self.package(),
+                self.level.to_std_string(),
+                self.role.to_std_string(),
+                self.platform.to_std_string(),
+                self.site.to_std_string(),
+            )
+        }
+    }
+
     /// Return an owned pkgcoord string
     pub fn pkgcoord(&self) -> CppBox<QString> {
         unsafe {
-            let pieces = self.distribution.split_q_string(&qs("-"));
+            let package = self.package();
             qs("(level: %1, role: %2, platform: %3, site: %4, package: %5)").arg_5_q_string(
                 self.level.as_ref(),
                 self.role.as_ref(),
                 self.platform.as_ref(),
                 self.site.as_ref(),
-                pieces.index(0),
+                qs(package).as_ref(),
             )
         }
     }
@@ -267,7 +312,10 @@ impl RowTrait for VersionPinRow<CppBox<QString>> {
                 .data(2)
                 .to_int_0a();
             let pkgcoord_id = source_table.item(row, COL_PKGCOORD_ID).data(2).to_int_0a();
-            let distribtution = source_table.item(row, COL_DISTRIBUTION).text();
+            let distribution = match table_distribution(source_table, row, dist_id) {
+                Some(distribution) => distribution,
+                None => return None,
+            };
             let level = source_table.item(row, COL_LEVEL).text();
             let role = source_table.item(row, COL_ROLE).text();
             let platform = source_table.item(row, COL_PLATFORM).text();
@@ -277,7 +325,7 @@ impl RowTrait for VersionPinRow<CppBox<QString>> {
                 vpin_id,
                 dist_id,
                 pkgcoord_id,
-                distribtution,
+                distribution,
                 level,
                 role,
                 platform,
@@ -301,7 +349,7 @@ impl RowSetterTrait for VersionPinRow<CppBox<QString>> {
             target_table.set_item(row, COL_ID, vpin_table_widget_item.into_ptr());
             // DISTRIBUTION
             let mut vpin_table_widget_item = QTableWidgetItem::new();
-            vpin_table_widget_item.set_text(&self.distribution);
+            set_distribution_item(&mut vpin_table_widget_item, &self.distribution);
             target_table.set_item(row, COL_DISTRIBUTION, vpin_table_widget_item.into_ptr());
             // LEVEL
             let mut vpin_table_widget_item = QTableWidgetItem::new();
@@ -372,6 +420,10 @@ impl RowSetterTrait for FindAllVersionPinsRow {
             vpin_table_widget_item.set_text(&QString::from_std_str(
                 self.distribution.to_string().as_str(),
             ));
+            let package = QVariant::from_q_string(&qs(self.distribution.package()));
+            vpin_table_widget_item.set_data(PIN_PACKAGE_ROLE, &package);
+            let version = QVariant::from_q_string(&qs(self.distribution.version()));
+            vpin_table_widget_item.set_data(PIN_VERSION_ROLE, &version);
             target_table.set_item(row, COL_DISTRIBUTION, vpin_table_widget_item.into_ptr());
             // LEVEL
             let mut vpin_table_widget_item = QTableWidgetItem::new();
@@ -430,3 +482,43 @@ impl RowSetterTrait for FindAllVersionPinsRow {
         }
     }
 }
+
+/// Present a distribution in a distribution cell of the versionpin table, recording its
+/// package and version in the PIN_PACKAGE_ROLE and PIN_VERSION_ROLE, so that they are
+/// read back without parsing the text of the cell
+///
+/// # Arguments
+/// * `item` - The distribution cell
+/// * `distribution` - The distribution of the pin
+///
+/// # Returns
+/// * None
+pub fn set_distribution_item(item: &mut QTableWidgetItem, distribution: &Distribution) {
+    unsafe {
+        item.set_text(&qs(distribution.to_string()));
+        let package = QVariant::from_q_string(&qs(distribution.package()));
+        item.set_data(PIN_PACKAGE_ROLE, &package);
+        let version = QVariant::from_q_string(&qs(distribution.version()));
+        item.set_data(PIN_VERSION_ROLE, &version);
+    }
+}
+
+// read the distribution at the row of the versionpin table, along with its id
+unsafe fn table_distribution(
+    table: &MutPtr<QTableWidget>,
+    row: i32,
+    dist_id: IdType,
+) -> Option<Distribution> {
+    let item = table.item(row, COL_DISTRIBUTION);
+    let package = item.data(PIN_PACKAGE_ROLE).to_string().to_std_string();
+    let version = item.data(PIN_VERSION_ROLE).to_string().to_std_string();
+    match Distribution::new(package, version) {
+        // pins which have yet to be saved carry no distribution id
+        Ok(distribution) if dist_id > 0 => Some(distribution.with_id(dist_id)),
+        Ok(distribution) => Some(distribution),
+        Err(err) => {
+            log::error!("row {} of the versionpin table: {}", row, err);
+            None
+        }
+    }
+}
@@ -1,10 +1,10 @@
 use crate::constants::*;
 use crate::table_headers;
 use crate::utility::qs;
-use qt_core::QFlags;
+use qt_core::{DropAction, QFlags};
 use qt_widgets::{
     cpp_core::MutPtr,
-    q_abstract_item_view::{EditTrigger, SelectionBehavior, SelectionMode},
+    q_abstract_item_view::{DragDropMode, EditTrigger, SelectionBehavior, SelectionMode},
     q_header_view::ResizeMode,
     qt_core::ContextMenuPolicy,
     QSplitter, QTableWidget,
@@ -34,10 +34,16 @@ pub fn create(vsplit_ptr: &mut MutPtr<QSplitter>) -> MutPtr<QTableWidget> {
         tablewidget_ptr.vertical_header().hide();
         tablewidget_ptr.set_selection_behavior(SelectionBehavior::SelectRows);
         tablewidget_ptr.set_edit_triggers(QFlags::from(EditTrigger::NoEditTriggers));
-        tablewidget_ptr.set_selection_mode(SelectionMode::SingleSelection);
+        tablewidget_ptr.set_selection_mode(SelectionMode::ExtendedSelection);
         tablewidget_ptr.set_show_grid(false);
         tablewidget_ptr.set_alternating_row_colors(true);
         tablewidget_ptr.set_context_menu_policy(ContextMenuPolicy::CustomContextMenu);
+        // accept distributions dragged from the packages tree. See drop_distribution
+        // a drop overwrites the cell it lands on, so that the row dropped on is known
+        tablewidget_ptr.set_drag_drop_mode(DragDropMode::DropOnly);
+        tablewidget_ptr.set_drag_drop_overwrite_mode(true);
+        tablewidget_ptr.set_default_drop_action(DropAction::CopyAction);
+        tablewidget_ptr.set_drop_indicator_shown(false);
         table_headers::setup(&mut tablewidget_ptr, &HEADERS);
         tablewidget_ptr
             .horizontal_header()
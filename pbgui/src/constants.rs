@@ -103,3 +103,42 @@ pub const CHNG_HEADERS: &[(i32, &str, bool)] = &[
     (COL_CHNG_OLD, "Old", false),
     (COL_CHNG_NEW, "New", false),
 ];
+
+/// pin history table's transaction id column index
+pub const COL_PH_TXID: i32 = 0;
+/// pin history table's change column index
+pub const COL_PH_CHANGE: i32 = 1;
+/// pin history table's author column index
+pub const COL_PH_AUTHOR: i32 = 2;
+/// pin history table's datetime column index
+pub const COL_PH_DATETIME: i32 = 3;
+/// pin history table's comment column index
+pub const COL_PH_COMMENT: i32 = 4;
+/// pin history table headers as an array of tuples where the tuple
+/// consists of (column index, name, visibility boolean)
+pub const PH_HEADERS: &[(i32, &str, bool)] = &[
+    (COL_PH_TXID, "Tx ID", false),
+    (COL_PH_CHANGE, "Change", false),
+    (COL_PH_AUTHOR, "Author", false),
+    (COL_PH_DATETIME, "Datetime", false),
+    (COL_PH_COMMENT, "Comment", false),
+];
+
+/// packages tree's data role holding the package of a distribution item. It is
+/// carried along when the item is dragged onto the versionpin table (Qt::UserRole + 1)
+pub const DIST_PACKAGE_ROLE: i32 = 257;
+/// packages tree's data role holding the version of a distribution item. It is
+/// carried along when the item is dragged onto the versionpin table (Qt::UserRole + 2)
+pub const DIST_VERSION_ROLE: i32 = 258;
+
+/// versionpin table's data role holding the package of the distribution of a pin, on
+/// its distribution cell (Qt::UserRole + 3)
+pub const PIN_PACKAGE_ROLE: i32 = 259;
+/// versionpin table's data role holding the version of the distribution of a pin, on
+/// its distribution cell (Qt::UserRole + 4)
+pub const PIN_VERSION_ROLE: i32 = 260;
+
+/// versionpin table's first data role holding a copy of the display, tooltip and
+/// foreground roles of a cell, from which a cell overwritten by a dropped
+/// distribution is restored (Qt::UserRole + 5 ..= Qt::UserRole + 7)
+pub const BACKUP_ROLE: i32 = 261;
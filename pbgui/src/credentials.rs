@@ -0,0 +1,211 @@
+//! Resolves the database password from one of several sources, so that it does
+//! not have to live in plain text inside of the pbgui preferences.
+//!
+//! The sources are consulted in the following order:
+//! * `password_env` - the name of an environment variable holding the password
+//! * `password_command` - a shell command whose stdout is the password
+//! * `passfile` - a `.pgpass`-style file (`host:port:dbname:user:password`)
+//! * `password` - the legacy inline password in the preferences file
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Errors which may arise while resolving the database password
+#[derive(Debug, PartialEq)]
+pub enum CredentialError {
+    /// The named environment variable is not set
+    MissingEnvVar(String),
+    /// The password command could not be run or exited with a non-zero status
+    CommandFailed(String),
+    /// The passfile could not be read
+    PassfileUnreadable(String),
+    /// The passfile is readable by group or other
+    PassfileInsecure(String),
+    /// The passfile does not contain an entry matching the connection
+    NoPassfileEntry(String),
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingEnvVar(var) => {
+                write!(f, "password environment variable {} is not set", var)
+            }
+            Self::CommandFailed(msg) => write!(f, "password command failed: {}", msg),
+            Self::PassfileUnreadable(msg) => write!(f, "unable to read passfile: {}", msg),
+            Self::PassfileInsecure(path) => write!(
+                f,
+                "passfile {} has group or world access; permissions should be 0600 or less",
+                path
+            ),
+            Self::NoPassfileEntry(path) => {
+                write!(f, "no matching entry for connection found in passfile {}", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CredentialError {}
+
+/// The connection coordinates used to look up an entry in a passfile
+#[derive(Debug, PartialEq)]
+pub struct PassfileKey<'a> {
+    pub host: &'a str,
+    pub port: u64,
+    pub dbname: &'a str,
+    pub user: &'a str,
+}
+
+/// Retrieve the password from the named environment variable
+///
+/// # Arguments
+/// * `var` - The name of the environment variable
+///
+/// # Returns
+/// * Ok(password) if the variable is set
+/// * Err(CredentialError) otherwise
+pub fn password_from_env(var: &str) -> Result<String, CredentialError> {
+    std::env::var(var).map_err(|_| CredentialError::MissingEnvVar(var.to_string()))
+}
+
+/// Run the supplied command through the shell, returning its stdout,
+/// minus the trailing newline, as the password.
+///
+/// # Arguments
+/// * `cmd` - The command to execute
+///
+/// # Returns
+/// * Ok(password) if the command succeeds
+/// * Err(CredentialError) otherwise
+pub fn password_from_command(cmd: &str) -> Result<String, CredentialError> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(&["/C", cmd]).output()
+    } else {
+        Command::new("sh").args(&["-c", cmd]).output()
+    }
+    .map_err(|e| CredentialError::CommandFailed(e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(CredentialError::CommandFailed(format!(
+            "exited with {}",
+            output.status
+        )));
+    }
+    let password = String::from_utf8(output.stdout)
+        .map_err(|e| CredentialError::CommandFailed(e.to_string()))?;
+    Ok(password.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+/// Look up the password for the supplied connection in a `.pgpass`-style file.
+/// As with libpq, the file is ignored unless it is only accessible by its owner.
+///
+/// # Arguments
+/// * `path` - The path to the passfile
+/// * `key` - The connection coordinates to match against
+///
+/// # Returns
+/// * Ok(password) if a matching entry is found
+/// * Err(CredentialError) otherwise
+pub fn password_from_passfile(path: &str, key: &PassfileKey) -> Result<String, CredentialError> {
+    check_passfile_permissions(Path::new(path))?;
+    let contents =
+        fs::read_to_string(path).map_err(|e| CredentialError::PassfileUnreadable(e.to_string()))?;
+    lookup_passfile_entry(&contents, key)
+        .ok_or_else(|| CredentialError::NoPassfileEntry(path.to_string()))
+}
+
+#[cfg(unix)]
+fn check_passfile_permissions(path: &Path) -> Result<(), CredentialError> {
+    use std::os::unix::fs::PermissionsExt;
+    let metadata =
+        fs::metadata(path).map_err(|e| CredentialError::PassfileUnreadable(e.to_string()))?;
+    if metadata.permissions().mode() & 0o077 != 0 {
+        return Err(CredentialError::PassfileInsecure(
+            path.display().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_passfile_permissions(_path: &Path) -> Result<(), CredentialError> {
+    Ok(())
+}
+
+// Find the first line in the passfile contents which matches the key. Fields
+// may be `*` to match anything, and `:` and `\` may be escaped with a backslash.
+fn lookup_passfile_entry(contents: &str, key: &PassfileKey) -> Option<String> {
+    let port = key.port.to_string();
+    let wanted = [key.host, port.as_str(), key.dbname, key.user];
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(split_passfile_line)
+        .filter(|fields| fields.len() == 5)
+        .find(|fields| {
+            wanted
+                .iter()
+                .zip(fields.iter())
+                .all(|(want, field)| field == "*" || field == want)
+        })
+        .map(|mut fields| fields.remove(4))
+}
+
+fn split_passfile_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+            }
+            ':' => fields.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> PassfileKey<'static> {
+        PassfileKey {
+            host: "127.0.0.1",
+            port: 5432,
+            dbname: "packrat",
+            user: "postgres",
+        }
+    }
+
+    #[test]
+    fn can_match_passfile_entry_with_wildcards() {
+        let contents = "# comment\nother:5432:packrat:postgres:nope\n*:5432:*:postgres:secret\n";
+        assert_eq!(
+            lookup_passfile_entry(contents, &key()),
+            Some("secret".to_string())
+        );
+    }
+
+    #[test]
+    fn can_unescape_passfile_entry() {
+        let contents = "127.0.0.1:5432:packrat:postgres:se\\:cr\\\\et";
+        assert_eq!(
+            lookup_passfile_entry(contents, &key()),
+            Some("se:cr\\et".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_passfile_entry_is_none() {
+        let contents = "127.0.0.1:5433:packrat:postgres:secret";
+        assert_eq!(lookup_passfile_entry(contents, &key()), None);
+    }
+}
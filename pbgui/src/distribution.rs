@@ -0,0 +1,222 @@
+//! Provides the Distribution type, which models a package at a specific version.
+//!
+//! Distributions are presented as `package-version` (eg `maya-2020.1`). Package names
+//! may themselves contain dashes, even followed by digits (eg `ocio-2-config`), and
+//! versions may carry a pre-release suffix (eg `1.0.0-beta.2`), so the text of a
+//! distribution is ambiguous. Wherever the package and version are known apart, they
+//! are carried apart. Only text, such as a manifest, is parsed: it is split on the
+//! first dash followed by a release of dotted numbers (`2020.1`), failing that on the
+//! first dash followed by a number (`12`), and failing that on the first dash
+//! followed by a digit.
+use packybara::types::IdType;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// Errors which may arise while constructing a Distribution
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DistributionError {
+    /// The package name is empty or contains whitespace
+    InvalidPackage(String),
+    /// The version is empty or contains whitespace
+    InvalidVersion(String),
+    /// The distribution could not be split into a package and version
+    MissingVersion(String),
+}
+
+impl fmt::Display for DistributionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPackage(package) => write!(f, "invalid package name: '{}'", package),
+            Self::InvalidVersion(version) => write!(f, "invalid version: '{}'", version),
+            Self::MissingVersion(dist) => write!(
+                f,
+                "unable to extract package and version from '{}'. expected package-version",
+                dist
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DistributionError {}
+
+/// A package at a specific version, along with its database id, when known. Distributions
+/// are compared and hashed by package and version alone, so that the same distribution is
+/// equal whether or not its id has been looked up.
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    package: String,
+    version: String,
+    id: Option<IdType>,
+}
+
+impl Distribution {
+    /// New up a Distribution from its package and version
+    ///
+    /// # Arguments
+    /// * `package` - The name of the package
+    /// * `version` - The version of the package
+    ///
+    /// # Returns
+    /// * Ok(Distribution) if successful
+    /// * Err(DistributionError) if either the package or version are invalid
+    pub fn new<P: Into<String>, V: Into<String>>(
+        package: P,
+        version: V,
+    ) -> Result<Self, DistributionError> {
+        let package = package.into();
+        let version = version.into();
+        if package.is_empty() || package.contains(char::is_whitespace) {
+            return Err(DistributionError::InvalidPackage(package));
+        }
+        if version.is_empty() || version.contains(char::is_whitespace) {
+            return Err(DistributionError::InvalidVersion(version));
+        }
+        Ok(Self {
+            package,
+            version,
+            id: None,
+        })
+    }
+
+    /// Set the database id of the distribution
+    pub fn with_id(mut self, id: IdType) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// The name of the package
+    pub fn package(&self) -> &str {
+        &self.package
+    }
+
+    /// The version of the package
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The database id of the distribution, if known
+    pub fn id(&self) -> Option<IdType> {
+        self.id
+    }
+}
+
+impl PartialEq for Distribution {
+    fn eq(&self, other: &Self) -> bool {
+        self.package == other.package && self.version == other.version
+    }
+}
+
+impl Eq for Distribution {}
+
+impl Hash for Distribution {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.package.hash(state);
+        self.version.hash(state);
+    }
+}
+
+impl PartialOrd for Distribution {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Distribution {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.package, &self.version).cmp(&(&other.package, &other.version))
+    }
+}
+
+impl FromStr for Distribution {
+    type Err = DistributionError;
+
+    fn from_str(dist: &str) -> Result<Self, Self::Err> {
+        let dist = dist.trim();
+        // the dashes followed by a digit, at which the version may start
+        let splits = dist
+            .char_indices()
+            .zip(dist.chars().skip(1))
+            .filter(|((_, c), next)| *c == '-' && next.is_ascii_digit())
+            .map(|((idx, _), _)| idx)
+            .collect::<Vec<_>>();
+        let release = |split: &&usize| dist[**split + 1..].split('-').next().unwrap_or("");
+        let is_number = |release: &str| release.chars().all(|c| c.is_ascii_digit());
+        let split = splits
+            .iter()
+            .find(|split| {
+                let release = release(split);
+                release.contains('.') && release.split('.').all(|n| !n.is_empty() && is_number(n))
+            })
+            .or_else(|| splits.iter().find(|split| is_number(release(split))))
+            .or_else(|| splits.first())
+            .ok_or_else(|| DistributionError::MissingVersion(dist.to_string()))?;
+        Self::new(&dist[..*split], &dist[split + 1..])
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.package, self.version)
+    }
+}
+
+// distributions are serialized as `package-version`, as they are presented
+impl Serialize for Distribution {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Distribution {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let dist = String::deserialize(deserializer)?;
+        Self::from_str(dist.as_str()).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_hyphenated_package_and_prerelease() {
+        let dist = Distribution::from_str("maya-plugins-1.0.0-beta.2").unwrap();
+        assert_eq!(dist.package(), "maya-plugins");
+        assert_eq!(dist.version(), "1.0.0-beta.2");
+        assert_eq!(dist.to_string(), "maya-plugins-1.0.0-beta.2");
+    }
+
+    #[test]
+    fn can_parse_package_with_dash_and_digit() {
+        let dist = Distribution::from_str("ocio-2-config-1.0").unwrap();
+        assert_eq!(dist.package(), "ocio-2-config");
+        assert_eq!(dist.version(), "1.0");
+        let dist = Distribution::from_str("foo-2d-3").unwrap();
+        assert_eq!(dist.package(), "foo-2d");
+        assert_eq!(dist.version(), "3");
+        let dist = Distribution::from_str("maya-2020.1-rc-1").unwrap();
+        assert_eq!(dist.package(), "maya");
+        assert_eq!(dist.version(), "2020.1-rc-1");
+        let dist = Distribution::from_str("nuke-12").unwrap();
+        assert_eq!(dist.version(), "12");
+    }
+
+    #[test]
+    fn missing_version_is_an_error() {
+        assert_eq!(
+            Distribution::from_str("maya"),
+            Err(DistributionError::MissingVersion("maya".to_string()))
+        );
+        assert!(Distribution::from_str("-1.0.0").is_err());
+    }
+
+    #[test]
+    fn id_is_ignored_by_equality() {
+        let dist = Distribution::new("maya", "2020.1").unwrap();
+        assert_eq!(dist.clone().with_id(12), dist);
+        assert_ne!(Distribution::new("maya", "2020.2").unwrap(), dist);
+    }
+}
@@ -0,0 +1,393 @@
+//! Exports versionpins, as presented in the versionpin table, to csv, json or markdown.
+//! Unlike the versionpin table, which only reports the number of withs for each pin,
+//! the export lists the withs themselves. Pending changes may optionally be included
+//! as additional columns.
+use crate::distribution::{Distribution, DistributionError};
+use crate::messaging::client_proxy::{ClientProxy, ConnectParams};
+use packybara::db::find_all::versionpins::FindAllVersionPinsRow;
+use packybara::packrat::PackratDb;
+use packybara::traits::*;
+use packybara::types::IdType;
+use packybara::LtreeSearchMode;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The supported export formats
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    /// Determine the format from the extension of the supplied path
+    ///
+    /// # Arguments
+    /// * `path` - The path to export to
+    ///
+    /// # Returns
+    /// * Some(ExportFormat) if the extension is recognized
+    /// * None otherwise
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| Self::from_str(ext).ok())
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            "md" | "markdown" => Ok(Self::Markdown),
+            _ => Err(ExportError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// A single exported versionpin
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ExportRow {
+    /// The versionpin id. New, unsaved pins have negative ids.
+    #[serde(skip)]
+    pub vpin_id: IdType,
+    pub distribution: Distribution,
+    pub level: String,
+    pub role: String,
+    pub platform: String,
+    pub site: String,
+    pub withs: Vec<String>,
+    /// The type of any pending changes to the pin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_change: Option<String>,
+    /// The value the pending changes would set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_value: Option<String>,
+}
+
+impl TryFrom<&FindAllVersionPinsRow> for ExportRow {
+    type Error = DistributionError;
+
+    fn try_from(pin: &FindAllVersionPinsRow) -> Result<Self, Self::Error> {
+        let distribution =
+            Distribution::new(pin.distribution.package(), pin.distribution.version())?
+                .with_id(pin.distribution_id);
+        Ok(Self {
+            vpin_id: pin.versionpin_id,
+            distribution,
+            level: pin.coords.level.to_string(),
+            role: pin.coords.role.to_string(),
+            platform: pin.coords.platform.to_string(),
+            site: pin.coords.site.to_string(),
+            withs: pin.withs.clone().unwrap_or_default(),
+            pending_change: None,
+            pending_value: None,
+        })
+    }
+}
+
+/// Errors which may arise while exporting pins
+#[derive(Debug)]
+pub enum ExportError {
+    /// The requested format is not supported
+    UnknownFormat(String),
+    /// Unable to write the export
+    Io(String),
+    /// Unable to retrieve the pins from the database
+    Query(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat(format) => write!(
+                f,
+                "unknown export format '{}'. expected csv, json or md",
+                format
+            ),
+            Self::Io(msg) => write!(f, "unable to write export: {}", msg),
+            Self::Query(msg) => write!(f, "unable to retrieve pins: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err.to_string())
+    }
+}
+
+impl From<DistributionError> for ExportError {
+    fn from(err: DistributionError) -> Self {
+        Self::Query(err.to_string())
+    }
+}
+
+const HEADERS: [&str; 6] = ["distribution", "level", "role", "platform", "site", "withs"];
+const PENDING_HEADERS: [&str; 2] = ["pending_change", "pending_value"];
+
+/// Export the rows to the file at the supplied path
+///
+/// # Arguments
+/// * `path` - The path of the file to write
+/// * `rows` - The rows to export
+/// * `format` - The ExportFormat to write
+/// * `include_pending` - Whether to include the pending change columns
+///
+/// # Returns
+/// * Ok(()) if successful
+/// * Err(ExportError) otherwise
+pub fn export_pins<P: AsRef<Path>>(
+    path: P,
+    rows: &[ExportRow],
+    format: ExportFormat,
+    include_pending: bool,
+) -> Result<(), ExportError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_pins(&mut writer, rows, format, include_pending)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Fill in the withs of each previously saved pin from the database. The
+/// versionpin table only tracks the number of withs per pin.
+///
+/// # Arguments
+/// * `db` - The PackratDb instance
+/// * `rows` - The rows to fill in
+///
+/// # Returns
+/// * Ok(()) if successful
+/// * Err(ExportError) otherwise
+pub fn expand_withs(db: &mut PackratDb, rows: &mut [ExportRow]) -> Result<(), ExportError> {
+    for row in rows.iter_mut().filter(|row| row.vpin_id > 0) {
+        let withs = db
+            .find_all_versionpin_withs(row.vpin_id)
+            .query()
+            .map_err(|e| ExportError::Query(e.to_string()))?;
+        row.withs = withs.into_iter().map(|w| w.with).collect();
+    }
+    Ok(())
+}
+
+/// Query the pins visible at the supplied coordinate and export them. This
+/// backs the command line export, which runs without the ui.
+///
+/// # Arguments
+/// * `params` - The database ConnectParams
+/// * `path` - The path of the file to write
+/// * `level` - The level to query
+/// * `role` - The role to query
+/// * `platform` - The platform to query
+/// * `site` - The site to query
+/// * `format` - The ExportFormat to write
+///
+/// # Returns
+/// * Ok(usize) - the number of pins exported
+/// * Err(ExportError) otherwise
+pub fn export_pins_from_db<P: AsRef<Path>>(
+    params: ConnectParams,
+    path: P,
+    level: &str,
+    role: &str,
+    platform: &str,
+    site: &str,
+    format: ExportFormat,
+) -> Result<usize, ExportError> {
+    let client = ClientProxy::connect(params).map_err(|e| ExportError::Query(e.to_string()))?;
+    let mut db = PackratDb::new(client);
+    let pins = db
+        .find_all_versionpins()
+        .level(level)
+        .role(role)
+        .platform(platform)
+        .site(site)
+        .search_mode(LtreeSearchMode::from_str("ancestor").expect("unable to find search mode"))
+        .query()
+        .map_err(|e| ExportError::Query(e.to_string()))?;
+    let rows = pins
+        .iter()
+        .map(ExportRow::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    export_pins(path, &rows, format, false)?;
+    Ok(rows.len())
+}
+
+/// Write the rows in the requested format
+///
+/// # Arguments
+/// * `writer` - The destination of the export
+/// * `rows` - The rows to export
+/// * `format` - The ExportFormat to write
+/// * `include_pending` - Whether to include the pending change columns
+///
+/// # Returns
+/// * Ok(()) if successful
+/// * Err(ExportError) otherwise
+pub fn write_pins<W: Write>(
+    writer: W,
+    rows: &[ExportRow],
+    format: ExportFormat,
+    include_pending: bool,
+) -> Result<(), ExportError> {
+    let table = rows
+        .iter()
+        .map(|row| row_fields(row, include_pending))
+        .collect::<Vec<_>>();
+    match format {
+        ExportFormat::Csv => write_csv(writer, &table, include_pending),
+        ExportFormat::Json => write_json(writer, rows, include_pending),
+        ExportFormat::Markdown => write_markdown(writer, &table, include_pending),
+    }
+}
+
+fn headers(include_pending: bool) -> Vec<&'static str> {
+    let mut headers = HEADERS.to_vec();
+    if include_pending {
+        headers.extend_from_slice(&PENDING_HEADERS);
+    }
+    headers
+}
+
+fn row_fields(row: &ExportRow, include_pending: bool) -> Vec<String> {
+    let mut fields = vec![
+        row.distribution.to_string(),
+        row.level.clone(),
+        row.role.clone(),
+        row.platform.clone(),
+        row.site.clone(),
+        row.withs.join(","),
+    ];
+    if include_pending {
+        fields.push(row.pending_change.clone().unwrap_or_default());
+        fields.push(row.pending_value.clone().unwrap_or_default());
+    }
+    fields
+}
+
+fn write_csv<W: Write>(
+    writer: W,
+    table: &[Vec<String>],
+    include_pending: bool,
+) -> Result<(), ExportError> {
+    let mut writer = csv::Writer::from_writer(writer);
+    let to_err = |e: csv::Error| ExportError::Io(e.to_string());
+    writer.write_record(headers(include_pending)).map_err(to_err)?;
+    for fields in table {
+        writer.write_record(fields).map_err(to_err)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_json<W: Write>(
+    writer: W,
+    rows: &[ExportRow],
+    include_pending: bool,
+) -> Result<(), ExportError> {
+    let to_err = |e: serde_json::Error| ExportError::Io(e.to_string());
+    if include_pending {
+        serde_json::to_writer_pretty(writer, rows).map_err(to_err)
+    } else {
+        let rows = rows
+            .iter()
+            .cloned()
+            .map(|row| ExportRow {
+                pending_change: None,
+                pending_value: None,
+                ..row
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(writer, &rows).map_err(to_err)
+    }
+}
+
+// escape characters which would otherwise break a markdown table cell
+fn escape_markdown(field: &str) -> String {
+    field.replace('|', "\\|").replace('\n', " ")
+}
+
+fn write_markdown<W: Write>(
+    mut writer: W,
+    table: &[Vec<String>],
+    include_pending: bool,
+) -> Result<(), ExportError> {
+    let headers = headers(include_pending);
+    writeln!(writer, "| {} |", headers.join(" | "))?;
+    writeln!(
+        writer,
+        "|{}|",
+        headers.iter().map(|_| " --- ").collect::<Vec<_>>().join("|")
+    )?;
+    for fields in table {
+        let fields = fields
+            .iter()
+            .map(|f| escape_markdown(f))
+            .collect::<Vec<_>>();
+        writeln!(writer, "| {} |", fields.join(" | "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<ExportRow> {
+        vec![ExportRow {
+            vpin_id: 1,
+            distribution: Distribution::new("maya", "2020.1").unwrap(),
+            level: "dev01".to_string(),
+            role: "model".to_string(),
+            platform: "any".to_string(),
+            site: "any".to_string(),
+            withs: vec!["mtoa".to_string(), "yeti".to_string()],
+            pending_change: Some("ChangeDistribution".to_string()),
+            pending_value: Some("2020.2".to_string()),
+        }]
+    }
+
+    #[test]
+    fn can_write_csv_with_expanded_withs() {
+        let mut out = Vec::new();
+        write_pins(&mut out, &rows(), ExportFormat::Csv, false).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "distribution,level,role,platform,site,withs\nmaya-2020.1,dev01,model,any,any,\"mtoa,yeti\"\n"
+        );
+    }
+
+    #[test]
+    fn can_write_markdown_with_pending() {
+        let mut out = Vec::new();
+        write_pins(&mut out, &rows(), ExportFormat::Markdown, true).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[2],
+            "| maya-2020.1 | dev01 | model | any | any | mtoa,yeti | ChangeDistribution | 2020.2 |"
+        );
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(
+            ExportFormat::from_path("/tmp/pins.md"),
+            Some(ExportFormat::Markdown)
+        );
+        assert_eq!(ExportFormat::from_path("/tmp/pins.txt"), None);
+    }
+}
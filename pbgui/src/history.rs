@@ -0,0 +1,578 @@
+//! Queries the revision history a page at a time, filtered by the database.
+//!
+//! Every transaction saved to the database is recorded as a revision, and a busy
+//! facility accumulates tens of thousands of them. Rather than loading all of them,
+//! the revisions table requests pages of `PAGE_SIZE` revisions matching a
+//! HistoryFilter, newest first, fetching the next page as the user scrolls.
+//!
+//! The history of a single pin is queried in full, as it rarely runs to more than a
+//! few dozen changes.
+use crate::change_type::PinCoord;
+use crate::time_travel::{parse_timestamp, ChangeAction};
+use chrono::NaiveDateTime;
+use packybara::packrat::PackratDb;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The number of revisions requested at a time
+pub const PAGE_SIZE: i64 = 200;
+
+/// The table recording the revisions
+const REVISIONS: &str = "revision";
+/// The view presenting the changes made by each revision, along with the level
+/// and package of the pin changed
+const CHANGES: &str = "pkgchange_view";
+
+/// Constrains the revisions returned. Unset constraints match every revision.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct HistoryFilter {
+    /// Matches authors containing the text, ignoring case
+    pub author: Option<String>,
+    /// Matches revisions made at or after the time
+    pub since: Option<NaiveDateTime>,
+    /// Matches revisions made at or before the time
+    pub until: Option<NaiveDateTime>,
+    /// Matches comments containing the text, ignoring case
+    pub comment: Option<String>,
+    /// Matches revisions changing pins at the show or level, or below it
+    pub level: Option<String>,
+    /// Matches revisions changing pins of the package
+    pub package: Option<String>,
+    /// Matches the revision with the transaction id, and those preceding it
+    pub revision: Option<i64>,
+}
+
+impl HistoryFilter {
+    /// New up a HistoryFilter from the text of the filter bar. Empty fields are
+    /// left unset.
+    ///
+    /// # Arguments
+    /// * `author` - The author text
+    /// * `since` - The start of the date range, as `YYYY-MM-DD [HH:MM[:SS]]`
+    /// * `until` - The end of the date range. A date alone includes the whole day.
+    /// * `comment` - The comment text
+    /// * `level` - The show or level
+    /// * `package` - The package
+    /// * `revision` - The transaction id of the newest revision
+    ///
+    /// # Returns
+    /// * Ok(HistoryFilter) if the dates and revision are valid
+    /// * Err(String) otherwise
+    pub fn from_fields(
+        author: &str,
+        since: &str,
+        until: &str,
+        comment: &str,
+        level: &str,
+        package: &str,
+        revision: &str,
+    ) -> Result<Self, String> {
+        let text = |field: &str| Some(field.trim().to_string()).filter(|f| !f.is_empty());
+        let date = |field: &str, end_of_day: bool| match text(field) {
+            Some(field) => parse_timestamp(field.as_str(), end_of_day).map(Some),
+            None => Ok(None),
+        };
+        Ok(Self {
+            author: text(author),
+            since: date(since, false)?,
+            until: date(until, true)?,
+            comment: text(comment),
+            level: text(level),
+            package: text(package),
+            revision: match text(revision) {
+                Some(revision) => Some(
+                    revision
+                        .parse::<i64>()
+                        .map_err(|_| format!("invalid revision: {}", revision))?,
+                ),
+                None => None,
+            },
+        })
+    }
+
+    /// Determine whether the filter is unconstrained
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    // the query parameters, in the order of the placeholders of revisions_sql
+    fn params(&self) -> Vec<Option<String>> {
+        let contains = |text: &Option<String>| {
+            text.as_ref()
+                .map(|t| format!("%{}%", escape_like(t.as_str())))
+        };
+        let timestamp =
+            |time: &Option<NaiveDateTime>| time.map(|time| time.format("%F %T").to_string());
+        vec![
+            contains(&self.author),
+            timestamp(&self.since),
+            timestamp(&self.until),
+            contains(&self.comment),
+            self.level.clone(),
+            self.level
+                .as_ref()
+                .map(|level| format!("{}.%", escape_like(level.as_str()))),
+            self.package.clone(),
+            self.revision.map(|revision| revision.to_string()),
+        ]
+    }
+}
+
+impl fmt::Display for HistoryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut constraints = Vec::new();
+        if let Some(author) = &self.author {
+            constraints.push(format!("author: {}", author));
+        }
+        if let Some(since) = &self.since {
+            constraints.push(format!("since: {}", since.format("%F %T")));
+        }
+        if let Some(until) = &self.until {
+            constraints.push(format!("until: {}", until.format("%F %T")));
+        }
+        if let Some(comment) = &self.comment {
+            constraints.push(format!("comment: {}", comment));
+        }
+        if let Some(level) = &self.level {
+            constraints.push(format!("level: {}", level));
+        }
+        if let Some(package) = &self.package {
+            constraints.push(format!("package: {}", package));
+        }
+        if let Some(revision) = &self.revision {
+            constraints.push(format!("revision: {} and earlier", revision));
+        }
+        if constraints.is_empty() {
+            write!(f, "all revisions")
+        } else {
+            write!(f, "{}", constraints.join(", "))
+        }
+    }
+}
+
+/// A revision, as presented in the revisions table
+#[derive(Debug, PartialEq, Clone)]
+pub struct RevisionRow {
+    pub transaction_id: i64,
+    pub author: String,
+    /// The time of the revision, formatted for display
+    pub datetime: String,
+    pub comment: String,
+}
+
+/// A page of revisions
+#[derive(Debug, PartialEq)]
+pub struct HistoryPage {
+    /// The filter the revisions match
+    pub filter: HistoryFilter,
+    /// The number of matching revisions preceding the page
+    pub offset: i64,
+    pub revisions: Vec<RevisionRow>,
+    /// Whether further revisions follow the page
+    pub has_more: bool,
+}
+
+impl HistoryPage {
+    /// New up a HistoryPage from the revisions queried for it. Up to `PAGE_SIZE + 1`
+    /// revisions are queried, the last of which, if found, shows that another page
+    /// follows, and is left for it.
+    ///
+    /// # Arguments
+    /// * `filter` - The filter the revisions match
+    /// * `offset` - The number of matching revisions preceding the page
+    /// * `revisions` - The revisions queried
+    ///
+    /// # Returns
+    /// * HistoryPage instance
+    pub fn new(filter: HistoryFilter, offset: i64, mut revisions: Vec<RevisionRow>) -> Self {
+        let has_more = revisions.len() as i64 > PAGE_SIZE;
+        revisions.truncate(PAGE_SIZE as usize);
+        Self {
+            filter,
+            offset,
+            revisions,
+            has_more,
+        }
+    }
+}
+
+/// Tracks the pages of revisions loaded into the revisions table, so that the next
+/// page is requested once, and pages of a previous filter are ignored.
+#[derive(Debug, Default)]
+pub struct HistoryPaging {
+    filter: HistoryFilter,
+    loaded: i64,
+    has_more: bool,
+    loading: bool,
+}
+
+impl HistoryPaging {
+    /// Start over with a new filter, returning the request for its first page
+    ///
+    /// # Arguments
+    /// * `filter` - The filter of the new search
+    ///
+    /// # Returns
+    /// * The filter and offset of the first page
+    pub fn start(&mut self, filter: HistoryFilter) -> (HistoryFilter, i64) {
+        *self = Self {
+            filter: filter.clone(),
+            loaded: 0,
+            has_more: false,
+            loading: true,
+        };
+        (filter, 0)
+    }
+
+    /// Request the next page, unless it is already on its way, or there is none
+    ///
+    /// # Returns
+    /// * Some((filter, offset)) of the next page
+    /// * None otherwise
+    pub fn next_page(&mut self) -> Option<(HistoryFilter, i64)> {
+        if self.loading || !self.has_more {
+            return None;
+        }
+        self.loading = true;
+        Some((self.filter.clone(), self.loaded))
+    }
+
+    /// Record the arrival of a page
+    ///
+    /// # Arguments
+    /// * `page` - The page received from the secondary thread
+    ///
+    /// # Returns
+    /// * true if the page is the one expected, and should be presented
+    /// * false if it belongs to a previous search
+    pub fn page_loaded(&mut self, page: &HistoryPage) -> bool {
+        if page.filter != self.filter || page.offset != self.loaded {
+            return false;
+        }
+        self.loaded += page.revisions.len() as i64;
+        self.has_more = page.has_more;
+        self.loading = false;
+        true
+    }
+}
+
+/// Query a page of revisions matching the filter, newest first. packybara's revisions
+/// query neither filters nor pages, so the query is made over the database's own
+/// connection, within a transaction which is rolled back once the rows are read.
+///
+/// # Arguments
+/// * `db` - The PackratDb instance
+/// * `filter` - The HistoryFilter constraining the revisions
+/// * `offset` - The number of matching revisions to skip
+///
+/// # Returns
+/// * Ok(HistoryPage) if successful
+/// * Err(String) otherwise
+pub fn query_revisions(
+    db: &mut PackratDb,
+    filter: HistoryFilter,
+    offset: i64,
+) -> Result<HistoryPage, String> {
+    let params = filter.params();
+    let mut tx = db.transaction();
+    // one more row than a page is requested, to learn whether another page follows
+    let rows = tx
+        .query(
+            revisions_sql(offset, PAGE_SIZE + 1).as_str(),
+            &[
+                &params[0], &params[1], &params[2], &params[3], &params[4], &params[5], &params[6],
+                &params[7],
+            ],
+        )
+        .map_err(|e| format!("unable to query revisions: {}", e))?;
+    let revisions = rows
+        .iter()
+        .map(|row| RevisionRow {
+            transaction_id: row.get(0),
+            author: row.get(1),
+            datetime: row.get(2),
+            comment: row.get::<_, Option<String>>(3).unwrap_or_default(),
+        })
+        .collect();
+    Ok(HistoryPage::new(filter, offset, revisions))
+}
+
+// The query for a page of revisions. Unset filter parameters are passed as null,
+// which disables their constraint. The patterns matched with LIKE are escaped by
+// escape_like, with the default escape character. Revisions are paged, and filtered
+// by revision, in the order of their transaction ids.
+fn revisions_sql(offset: i64, limit: i64) -> String {
+    format!(
+        "SELECT transaction_id::bigint, author::text, \
+         to_char(datetime, 'YYYY-MM-DD HH12:MI:SS AM'), comment::text \
+         FROM {revisions} \
+         WHERE ($1::text IS NULL OR author::text ILIKE $1) \
+         AND ($2::text IS NULL OR datetime >= $2::timestamp) \
+         AND ($3::text IS NULL OR datetime <= $3::timestamp) \
+         AND ($4::text IS NULL OR comment::text ILIKE $4) \
+         AND ($5::text IS NULL OR transaction_id IN \
+         (SELECT transaction_id FROM {changes} \
+         WHERE level::text = $5 OR level::text LIKE $6)) \
+         AND ($7::text IS NULL OR transaction_id IN \
+         (SELECT transaction_id FROM {changes} WHERE package::text = $7)) \
+         AND ($8::text IS NULL OR transaction_id <= $8::bigint) \
+         ORDER BY transaction_id DESC LIMIT {limit} OFFSET {offset}",
+        revisions = REVISIONS,
+        changes = CHANGES,
+        limit = limit,
+        offset = offset,
+    )
+}
+
+// escape the characters of the text which LIKE would otherwise read as wildcards
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// A change made to a single versionpin, along with the revision making it
+#[derive(Debug, PartialEq, Clone)]
+pub struct PinHistoryEntry {
+    pub transaction_id: i64,
+    /// The action recorded for the change: add, change or delete
+    pub action: String,
+    /// The distribution pinned before the change
+    pub old: Option<String>,
+    /// The distribution pinned after the change
+    pub new: Option<String>,
+    pub author: String,
+    /// The time of the revision, formatted for display
+    pub datetime: String,
+    pub comment: String,
+}
+
+impl PinHistoryEntry {
+    /// Describe the change for the pin history table
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * String describing the change
+    pub fn describe(&self) -> String {
+        let dist = |dist: &Option<String>| dist.clone().unwrap_or_else(|| "?".to_string());
+        match ChangeAction::from_str(self.action.as_str()) {
+            Ok(ChangeAction::Add) => format!("created, pinning {}", dist(&self.new)),
+            Ok(ChangeAction::Change) => format!("{} -> {}", dist(&self.old), dist(&self.new)),
+            Ok(ChangeAction::Delete) => format!("deleted, was pinning {}", dist(&self.old)),
+            Err(_) => format!(
+                "{}: {} -> {}",
+                self.action,
+                dist(&self.old),
+                dist(&self.new)
+            ),
+        }
+    }
+}
+
+/// Query every recorded change to the pin of a package at a coordinate, newest first.
+/// The changes are found by packybara's change query, which records the changes made
+/// to the distributions of pins, but not those made to their withs. Each change is
+/// described by the revision making it.
+///
+/// # Arguments
+/// * `db` - The PackratDb instance
+/// * `coord` - The package and coordinate of the pin
+///
+/// # Returns
+/// * Ok(Vec<PinHistoryEntry>) if successful
+/// * Err(String) otherwise
+pub fn query_pin_history(
+    db: &mut PackratDb,
+    coord: &PinCoord,
+) -> Result<Vec<PinHistoryEntry>, String> {
+    let mut changes = db
+        .find_all_changes()
+        .query()
+        .map_err(|e| format!("unable to look up the changes: {}", e))?;
+    changes.retain(|change| {
+        change.package.to_string() == coord.package
+            && change.level.to_string() == coord.level
+            && change.role.to_string() == coord.role
+            && change.platform.to_string() == coord.platform
+            && change.site.to_string() == coord.site
+    });
+    if changes.is_empty() {
+        return Ok(Vec::new());
+    }
+    changes.sort_by_key(|change| Reverse((change.transaction_id, change.id)));
+    let revisions = db
+        .find_all_revisions()
+        .query()
+        .map_err(|e| format!("unable to look up the revisions: {}", e))?
+        .into_iter()
+        .map(|revision| (revision.transaction_id as i64, revision))
+        .collect::<HashMap<_, _>>();
+    let distribution = |version: String| {
+        Some(version)
+            .filter(|v| !v.is_empty())
+            .map(|v| format!("{}-{}", coord.package, v))
+    };
+    Ok(changes
+        .iter()
+        .map(|change| {
+            let transaction_id = change.transaction_id as i64;
+            let revision = revisions.get(&transaction_id);
+            PinHistoryEntry {
+                transaction_id,
+                action: change.action.to_string(),
+                old: distribution(change.old.version().to_string()),
+                new: distribution(change.new.version().to_string()),
+                author: revision.map_or_else(String::new, |r| r.author.to_string()),
+                datetime: revision
+                    .map_or_else(String::new, |r| r.datetime.format("%F %r").to_string()),
+                comment: revision.map_or_else(String::new, |r| r.comment.to_string()),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_from_fields() {
+        let filter = HistoryFilter::from_fields(
+            " jgerber ",
+            "2020-03-01",
+            "2020-03-10",
+            "",
+            "dev01",
+            "",
+            "",
+        )
+        .unwrap();
+        assert_eq!(filter.author, Some("jgerber".to_string()));
+        assert_eq!(filter.comment, None);
+        assert_eq!(
+            filter.params(),
+            vec![
+                Some("%jgerber%".to_string()),
+                Some("2020-03-01 00:00:00".to_string()),
+                Some("2020-03-10 23:59:59".to_string()),
+                None,
+                Some("dev01".to_string()),
+                Some("dev01.%".to_string()),
+                None,
+                None,
+            ]
+        );
+        let filter = HistoryFilter::from_fields("", "", "", "100%_done", "", "", "").unwrap();
+        assert_eq!(filter.params()[3], Some(r"%100\%\_done%".to_string()));
+        assert!(HistoryFilter::from_fields("", "", "", "", "", "", "")
+            .unwrap()
+            .is_empty());
+        assert!(HistoryFilter::from_fields("", "last week", "", "", "", "", "").is_err());
+        assert!(HistoryFilter::from_fields("", "", "", "", "", "", "tx12").is_err());
+    }
+
+    #[test]
+    fn paging_ignores_stale_pages() {
+        let revision = RevisionRow {
+            transaction_id: 1,
+            author: "jgerber".to_string(),
+            datetime: String::new(),
+            comment: String::new(),
+        };
+        // pages are made from the revisions queried, one more than a page if another follows
+        let page = |filter: &HistoryFilter, offset: i64, count: usize| {
+            HistoryPage::new(filter.clone(), offset, vec![revision.clone(); count])
+        };
+        let mut paging = HistoryPaging::default();
+        let old = HistoryFilter::default();
+        paging.start(old.clone());
+        let (new, offset) = paging.start(HistoryFilter {
+            author: Some("jgerber".to_string()),
+            ..HistoryFilter::default()
+        });
+        assert_eq!(offset, 0);
+        assert!(!paging.page_loaded(&page(&old, 0, 201)));
+        assert_eq!(paging.next_page(), None);
+        let first = page(&new, 0, 201);
+        assert_eq!((first.revisions.len(), first.has_more), (200, true));
+        assert!(paging.page_loaded(&first));
+        assert_eq!(paging.next_page(), Some((new.clone(), 200)));
+        assert_eq!(paging.next_page(), None);
+        assert!(paging.page_loaded(&page(&new, 200, 10)));
+        assert_eq!(paging.next_page(), None);
+    }
+
+    #[test]
+    fn revisions_sql_matches_params() {
+        let filter = HistoryFilter {
+            level: Some("dev_01".to_string()),
+            ..HistoryFilter::default()
+        };
+        let params = filter.params();
+        assert_eq!(params[4], Some("dev_01".to_string()));
+        assert_eq!(params[5], Some(r"dev\_01.%".to_string()));
+        // every parameter has a placeholder, and there are no others
+        let sql = revisions_sql(400, PAGE_SIZE + 1);
+        for idx in 1..=params.len() {
+            assert!(sql.contains(&format!("${}", idx)), "missing ${}", idx);
+        }
+        assert!(!sql.contains(&format!("${}", params.len() + 1)));
+        // the level is cast from its ltree to match it as text
+        assert!(sql.contains("level::text = $5 OR level::text LIKE $6"));
+        assert!(sql.ends_with("ORDER BY transaction_id DESC LIMIT 201 OFFSET 400"));
+    }
+
+    #[test]
+    fn describe_pin_changes() {
+        let entry = |action: &str, old: Option<&str>, new: Option<&str>| PinHistoryEntry {
+            transaction_id: 3,
+            action: action.to_string(),
+            old: old.map(str::to_string),
+            new: new.map(str::to_string),
+            author: "jgerber".to_string(),
+            datetime: String::new(),
+            comment: String::new(),
+        };
+        assert_eq!(
+            entry("add", None, Some("maya-2018.1")).describe(),
+            "created, pinning maya-2018.1"
+        );
+        assert_eq!(
+            entry("change", Some("maya-2018.1"), Some("maya-2019")).describe(),
+            "maya-2018.1 -> maya-2019"
+        );
+        assert_eq!(
+            entry("delete", Some("maya-2019"), None).describe(),
+            "deleted, was pinning maya-2019"
+        );
+    }
+
+    // runs each query against a packrat database, so that a query which does not
+    // match its schema fails. The database is given as a connection string by
+    // PBGUI_TEST_DB. Run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn queries_match_the_schema() {
+        use crate::messaging::client_proxy::{Client, NoTls};
+        let url = std::env::var("PBGUI_TEST_DB").expect("PBGUI_TEST_DB is not set");
+        let client = Client::connect(url.as_str(), NoTls).expect("unable to connect");
+        let mut db = PackratDb::new(client);
+        let filter = HistoryFilter::from_fields(
+            "a",
+            "2000-01-01",
+            "2100-01-01",
+            "a",
+            "facility",
+            "maya",
+            "1000",
+        )
+        .unwrap();
+        query_revisions(&mut db, filter, 0).unwrap();
+        let coord = PinCoord::new("maya", "facility", "any", "any", "any");
+        query_pin_history(&mut db, &coord).unwrap();
+    }
+}
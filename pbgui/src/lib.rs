@@ -1,5 +1,6 @@
 pub mod cache;
 pub mod constants;
+pub mod credentials;
 pub mod slot_functions;
 pub mod utility;
 // needed so that qt wont segfault (what was I referring to????)
@@ -21,15 +22,29 @@ pub(crate) use components::{
     withs_splitter,
 };
 pub(crate) use slot_functions::{
-    choose_distribution, save_packages_xml, save_versionpin_changes, select_history,
-    store_withpackage_changes, update_changes_table, update_versionpin_table, update_withpackages,
+    choose_distribution, export_pins, import_manifest, pin_history, pin_templates,
+    restore_revision,
+    review_versionpin_changes, save_packages_xml, save_versionpin_changes, select_history,
+    store_withpackage_changes, update_changes_table, update_versionpin_table,
+    update_withpackages,
 };
 pub mod change_type;
+pub mod distribution;
+pub mod export;
+pub mod history;
 pub mod main_window;
 pub use main_window::SearchMode;
 pub mod traits;
 pub use traits::{RowSetterTrait, RowTrait};
 
 pub mod logger;
+pub mod manifest;
 pub mod messaging;
+pub mod packages_xml;
+pub mod permissions;
 pub mod prefs;
+pub mod templates;
+pub mod time_travel;
+pub mod tree_search;
+pub mod versions;
+pub mod withs_check;
@@ -1,16 +1,260 @@
 //! Provides the implementation of the Rust Logger for the application
-//! which sends logs to the UI via the messaging module's mechanisms.
+//! which sends logs to the UI via the messaging module's mechanisms, and
+//! optionally writes them to a rotating log file on disk.
+//!
+//! Which records are logged is governed by a `LogFilter`, which may be changed
+//! while the application is running via `set_log_filter`. Filters are written
+//! in the same syntax as `env_logger`: a comma separated list of directives,
+//! each of which is either a level, which sets the default, or `target=level`,
+//! which overrides the level for a target and its children.
+//! (eg `info,pbgui::messaging=debug,packybara=off`)
 use crate::messaging::outgoing::oui_logger::OUiLogger;
 use crate::messaging::OMsg;
 use crate::messaging::Sender;
+use chrono::Local;
+use lazy_static::lazy_static;
 use log::SetLoggerError;
-use log::{Level, Log, Metadata, Record};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, RwLock};
+
+lazy_static! {
+    // the filter consulted by the UiLogger. Shared so that it may be changed at runtime
+    static ref LOG_FILTER: RwLock<LogFilter> = RwLock::new(LogFilter::default());
+}
+
+/// Error returned when a log filter cannot be parsed
+#[derive(Debug, PartialEq)]
+pub struct LogFilterError(String);
+
+impl fmt::Display for LogFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid log filter: {}", self.0)
+    }
+}
+
+impl std::error::Error for LogFilterError {}
+
+/// Determines which records are logged, via a default level and per-target overrides
+#[derive(Debug, PartialEq, Clone)]
+pub struct LogFilter {
+    default: LevelFilter,
+    overrides: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            default: LevelFilter::Warn,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl LogFilter {
+    /// The level applied to targets without an override
+    pub fn default_level(&self) -> LevelFilter {
+        self.default
+    }
+
+    /// The per-target overrides, as a comma separated list of `target=level`
+    pub fn overrides_spec(&self) -> String {
+        self.overrides
+            .iter()
+            .map(|(target, level)| format!("{}={}", target, level.to_string().to_lowercase()))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The most verbose level enabled for any target
+    pub fn max_level(&self) -> LevelFilter {
+        self.overrides
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, std::cmp::max)
+    }
+
+    /// Determine whether a record at the supplied level and target should be logged.
+    /// The override for the most specific matching target wins.
+    pub fn enabled(&self, target: &str, level: Level) -> bool {
+        let matches = |name: &String| {
+            target == name
+                || (target.starts_with(name.as_str()) && target[name.len()..].starts_with("::"))
+        };
+        let filter = self
+            .overrides
+            .iter()
+            .filter(|(name, _)| matches(name))
+            .max_by_key(|(name, _)| name.len())
+            .map_or(self.default, |(_, level)| *level);
+        level <= filter
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = LogFilterError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self::default();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            match parts.next() {
+                Some(level) => {
+                    let level = LevelFilter::from_str(level.trim())
+                        .map_err(|_| LogFilterError(format!("unknown level in '{}'", directive)))?;
+                    if name.is_empty() {
+                        return Err(LogFilterError(format!("missing target in '{}'", directive)));
+                    }
+                    filter.overrides.push((name.to_string(), level));
+                }
+                None => match LevelFilter::from_str(name) {
+                    Ok(level) => filter.default = level,
+                    // as with env_logger, a bare target enables all of its records
+                    Err(_) => filter.overrides.push((name.to_string(), LevelFilter::Trace)),
+                },
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.to_string().to_lowercase())?;
+        if !self.overrides.is_empty() {
+            write!(f, ",{}", self.overrides_spec())?;
+        }
+        Ok(())
+    }
+}
+
+/// Replace the active log filter, taking effect immediately.
+///
+/// # Arguments
+/// * `spec` - The filter, in `env_logger` syntax (eg `info,pbgui::messaging=debug`)
+///
+/// # Returns
+/// * Ok(LogFilter) - the newly active filter
+/// * Err(LogFilterError) if the spec is invalid
+pub fn set_log_filter(spec: &str) -> Result<LogFilter, LogFilterError> {
+    let filter = LogFilter::from_str(spec)?;
+    log::set_max_level(filter.max_level());
+    *LOG_FILTER.write().expect("log filter lock poisoned") = filter.clone();
+    Ok(filter)
+}
+
+/// Retrieve a copy of the active log filter
+pub fn log_filter() -> LogFilter {
+    LOG_FILTER.read().expect("log filter lock poisoned").clone()
+}
+
+/// The name of the log file written by the RotatingFileSink
+pub const LOG_FILE_NAME: &str = "pbgui.log";
+
+/// Writes log records to a file, rotating it once it exceeds a maximum size.
+/// Rotated files are suffixed with a number (pbgui.log.1 being the most recent),
+/// and only `max_files` of them are kept.
+pub struct RotatingFileSink {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: Mutex<(File, u64)>,
+}
+
+impl RotatingFileSink {
+    /// New up a RotatingFileSink writing to `pbgui.log` in the supplied directory,
+    /// which is created if it does not exist.
+    ///
+    /// # Arguments
+    /// * `directory` - The directory to write logs to
+    /// * `max_size` - The size, in bytes, at which the log is rotated
+    /// * `max_files` - The number of rotated logs to keep
+    ///
+    /// # Returns
+    /// * io::Result<RotatingFileSink>
+    pub fn new<P: AsRef<Path>>(directory: P, max_size: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(directory.as_ref())?;
+        let path = directory.as_ref().join(LOG_FILE_NAME);
+        let file = Self::open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_size,
+            max_files,
+            file: Mutex::new((file, size)),
+        })
+    }
+
+    /// The path of the active log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write a record to the log, rotating first if the record would take the
+    /// log past its maximum size.
+    ///
+    /// # Arguments
+    /// * `line` - The formatted record, without a trailing newline
+    ///
+    /// # Returns
+    /// * io::Result<()>
+    pub fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut guard = self.file.lock().expect("log file mutex poisoned");
+        let len = line.len() as u64 + 1;
+        if guard.1 > 0 && guard.1 + len > self.max_size {
+            guard.0.flush()?;
+            self.rotate()?;
+            *guard = (Self::open(&self.path)?, 0);
+        }
+        writeln!(guard.0, "{}", line)?;
+        guard.1 += len;
+        Ok(())
+    }
+
+    /// Flush the active log file
+    pub fn flush(&self) -> io::Result<()> {
+        self.file.lock().expect("log file mutex poisoned").0.flush()
+    }
+
+    fn open(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    fn rotated_path(&self, idx: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", idx));
+        PathBuf::from(path)
+    }
+
+    // shift each rotated log up by one, dropping the oldest
+    fn rotate(&self) -> io::Result<()> {
+        if self.max_files == 0 {
+            return fs::remove_file(&self.path);
+        }
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for idx in (1..self.max_files).rev() {
+            let from = self.rotated_path(idx);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(idx + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))
+    }
+}
 
 /// Stores state for Rust Log implementation which communicates with the UI
 /// Log component
 pub struct UiLogger {
-    min_level: Level,
     to_thread_sender: Sender<OMsg>,
+    file_sink: Option<RotatingFileSink>,
 }
 
 fn cs(input: Option<&str>) -> Option<String> {
@@ -22,7 +266,10 @@ fn cs(input: Option<&str>) -> Option<String> {
 
 impl Log for UiLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.min_level
+        LOG_FILTER
+            .read()
+            .expect("log filter lock poisoned")
+            .enabled(metadata.target(), metadata.level())
     }
 
     fn log(&self, record: &Record) {
@@ -33,6 +280,21 @@ impl Log for UiLogger {
             let line = record.line();
 
             let msg = format!("{}", record.args());
+            if let Some(ref sink) = self.file_sink {
+                let dt = Local::now().format("%a %b %e %T %Y");
+                let entry = format!(
+                    "{} {:<5} {} {}:{} {}",
+                    dt,
+                    level,
+                    target,
+                    file.as_deref().unwrap_or(""),
+                    line.unwrap_or(0),
+                    msg
+                );
+                if let Err(e) = sink.write_line(entry.as_str()) {
+                    eprintln!("unable to write to {}: {}", sink.path().display(), e);
+                }
+            }
             self.to_thread_sender
                 .send(OMsg::UiLogger(OUiLogger::SendLog {
                     level,
@@ -45,7 +307,11 @@ impl Log for UiLogger {
         }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Some(ref sink) = self.file_sink {
+            let _ = sink.flush();
+        }
+    }
 }
 
 impl UiLogger {
@@ -53,29 +319,79 @@ impl UiLogger {
     /// submodule.
     pub fn new(to_thread_sender: Sender<OMsg>) -> Self {
         Self {
-            min_level: Level::Debug,
             to_thread_sender,
+            file_sink: None,
         }
     }
 
+    /// Set the sink which records are written to in addition to the UI.
+    pub fn set_file_sink(&mut self, file_sink: Option<RotatingFileSink>) {
+        self.file_sink = file_sink;
+    }
+
     /// Set the minimum log level to report logs for, as an instance of `Level`.
+    /// Per-target overrides in the active filter are retained.
     pub fn set_log_level(&mut self, level: Level) {
-        self.min_level = level;
+        let mut filter = LOG_FILTER.write().expect("log filter lock poisoned");
+        filter.default = level.to_level_filter();
+        log::set_max_level(filter.max_level());
     }
 }
 
-/// Initialize the logger for the application, given a sender and a default level. This method should
-/// be called once, after the QApplication has been instantiated.
-pub fn init(to_thread_sender: Sender<OMsg>, default_level: &str) -> Result<(), SetLoggerError> {
+/// Initialize the logger for the application, given a sender, a default filter, and an optional
+/// file sink. This method should be called once, after the QApplication has been instantiated.
+/// An invalid filter falls back on the warn level.
+pub fn init(
+    to_thread_sender: Sender<OMsg>,
+    default_level: &str,
+    file_sink: Option<RotatingFileSink>,
+) -> Result<(), SetLoggerError> {
     let mut logger = UiLogger::new(to_thread_sender);
-    let level = match default_level {
-        "trace" => Level::Trace,
-        "debug" => Level::Debug,
-        "info" => Level::Info,
-        "warn" => Level::Warn,
-        "error" => Level::Error,
-        _ => Level::Warn,
-    };
-    logger.set_log_level(level);
-    log::set_boxed_logger(Box::new(logger)).map(|()| log::set_max_level(level.to_level_filter()))
+    logger.set_file_sink(file_sink);
+    let filter = LogFilter::from_str(default_level).unwrap_or_else(|e| {
+        eprintln!("{}. defaulting to warn", e);
+        LogFilter::default()
+    });
+    let max_level = filter.max_level();
+    *LOG_FILTER.write().expect("log filter lock poisoned") = filter;
+    log::set_boxed_logger(Box::new(logger)).map(|()| log::set_max_level(max_level))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_applies_most_specific_override() {
+        let spec = "info, pbgui::messaging=debug,pbgui::messaging::thread=off";
+        let filter = LogFilter::from_str(spec).unwrap();
+        assert!(filter.enabled("pbgui::main_window", Level::Info));
+        assert!(!filter.enabled("pbgui::main_window", Level::Debug));
+        assert!(filter.enabled("pbgui::messaging::event", Level::Debug));
+        assert!(!filter.enabled("pbgui::messaging::thread", Level::Error));
+        assert!(!filter.enabled("pbgui::messaging_ext", Level::Debug));
+        assert_eq!(filter.max_level(), LevelFilter::Debug);
+        assert_eq!(
+            filter.to_string(),
+            "info,pbgui::messaging=debug,pbgui::messaging::thread=off"
+        );
+        assert!(LogFilter::from_str("pbgui=loud").is_err());
+    }
+
+    #[test]
+    fn file_sink_rotates_and_keeps_max_files() {
+        let dir = std::env::temp_dir().join(format!("pbgui_logger_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let sink = RotatingFileSink::new(&dir, 10, 2).unwrap();
+        for msg in &["first", "second", "third", "fourth"] {
+            sink.write_line(msg).unwrap();
+        }
+        sink.flush().unwrap();
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read(LOG_FILE_NAME), "fourth\n");
+        assert_eq!(read("pbgui.log.1"), "third\n");
+        assert_eq!(read("pbgui.log.2"), "second\n");
+        assert!(!dir.join("pbgui.log.3").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
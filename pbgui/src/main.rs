@@ -1,37 +1,43 @@
 #![windows_subsystem = "windows"]
 use crossbeam_channel::{unbounded as channel, Receiver, Sender};
 use main_error::MainError;
-use pbgui::constants::{COL_DISTRIBUTION, COL_LEVEL, COL_PLATFORM, COL_ROLE, COL_SITE};
+use pbgui::constants::{
+    COL_DISTRIBUTION, COL_LEVEL, COL_PLATFORM, COL_ROLE, COL_SITE, PIN_PACKAGE_ROLE,
+};
+use pbgui::distribution::Distribution;
+use pbgui::export::{export_pins_from_db, ExportError, ExportFormat};
+use pbgui::logger::{LogFilter, RotatingFileSink};
 use pbgui::main_window;
+use pbgui::manifest::read_manifest;
 use pbgui::messaging::init;
+use pbgui::permissions::Permissions;
 use pbgui::messaging::{
     event::Event, new_event_handler, thread as pbthread, IMsg, OMsg, OVpinDialog,
 };
 use pbgui::prefs::*;
+use pbgui::slot_functions::{drop_distribution, import_manifest};
 use pbgui::utility::{distribution_from_idx, qs};
 use pbgui_vpin::vpin_dialog;
-use std::collections::HashMap;
 
 use qt_core::{
-    ApplicationAttribute, QChar, QCoreApplication, QModelIndex, QResource, QString, Slot,
+    ApplicationAttribute, QCoreApplication, QModelIndex, QResource, QTimer, Slot,
     SlotOfQModelIndex,
 };
 use qt_thread_conductor::conductor::Conductor;
 use qt_widgets::{
-    cpp_core::{CppBox, MutPtr, Ref},
-    QApplication, QMainWindow,
+    cpp_core::{MutPtr, Ref},
+    QApplication, QMainWindow, QTableWidgetItem, SlotOfQTableWidgetItem,
 };
-use rustqt_utils::{enclose, ToQString};
+use rustqt_utils::enclose;
+use std::cell::RefCell;
 use std::rc::Rc;
 use structopt::StructOpt;
 
-/// Map used to
-type RoleMap = HashMap<String, CppBox<QString>>;
 #[derive(StructOpt, Debug, PartialEq)]
 pub struct PbGui {
     /// Set the log level. This may target one or more
-    /// specific modules or be general.
-    /// (levels: trace, debug, info, warn, error)
+    /// specific modules or be general, using env_logger syntax.
+    /// (levels: trace, debug, info, warn, error. eg info,pbgui::messaging=debug)
     #[structopt(long)]
     pub loglevel: Option<String>,
 
@@ -43,6 +49,36 @@ pub struct PbGui {
     /// in the user's work directory in addition to standard locations.
     #[structopt(short, long)]
     pub testmode: bool,
+
+    /// Start in read-only mode. Pins may be browsed but not changed.
+    #[structopt(long)]
+    pub readonly: bool,
+
+    /// Import versionpin changes from a csv or yaml manifest at startup.
+    /// Valid entries are loaded as pending changes.
+    #[structopt(short, long)]
+    pub import: Option<String>,
+
+    /// Export the pins visible at the supplied level, role, platform and site
+    /// to a csv, json or md file and exit, without starting the ui.
+    #[structopt(long)]
+    pub export_pins: Option<String>,
+
+    /// The level to export pins from (used with --export-pins)
+    #[structopt(long, default_value = "facility")]
+    pub level: String,
+
+    /// The role to export pins from (used with --export-pins)
+    #[structopt(long, default_value = "any")]
+    pub role: String,
+
+    /// The platform to export pins from (used with --export-pins)
+    #[structopt(long, default_value = "any")]
+    pub platform: String,
+
+    /// The site to export pins from (used with --export-pins)
+    #[structopt(long, default_value = "any")]
+    pub site: String,
 }
 
 //fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -62,7 +98,7 @@ fn main() -> Result<(), MainError> {
         PbGui { testmode, .. } => testmode,
     };
 
-    let preference = if let PbGui {
+    let mut preference = if let PbGui {
         prefs: Some(ref prefs),
         ..
     } = opt
@@ -77,6 +113,50 @@ fn main() -> Result<(), MainError> {
         };
         PbguiPrefs::load(&finder, ctx)?
     };
+    preference.database.resolve_password()?;
+    if let Some(ref path) = opt.export_pins {
+        let format = ExportFormat::from_path(path)
+            .ok_or_else(|| ExportError::UnknownFormat(path.to_string()))?;
+        let count = export_pins_from_db(
+            preference.as_connectparams(),
+            path,
+            opt.level.as_str(),
+            opt.role.as_str(),
+            opt.platform.as_str(),
+            opt.site.as_str(),
+            format,
+        )?;
+        println!("exported {} pins to {}", count, path);
+        return Ok(());
+    }
+    // read the manifest up front, so that a malformed manifest is reported
+    // before the ui starts
+    let manifest_entries = match opt.import {
+        Some(ref manifest) => Some(read_manifest(manifest)?),
+        None => None,
+    };
+    let log_file_sink = if preference.log.file {
+        let directory = preference.log.directory();
+        match RotatingFileSink::new(
+            directory.as_str(),
+            preference.log.max_file_size,
+            preference.log.max_files,
+        ) {
+            Ok(sink) => Some(sink),
+            Err(e) => {
+                eprintln!("unable to write logs to {}: {}", directory, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let user = whoami::username();
+    let permissions = if opt.readonly {
+        Permissions::readonly(user, "pbgui was started with --readonly")
+    } else {
+        Permissions::from_prefs(&preference, user.as_str())
+    };
     // {
     //     env::set_var("RUST_LOG", level);
     // }
@@ -102,41 +182,62 @@ fn main() -> Result<(), MainError> {
         let _result = QResource::register_resource_q_string(&qs(
             "/Users/jgerber/bin/pbgui-resources/pbgui_logger.rcc",
         ));
-        let pbgui_root = main_window::MainWindow::new(to_thread_sender.clone());
+        let pbgui_root = main_window::MainWindow::new(to_thread_sender.clone(), permissions);
+        pbgui_root
+            .main_win()
+            .save_review_dialog()
+            .set_confirm_threshold(preference.save_review.confirm_threshold);
+        pbgui_root
+            .main_win()
+            .logger()
+            .set_max_rows(preference.log.max_rows);
+        pbgui_root
+            .main_win()
+            .set_template_library(preference.templates.library());
+        // reflect the initial log filter in the log level selector. As with
+        // logger::init, an invalid filter falls back on the default
+        let filter = log_level.parse::<LogFilter>().unwrap_or_default();
+        pbgui_root.main_win().logger().inner().set_log_level_selection(
+            filter.default_level().to_string().as_str(),
+            filter.overrides_spec().as_str(),
+        );
         init::packages_tree::init(to_thread_sender.clone());
         init::package_withs::init(to_thread_sender.clone());
         init::main_toolbar::init(to_thread_sender.clone());
 
         let dialog = Rc::new(create_dialog("unset", "unset", pbgui_root.main()));
+        // the distribution presented by the dialog
+        let dialog_dist: Rc<RefCell<Option<Distribution>>> = Rc::new(RefCell::new(None));
         init::vpin_dialog::init(to_thread_sender.clone(), "facility");
+        if let Some(entries) = manifest_entries {
+            if pbgui_root.main_win().permissions().is_readonly() {
+                log::warn!("pbgui is read-only. skipping manifest import");
+            } else {
+                import_manifest::send_manifest_entries(entries, &to_thread_sender);
+            }
+        }
 
         // we create a slot that is triggered when OK is pressed to act only in the event
         // that the user has requested action.
         let inner_main_win = pbgui_root.main_win();
         let accepted_slot = Slot::new(
             // TODO: move implementation to slot_functions
-            enclose! { (dialog, inner_main_win, to_thread_sender) move || {
-                let roles = if let Some(roles) = dialog.selected_roles() {
-                    roles
-                } else {
-                    vec!["any".to_string()]
-                };
-
-                let level = if let Some(selected_level) = dialog.selected_level() {
-                     selected_level
-                } else {
-                    dialog.show_name()
-                };
-
-                let site = match dialog.selected_site(){
-                    Some(site) => site,
-                    None =>"any".to_string()
+            enclose! { (dialog, dialog_dist, inner_main_win, to_thread_sender) move || {
+                let dist = match dialog_dist.borrow().clone() {
+                    Some(dist) => dist,
+                    None => {
+                        log::error!("the vpin dialog has no distribution");
+                        return;
+                    }
                 };
 
-                let dist = dialog.distribution();
-
-                // TODO: pass in platform
-                let platform = "any".to_string();
+                // one pin per level, role, site and platform left checked in the dialog's preview
+                let mut combinations = dialog.selected_combinations();
+                if combinations.is_empty() {
+                    log::warn!("no level, role, site and platform combinations selected. skipping");
+                    dialog.accept();
+                    return;
+                }
 
                 // Identify whether the vpin table either (a) already has a pkgcoord that matches our
                 // choices or (b) whether the query button has yet to be pressed. In either case we log and return
@@ -149,76 +250,41 @@ fn main() -> Result<(), MainError> {
                 //     dialog.accept();
                 //     return;
                 // }
-                let level_qs = &level.to_qstring();
-                let mut roles_map = roles.into_iter().fold( RoleMap::new(), |mut acc, rol| {
-                    acc.insert(rol.clone(), rol.to_qstring()); acc}
-                );
-                if roles_map.is_empty() {
-                    log::error!("roles_map is empty but should not be");
-                }
-                let platform_qs = platform.to_qstring();
-                let site_qs = site.to_qstring();
-                let package_qs = dialog.package_qs();
-                let dash = QChar::from_int(45); // 45 is ascii code for dash
                 // check to see if we match the package and coords
                 for row in 0..cnt {
-                    let level_ = vpin_table.item(row,COL_LEVEL);
-                     if level_qs.compare_q_string(level_.text().as_ref()) != 0 {
-                         continue;
-                        }
-
-                    let platform_ = vpin_table.item(row, COL_PLATFORM);
-                    if platform_qs.compare_q_string(platform_.text().as_ref()) != 0 {
-                        continue;}
-
-                    let site_ =  vpin_table.item(row, COL_SITE);
-                    if site_qs.compare_q_string(site_.text().as_ref()) != 0 {
-                        continue;}
-
-                    let distribution = vpin_table.item(row, COL_DISTRIBUTION).text();
-                    // doenst work unless the following is split in two
-                    let package_ = distribution.split_q_char(dash.as_ref());
-                    let package_ = package_.first();
-                    if package_qs.compare_q_string(package_) != 0 {
+                    let level_ = vpin_table.item(row,COL_LEVEL).text().to_std_string();
+                    let package_ = vpin_table
+                        .item(row, COL_DISTRIBUTION)
+                        .data(PIN_PACKAGE_ROLE)
+                        .to_string()
+                        .to_std_string();
+                    if package_ != dist.package() {
                         continue;
-                    } ;
-
-                    //now we tackle roles. we remove any roles from the map that match, as roles is the
-                    let role_ =  vpin_table.item(row, COL_ROLE).text();
-                    let mut remove = Vec::new();
-                    {
-                        for (role_str,role_qs) in roles_map.iter() {
-                            if role_.compare_q_string(role_qs) == 0 {
-                                // have to clone this as it relates to the map borrow
-                                remove.push(role_str.clone());
-                            }
-                        }
-                    }
-                    for role_str in remove {
-                        roles_map.remove(&role_str);
                     }
+                    // remove any combinations which the row already pins
+                    let role_ = vpin_table.item(row, COL_ROLE).text().to_std_string();
+                    let site_ = vpin_table.item(row, COL_SITE).text().to_std_string();
+                    let platform_ = vpin_table.item(row, COL_PLATFORM).text().to_std_string();
+                    combinations.retain(|combination| {
+                        combination.level != level_
+                            || combination.role != role_
+                            || combination.site != site_
+                            || combination.platform != platform_
+                    });
                 }
 
-                if roles_map.is_empty() {
+                if combinations.is_empty() {
                     log::warn!("requested package and pkgcoordinates match existing items in versionpin table. skipping");
                     dialog.accept();
                     return;
                 }
 
-                // now we create roles from the remaining keys in roles_map.
-                let roles = roles_map.drain().fold(Vec::new(), |mut acc, (k,_v)| {acc.push(k); acc});
                 to_thread_sender
                 .send(OMsg::VpinDialog(
                     OVpinDialog::SetVpin {
                         dist,
-                        // and one or more roles
-                        roles,
-                        // at the supplied level
-                        level,
-                        // and site
-                        site,
-                        // and platform
-                        platform,
+                        // for each level, role, site and platform
+                        combinations,
                     },
                 ))
                 .expect("unable to get vpins");
@@ -233,9 +299,16 @@ fn main() -> Result<(), MainError> {
         let mtoolbar = pbgui_root.main_win().main_toolbar();
 
         let exec_dialog_slot = SlotOfQModelIndex::new(
-            enclose! { (dialog, to_thread_sender) move |idx: Ref<QModelIndex>| {
+            enclose! { (dialog, dialog_dist, to_thread_sender, mtoolbar, inner_main_win)
+            move |idx: Ref<QModelIndex>| {
+                if inner_main_win.is_time_travelling() {
+                    log::warn!("unable to pin while viewing a past revision");
+                    return;
+                }
                 if let Some(dist) = distribution_from_idx(idx) {
-                    dialog.set_distribution(dist.as_str());
+                    dialog.set_distribution(dist.to_string().as_str());
+                    dialog_dist.replace(Some(dist));
+                    dialog.set_preset(None);
                     let show = mtoolbar.show_string();
                     dialog.set_show_name(show.as_str());
                     update_vpin_dialog(&to_thread_sender, show);
@@ -250,6 +323,50 @@ fn main() -> Result<(), MainError> {
             .clicked()
             .connect(&exec_dialog_slot);
 
+        // distributions dropped on the versionpin table are handled as Qt applies them to
+        // the table. The vpin dialog is opened once the drop has finished.
+        let dropped_dist: Rc<RefCell<Option<Distribution>>> = Rc::new(RefCell::new(None));
+        let mut drop_timer = QTimer::new_0a();
+        drop_timer.set_single_shot(true);
+        let mut drop_timer_ptr = drop_timer.as_mut_ptr();
+        let item_changed_slot = SlotOfQTableWidgetItem::new(
+            enclose! { (dropped_dist, inner_main_win, to_thread_sender)
+            move |item: MutPtr<QTableWidgetItem>| {
+                if !drop_distribution::is_dropped_item(item) {
+                    drop_distribution::backup_item(item);
+                    return;
+                }
+                let dist = drop_distribution::drop_distribution(
+                    inner_main_win.clone(),
+                    to_thread_sender.clone(),
+                    item,
+                );
+                if let Some(dist) = dist {
+                    dropped_dist.replace(Some(dist));
+                    drop_timer_ptr.start_1a(0);
+                }
+            }},
+        );
+        let drop_slot = Slot::new(
+            enclose! { (dialog, dialog_dist, dropped_dist, inner_main_win, to_thread_sender,
+                mtoolbar) move || {
+                if let Some(dist) = dropped_dist.borrow_mut().take() {
+                    dialog.set_distribution(dist.to_string().as_str());
+                    dialog_dist.replace(Some(dist));
+                    dialog.set_preset(Some(drop_distribution::toolbar_preset(&inner_main_win)));
+                    let show = mtoolbar.show_string();
+                    dialog.set_show_name(show.as_str());
+                    update_vpin_dialog(&to_thread_sender, show);
+                    let _result = dialog.dialog_mut().exec();
+                }
+            }},
+        );
+        inner_main_win
+            .vpin_table()
+            .item_changed()
+            .connect(&item_changed_slot);
+        drop_timer.timeout().connect(&drop_slot);
+
         let app_update = new_event_handler(dialog.clone(), pbgui_root.main_win(), receiver);
 
         let my_conductor = Conductor::<Event>::new(&app_update);
@@ -258,12 +375,14 @@ fn main() -> Result<(), MainError> {
 
         pbthread::create(
             preference.as_connectparams(),
+            preference.install.roots(),
             pbgui_root.main(),
             my_conductor,
             sender,
             to_thread_receiver,
             to_thread_sender,
             log_level,
+            log_file_sink,
         )
     });
 }
@@ -294,6 +413,9 @@ pub fn update_vpin_dialog(to_thread_sender: &Sender<OMsg>, show: String) {
     to_thread_sender
         .send(OMsg::VpinDialog(OVpinDialog::GetSites))
         .expect("unable to get sites");
+    to_thread_sender
+        .send(OMsg::VpinDialog(OVpinDialog::GetPlatforms))
+        .expect("unable to get platforms");
     to_thread_sender
         .send(OMsg::VpinDialog(OVpinDialog::GetLevels(show)))
         .expect("unable to get levels");
@@ -302,6 +424,7 @@ pub fn update_vpin_dialog(to_thread_sender: &Sender<OMsg>, show: String) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use qt_core::{QChar, QString};
     #[test]
     fn qchar_dash() {
         unsafe {
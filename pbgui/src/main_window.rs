@@ -1,40 +1,62 @@
 //! Provides the MainWindow component, which, as it sounds, houses the QMainWindow for the application.
 
 use crate::components::dist_tree::tree;
+use crate::components::packages_xml_dialog::PackagesXmlDialog;
+use crate::components::pin_history::PinHistory;
+use crate::components::revisions_filter::RevisionsFilter;
+use crate::components::save_review_dialog::SaveReviewDialog;
+use crate::components::version_picker_dialog::VersionPickerDialog;
+use crate::change_type::PinCoord;
+use crate::history::HistoryPaging;
+use crate::manifest::ManifestEntry;
+use crate::messaging::outgoing::OPackageWiths;
 use crate::messaging::OMsg;
 use crate::messaging::Sender;
+use crate::permissions::Permissions;
+use crate::time_travel::{AsOf, PastPins};
+use crate::traits::RowTrait;
+use crate::versionpin_row::VersionPinRow;
 use crate::{
     bottom_stacked_widget::create_bottom_stacked_widget,
     cache::PinChangesCache,
     center_widget,
     choose_distribution::choose_alternative_distribution,
-    constants::COL_REV_TXID,
-    left_toolbar, package_withs_list, packages_tree,
-    save_packages_xml::save_packages_xml,
+    export_pins::export_pins,
+    import_manifest::import_manifest,
+    pin_history::{goto_revision, select_pin_history, update_pin_history},
+    pin_templates::{apply_pin_template, save_pin_template},
+    restore_revision::restore_revision,
+    constants::{COL_LEVEL, COL_REV_TXID},
+    left_toolbar, logger, package_withs_list, packages_tree,
+    review_versionpin_changes::request_save_review,
+    save_packages_xml::{save_packages_xml, write_packages_xml},
     save_versionpin_changes::save_versionpin_changes,
-    select_history::select_history,
+    select_history::{fetch_more_history, search_history, select_history},
     store_withpackage_changes,
     update_changes_table::update_changes_table,
     update_versionpin_table::update_vpin_table,
-    update_withpackages::update_withpackages,
+    update_withpackages::{active_vpin_row, update_withpackages},
     utility::{create_vlayout, load_stylesheet, qs, resize_window_to_screen},
     versionpin_table, versionpin_table_splitter, withs_splitter, LeftToolBarActions,
 };
 use log;
+use packybara::types::IdType;
 use pbgui_logger::LogWin;
 use pbgui_menubar::MenuBar;
 use pbgui_toolbar::toolbar;
 use pbgui_withs::WithsList;
 
 use qt_core::{
-    QItemSelection, QPoint, QString, Slot, SlotOfBool, SlotOfQItemSelectionQItemSelection,
+    QItemSelection, QModelIndex, QPoint, QString, Slot, SlotOfBool, SlotOfInt,
+    SlotOfQItemSelectionQItemSelection, SlotOfQModelIndexIntInt,
 };
 use qt_gui::QIcon;
 use qt_gui::QKeySequence;
 use qt_widgets::{
     cpp_core::{CppBox, MutPtr, Ref as QRef},
-    QAction, QMainWindow, QMenu, QMenuBar, QPushButton, QShortcut, QSplitter, QStackedWidget,
-    QTableWidget, QToolButton, QVBoxLayout, QWidget, SlotOfQPoint,
+    q_abstract_item_view::DragDropMode,
+    QAction, QLabel, QMainWindow, QMenu, QMenuBar, QMessageBox, QPushButton, QShortcut,
+    QSplitter, QStackedWidget, QTableWidget, QToolButton, QVBoxLayout, QWidget, SlotOfQPoint,
 };
 use rustqt_utils::enclose;
 use std::cell::RefCell;
@@ -71,13 +93,26 @@ pub struct InnerMainWindow<'a> {
     revision_changes_table: MutPtr<QTableWidget>,
     history_button: MutPtr<QToolButton>,
     revisions_table: MutPtr<QTableWidget>,
+    revisions_filter: RevisionsFilter,
+    history_paging: RefCell<HistoryPaging>,
     log_win: Rc<LogWin<'a>>,
     log_button: MutPtr<QToolButton>,
+    pin_history_button: MutPtr<QToolButton>,
+    pin_history: PinHistory,
     toggle_log_ctrls_button: MutPtr<QPushButton>,
     dist_popup_menu: MutPtr<QMenu>,
     dist_popup_action: MutPtr<QAction>,
+    save_template_action: MutPtr<QAction>,
     left_toolbar_actions: LeftToolBarActions,
     search_shortcut: MutPtr<QShortcut>,
+    readonly_banner: MutPtr<QLabel>,
+    time_travel_banner: MutPtr<QLabel>,
+    permissions: Rc<Permissions>,
+    save_review_dialog: Rc<SaveReviewDialog<'a>>,
+    version_picker_dialog: Rc<VersionPickerDialog<'a>>,
+    packages_xml_dialog: Rc<PackagesXmlDialog>,
+    template_library: RefCell<String>,
+    past_pins: RefCell<Option<PastPins>>,
 }
 
 impl<'a> InnerMainWindow<'a> {
@@ -92,6 +127,7 @@ impl<'a> InnerMainWindow<'a> {
     /// slots and external consumers.
     pub fn new(
         to_thread_sender: Sender<OMsg>,
+        permissions: Permissions,
     ) -> (
         InnerMainWindow<'a>,
         CppBox<QMainWindow>,
@@ -104,6 +140,12 @@ impl<'a> InnerMainWindow<'a> {
                 create_main_window();
             let mut main_window_ptr = main_window.as_mut_ptr();
             let main_toolbar = Rc::new(create_top_toolbar(main_window_ptr));
+            // banner explaining why editing is disabled. hidden unless read-only
+            let readonly_banner =
+                create_banner(&mut main_layout_ptr, "ReadOnlyBanner", "rgb(150, 60, 60)");
+            // banner naming the revision shown while time travelling
+            let time_travel_banner =
+                create_banner(&mut main_layout_ptr, "TimeTravelBanner", "rgb(170, 110, 30)");
 
             // create left toolbar
             let left_toolbar_actions =
@@ -127,6 +169,7 @@ impl<'a> InnerMainWindow<'a> {
             let (
                 pinchanges_ptr,
                 revisions_ptr,
+                revisions_filter,
                 changes_table_ptr,
                 log_win,
                 save_button,
@@ -134,6 +177,8 @@ impl<'a> InnerMainWindow<'a> {
                 pinchanges_button_ptr,
                 history_button_ptr,
                 log_button,
+                pin_history_button,
+                pin_history,
                 toggle_log_ctrls_button,
                 controls_ptr,
                 mode_icon,
@@ -147,6 +192,9 @@ impl<'a> InnerMainWindow<'a> {
             let _choose_withs_action =
                 dist_popup_menu.add_action_q_string(&QString::from_std_str("Withs"));
 
+            let save_template_action = dist_popup_menu
+                .add_action_q_string(&QString::from_std_str("Save Selection As Template..."));
+
             let dist_popup_menu_ptr = dist_popup_menu.as_mut_ptr();
 
             // create the with with package list on the right hand side
@@ -160,6 +208,13 @@ impl<'a> InnerMainWindow<'a> {
             let search_shortcut =
                 QShortcut::new_2a(key_seq.as_ref(), item_list_ptr.borrow().main());
 
+            // dialog used to review pending changes before they are saved
+            let save_review_dialog = Rc::new(SaveReviewDialog::create(main_widget_ptr));
+            // dialog used to choose an alternative version of a pinned distribution
+            let version_picker_dialog = Rc::new(VersionPickerDialog::create(main_widget_ptr));
+            // dialog used to preview a packages.xml before it is written
+            let packages_xml_dialog = Rc::new(PackagesXmlDialog::create(main_widget_ptr));
+
             // persist data
             let pinchanges_cache = Rc::new(PinChangesCache::new());
             // final housekeeping before showing main window
@@ -191,15 +246,28 @@ impl<'a> InnerMainWindow<'a> {
                 bottom_ctrls_stacked_widget: controls_ptr,
                 dist_popup_menu: dist_popup_menu_ptr,
                 dist_popup_action: choose_dist_action,
+                save_template_action,
                 pin_changes_button: pinchanges_button_ptr,
                 revision_changes_table: changes_table_ptr,
                 history_button: history_button_ptr,
                 revisions_table: revisions_ptr,
+                revisions_filter,
+                history_paging: RefCell::new(HistoryPaging::default()),
                 log_win: Rc::new(log_win),
                 log_button,
+                pin_history_button,
+                pin_history,
                 toggle_log_ctrls_button,
                 left_toolbar_actions,
                 search_shortcut: search_shortcut.into_ptr(),
+                readonly_banner,
+                time_travel_banner,
+                permissions: Rc::new(permissions),
+                save_review_dialog,
+                version_picker_dialog,
+                packages_xml_dialog,
+                template_library: RefCell::new(String::new()),
+                past_pins: RefCell::new(None),
             };
 
             //
@@ -208,6 +276,7 @@ impl<'a> InnerMainWindow<'a> {
 
             // configuration
             view_withs.set_checked(false);
+            main_window_inst.apply_permissions();
 
             (main_window_inst, main_window, dist_popup_menu, mode_icon)
         }
@@ -438,6 +507,29 @@ impl<'a> InnerMainWindow<'a> {
         self.log_button
     }
 
+    /// Returns a mutable pointer to the pin history button
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * MutPtr<QToolButton>
+    pub fn pin_history_button(&self) -> MutPtr<QToolButton> {
+        self.pin_history_button
+    }
+
+    /// Returns a reference to the page presenting the history of the selected
+    /// versionpin
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * &PinHistory
+    pub fn pin_history(&self) -> &PinHistory {
+        &self.pin_history
+    }
+
     /// Returns a mutable pointer to the toggle log controls button
     ///
     /// # Arguments
@@ -459,6 +551,29 @@ impl<'a> InnerMainWindow<'a> {
         self.revisions_table
     }
 
+    /// Returns a reference to the filter bar above the revisions table
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * &RevisionsFilter
+    pub fn revisions_filter(&self) -> &RevisionsFilter {
+        &self.revisions_filter
+    }
+
+    /// Returns the paging state of the revisions table, which tracks the pages of
+    /// revisions requested and received
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * &RefCell<HistoryPaging>
+    pub fn history_paging(&self) -> &RefCell<HistoryPaging> {
+        &self.history_paging
+    }
+
     /// Returns a reference to the LeftToolBarActions instance, which collects
     /// all of the left toolbar's QActions and makes them available.
     ///
@@ -504,6 +619,245 @@ impl<'a> InnerMainWindow<'a> {
     pub fn dist_popup_action(&self) -> MutPtr<QAction> {
         self.dist_popup_action
     }
+
+    /// Returns a mutable pointer to the popup action which saves the selected
+    /// versionpins as a template
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * MutPtr<QAction>
+    pub fn save_template_action(&self) -> MutPtr<QAction> {
+        self.save_template_action
+    }
+
+    /// Returns the path to the pin template library
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * The path as a String
+    pub fn template_library(&self) -> String {
+        self.template_library.borrow().clone()
+    }
+
+    /// Set the path to the pin template library
+    ///
+    /// # Arguments
+    /// * `path` - The path to the yaml library
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_template_library<I: Into<String>>(&self, path: I) {
+        self.template_library.replace(path.into());
+    }
+
+    /// Returns a reference counted pointer to the user's Permissions
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Rc<Permissions>
+    pub fn permissions(&self) -> Rc<Permissions> {
+        self.permissions.clone()
+    }
+
+    /// Returns a reference counted pointer to the SaveReviewDialog
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Rc<SaveReviewDialog>
+    pub fn save_review_dialog(&self) -> Rc<SaveReviewDialog<'a>> {
+        self.save_review_dialog.clone()
+    }
+
+    /// Returns a reference counted pointer to the VersionPickerDialog
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Rc<VersionPickerDialog>
+    pub fn version_picker_dialog(&self) -> Rc<VersionPickerDialog<'a>> {
+        self.version_picker_dialog.clone()
+    }
+
+    /// Returns a reference counted pointer to the PackagesXmlDialog
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Rc<PackagesXmlDialog>
+    pub fn packages_xml_dialog(&self) -> Rc<PackagesXmlDialog> {
+        self.packages_xml_dialog.clone()
+    }
+
+    /// Determine whether the versionpin table presents the pins of a past revision
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * bool
+    pub fn is_time_travelling(&self) -> bool {
+        self.past_pins.borrow().is_some()
+    }
+
+    /// Enter or leave time travel. While the versionpin table presents the pins of a
+    /// past revision, the banner naming it is shown and editing is disabled.
+    ///
+    /// # Arguments
+    /// * `past` - The point in time and the pins reconstructed for it, or None to
+    /// return to the present
+    ///
+    /// # Returns
+    /// * None
+    pub fn set_past_pins(&self, past: Option<(AsOf, PastPins)>) {
+        unsafe {
+            let mut banner = self.time_travel_banner;
+            let mut restore_btn = self.main_toolbar.restore_btn();
+            let readonly = self.permissions.is_readonly();
+            match past {
+                Some((as_of, pins)) => {
+                    banner.set_text(&qs(format!(
+                        "Viewing the versionpins as of {} (revision {}). Read-only.",
+                        as_of, pins.revision
+                    )));
+                    banner.set_visible(true);
+                    restore_btn.set_enabled(!readonly && !pins.is_current());
+                    self.past_pins.replace(Some(pins));
+                    self.set_editing_enabled(false);
+                    // the withs list presents the withs of the selected pin of the past
+                    self.package_withs_list().borrow().clear();
+                }
+                None => {
+                    banner.set_visible(false);
+                    restore_btn.set_enabled(false);
+                    self.past_pins.replace(None);
+                    self.set_editing_enabled(!readonly);
+                }
+            }
+        }
+    }
+
+    /// Retrieve the revision being viewed, along with the changes which would restore
+    /// the versionpins to it
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Some((revision, changes as manifest entries)) while time travelling
+    /// * None otherwise
+    pub fn restore_entries(&self) -> Option<(IdType, Vec<ManifestEntry>)> {
+        self.past_pins
+            .borrow()
+            .as_ref()
+            .map(|pins| (pins.revision, pins.restore_entries()))
+    }
+
+    /// Retrieve the withs of the pin of the past presented at a row of the versionpin
+    /// table, while time travelling
+    ///
+    /// # Arguments
+    /// * `row` - The row of the versionpin table
+    ///
+    /// # Returns
+    /// * Some(withs) if the pin's withs at the revision are known
+    /// * None otherwise
+    pub fn past_withs(&self, row: i32) -> Option<Vec<String>> {
+        let past_pins = self.past_pins.borrow();
+        let pins = past_pins.as_ref()?;
+        let row = VersionPinRow::<CppBox<QString>>::from_table_at_row(&self.vpin_table, row)?;
+        let coord = unsafe {
+            PinCoord::new(
+                row.distribution.package(),
+                row.level.to_std_string(),
+                row.role.to_std_string(),
+                row.platform.to_std_string(),
+                row.site.to_std_string(),
+            )
+        };
+        pins.pins
+            .iter()
+            .find(|pin| pin.coord() == coord)
+            .and_then(|pin| pin.withs.clone())
+    }
+
+    /// Configure the ui according to the user's permissions. In read-only mode, the
+    /// controls which save or stage changes are disabled, and the banner explaining
+    /// why is shown.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * None
+    pub fn apply_permissions(&self) {
+        unsafe {
+            let reason = match self.permissions.readonly_reason() {
+                Some(reason) => reason,
+                None => return,
+            };
+            log::info!("Editing disabled: {}", reason);
+            let mut banner = self.readonly_banner;
+            banner.set_text(&qs(format!("Read-only mode: {}", reason)));
+            banner.set_visible(true);
+            self.set_editing_enabled(false);
+        }
+    }
+
+    // enable or disable the controls which save or stage changes
+    unsafe fn set_editing_enabled(&self, enabled: bool) {
+        let mut vpin_table = self.vpin_table;
+        vpin_table.set_drag_drop_mode(if enabled {
+            DragDropMode::DropOnly
+        } else {
+            DragDropMode::NoDragDrop
+        });
+        self.save_button().set_enabled(enabled);
+        self.dist_popup_action().set_enabled(enabled);
+        self.main_menubar()
+            .inner()
+            .import_manifest_action()
+            .set_enabled(enabled);
+        self.main_menubar()
+            .inner()
+            .apply_template_action()
+            .set_enabled(enabled);
+        self.package_withs_list()
+            .borrow()
+            .save_button()
+            .set_enabled(enabled);
+    }
+}
+
+// create a banner displayed at the top of the main window, hidden until it is
+// given something to say
+fn create_banner(
+    layout: &mut MutPtr<QVBoxLayout>,
+    name: &str,
+    background: &str,
+) -> MutPtr<QLabel> {
+    unsafe {
+        let mut banner = QLabel::new();
+        banner.set_object_name(&qs(name));
+        banner.set_word_wrap(true);
+        banner.set_style_sheet(&qs(format!(
+            "QLabel#{} {{ background-color: {}; color: white; padding: 4px; }}",
+            name, background
+        )));
+        banner.set_visible(false);
+        let banner_ptr = banner.as_mut_ptr();
+        layout.add_widget(banner.into_ptr());
+        banner_ptr
+    }
 }
 
 // create the main window, the main menubar, and the central widget
@@ -559,33 +913,49 @@ pub struct MainWindow<'a> {
     //
     query_button_clicked: Slot<'a>,
     save_clicked: Slot<'a>,
+    save_review_accepted: Slot<'a>,
     choose_distribution_triggered: Slot<'a>,
     show_dist_menu: SlotOfQPoint<'a>,
     select_pin_changes: Slot<'a>,
-    select_history: Slot<'a>,
+    select_history: SlotOfBool<'a>,
+    search_history: Slot<'a>,
+    revisions_scrolled: SlotOfInt<'a>,
     select_log: Slot<'a>,
+    select_pin_history: SlotOfBool<'a>,
+    goto_revision: Slot<'a>,
     toggle_log_ctrls: SlotOfBool<'a>,
+    apply_log_level: Slot<'a>,
     toggle_packages_tree: SlotOfBool<'a>,
     toggle_withs: SlotOfBool<'a>,
     toggle_vpin_changes: SlotOfBool<'a>,
     revision_changed: SlotOfQItemSelectionQItemSelection<'a>,
     distribution_changed: SlotOfQItemSelectionQItemSelection<'a>,
     save_withpackages: Slot<'a>,
+    withs_edited: SlotOfQModelIndexIntInt<'a>,
+    reload_with_packages: Slot<'a>,
     save_packages_xml: Slot<'a>,
+    packages_xml_accepted: Slot<'a>,
+    import_manifest: Slot<'a>,
+    export_pins: Slot<'a>,
+    save_pin_template: Slot<'a>,
+    apply_pin_template: Slot<'a>,
+    toggle_time_travel: SlotOfBool<'a>,
+    restore_revision: Slot<'a>,
 }
 
 impl<'a> MainWindow<'a> {
     /// New up the MainWindow instance
     ///
     /// # Arguments
-    /// * None
+    /// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+    /// * `permissions` - The Permissions granted to the current user
     ///
     /// # Returns
     /// * MainWindow instance
-    pub fn new(to_thread_sender: Sender<OMsg>) -> MainWindow<'a> {
+    pub fn new(to_thread_sender: Sender<OMsg>, permissions: Permissions) -> MainWindow<'a> {
         unsafe {
             let (pbgui_root, pbgui_main_cppbox, dist_popup_menu_box, logger_icon) =
-                InnerMainWindow::new(to_thread_sender.clone());
+                InnerMainWindow::new(to_thread_sender.clone(), permissions);
             let main = Rc::new(pbgui_root);
             let main_win = MainWindow {
                 main: main.clone(),
@@ -603,6 +973,14 @@ impl<'a> MainWindow<'a> {
                 }}),
 
                 save_clicked: Slot::new(enclose! { (main, to_thread_sender) move || {
+                    request_save_review(
+                        main.cache(),
+                        main.permissions(),
+                        to_thread_sender.clone()
+                    );
+                } }),
+
+                save_review_accepted: Slot::new(enclose! { (main, to_thread_sender) move || {
                     save_versionpin_changes(
                         main.main_widget(),
                         main.cache(),
@@ -621,8 +999,10 @@ impl<'a> MainWindow<'a> {
                             return;
                         }
                         let current_row = vpin_tablewidget_ptr.current_row();
+                        let site = main.main_toolbar().site().current_text().to_std_string();
                         choose_alternative_distribution(
                             current_row,
+                            site,
                             vpin_tablewidget_ptr,
                             main.main_widget(),
                             main.vpin_requested_changes_table(),
@@ -641,6 +1021,15 @@ impl<'a> MainWindow<'a> {
                         log::error!("dist_popup_menu_ptr is null");
                         return;
                     }
+                    // only offer to change the version of pins the user may edit, which
+                    // excludes the pins of a past revision
+                    let vpin_table = main.vpin_table();
+                    let row = vpin_table.row_at(pos.y());
+                    let editable = row >= 0 && !main.is_time_travelling()
+                        && main.permissions().can_edit_level(
+                            vpin_table.item(row, COL_LEVEL).text().to_std_string().as_str()
+                        );
+                    main.dist_popup_action().set_enabled(editable);
                     let _action = main.dist_popup_menu()
                         .exec_1a_mut(main.vpin_table().map_to_global(pos).as_ref());
                 }}),
@@ -650,23 +1039,64 @@ impl<'a> MainWindow<'a> {
                     main.bottom_ctrls_stacked_widget().set_current_index(0);
                 }}),
 
-                select_history: Slot::new(enclose! { (main, to_thread_sender) move || {
-                    let mut revisions_ptr = main.revisions_table();
-                    let mut stacked_ptr = main.bottom_stacked_widget();
-                    let mut controls_ptr = main.bottom_ctrls_stacked_widget();
-                    select_history(&mut revisions_ptr, &mut stacked_ptr, to_thread_sender.clone());
-                    controls_ptr.set_current_index(1);
+                // searching the history is left to the page being selected, rather
+                // than deselected
+                select_history: SlotOfBool::new(
+                    enclose! { (main, to_thread_sender) move |state: bool| {
+                        if state {
+                            select_history(main.clone(), to_thread_sender.clone());
+                        }
+                    }},
+                ),
+
+                search_history: Slot::new(enclose! { (main, to_thread_sender) move || {
+                    search_history(main.clone(), to_thread_sender.clone());
                 }}),
 
+                revisions_scrolled: SlotOfInt::new(
+                    enclose! { (main, to_thread_sender) move |value: i32| {
+                        fetch_more_history(main.clone(), to_thread_sender.clone(), value);
+                    }},
+                ),
+
                 select_log: Slot::new(enclose! { (main) move || {
                     main.bottom_stacked_widget().set_current_index(2);
                     main.bottom_ctrls_stacked_widget().set_current_index(2);
                 }}),
 
+                select_pin_history: SlotOfBool::new(
+                    enclose! { (main, to_thread_sender) move |state: bool| {
+                        if state {
+                            select_pin_history(main.clone(), to_thread_sender.clone());
+                        }
+                    }},
+                ),
+
+                goto_revision: Slot::new(enclose! { (main) move || {
+                    goto_revision(main.clone());
+                }}),
+
                 toggle_log_ctrls: SlotOfBool::new(enclose! { (main) move |state: bool| {
                     main.logger().inner().set_ctrls_visible(state);
                 }}),
 
+                apply_log_level: Slot::new(enclose! { (main) move || {
+                    let log_win = main.logger().inner();
+                    let spec = log_win.log_level_spec();
+                    match logger::set_log_filter(spec.as_str()) {
+                        Ok(filter) => {
+                            log_win.show_levels_up_to(filter.max_level());
+                            log::info!("log level set to {}", filter);
+                        }
+                        Err(err) => {
+                            let mut mb = QMessageBox::new();
+                            mb.set_text(&qs("Unable to set log level"));
+                            mb.set_informative_text(&qs(err.to_string()));
+                            mb.exec();
+                        }
+                    }
+                }}),
+
                 toggle_packages_tree: SlotOfBool::new(enclose! { (main) move |state: bool| {
                     let mut frame = main.withs_splitter().widget(0);
                     frame.set_visible(state);
@@ -703,13 +1133,25 @@ impl<'a> MainWindow<'a> {
 
                 distribution_changed: SlotOfQItemSelectionQItemSelection::new(
                     enclose! { (main, to_thread_sender)
-                    move |selected: QRef<QItemSelection>, _deselected: QRef<QItemSelection>| {
-                        let ind = selected.indexes();
-                        if ind.count_0a() > 0 {
-                            let mut vpin_tablewidget_ptr = main.vpin_table();
-                            let txid = ind.at(COL_REV_TXID);
+                    move |_selected: QRef<QItemSelection>, _deselected: QRef<QItemSelection>| {
+                        let mut vpin_tablewidget_ptr = main.vpin_table();
+                        // the withs list edits a single pin, and is disabled otherwise. While
+                        // time travelling, it presents the withs of the pin at the revision,
+                        // which may not be saved
+                        let active_row = active_vpin_row(&vpin_tablewidget_ptr);
+                        let mut withs_widget = main.package_withs_list().borrow().main();
+                        withs_widget.set_enabled(active_row.is_some());
+                        if main.is_time_travelling() {
+                            let withs_list = main.package_withs_list();
+                            withs_list.borrow().clear();
+                            match active_row.map(|row| main.past_withs(row)) {
+                                Some(Some(withs)) => withs_list.borrow().set_items(withs),
+                                Some(None) => log::info!("the withs of the pin are not known"),
+                                None => (),
+                            }
+                        } else if let Some(row) = active_row {
                             update_withpackages(
-                                txid.row(),
+                                row,
                                 &mut vpin_tablewidget_ptr,
                                 main.package_withs_list(),
                                 main.cache(),
@@ -718,22 +1160,53 @@ impl<'a> MainWindow<'a> {
                         } else {
                             main.package_withs_list().borrow().clear()
                         }
+                        update_pin_history(main.clone(), to_thread_sender.clone());
                     }},
                 ),
-                save_withpackages: Slot::new(enclose! { (main) move || {
-                    let mut pinchanges_ptr = main.vpin_requested_changes_table();
+                save_withpackages: Slot::new(enclose! { (main, to_thread_sender) move || {
                     store_withpackage_changes::store_withpackage_changes(
-                        main.package_withs_list(),
-                        main.vpin_table(),
-                        &mut pinchanges_ptr,
-                        main.cache(),
+                        main.clone(),
+                        to_thread_sender.clone(),
                     );
                 }}),
+                withs_edited: SlotOfQModelIndexIntInt::new(enclose! { (main)
+                move |_parent: QRef<QModelIndex>, _first: i32, _last: i32| {
+                    store_withpackage_changes::check_edited_withs(main.clone());
+                }}),
+                reload_with_packages: Slot::new(enclose! { (to_thread_sender) move || {
+                    log::info!("reloading the with packages");
+                    to_thread_sender
+                        .send(OMsg::PackageWiths(OPackageWiths::GetPackages))
+                        .expect("unable to reload packages");
+                }}),
                 save_packages_xml: Slot::new(enclose! { (main, to_thread_sender) move || {
                     let toolbar = main.main_toolbar();
                     let level_cb = toolbar.level();
                     save_packages_xml(main.main(), level_cb, to_thread_sender.clone());
                 }}),
+                packages_xml_accepted: Slot::new(enclose! { (main, to_thread_sender) move || {
+                    write_packages_xml(main.clone(), to_thread_sender.clone());
+                }}),
+                import_manifest: Slot::new(enclose! { (main, to_thread_sender) move || {
+                    import_manifest(main.main(), to_thread_sender.clone());
+                }}),
+                export_pins: Slot::new(enclose! { (main, to_thread_sender) move || {
+                    export_pins(main.clone(), to_thread_sender.clone());
+                }}),
+                save_pin_template: Slot::new(enclose! { (main, to_thread_sender) move || {
+                    save_pin_template(main.clone(), to_thread_sender.clone());
+                }}),
+                apply_pin_template: Slot::new(enclose! { (main, to_thread_sender) move || {
+                    apply_pin_template(main.clone(), to_thread_sender.clone());
+                }}),
+                toggle_time_travel: SlotOfBool::new(
+                    enclose! { (main, to_thread_sender) move |_state: bool| {
+                        update_vpin_table(main.clone(), to_thread_sender.clone());
+                    }},
+                ),
+                restore_revision: Slot::new(enclose! { (main, to_thread_sender) move || {
+                    restore_revision(main.clone(), to_thread_sender.clone());
+                }}),
             };
 
             //
@@ -748,8 +1221,31 @@ impl<'a> MainWindow<'a> {
                 .activated()
                 .connect(&main_win.query_button_clicked);
 
+            main.main_toolbar()
+                .as_of_line_edit()
+                .return_pressed()
+                .connect(&main_win.query_button_clicked);
+
+            main.main_toolbar()
+                .as_of_btn()
+                .toggled()
+                .connect(&main_win.toggle_time_travel);
+
+            main.main_toolbar()
+                .restore_btn()
+                .clicked()
+                .connect(&main_win.restore_revision);
+
             main.save_button().clicked().connect(&main_win.save_clicked);
 
+            main.save_review_dialog()
+                .accepted()
+                .connect(&main_win.save_review_accepted);
+
+            main.packages_xml_dialog()
+                .accepted()
+                .connect(&main_win.packages_xml_accepted);
+
             main.dist_popup_action()
                 .triggered()
                 .connect(&main_win.choose_distribution_triggered);
@@ -768,15 +1264,48 @@ impl<'a> MainWindow<'a> {
                 .toggled()
                 .connect(&main_win.select_history);
 
+            main.revisions_filter()
+                .search_btn()
+                .clicked()
+                .connect(&main_win.search_history);
+            for mut line_edit in main.revisions_filter().line_edits() {
+                line_edit.return_pressed().connect(&main_win.search_history);
+            }
+
+            main.revisions_table()
+                .vertical_scroll_bar()
+                .value_changed()
+                .connect(&main_win.revisions_scrolled);
+
             main.log_button()
                 .default_action()
                 .toggled()
                 .connect(&main_win.select_log);
 
+            main.pin_history_button()
+                .default_action()
+                .toggled()
+                .connect(&main_win.select_pin_history);
+
+            main.pin_history()
+                .goto_revision_btn()
+                .clicked()
+                .connect(&main_win.goto_revision);
+
             main.toggle_log_ctrls_button()
                 .clicked()
                 .connect(&main_win.toggle_log_ctrls);
 
+            let log_win = main.logger().inner();
+            log_win
+                .apply_log_level_button()
+                .clicked()
+                .connect(&main_win.apply_log_level);
+            log_win
+                .log_overrides_le()
+                .return_pressed()
+                .connect(&main_win.apply_log_level);
+
             main.left_toolbar_actions()
                 .view_packages
                 .toggled()
@@ -809,6 +1338,17 @@ impl<'a> MainWindow<'a> {
                 .clicked()
                 .connect(&main_win.save_withpackages);
 
+            // the withs are checked as they are added, removed and moved
+            let withs_model = main.package_withs_list().borrow().model();
+            withs_model.rows_inserted().connect(&main_win.withs_edited);
+            withs_model.rows_removed().connect(&main_win.withs_edited);
+
+            main.package_withs_list()
+                .borrow()
+                .reload_button()
+                .clicked()
+                .connect(&main_win.reload_with_packages);
+
             // set initial state of with button to on
             let mut button = main.left_toolbar_actions().view_withs;
             button.toggle();
@@ -819,6 +1359,28 @@ impl<'a> MainWindow<'a> {
                 .triggered()
                 .connect(&main_win.save_packages_xml);
 
+            main.main_menubar()
+                .inner()
+                .import_manifest_action()
+                .triggered()
+                .connect(&main_win.import_manifest);
+
+            main.main_menubar()
+                .inner()
+                .export_pins_action()
+                .triggered()
+                .connect(&main_win.export_pins);
+
+            main.main_menubar()
+                .inner()
+                .apply_template_action()
+                .triggered()
+                .connect(&main_win.apply_pin_template);
+
+            main.save_template_action()
+                .triggered()
+                .connect(&main_win.save_pin_template);
+
             main_win
         }
     }
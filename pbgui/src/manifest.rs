@@ -0,0 +1,332 @@
+//! Parses manifests of versionpin changes, allowing a batch of changes (typically
+//! supplied by a supervisor as a spreadsheet) to be loaded as pending changes rather
+//! than entered one at a time via the ui.
+//!
+//! Manifests may be written as csv or yaml. Both formats describe the same fields:
+//!
+//! ```text
+//! op,package,version,level,role,platform,site,withs
+//! AddDistribution,maya,2020.1,dev01,model,any,any,
+//! ChangeDistribution,houdini,18.0.1,dev01.rd,fx,any,any,
+//! ChangeWiths,nuke,,dev01,comp,any,any,"ocio, gizmos"
+//! ```
+//!
+//! ```yaml
+//! - op: AddDistribution
+//!   package: maya
+//!   version: "2020.1"
+//!   level: dev01
+//!   role: model
+//! - op: ChangeWiths
+//!   package: nuke
+//!   level: dev01
+//!   role: comp
+//!   withs: [ocio, gizmos]
+//! ```
+//!
+//! `role`, `platform` and `site` default to `any`. `version` is required by
+//! `AddDistribution` and `ChangeDistribution`, and ignored by `ChangeWiths`.
+use crate::change_type::{Change, PinCoord};
+use crate::distribution::Distribution;
+use crate::withs_check::LevelWiths;
+use packybara::types::IdType;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The default value for the role, platform and site of an entry
+const ANY: &str = "any";
+
+fn any() -> String {
+    ANY.to_string()
+}
+
+/// The operation requested by a manifest entry
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+pub enum ManifestOp {
+    #[serde(alias = "add")]
+    AddDistribution,
+    #[serde(alias = "change")]
+    ChangeDistribution,
+    #[serde(alias = "withs")]
+    ChangeWiths,
+}
+
+/// A single requested change read from a manifest
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct ManifestEntry {
+    /// The line (csv) or entry number (yaml) the entry was read from
+    #[serde(skip)]
+    pub line: usize,
+    pub op: ManifestOp,
+    pub package: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    pub level: String,
+    #[serde(default = "any")]
+    pub role: String,
+    #[serde(default = "any")]
+    pub platform: String,
+    #[serde(default = "any")]
+    pub site: String,
+    #[serde(default)]
+    pub withs: Vec<String>,
+}
+
+impl ManifestEntry {
+    /// The distribution named by the entry, if it supplies a valid version
+    pub fn distribution(&self) -> Option<Distribution> {
+        self.version
+            .as_ref()
+            .and_then(|version| Distribution::new(self.package.as_str(), version.as_str()).ok())
+    }
+
+    /// The package and coordinate of the pin the entry applies to
+    pub fn pin_coord(&self) -> PinCoord {
+        PinCoord::new(
+            self.package.as_str(),
+            self.level.as_str(),
+            self.role.as_str(),
+            self.platform.as_str(),
+            self.site.as_str(),
+        )
+    }
+
+    /// Describe the package coordinate of the entry, in the same form as the
+    /// context column of the pin changes table.
+    pub fn pkgcoord(&self) -> String {
+        format!(
+            "(level: {}, role: {}, platform: {}, site: {}, package: {})",
+            self.level, self.role, self.platform, self.site, self.package
+        )
+    }
+}
+
+impl fmt::Display for ManifestEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.op, self.pkgcoord())
+    }
+}
+
+// The csv flavor of an entry. Withs are supplied as a single field.
+#[derive(Debug, Deserialize)]
+struct CsvEntry {
+    op: ManifestOp,
+    package: String,
+    #[serde(default)]
+    version: Option<String>,
+    level: String,
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    platform: Option<String>,
+    #[serde(default)]
+    site: Option<String>,
+    #[serde(default)]
+    withs: Option<String>,
+}
+
+// csv fields which are present but blank should fall back on their defaults
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+impl CsvEntry {
+    fn into_entry(self, line: usize) -> ManifestEntry {
+        ManifestEntry {
+            line,
+            op: self.op,
+            package: self.package,
+            version: non_empty(self.version),
+            level: self.level,
+            role: non_empty(self.role).unwrap_or_else(any),
+            platform: non_empty(self.platform).unwrap_or_else(any),
+            site: non_empty(self.site).unwrap_or_else(any),
+            withs: split_withs(self.withs.as_ref().map(String::as_str).unwrap_or("")),
+        }
+    }
+}
+
+// split a list of withs separated by commas, semicolons or whitespace
+fn split_withs(withs: &str) -> Vec<String> {
+    withs
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// Errors which may arise while reading a manifest
+#[derive(Debug)]
+pub enum ManifestError {
+    /// The manifest could not be read from disk
+    Io(String),
+    /// The manifest extension is neither csv nor yaml
+    UnknownFormat(String),
+    /// The manifest could not be parsed
+    Parse(String),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(msg) => write!(f, "unable to read manifest: {}", msg),
+            Self::UnknownFormat(path) => write!(
+                f,
+                "unable to determine manifest format of {}. expected .csv, .yaml or .yml",
+                path
+            ),
+            Self::Parse(msg) => write!(f, "unable to parse manifest: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+/// Read the manifest at the supplied path, choosing the format from the extension
+///
+/// # Arguments
+/// * `path` - The path to a .csv, .yaml or .yml manifest
+///
+/// # Returns
+/// * Ok(Vec<ManifestEntry>) if successful
+/// * Err(ManifestError) otherwise
+pub fn read_manifest<P: AsRef<Path>>(path: P) -> Result<Vec<ManifestEntry>, ManifestError> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|e| ManifestError::Io(e.to_string()))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => parse_csv(&contents),
+        Some("yaml") | Some("yml") => parse_yaml(&contents),
+        _ => Err(ManifestError::UnknownFormat(path.display().to_string())),
+    }
+}
+
+/// Parse a csv manifest. The first row must name the columns.
+///
+/// # Arguments
+/// * `contents` - The contents of the manifest
+///
+/// # Returns
+/// * Ok(Vec<ManifestEntry>) if successful
+/// * Err(ManifestError) otherwise
+pub fn parse_csv(contents: &str) -> Result<Vec<ManifestEntry>, ManifestError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(contents.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| ManifestError::Parse(e.to_string()))?
+        .clone();
+    let mut entries = Vec::new();
+    for result in reader.records() {
+        let record = result.map_err(|e| ManifestError::Parse(e.to_string()))?;
+        let line = record.position().map_or(0, |pos| pos.line() as usize);
+        let entry = record
+            .deserialize::<CsvEntry>(Some(&headers))
+            .map_err(|e| ManifestError::Parse(format!("line {}: {}", line, e)))?;
+        entries.push(entry.into_entry(line));
+    }
+    Ok(entries)
+}
+
+/// Parse a yaml manifest, which is a list of entries
+///
+/// # Arguments
+/// * `contents` - The contents of the manifest
+///
+/// # Returns
+/// * Ok(Vec<ManifestEntry>) if successful
+/// * Err(ManifestError) otherwise
+pub fn parse_yaml(contents: &str) -> Result<Vec<ManifestEntry>, ManifestError> {
+    let mut entries: Vec<ManifestEntry> =
+        serde_yaml::from_str(contents).map_err(|e| ManifestError::Parse(e.to_string()))?;
+    for (idx, entry) in entries.iter_mut().enumerate() {
+        entry.line = idx + 1;
+    }
+    Ok(entries)
+}
+
+/// A manifest entry which has been validated against the database, along with
+/// the information required to present it in the pin changes table.
+#[derive(Debug, PartialEq)]
+pub struct ValidatedEntry {
+    /// The line the entry was read from
+    pub line: usize,
+    pub change: Change,
+    /// The level the change applies to
+    pub level: String,
+    /// The package and coordinate of the versionpin
+    pub coord: PinCoord,
+    /// The versionpin the change applies to, if it already exists. The withs of a
+    /// pin which does not exist yet are staged against its pending new pin, which
+    /// supplies the vpin_id of the change.
+    pub vpin_id: Option<IdType>,
+    /// The package coordinate of the versionpin, if it already exists
+    pub pkgcoord_id: Option<IdType>,
+    pub context: String,
+    pub old_value: String,
+    pub new_value: String,
+    /// The withs of the other pins at the coordinate, for a ChangeWiths, so that the
+    /// withs may be checked for with-cycles once they are staged
+    pub level_withs: LevelWiths,
+}
+
+/// A manifest entry which failed validation
+#[derive(Debug, PartialEq)]
+pub struct ManifestRejection {
+    pub line: usize,
+    pub entry: String,
+    pub reason: String,
+}
+
+impl ManifestRejection {
+    /// New up a rejection for the supplied entry
+    pub fn new<R: Into<String>>(entry: &ManifestEntry, reason: R) -> Self {
+        Self {
+            line: entry.line,
+            entry: entry.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ManifestRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} - {}", self.line, self.entry, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_csv_with_defaults_and_withs() {
+        let contents = "op,package,version,level,role,platform,site,withs\n\
+                        add,maya,2020.1,dev01,model,,,\n\
+                        ChangeWiths,nuke,,dev01,comp,any,any,\"ocio, gizmos\"\n";
+        let entries = parse_csv(contents).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].op, ManifestOp::AddDistribution);
+        assert_eq!(
+            entries[0].distribution().map(|d| d.to_string()),
+            Some("maya-2020.1".to_string())
+        );
+        assert_eq!(entries[0].platform, "any");
+        assert_eq!(entries[1].version, None);
+        assert_eq!(entries[1].withs, vec!["ocio", "gizmos"]);
+        assert_eq!(entries[1].line, 3);
+    }
+
+    #[test]
+    fn can_parse_yaml() {
+        let contents = "- op: ChangeDistribution\n  package: houdini\n  version: \"18.0.1\"\n  level: dev01.rd\n";
+        let entries = parse_yaml(contents).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].op, ManifestOp::ChangeDistribution);
+        assert_eq!(entries[0].role, "any");
+        assert_eq!(entries[0].line, 1);
+    }
+}
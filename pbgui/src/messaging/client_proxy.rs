@@ -1,16 +1,60 @@
 //! Provides an interface to the Packrat Database Client.
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
 pub use packybara::packrat::{Client, NoTls};
+use postgres_openssl::MakeTlsConnector;
+use serde::Deserialize;
+use std::borrow::Cow;
 use std::fmt;
 
+/// Placeholder written in place of the password whenever ConnectParams are displayed
+pub const REDACTED: &str = "********";
+
+/// The TLS mode used when connecting to the database. The names and semantics
+/// mirror libpq's `sslmode`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    /// Do not use TLS
+    Disable,
+    /// Use TLS if the server supports it, without verifying the certificate
+    Prefer,
+    /// Require TLS, without verifying the certificate
+    Require,
+    /// Require TLS and verify the server certificate against the CA
+    VerifyCa,
+    /// Require TLS, verify the certificate and the server's host name
+    VerifyFull,
+}
+
+impl SslMode {
+    // The sslmode as understood by the postgres connection string. Verification
+    // is handled by the TlsConnector, so the verify modes map onto `require`.
+    fn as_connect_str(&self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Prefer => "prefer",
+            Self::Require | Self::VerifyCa | Self::VerifyFull => "require",
+        }
+    }
+}
+
+impl Default for SslMode {
+    fn default() -> Self {
+        Self::Disable
+    }
+}
+
 /// ConnectParams provide connection parameters for the ClientProxy via
 /// ClientProxy::new.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Clone)]
 pub struct ConnectParams<'a> {
     pub host: &'a str,
     pub user: &'a str,
     pub password: &'a str,
     pub dbname: &'a str,
     pub port: u64,
+    pub sslmode: SslMode,
+    pub ca_cert: Option<&'a str>,
 }
 
 impl<'a> ConnectParams<'a> {
@@ -35,17 +79,85 @@ impl<'a> ConnectParams<'a> {
             password,
             dbname,
             port,
+            sslmode: SslMode::default(),
+            ca_cert: None,
         }
     }
+
+    /// Set the TLS options, consuming and returning self.
+    ///
+    /// # Arguments
+    /// * `sslmode` - The SslMode to connect with
+    /// * `ca_cert` - Optional path to a PEM encoded CA certificate
+    ///
+    /// # Returns
+    /// * ConnectParams instance
+    pub fn tls(mut self, sslmode: SslMode, ca_cert: Option<&'a str>) -> Self {
+        self.sslmode = sslmode;
+        self.ca_cert = ca_cert;
+        self
+    }
+
+    /// Generate the connection string, including the password. This should
+    /// only ever be handed to the client; use Display for anything that may
+    /// end up in a log.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * The connection string
+    pub fn connect_string(&self) -> String {
+        self.format_with_password(self.password)
+    }
+
+    fn format_with_password(&self, password: &str) -> String {
+        format!(
+            "host={} user={} dbname={} password={} port={} sslmode={}",
+            quote_value(self.host),
+            quote_value(self.user),
+            quote_value(self.dbname),
+            quote_value(password),
+            self.port,
+            self.sslmode.as_connect_str()
+        )
+    }
+}
+
+// Quote a value of the connection string if it is empty, or contains whitespace or a
+// quote, escaping its quotes and backslashes, so that it may not end the value early
+// and introduce parameters of its own.
+fn quote_value(value: &str) -> Cow<'_, str> {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '\'' || c == '\\');
+    if !needs_quotes {
+        return Cow::Borrowed(value);
+    }
+    Cow::Owned(format!(
+        "'{}'",
+        value.replace('\\', "\\\\").replace('\'', "\\'")
+    ))
 }
 
 impl<'a> std::fmt::Display for ConnectParams<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "host={} user={} dbname={} password={} port={}",
-            self.host, self.user, self.dbname, self.password, self.port
-        )
+        write!(f, "{}", self.format_with_password(REDACTED))
+    }
+}
+
+impl<'a> std::fmt::Debug for ConnectParams<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectParams")
+            .field("host", &self.host)
+            .field("user", &self.user)
+            .field("password", &REDACTED)
+            .field("dbname", &self.dbname)
+            .field("port", &self.port)
+            .field("sslmode", &self.sslmode)
+            .field("ca_cert", &self.ca_cert)
+            .finish()
     }
 }
 
@@ -67,10 +179,38 @@ impl ClientProxy {
     /// * Ok(Client) if successful
     /// * Err(error) otherwise
     pub fn connect(params: ConnectParams) -> Result<Client, Box<dyn std::error::Error>> {
-        let connect_str = params.to_string();
-        let client = Client::connect(connect_str.as_str(), NoTls)?;
+        log::info!("connecting to database: {}", params);
+        let connect_str = params.connect_string();
+        let client = match params.sslmode {
+            SslMode::Disable => Client::connect(connect_str.as_str(), NoTls)?,
+            _ => Client::connect(connect_str.as_str(), Self::tls_connector(&params)?)?,
+        };
         Ok(client)
     }
+
+    // Build a TlsConnector for the supplied params, configuring certificate and
+    // host name verification according to the sslmode.
+    fn tls_connector(
+        params: &ConnectParams,
+    ) -> Result<MakeTlsConnector, Box<dyn std::error::Error>> {
+        let mut builder = SslConnector::builder(SslMethod::tls())?;
+        if let Some(ca_cert) = params.ca_cert {
+            builder.set_ca_file(ca_cert)?;
+        }
+        let sslmode = params.sslmode;
+        match sslmode {
+            SslMode::Prefer | SslMode::Require => builder.set_verify(SslVerifyMode::NONE),
+            _ => builder.set_verify(SslVerifyMode::PEER),
+        }
+        let mut connector = MakeTlsConnector::new(builder.build());
+        if sslmode != SslMode::VerifyFull {
+            connector.set_callback(|config, _| {
+                config.set_verify_hostname(false);
+                Ok(())
+            });
+        }
+        Ok(connector)
+    }
 }
 
 #[cfg(test)]
@@ -80,7 +220,7 @@ mod tests {
     fn can_default_construct() {
         let default = ConnectParams::default();
         assert_eq!(
-            "host=127.0.0.1 user=postgres dbname=packrat password=example port=5432",
+            "host=127.0.0.1 user=postgres dbname=packrat password=******** port=5432 sslmode=disable",
             default.to_string().as_str()
         );
     }
@@ -92,8 +232,39 @@ mod tests {
             ..Default::default()
         };
         assert_eq!(
-            "host=Fred user=postgres dbname=packrat password=example port=5432",
+            "host=Fred user=postgres dbname=packrat password=******** port=5432 sslmode=disable",
             cp.to_string().as_str()
         )
     }
+
+    #[test]
+    fn connect_string_includes_password() {
+        let cp = ConnectParams::default().tls(SslMode::VerifyFull, None);
+        assert_eq!(
+            "host=127.0.0.1 user=postgres dbname=packrat password=example port=5432 sslmode=require",
+            cp.connect_string().as_str()
+        );
+    }
+
+    #[test]
+    fn connect_string_quotes_password() {
+        let cp = ConnectParams {
+            password: r"it's a \secret",
+            ..Default::default()
+        };
+        assert!(cp
+            .connect_string()
+            .contains(r"password='it\'s a \\secret' port=5432"));
+        let cp = ConnectParams {
+            password: "",
+            ..Default::default()
+        };
+        assert!(cp.connect_string().contains("password='' port"));
+    }
+
+    #[test]
+    fn debug_redacts_password() {
+        let cp = ConnectParams::default();
+        assert!(!format!("{:?}", cp).contains("example"));
+    }
 }
@@ -4,13 +4,27 @@ use super::*;
 #[derive(Debug, PartialEq)]
 pub enum MainWin {
     GetVpins,
+    /// Reconstruct the versionpins as of a past revision
+    GetPastVpins,
     GetWithsForVpin,
     GetTransactionChanges,
     GetHistoryRevisions,
+    /// List the changes made to a versionpin
+    GetPinHistory,
+    GetLevelImpact,
     SaveVpinChanges,
+    ImportManifest,
+    ExportPins,
+    SavePinTemplate,
+    ApplyPinTemplate,
+    ValidateWiths,
     /// Choose a distribution from a list of alternative distributions
     /// from a popup
     ChooseDistribution,
+    DropDistribution,
+    RestoreRevision,
+    /// Preview a packages.xml prior to writing it
+    PreviewPackagesXml,
     SavePackagesXml,
 }
 
@@ -24,13 +38,24 @@ impl ToQString for MainWin {
     fn to_qstring(&self) -> CppBox<QString> {
         match &self {
             MainWin::GetVpins => QString::from_std_str("MainWin::GetVpins"),
+            MainWin::GetPastVpins => QString::from_std_str("MainWin::GetPastVpins"),
             MainWin::GetWithsForVpin => QString::from_std_str("MainWin::GetWithsForVpin"),
             MainWin::GetTransactionChanges => {
                 QString::from_std_str("MainWin::GetTransactionChanges")
             }
             MainWin::GetHistoryRevisions => QString::from_std_str("MainWin::GetHistoryRevisions"),
+            MainWin::GetPinHistory => QString::from_std_str("MainWin::GetPinHistory"),
+            MainWin::GetLevelImpact => QString::from_std_str("MainWin::GetLevelImpact"),
             MainWin::SaveVpinChanges => QString::from_std_str("MainWin::SaveVpinChanges"),
+            MainWin::ImportManifest => QString::from_std_str("MainWin::ImportManifest"),
+            MainWin::ExportPins => QString::from_std_str("MainWin::ExportPins"),
+            MainWin::SavePinTemplate => QString::from_std_str("MainWin::SavePinTemplate"),
+            MainWin::ApplyPinTemplate => QString::from_std_str("MainWin::ApplyPinTemplate"),
+            MainWin::ValidateWiths => QString::from_std_str("MainWin::ValidateWiths"),
             MainWin::ChooseDistribution => QString::from_std_str("MainWin::ChooseDistribution"),
+            MainWin::DropDistribution => QString::from_std_str("MainWin::DropDistribution"),
+            MainWin::RestoreRevision => QString::from_std_str("MainWin::RestoreRevision"),
+            MainWin::PreviewPackagesXml => QString::from_std_str("MainWin::PreviewPackagesXml"),
             MainWin::SavePackagesXml => QString::from_std_str("MainWin::SavePackagesXml"),
         }
     }
@@ -40,11 +65,22 @@ impl FromQString for MainWin {
     fn from_qstring(qs: Ref<QString>) -> Self {
         match qs.to_std_string().as_str() {
             "MainWin::GetVpins" => MainWin::GetVpins,
+            "MainWin::GetPastVpins" => MainWin::GetPastVpins,
             "MainWin::GetWithsForVpin" => MainWin::GetWithsForVpin,
             "MainWin::GetTransactionChanges" => MainWin::GetTransactionChanges,
             "MainWin::GetHistoryRevisions" => MainWin::GetHistoryRevisions,
+            "MainWin::GetPinHistory" => MainWin::GetPinHistory,
+            "MainWin::GetLevelImpact" => MainWin::GetLevelImpact,
             "MainWin::SaveVpinChanges" => MainWin::SaveVpinChanges,
+            "MainWin::ImportManifest" => MainWin::ImportManifest,
+            "MainWin::ExportPins" => MainWin::ExportPins,
+            "MainWin::SavePinTemplate" => MainWin::SavePinTemplate,
+            "MainWin::ApplyPinTemplate" => MainWin::ApplyPinTemplate,
+            "MainWin::ValidateWiths" => MainWin::ValidateWiths,
             "MainWin::ChooseDistribution" => MainWin::ChooseDistribution,
+            "MainWin::DropDistribution" => MainWin::DropDistribution,
+            "MainWin::RestoreRevision" => MainWin::RestoreRevision,
+            "MainWin::PreviewPackagesXml" => MainWin::PreviewPackagesXml,
             "MainWin::SavePackagesXml" => MainWin::SavePackagesXml,
             _ => panic!("Unable to convert to Event"),
         }
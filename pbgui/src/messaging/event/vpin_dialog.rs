@@ -5,6 +5,7 @@ use super::*;
 pub enum VpinDialog {
     UpdateRoles,
     UpdateSites,
+    UpdatePlatforms,
     UpdateLevels,
     SetShow,
     SetVpin,
@@ -21,6 +22,7 @@ impl ToQString for VpinDialog {
         match &self {
             VpinDialog::UpdateRoles => QString::from_std_str("VpinDialog::UpdateRoles"),
             VpinDialog::UpdateSites => QString::from_std_str("VpinDialog::UpdateSites"),
+            VpinDialog::UpdatePlatforms => QString::from_std_str("VpinDialog::UpdatePlatforms"),
             VpinDialog::UpdateLevels => QString::from_std_str("VpinDialog::UpdateLevels"),
             VpinDialog::SetShow => QString::from_std_str("VpinDialog::SetShow"),
             VpinDialog::SetVpin => QString::from_std_str("VpinDialog::SetVpin"),
@@ -33,6 +35,7 @@ impl FromQString for VpinDialog {
         match qs.to_std_string().as_str() {
             "VpinDialog::UpdateRoles" => VpinDialog::UpdateRoles,
             "VpinDialog::UpdateSites" => VpinDialog::UpdateSites,
+            "VpinDialog::UpdatePlatforms" => VpinDialog::UpdatePlatforms,
             "VpinDialog::UpdateLevels" => VpinDialog::UpdateLevels,
             "VpinDialog::SetShow" => VpinDialog::SetShow,
             "VpinDialog::SetVpin" => VpinDialog::SetVpin,
@@ -2,6 +2,11 @@
 //! logging errors
 use super::*;
 use crate::change_type::{Change, ChangeType};
+use crate::components::packages_xml_dialog::PendingPackagesXml;
+use crate::import_manifest::stage_manifest_changes;
+use crate::review_versionpin_changes::show_save_review;
+use crate::store_withpackage_changes::stage_withpackage_changes;
+use crate::time_travel::PinState;
 use crate::versionpin_changes_row::VersionPinChangesRow;
 use crate::versionpin_row::VersionPinRow;
 use crate::{
@@ -11,16 +16,14 @@ use crate::{
     traits::{RowSetterTrait, RowTrait},
     utility::{qs, update_row, RowType},
 };
-use packybara::db::find_all::distributions::FindAllDistributionsRow;
 use packybara::types::IdType;
 use qt_core::{QString, QVariant};
 use qt_gui::{QBrush, QColor};
 use qt_widgets::{
     cpp_core::{CppBox, MutPtr},
-    qt_core::QStringList,
-    QInputDialog, QMessageBox, QTableWidgetItem,
+    q_message_box::Icon,
+    QMessageBox, QTableWidget, QTableWidgetItem,
 };
-use std::collections::HashMap;
 use std::rc::Rc;
 
 macro_rules! qcolor_blue {
@@ -38,6 +41,9 @@ pub fn match_main_win<'a>(
         match event {
             MainWin::GetVpins => {
                 if let Ok(IMsg::MainWin(IMainWin::Vpins(vpins))) = receiver.recv() {
+                    main_win.set_past_pins(None);
+                    // the selection is lost along with the rows
+                    main_win.pin_history().clear();
                     let mut vpin_tablewidget_ptr = main_win.vpin_table();
 
                     //let mut cnt = 0;
@@ -55,6 +61,29 @@ pub fn match_main_win<'a>(
                     log::error!("PackagesTree::GetPackages IMsg does not match event state");
                 }
             }
+            MainWin::GetPastVpins => {
+                if let Ok(IMsg::MainWin(IMainWin::PastVpins { as_of, pins })) = receiver.recv() {
+                    let mut vpin_table = main_win.vpin_table();
+                    main_win.pin_history().clear();
+                    vpin_table.set_sorting_enabled(false);
+                    vpin_table.set_row_count(0);
+                    vpin_table.set_row_count(pins.pins.len() as i32);
+                    for (row, pin) in pins.pins.iter().enumerate() {
+                        pin.row.set_table_row(&mut vpin_table, row as i32);
+                        mark_past_pin(&mut vpin_table, row as i32, &pin.state);
+                    }
+                    vpin_table.set_sorting_enabled(true);
+                    log::info!(
+                        "showing {} pin(s) as of {}. {} pin(s) have been added since",
+                        pins.pins.len(),
+                        as_of,
+                        pins.added_since.len()
+                    );
+                    main_win.set_past_pins(Some((as_of, pins)));
+                } else {
+                    log::error!("MainWin::GetPastVpins IMsg does not match event state");
+                }
+            }
             MainWin::GetWithsForVpin => {
                 if let Ok(IMsg::MainWin(IMainWin::WithPackages(withs))) = receiver.recv() {
                     let withs = withs.iter().map(|x| x.with.as_str()).collect();
@@ -142,60 +171,96 @@ pub fn match_main_win<'a>(
                 }
             }
             MainWin::GetHistoryRevisions => {
-                if let Ok(IMsg::MainWin(IMainWin::HistoryRevisions(revisions))) = receiver.recv() {
+                if let Ok(IMsg::MainWin(IMainWin::HistoryRevisions(page))) = receiver.recv() {
+                    if !main_win.history_paging().borrow_mut().page_loaded(&page) {
+                        log::debug!("ignoring revisions of a previous search");
+                        return;
+                    }
                     let mut revisions_ptr = main_win.revisions_table();
-                    let r_len = revisions.len() as i32;
-                    revisions_ptr.set_row_count(r_len);
-                    //let mut cnt = 0; // implementing clippy suggestion
-                    for (cnt, revision) in revisions.into_iter().enumerate() {
+                    if page.offset == 0 {
+                        revisions_ptr.set_row_count(0);
+                    }
+                    let first_row = revisions_ptr.row_count();
+                    revisions_ptr.set_row_count(first_row + page.revisions.len() as i32);
+                    for (cnt, revision) in page.revisions.iter().enumerate() {
+                        let row = first_row + cnt as i32;
                         let mut revisions_table_item = QTableWidgetItem::new();
                         let variant = QVariant::from_int(revision.transaction_id as i32);
                         revisions_table_item.set_data(
                             2, // EditRole
                             variant.as_ref(),
                         );
-                        revisions_ptr.set_item(
-                            cnt as i32,
-                            COL_REV_TXID,
-                            revisions_table_item.into_ptr(),
-                        );
+                        revisions_ptr.set_item(row, COL_REV_TXID, revisions_table_item.into_ptr());
                         // Author
                         let mut revisions_table_item = QTableWidgetItem::new();
                         revisions_table_item
-                            .set_text(&QString::from_std_str(revision.author.to_string().as_str()));
+                            .set_text(&QString::from_std_str(revision.author.as_str()));
                         revisions_ptr.set_item(
-                            cnt as i32,
+                            row,
                             COL_REV_AUTHOR,
                             revisions_table_item.into_ptr(),
                         );
                         // Datetime
                         let mut revisions_table_item = QTableWidgetItem::new();
-                        revisions_table_item.set_text(&QString::from_std_str(
-                            revision.datetime.format("%F %r").to_string().as_str(),
-                        ));
+                        revisions_table_item
+                            .set_text(&QString::from_std_str(revision.datetime.as_str()));
                         revisions_ptr.set_item(
-                            cnt as i32,
+                            row,
                             COL_REV_DATETIME,
                             revisions_table_item.into_ptr(),
                         );
                         // comment
                         let mut revisions_table_item = QTableWidgetItem::new();
-                        revisions_table_item.set_text(&QString::from_std_str(
-                            revision.comment.to_string().as_str(),
-                        ));
+                        revisions_table_item
+                            .set_text(&QString::from_std_str(revision.comment.as_str()));
                         revisions_ptr.set_item(
-                            cnt as i32,
+                            row,
                             COL_REV_COMMENT,
                             revisions_table_item.into_ptr(),
                         );
-                        //cnt += 1;
                     }
+                    // a search ending at a revision is a jump to it
+                    if page.offset == 0
+                        && page.filter.revision.is_some()
+                        && !page.revisions.is_empty()
+                    {
+                        revisions_ptr.select_row(0);
+                    }
+                    main_win.revisions_filter().set_status(
+                        format!(
+                            "{}{} revision(s) of {}",
+                            revisions_ptr.row_count(),
+                            if page.has_more { "+" } else { "" },
+                            page.filter
+                        )
+                        .as_str(),
+                    );
                 } else {
                     log::error!(
                         "PackagesTree::GetHistoryRevisions IMsg does not match event state"
                     );
                 }
             }
+            MainWin::GetPinHistory => {
+                if let Ok(IMsg::MainWin(IMainWin::PinHistory { vpin_id, entries })) =
+                    receiver.recv()
+                {
+                    let pin_history = main_win.pin_history();
+                    // the selection may have moved on while the history was queried
+                    if pin_history.vpin_id() == Some(vpin_id) {
+                        pin_history.set_entries(entries);
+                    }
+                } else {
+                    log::error!("MainWin::GetPinHistory IMsg does not match event state");
+                }
+            }
+            MainWin::GetLevelImpact => {
+                if let Ok(IMsg::MainWin(IMainWin::LevelImpact(impact))) = receiver.recv() {
+                    show_save_review(main_win.clone(), impact);
+                } else {
+                    log::error!("MainWin::GetLevelImpact IMsg does not match event state");
+                }
+            }
             MainWin::SaveVpinChanges => {
                 if let Ok(IMsg::MainWin(IMainWin::SaveVpinChanges(success))) = receiver.recv() {
                     let toolbar = main_win.main_toolbar();
@@ -224,158 +289,298 @@ pub fn match_main_win<'a>(
                     );
                 }
             }
+            MainWin::ImportManifest => {
+                if let Ok(IMsg::MainWin(IMainWin::ImportManifest { accepted, rejected })) =
+                    receiver.recv()
+                {
+                    stage_manifest_changes(main_win.clone(), accepted, rejected, "manifest");
+                } else {
+                    log::error!("MainWin::ImportManifest IMsg does not match event state");
+                }
+            }
+            MainWin::RestoreRevision => {
+                if let Ok(IMsg::MainWin(IMainWin::RestoreRevision {
+                    revision,
+                    accepted,
+                    rejected,
+                })) = receiver.recv()
+                {
+                    let source = format!("revision {}", revision);
+                    stage_manifest_changes(main_win.clone(), accepted, rejected, source.as_str());
+                } else {
+                    log::error!("MainWin::RestoreRevision IMsg does not match event state");
+                }
+            }
+            MainWin::ExportPins => {
+                if let Ok(IMsg::MainWin(IMainWin::ExportPins { count, path })) = receiver.recv() {
+                    log::info!("exported {} pins to {}", count, path);
+                    let mut mb = QMessageBox::new();
+                    mb.set_text(&qs(format!("Exported {} pins to {}", count, path)));
+                    mb.exec();
+                } else {
+                    log::error!("MainWin::ExportPins IMsg does not match event state");
+                }
+            }
+            MainWin::SavePinTemplate => {
+                if let Ok(IMsg::MainWin(IMainWin::SavePinTemplate {
+                    name,
+                    count,
+                    library,
+                })) = receiver.recv()
+                {
+                    log::info!("saved {} pins as template {} in {}", count, name, library);
+                    let mut mb = QMessageBox::new();
+                    mb.set_text(&qs(format!("Saved {} pins as template {}", count, name)));
+                    mb.exec();
+                } else {
+                    log::error!("MainWin::SavePinTemplate IMsg does not match event state");
+                }
+            }
+            MainWin::ApplyPinTemplate => {
+                if let Ok(IMsg::MainWin(IMainWin::ApplyPinTemplate {
+                    name,
+                    accepted,
+                    rejected,
+                })) = receiver.recv()
+                {
+                    let source = format!("template {}", name);
+                    stage_manifest_changes(main_win.clone(), accepted, rejected, source.as_str());
+                } else {
+                    log::error!("MainWin::ApplyPinTemplate IMsg does not match event state");
+                }
+            }
+            MainWin::ValidateWiths => {
+                if let Ok(IMsg::MainWin(IMainWin::ValidateWiths {
+                    vpin_id,
+                    coord,
+                    withs,
+                    level_withs,
+                })) = receiver.recv()
+                {
+                    stage_withpackage_changes(main_win.clone(), vpin_id, coord, withs, level_withs);
+                } else {
+                    log::error!("MainWin::ValidateWiths IMsg does not match event state");
+                }
+            }
             MainWin::ChooseDistribution => {
                 if let Ok(IMsg::MainWin(IMainWin::ChooseDistribution {
-                    distributions,
+                    versions,
                     package,
                     version,
                     row, // row in the versionpin table
                 })) = receiver.recv()
                 {
-                    let pinchange_cache = main_win.cache();
-                    let root_widget_ptr = main_win.main_widget();
-                    let vpin_table = main_win.vpin_table();
-                    let mut distribution = vpin_table.item(row, COL_DISTRIBUTION);
-                    let mut pinchanges_ptr = main_win.vpin_requested_changes_table();
-
-                    let (versions_list, idx, dist_versions) =
-                        build_qstring_list_and_map(version.as_str(), distributions);
-                    let mut ok_or_cancel = false;
-                    let ok_or_cancel_ptr = MutPtr::from_raw(&mut ok_or_cancel);
                     // Get New version by popping up a Dialog
-                    let new_version = QInputDialog::get_item_7a(
-                        root_widget_ptr,
-                        &qs("Pick Version"),
-                        &qs(package.as_str()),
-                        &versions_list,
-                        idx,
-                        false,
-                        ok_or_cancel_ptr,
+                    let picked = main_win.version_picker_dialog().exec(
+                        package.as_str(),
+                        version.as_str(),
+                        versions,
                     );
-                    if ok_or_cancel_ptr.is_null() {
-                        log::error!("ok_or_cancel_ptr is null. Problem on QT side. Returning");
-                        return;
-                    }
-                    if !(*ok_or_cancel_ptr) {
-                        log::info!("cancelled");
+                    if let Some(picked) = picked {
+                        stage_distribution_change(
+                            &main_win,
+                            row,
+                            package.as_str(),
+                            version,
+                            picked.version,
+                            picked.id,
+                        );
                     } else {
-                        let new_version_string = new_version.to_std_string();
-                        let new_dist_id = match dist_versions.get(new_version_string.as_str()) {
-                            Some(id) => id,
-                            // TODO: handle this more appropriately
-                            None => {
-                                log::error!("ERROR: Unable to get dist id.");
-                                return;
-                            }
-                        };
-                        let orig_vpin_table_distribution =
-                            format!("{}-{}", package, version.as_str());
-                        let new_distribution = format!("{}-{}", package, new_version_string);
-                        if orig_vpin_table_distribution == new_distribution {
-                            log::info!("new value and old value match. Skipping");
-                            return;
-                        }
-                        // retrieve the value of the versionpin row from the versionpin table
-                        let vpin_row = VersionPinRow::<CppBox<QString>>::from_table_at_row(
-                            &vpin_table, //&vpin_tablewidget_ptr,
+                        log::info!("cancelled");
+                    }
+                } else {
+                    log::error!("PackagesTree::ChooseDistribution IMsg does not match event state");
+                }
+            }
+            MainWin::DropDistribution => {
+                if let Ok(IMsg::MainWin(IMainWin::DropDistribution { distribution, row })) =
+                    receiver.recv()
+                {
+                    let vpin_table = main_win.vpin_table();
+                    let package = distribution.package();
+                    // the table presents the pending version of the pin, if it has one
+                    let current = VersionPinRow::<CppBox<QString>>::from_table_at_row(
+                        &vpin_table,
+                        row,
+                    )
+                    .map(|vpin_row| vpin_row.distribution)
+                    .filter(|current| current.package() == package);
+                    match (current, distribution.id()) {
+                        (Some(current), Some(dist_id)) => stage_distribution_change(
+                            &main_win,
                             row,
-                        )
-                        .ok_or(false)
-                        .expect("unable to retrieve the versionpin row from table");
-
-                        // cache the change. we will use this later to update the db. The rest of
-                        // the code is for updating the ui
-                        let new_value_qstr = QString::from_std_str(new_distribution);
-                        // build up new string
-                        distribution.set_text(&new_value_qstr);
-                        // if we arleady have the key in the pinchange table, we update the "to" value and
-                        // leave the from value alone, as it is the original state of the table prior to our
-                        // proposed changes - which we have not confirmed by hitting save yet.
-
-                        if pinchange_cache.has_key(vpin_row.pkgcoord_id) {
-                            let row = match pinchange_cache.index(vpin_row.pkgcoord_id) {
-                                Some(r) => r,
-                                None => {
-                                    log::error!("ERROR: Problem retrieving row from QT");
-                                    return;
-                                }
-                            };
-                            let mut item = pinchanges_ptr.item(row, COL_PC_NEW_VALUE);
-                            if item.is_null() {
-                                log::error!("problem retreiving row from pinchanges_ptr using cached row number. item is null");
-                                return;
-                            }
-                            item.set_text(&new_version);
-                            let change = Change::ChangeDistribution {
-                                vpin_id: vpin_row.id,
-                                new_dist_id: *new_dist_id,
-                            };
-                            pinchange_cache.cache_change_at(change, row);
-                        } else {
-                            // this is a new modification for this row of the vpin table
-                            let vpc_row = VersionPinChangesRow::<CppBox<QString>>::new(
-                                ChangeType::ChangeDistribution,
-                                vpin_row.pkgcoord(),
-                                qs(version.as_str()),
-                                new_version,
-                            );
-                            pinchange_cache.cache_original_version(vpin_row.id, version);
-                            let row_cnt = pinchanges_ptr.row_count() + 1;
-                            // increase the row count by 1 in the pinchanges table
-                            pinchanges_ptr.set_row_count(row_cnt);
-                            // cache the VersionPinChangesRow instance at the next row in the table
-                            vpc_row.set_table_row(&mut pinchanges_ptr, row_cnt - 1);
-                            let update_color = qcolor_blue!();
-                            distribution
-                                .set_foreground(&QBrush::from_q_color(update_color.as_ref()));
-                            distribution.table_widget().clear_selection();
-                            let idx = pinchange_cache.row_count();
-                            // cache the index of the change (idx) by the pkgcoord's db id.
-                            pinchange_cache.cache_dist(vpin_row.pkgcoord_id, idx);
-                            let change = Change::ChangeDistribution {
-                                vpin_id: vpin_row.id,
-                                new_dist_id: *new_dist_id,
-                            };
-                            // cache the change
-                            pinchange_cache.cache_change(change);
+                            package,
+                            current.version().to_string(),
+                            distribution.version().to_string(),
+                            dist_id,
+                        ),
+                        (Some(_), None) => {
+                            log::error!("the id of {} was not looked up. skipping", distribution)
                         }
+                        (None, _) => log::warn!(
+                            "versionpin table row {} no longer pins {}. skipping",
+                            row,
+                            package
+                        ),
                     }
                 } else {
-                    log::error!("PackagesTree::ChooseDistribution IMsg does not match event state");
+                    log::error!("MainWin::DropDistribution IMsg does not match event state");
+                }
+            }
+            MainWin::PreviewPackagesXml => {
+                if let Ok(IMsg::MainWin(IMainWin::PreviewPackagesXml {
+                    show,
+                    output,
+                    xml,
+                    existing,
+                })) = receiver.recv()
+                {
+                    if existing.as_ref() == Some(&xml) {
+                        log::info!("{} is up to date", output);
+                        let mut mb = QMessageBox::new();
+                        mb.set_text(&qs(format!(
+                            "{} is up to date with the packages.xml for {}",
+                            output, show
+                        )));
+                        mb.exec();
+                        return;
+                    }
+                    main_win
+                        .packages_xml_dialog()
+                        .exec(PendingPackagesXml { show, output, xml }, existing);
+                } else {
+                    log::error!("MainWin::PreviewPackagesXml IMsg does not match event state");
                 }
             }
             MainWin::SavePackagesXml => {
-                // TODO
-                if let Ok(IMsg::MainWin(IMainWin::SavePackagesXml(_success))) = receiver.recv() {
-                    log::info!("wrote out packages.xml to disk");
+                if let Ok(IMsg::MainWin(IMainWin::SavePackagesXml {
+                    show,
+                    output,
+                    result,
+                })) = receiver.recv()
+                {
+                    let mut mb = QMessageBox::new();
+                    match result {
+                        Ok(_) => {
+                            log::info!("wrote out packages.xml for {} to {}", show, output);
+                            mb.set_text(&qs(format!(
+                                "Wrote the packages.xml for {} to {}",
+                                show, output
+                            )));
+                        }
+                        Err(err) => {
+                            log::error!("Unable to save packages.xml to {}: {}", output, err);
+                            mb.set_icon(Icon::Warning);
+                            mb.set_text(&qs(format!("Unable to write {}", output)));
+                            mb.set_informative_text(&qs(err));
+                        }
+                    }
+                    mb.exec();
                 } else {
-                    log::error!("Unable to save packages.xml to disk");
+                    log::error!("MainWin::SavePackagesXml IMsg does not match event state");
                 }
             }
         }
     }
 }
 
-// Construct a qstringlist of versions, identify the index of the currently selected version,
-// and provide a hasmap mapping the version to the id
-fn build_qstring_list_and_map(
-    version: &str,
-    results: Vec<FindAllDistributionsRow>,
-) -> (CppBox<QStringList>, i32, HashMap<String, IdType>) {
-    unsafe {
-        let mut versions_list = QStringList::new();
-        let mut idx = 0;
-        // implementing clippy suggestion
-        //let mut cnt = 0;
-        let mut dist_versions = HashMap::new();
-        for (cnt, r) in results.into_iter().enumerate() {
-            if r.version == version {
-                idx = cnt as i32;
+// Stage a change of the distribution of the versionpin at `row` of the versionpin table,
+// from `version` to `new_version_string`, presenting it in the changes table. A pending
+// change of the same pin is updated in place.
+unsafe fn stage_distribution_change(
+    main_win: &InnerMainWindow,
+    row: i32,
+    package: &str,
+    version: String,
+    new_version_string: String,
+    new_dist_id: IdType,
+) {
+    let pinchange_cache = main_win.cache();
+    let vpin_table = main_win.vpin_table();
+    let mut distribution = vpin_table.item(row, COL_DISTRIBUTION);
+    let mut pinchanges_ptr = main_win.vpin_requested_changes_table();
+    let new_version = qs(new_version_string.as_str());
+    let orig_vpin_table_distribution = format!("{}-{}", package, version.as_str());
+    let new_distribution = format!("{}-{}", package, new_version_string);
+    if orig_vpin_table_distribution == new_distribution {
+        log::info!("new value and old value match. Skipping");
+        return;
+    }
+    // retrieve the value of the versionpin row from the versionpin table
+    let vpin_row = VersionPinRow::<CppBox<QString>>::from_table_at_row(
+        &vpin_table, //&vpin_tablewidget_ptr,
+        row,
+    )
+    .ok_or(false)
+    .expect("unable to retrieve the versionpin row from table");
+
+    // cache the change. we will use this later to update the db. The rest of
+    // the code is for updating the ui
+    let new_value_qstr = QString::from_std_str(new_distribution);
+    // build up new string
+    distribution.set_text(&new_value_qstr);
+    distribution.set_data(PIN_VERSION_ROLE, &QVariant::from_q_string(&new_version));
+    // if we arleady have the key in the pinchange table, we update the "to" value and
+    // leave the from value alone, as it is the original state of the table prior to our
+    // proposed changes - which we have not confirmed by hitting save yet.
+
+    if pinchange_cache.has_key(vpin_row.pkgcoord_id) {
+        let row = match pinchange_cache.index(vpin_row.pkgcoord_id) {
+            Some(r) => r,
+            None => {
+                log::error!("ERROR: Problem retrieving row from QT");
+                return;
             }
-            //cnt += 1;
-            dist_versions.insert(r.version.clone(), r.id);
-            versions_list.append_q_string(&QString::from_std_str(r.version));
+        };
+        let mut item = pinchanges_ptr.item(row, COL_PC_NEW_VALUE);
+        if item.is_null() {
+            log::error!("problem retreiving row from pinchanges_ptr using cached row number. item is null");
+            return;
         }
-        (versions_list, idx, dist_versions)
+        item.set_text(&new_version);
+        let change = Change::ChangeDistribution {
+            vpin_id: vpin_row.id,
+            new_dist_id,
+        };
+        pinchange_cache.cache_change_at(change, row);
+    } else {
+        // this is a new modification for this row of the vpin table
+        let vpc_row = VersionPinChangesRow::<CppBox<QString>>::new(
+            ChangeType::ChangeDistribution,
+            vpin_row.pkgcoord(),
+            qs(version.as_str()),
+            new_version,
+        );
+        pinchange_cache.cache_original_version(vpin_row.id, version);
+        pinchange_cache.cache_level(vpin_row.id, vpin_row.level.to_std_string());
+        let row_cnt = pinchanges_ptr.row_count() + 1;
+        // increase the row count by 1 in the pinchanges table
+        pinchanges_ptr.set_row_count(row_cnt);
+        // cache the VersionPinChangesRow instance at the next row in the table
+        vpc_row.set_table_row(&mut pinchanges_ptr, row_cnt - 1);
+        let update_color = qcolor_blue!();
+        distribution.set_foreground(&QBrush::from_q_color(update_color.as_ref()));
+        distribution.table_widget().clear_selection();
+        let idx = pinchange_cache.row_count();
+        // cache the index of the change (idx) by the pkgcoord's db id.
+        pinchange_cache.cache_dist(vpin_row.pkgcoord_id, idx);
+        let change = Change::ChangeDistribution {
+            vpin_id: vpin_row.id,
+            new_dist_id,
+        };
+        // cache the change
+        pinchange_cache.cache_change(change);
     }
 }
+
+// highlight the distribution of a pin of the past which differs from the pin today
+unsafe fn mark_past_pin(vpin_table: &mut MutPtr<QTableWidget>, row: i32, state: &PinState) {
+    let tooltip = match state {
+        PinState::Unchanged => return,
+        PinState::Changed(version) => format!("Pinned to {} today", version),
+        PinState::Deleted => "Deleted since".to_string(),
+    };
+    let mut item = vpin_table.item(row, COL_DISTRIBUTION);
+    item.set_foreground(&QBrush::from_q_color(QColor::from_rgb_3a(255, 200, 90).as_ref()));
+    item.set_tool_tip(&qs(tooltip));
+}
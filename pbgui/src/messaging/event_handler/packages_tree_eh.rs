@@ -44,6 +44,9 @@ pub fn match_packages_tree<'a>(
                         model.remove_rows_3a(0, 1, idx.as_ref());
                         let inner = tree.inner();
                         inner.set_children(item, dists_ref, true);
+                        inner.set_dists_draggable(item);
+                        // narrow the distributions to those matching the package filter
+                        inner.apply_version_search(item);
                     }
                 }
             } else {
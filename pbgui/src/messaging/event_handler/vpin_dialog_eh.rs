@@ -4,7 +4,8 @@ use super::*;
 use crate::change_type::{Change, ChangeType};
 use crate::components::{versionpin_changes_row::*, versionpin_row::*};
 use crate::traits::RowSetterTrait;
-use qt_widgets::cpp_core::CppBox;
+use crate::messaging::incoming::ivpin_dialog::PinConflict;
+use qt_widgets::{cpp_core::CppBox, q_message_box::StandardButton, QMessageBox};
 use rustqt_utils::{qs, ToQString};
 
 pub fn match_vpin_dialog<'a>(
@@ -22,6 +23,14 @@ pub fn match_vpin_dialog<'a>(
                 log::error!("Event::UpdateSites IMsg does not match event state");
             }
         }
+        VpinDialog::UpdatePlatforms => {
+            if let Ok(IMsg::VpinDialog(IVpinDialog::Platforms(platforms))) = receiver.recv() {
+                let platforms_ref = platforms.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+                dialog.set_platforms(platforms_ref);
+            } else {
+                log::error!("Event::UpdatePlatforms IMsg does not match event state");
+            }
+        }
         VpinDialog::UpdateRoles => {
             if let Ok(IMsg::VpinDialog(IVpinDialog::Roles(roles))) = receiver.recv() {
                 let roles_ref = roles.iter().map(|x| x.as_str()).collect::<Vec<_>>();
@@ -45,10 +54,35 @@ pub fn match_vpin_dialog<'a>(
             }
         }
         VpinDialog::SetVpin => {
-            if let Ok(IMsg::VpinDialog(IVpinDialog::SetVpin(changes))) = receiver.recv() {
+            if let Ok(IMsg::VpinDialog(IVpinDialog::SetVpin { changes, conflicts })) =
+                receiver.recv()
+            {
                 // get cache
                 let cache = main_win.cache();
+                // refuse pins which collide with a pending new pin
+                let (changes, pending): (Vec<_>, Vec<_>) =
+                    changes.into_iter().partition(|change| {
+                        change
+                            .pin_coord()
+                            .map_or(true, |coord| cache.pending_pin_at(&coord).is_none())
+                    });
                 unsafe {
+                    if !pending.is_empty() {
+                        let report = pending
+                            .iter()
+                            .filter_map(|change| change.pin_coord())
+                            .map(|coord| format!("{:?}", coord))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+                        log::warn!("rejected pins duplicating pending changes:\n{}", report);
+                        let mut mb = QMessageBox::new();
+                        mb.set_text(&qs(format!(
+                            "{} requested pin(s) duplicate a pending change and were skipped.",
+                            pending.len()
+                        )));
+                        mb.set_detailed_text(&qs(report));
+                        mb.exec();
+                    }
                     // we have to
                     // set a versionpin_table row per change X
                     // set a versionpin_changes_table row per change
@@ -73,18 +107,11 @@ pub fn match_vpin_dialog<'a>(
                             ref site,
                         } = change
                         {
-                            let version = distribution
-                                .split('-')
-                                //.skip(1)
-                                //.next()
-                                .nth(1)
-                                .ok_or(false)
-                                .expect("Unable to split version from dist");
                             let versionpin_row = VersionPinRow::<CppBox<QString>>::new(
                                 id,
                                 id,
                                 id,
-                                distribution.to_qstring(),
+                                distribution.clone(),
                                 level.to_qstring(),
                                 role.to_qstring(),
                                 platform.to_qstring(),
@@ -96,7 +123,7 @@ pub fn match_vpin_dialog<'a>(
                                 ChangeType::AddDistribution,
                                 versionpin_row.pkgcoord(),
                                 qs(""),
-                                qs(version),
+                                qs(distribution.version()),
                             );
 
                             vpc_row.set_table_row(&mut pinchanges_ptr, changes_row_count + cnt);
@@ -110,6 +137,9 @@ pub fn match_vpin_dialog<'a>(
                         };
                     }
                     versionpin_table.set_sorting_enabled(true);
+                    if !conflicts.is_empty() {
+                        offer_conflict_conversion(main_win.clone(), conflicts);
+                    }
                 }
 
             // get
@@ -119,6 +149,73 @@ pub fn match_vpin_dialog<'a>(
         }
     }
 }
+// Requested pins which collide with existing pins in the database are refused. The
+// user is offered the chance to change the version of the existing pins instead.
+unsafe fn offer_conflict_conversion(main_win: Rc<InnerMainWindow>, conflicts: Vec<PinConflict>) {
+    let report = conflicts
+        .iter()
+        .map(|c| {
+            format!(
+                "{} at (level: {}, role: {}, platform: {}, site: {}): {} -> {}",
+                c.coord.package,
+                c.coord.level,
+                c.coord.role,
+                c.coord.platform,
+                c.coord.site,
+                c.existing_version,
+                c.version
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut mb = QMessageBox::new();
+    mb.set_text(&qs(format!(
+        "{} requested pin(s) already exist with a different version.",
+        conflicts.len()
+    )));
+    mb.set_informative_text(&qs("Change the version of the existing pin(s) instead?"));
+    mb.set_detailed_text(&qs(report));
+    mb.set_standard_buttons(StandardButton::Yes | StandardButton::No);
+    mb.set_default_button_standard_button(StandardButton::No);
+    if mb.exec() != StandardButton::Yes.to_int() {
+        log::info!("refused {} conflicting pin(s)", conflicts.len());
+        return;
+    }
+    let cache = main_win.cache();
+    let mut pinchanges_ptr = main_win.vpin_requested_changes_table();
+    for conflict in conflicts {
+        let change = Change::ChangeDistribution {
+            vpin_id: conflict.vpin_id,
+            new_dist_id: conflict.new_dist_id,
+        };
+        let vpc_row = VersionPinChangesRow::<CppBox<QString>>::new(
+            ChangeType::ChangeDistribution,
+            qs(format!(
+                "(level: {}, role: {}, platform: {}, site: {}, package: {})",
+                conflict.coord.level,
+                conflict.coord.role,
+                conflict.coord.platform,
+                conflict.coord.site,
+                conflict.coord.package
+            )),
+            qs(conflict.existing_version.as_str()),
+            qs(conflict.version),
+        );
+        cache.cache_level(conflict.vpin_id, conflict.coord.level);
+        // a pending change to the same pin is replaced
+        if let Some(row) = cache.index(conflict.pkgcoord_id) {
+            cache.cache_change_at(change, row);
+            vpc_row.set_table_row(&mut pinchanges_ptr, row);
+            continue;
+        }
+        cache.cache_original_version(conflict.vpin_id, conflict.existing_version);
+        let row = pinchanges_ptr.row_count();
+        cache.cache_dist(conflict.pkgcoord_id, cache.row_count());
+        cache.cache_change(change);
+        vpc_row.set_table_row(&mut pinchanges_ptr, row);
+    }
+}
+
 /*
 id: IdType,
         dist_id: IdType,
@@ -1,22 +1,46 @@
 use super::*;
+use crate::change_type::PinCoord;
+use crate::distribution::Distribution;
+use crate::history::{HistoryPage, PinHistoryEntry};
+use crate::manifest::{ManifestRejection, ValidatedEntry};
+use crate::time_travel::{AsOf, PastPins};
+use crate::versions::VersionInfo;
+use crate::withs_check::LevelWiths;
 use packybara::db::find_all::changes::FindAllChangesRow;
-use packybara::db::find_all::distributions::FindAllDistributionsRow;
-use packybara::db::find_all::revisions::FindAllRevisionsRow;
 use packybara::db::find_all::versionpin_withs::FindAllWithsRow;
 use packybara::db::find_all::versionpins::FindAllVersionPinsRow;
+use packybara::types::IdType;
+use std::collections::HashMap;
 
 /// Responsies returning to the main gui thread from the secondary thread
 /// for the main window.
 pub enum IMainWin {
     /// Returns a vector of versionpin row data
     Vpins(Vec<FindAllVersionPinsRow>),
+    /// Returns the versionpins as they stood at a past revision
+    PastVpins {
+        /// the point in time requested
+        as_of: AsOf,
+        /// and the reconstructed pins.
+        pins: PastPins,
+    },
     /// Returns the results of querying with packages maching a
     /// query rpovided to OMainWin
     WithPackages(Vec<FindAllWithsRow>),
     /// Returns a vector of Changes
     Changes(Vec<FindAllChangesRow>),
-    /// Returns a vector of revision rows
-    HistoryRevisions(Vec<FindAllRevisionsRow>),
+    /// Returns a page of revision rows
+    HistoryRevisions(HistoryPage),
+    /// Returns the changes made to a versionpin, newest first
+    PinHistory {
+        /// The id of the versionpin
+        vpin_id: IdType,
+        /// and its changes.
+        entries: Vec<PinHistoryEntry>,
+    },
+    /// Returns a map of level to the number of descendant levels inheriting
+    /// from it, for the levels requested.
+    LevelImpact(HashMap<String, usize>),
     /// Returns success/faliure after updating the database with versionpin
     /// changes previously stashed in the vpin changes table.
     SaveVpinChanges(bool), //consider changing to Result<(),>
@@ -24,8 +48,9 @@ pub enum IMainWin {
     /// and provides the original package, version and row from the
     /// versionpin table.
     ChooseDistribution {
-        /// list of distributions found
-        distributions: Vec<FindAllDistributionsRow>,
+        /// versions of the package found, along with their pin counts and
+        /// install metadata
+        versions: Vec<VersionInfo>,
         /// for package:
         package: String,
         /// with original version
@@ -33,9 +58,75 @@ pub enum IMainWin {
         /// found in versionpin table at row
         row: i32,
     },
-    /// Returns a bool indicating success or faliure after attempting to
-    /// save state as packages.xml to a provided location
-    SavePackagesXml(bool),
+    /// Returns the distribution dropped onto a versionpin of its package.
+    DropDistribution {
+        /// The distribution, along with its id
+        distribution: Distribution,
+        /// dropped on the versionpin at row in the versionpin table
+        row: i32,
+    },
+    /// Returns the results of validating a versionpin change manifest
+    ImportManifest {
+        /// entries which passed validation
+        accepted: Vec<ValidatedEntry>,
+        /// and entries which did not, along with the reason.
+        rejected: Vec<ManifestRejection>,
+    },
+    /// Returns the number of pins exported, and the path they were exported to
+    ExportPins { count: usize, path: String },
+    /// Returns the name of the template saved, its number of pins, and the library
+    /// it was saved to
+    SavePinTemplate {
+        name: String,
+        count: usize,
+        library: String,
+    },
+    /// Returns the results of validating the pins of a template against a level
+    ApplyPinTemplate {
+        /// the name of the template
+        name: String,
+        /// pins which passed validation, as changes
+        accepted: Vec<ValidatedEntry>,
+        /// and pins which did not, along with the reason.
+        rejected: Vec<ManifestRejection>,
+    },
+    /// Returns the withs of the pins sharing a pin's coordinate, along with the
+    /// request, so that the proposed withs may be validated
+    ValidateWiths {
+        vpin_id: IdType,
+        coord: PinCoord,
+        withs: Vec<String>,
+        /// the withs of the other pins at the coordinate, keyed by package
+        level_withs: LevelWiths,
+    },
+    /// Returns the results of validating the changes which restore a past revision
+    RestoreRevision {
+        revision: IdType,
+        /// changes which passed validation
+        accepted: Vec<ValidatedEntry>,
+        /// and changes which did not, along with the reason.
+        rejected: Vec<ManifestRejection>,
+    },
+    /// Returns an exported packages.xml, along with the file it would replace
+    PreviewPackagesXml {
+        /// The show the file is exported for
+        show: String,
+        /// The path the file is to be written to
+        output: String,
+        /// The exported contents
+        xml: String,
+        /// The contents of the file at output, if it exists
+        existing: Option<String>,
+    },
+    /// Returns the outcome of writing a packages.xml to a provided location
+    SavePackagesXml {
+        /// The show the file was exported for
+        show: String,
+        /// The path written to
+        output: String,
+        /// Ok, or the reason the file could not be written
+        result: Result<(), String>,
+    },
 }
 
 impl ToIMsg for IMainWin {
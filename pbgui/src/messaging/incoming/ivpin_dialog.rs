@@ -1,5 +1,23 @@
 use super::*;
-use crate::change_type::Change;
+use crate::change_type::{Change, PinCoord};
+use packybara::types::IdType;
+
+/// A requested new pin which collides with a pin already in the database
+#[derive(Debug, PartialEq)]
+pub struct PinConflict {
+    /// The package and coordinate shared by the pins
+    pub coord: PinCoord,
+    /// The id of the existing versionpin
+    pub vpin_id: IdType,
+    /// The id of the existing versionpin's package coordinate
+    pub pkgcoord_id: IdType,
+    /// The version currently pinned
+    pub existing_version: String,
+    /// The version requested
+    pub version: String,
+    /// The id of the requested distribution
+    pub new_dist_id: IdType,
+}
 /// Responses returning to the main ui thread from the secondary thread for the
 /// versionpin dialog element.
 pub enum IVpinDialog {
@@ -7,13 +25,19 @@ pub enum IVpinDialog {
     Roles(Vec<String>),
     /// Returns a vector of all site names
     Sites(Vec<String>),
+    /// Returns a vector of all platform names
+    Platforms(Vec<String>),
     /// Returns a vector of levels in a LevelMap, which is a type alias for
     /// a HashMap<String, Vec<String>>;
     Levels(LevelMap),
     /// Set the current show for the diaog
     SetShow(String),
-    /// Set the versionpin to a vector of changes
-    SetVpin(Vec<Change>),
+    /// Set the versionpin to a vector of changes, along with any
+    /// requested pins which collide with existing pins.
+    SetVpin {
+        changes: Vec<Change>,
+        conflicts: Vec<PinConflict>,
+    },
 }
 
 impl ToIMsg for IVpinDialog {
@@ -18,6 +18,9 @@ where
     to_thread_sender
         .send(OMsg::VpinDialog(OVpinDialog::GetSites))
         .expect("unable to get sites");
+    to_thread_sender
+        .send(OMsg::VpinDialog(OVpinDialog::GetPlatforms))
+        .expect("unable to get platforms");
     to_thread_sender
         .send(OMsg::VpinDialog(OVpinDialog::GetLevels(
             default_show.into(),
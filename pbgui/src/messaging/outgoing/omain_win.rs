@@ -1,6 +1,14 @@
 use super::*;
-use crate::change_type::Change;
+use crate::change_type::{Change, PinCoord};
+use crate::distribution::Distribution;
+use crate::export::{ExportFormat, ExportRow};
+use crate::history::HistoryFilter;
+use crate::manifest::ManifestEntry;
+use crate::templates::PinTemplate;
+use crate::time_travel::AsOf;
 use crate::SearchMode;
+use packybara::types::IdType;
+use std::collections::HashMap;
 
 #[derive(Debug, PartialEq)]
 pub enum OMainWin {
@@ -15,6 +23,9 @@ pub enum OMainWin {
         platform: String,
         site: String,
         dir: String,
+        /// Reconstruct the pins as they stood at a past revision, rather
+        /// than returning the pins today
+        as_of: Option<AsOf>,
     },
     /// Request a list of withs
     GetWithsForVpin {
@@ -26,12 +37,31 @@ pub enum OMainWin {
         /// for a given transaction id.
         tx_id: i32,
     },
-    /// Request the list of historical revisions from the db.
-    GetHistoryRevisions,
+    /// Request a page of historical revisions from the db, newest first,
+    GetHistoryRevisions {
+        /// matching the filter,
+        filter: HistoryFilter,
+        /// after skipping offset matching revisions.
+        offset: i64,
+    },
+    /// Request the changes made to a versionpin
+    GetPinHistory {
+        /// with the supplied id,
+        vpin_id: IdType,
+        /// whose changes are recorded against its package and coordinate.
+        coord: PinCoord,
+    },
+    /// Request the number of descendant levels inheriting from each level
+    GetLevelImpact {
+        /// for the levels affected by the pending changes.
+        levels: Vec<String>,
+    },
     /// save all proposed versionpin changes,
     SaveVpinChanges {
         /// given a vector of Change instances,
         changes: Vec<Change>,
+        /// the coordinates of the new pins which changes refer to by fake id,
+        new_pins: HashMap<IdType, PinCoord>,
         /// the user making the changes
         user: String,
         /// And the user's comments regarding the changes.
@@ -46,13 +76,82 @@ pub enum OMainWin {
         version: String,
         /// found at versionpin table row in he versionpin table.
         row: i32,
+        /// The current site, whose install root is inspected for the versions
+        site: String,
+    },
+    /// Look up a distribution dropped from the packages tree onto a versionpin
+    /// of its package, in order to change the version of the pin.
+    DropDistribution {
+        /// The dropped distribution
+        distribution: Distribution,
+        /// dropped on the versionpin at row in the versionpin table.
+        row: i32,
+    },
+    /// Validate the entries of a versionpin change manifest against the database
+    ImportManifest {
+        /// given the entries read from the manifest.
+        entries: Vec<ManifestEntry>,
+    },
+    /// Export versionpins to disk
+    ExportPins {
+        /// given the rows gathered from the versionpin table,
+        rows: Vec<ExportRow>,
+        /// the path to write to,
+        path: String,
+        /// the format to write,
+        format: ExportFormat,
+        /// and whether to include the pending changes.
+        include_pending: bool,
+    },
+    /// Save pins to the template library
+    SavePinTemplate {
+        /// under the supplied name,
+        name: String,
+        /// given the rows selected in the versionpin table,
+        rows: Vec<ExportRow>,
+        /// and the path to the library.
+        library: String,
+    },
+    /// Validate the pins of a template against a level
+    ApplyPinTemplate {
+        /// given the template
+        template: PinTemplate,
+        /// and the level to apply it to.
+        level: String,
+    },
+    /// Look up the withs of the pins sharing a pin's coordinate, in order to
+    /// validate the proposed withs of the pin
+    ValidateWiths {
+        /// given the versionpin id,
+        vpin_id: IdType,
+        /// its coordinate,
+        coord: PinCoord,
+        /// and its proposed withs.
+        withs: Vec<String>,
+    },
+    /// Validate the changes which return the versionpins to their state at a past
+    /// revision
+    RestoreRevision {
+        /// given the revision,
+        revision: IdType,
+        /// and the changes, as manifest entries.
+        entries: Vec<ManifestEntry>,
+    },
+    /// Export a packages.xml, along with the contents of the file it would replace
+    PreviewPackagesXml {
+        /// For a given show
+        show: String,
+        /// to a specified location
+        output: String,
     },
-    /// Save a packages.xml
+    /// Save a packages.xml previously exported
     SavePackagesXml {
         /// For a given show
         show: String,
         /// to a specified location
         output: String,
+        /// with the supplied contents.
+        xml: String,
     },
 }
 
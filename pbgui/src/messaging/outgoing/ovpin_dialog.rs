@@ -1,4 +1,6 @@
 use super::*;
+use crate::distribution::Distribution;
+use pbgui_vpin::vpin_dialog::PinCombination;
 
 #[derive(Debug, PartialEq)]
 /// Requests originating from the main ui thread for the
@@ -6,6 +8,8 @@ use super::*;
 pub enum OVpinDialog {
     /// Retrieve the list of sites
     GetSites,
+    /// Retrieve the list of platforms
+    GetPlatforms,
     /// Retrieve the list of roles
     GetRoles,
     /// Retrieve the list of roles for the provided show
@@ -13,15 +17,9 @@ pub enum OVpinDialog {
     /// Add a versionpin or versionpins to the show,
     SetVpin {
         /// for the provided  distribution
-        dist: String,
-        /// and one or more roles
-        roles: Vec<String>,
-        /// at the supplied level
-        level: String,
-        /// and site
-        site: String,
-        /// and platform
-        platform: String,
+        dist: Distribution,
+        /// once per combination of level, role, site and platform
+        combinations: Vec<PinCombination>,
     },
 }
 
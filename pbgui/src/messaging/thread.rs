@@ -1,7 +1,7 @@
 //! Handles mpsc::channel delivered OMsg queries in a separate thread, which sends response IMsgs via an mpsc::channel, and
 //! notifies QT using Conductor.
 use crate::{
-    logger,
+    logger::{self, RotatingFileSink},
     messaging::{
         client_proxy::{ClientProxy, ConnectParams},
         event::{MainToolbar, MainWin, PackageWiths, PackagesTree},
@@ -13,12 +13,14 @@ use crate::{
 use crossbeam_channel::{Receiver, Sender};
 use crossbeam_utils::thread;
 use log;
+use main_win::MainWinState;
 use packybara::packrat::PackratDb;
 use packybara::traits::*;
 use pbgui_vpin::vpin_dialog::LevelMap;
 use qt_core::Slot;
 use qt_thread_conductor::conductor::Conductor;
 use qt_widgets::{cpp_core::MutPtr, QApplication, QMainWindow};
+use std::collections::HashMap;
 
 pub mod vpin_dialog;
 use vpin_dialog::match_vpin_dialog;
@@ -44,22 +46,29 @@ pub use ui_logger::match_ui_logger;
 /// handles complex data, and the `conductor` notifies QT.
 ///
 /// # Arguments
+/// * `connect_params` - The parameters used to connect to the database
+/// * `install_roots` - Map of site name to the directory distributions are installed under
 /// * `main_window` - Mutable MutPtr wrapped QMainWindow instance
 /// * `conductor` - Mutable instance of the Conductor<Event>, responsible for signaling
 ///                 to QT
 /// * sender - Sends IMsg's to the UI thread
 /// * receiver - Receives OMsg's from the UI thread
+/// * to_thread_sender - Sends OMsg's to the secondary thread. Used by the logger
+/// * log_level - The initial log level
+/// * log_file_sink - Optional sink which logs are written to in addition to the UI
 ///
 /// # Returns
 /// * i32 - The status
 pub fn create(
     connect_params: ConnectParams,
+    install_roots: HashMap<String, String>,
     mut main_window: MutPtr<QMainWindow>,
     mut conductor: Conductor<Event>,
     sender: Sender<IMsg>,
     receiver: Receiver<OMsg>,
     to_thread_sender: Sender<OMsg>,
     log_level: &str,
+    log_file_sink: Option<RotatingFileSink>,
 ) -> i32 {
     let mut result = 0;
     thread::scope(|s| {
@@ -75,6 +84,7 @@ pub fn create(
                 }
             };
             let mut db = PackratDb::new(client);
+            let mut main_win_state = MainWinState::new(install_roots);
             //let mut show: Option<String> = None;
             loop {
                 let msg = receiver.recv().expect("Unable to unwrap received msg");
@@ -92,7 +102,7 @@ pub fn create(
                         match_main_toolbar(msg, &mut db, &mut conductor, &sender);
                     }
                     OMsg::MainWin(msg) => {
-                        match_main_win(msg, &mut db, &mut conductor, &sender);
+                        match_main_win(msg, &mut db, &mut main_win_state, &mut conductor, &sender);
                     }
                     OMsg::UiLogger(msg) => {
                         match_ui_logger(msg, &mut conductor, &sender);
@@ -109,7 +119,7 @@ pub fn create(
         // so that the scope lives longer than the application
         unsafe {
             main_window.show();
-            match logger::init(to_thread_sender, log_level) {
+            match logger::init(to_thread_sender, log_level, log_file_sink) {
                 Ok(_) => (),
                 Err(e) => println!("{:?}", e),
             }
@@ -1,17 +1,64 @@
 use super::*;
-use crate::change_type::Change;
+use crate::change_type::{Change, PinCoord};
+use crate::distribution::Distribution;
+use crate::export::{expand_withs, export_pins, ExportRow};
+use crate::history::{query_pin_history, query_revisions};
+use crate::manifest::{ManifestEntry, ManifestOp, ManifestRejection, ValidatedEntry};
+use crate::templates::{PinTemplate, TemplateLibrary, TemplatePin};
+use crate::time_travel::{AsOf, ChangeAction, PastChange, PastPins, PinQuery};
+use crate::versionpin_row::VersionPinRow;
+use crate::versions::VersionInfo;
+use crate::withs_check::{describe, find_staged_cycles, LevelWiths, WithsIssue};
+use chrono::NaiveDateTime;
+use packybara::db::find_all::changes::FindAllChangesRow;
+use packybara::db::find_all::versionpins::FindAllVersionPinsRow;
 use packybara::db::update::versionpins::VersionPinChange;
 use packybara::packrat::PackratDb;
 use packybara::LtreeSearchMode;
-use packybara::OrderDirection;
-use packybara::OrderRevisionBy;
+use packybara::{OrderDirection, OrderRevisionBy};
 
+use crate::utility::descendant_level_count;
 use crate::SearchMode;
+use packybara::types::IdType;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::str::FromStr;
 
+// The id of the versionpin most recently created within the current transaction
+const NEW_VERSIONPIN_ID: &str =
+    "SELECT currval(pg_get_serial_sequence('versionpin', 'id'))::bigint";
+
+/// State the secondary thread keeps between MainWin requests
+#[derive(Debug, Default)]
+pub(crate) struct MainWinState {
+    /// Map of site name to the directory distributions are installed under
+    install_roots: HashMap<String, String>,
+    /// The number of versionpins in the facility using each version of each package,
+    /// keyed by package and version. Cached until the versionpins are next queried
+    /// or saved.
+    pin_counts: Option<HashMap<String, HashMap<String, usize>>>,
+}
+
+impl MainWinState {
+    /// New up a MainWinState
+    ///
+    /// # Arguments
+    /// * `install_roots` - Map of site name to install root
+    ///
+    /// # Returns
+    /// * MainWinState instance
+    pub(crate) fn new(install_roots: HashMap<String, String>) -> Self {
+        Self {
+            install_roots,
+            pin_counts: None,
+        }
+    }
+}
+
 pub(crate) fn match_main_win(
     msg: OMainWin,
     db: &mut PackratDb,
+    state: &mut MainWinState,
     conductor: &mut qt_thread_conductor::conductor::Conductor<Event>,
     sender: &Sender<IMsg>,
 ) {
@@ -24,7 +71,41 @@ pub(crate) fn match_main_win(
             platform,
             site,
             dir,
+            as_of,
         } => {
+            // the pins may have changed since the pin counts were cached
+            state.pin_counts = None;
+            if let Some(as_of) = as_of {
+                let query = PinQuery {
+                    level,
+                    role,
+                    platform,
+                    site,
+                    dir,
+                    isolate_facility: mode == SearchMode::Show,
+                };
+                let mut pins = match rewind_vpins(db, &as_of, &query) {
+                    Ok(pins) => pins,
+                    Err(err) => {
+                        sender
+                            .send(IMsg::Error(format!(
+                                "Unable to reconstruct version pins as of {}: {}",
+                                as_of, err
+                            )))
+                            .expect("unable to send error msg");
+                        conductor.signal(Event::Error);
+                        return;
+                    }
+                };
+                if let Some(package_str) = package {
+                    pins.pins.retain(|pin| pin.coord().package.starts_with(&package_str));
+                }
+                sender
+                    .send(IMainWin::PastVpins { as_of, pins }.to_imsg())
+                    .expect("unable to send past version pins");
+                conductor.signal(MainWin::GetPastVpins.to_event());
+                return;
+            }
             // TODO: add the package to the search
             let results = db
                 .find_all_versionpins()
@@ -49,10 +130,10 @@ pub(crate) fn match_main_win(
                     return;
                 }
             };
-            if let Some(package_str) = package {
+            if let Some(ref package_str) = package {
                 vpins = vpins
                     .into_iter()
-                    .filter(|x| x.distribution.package().starts_with(&package_str))
+                    .filter(|x| x.distribution.package().starts_with(package_str))
                     .collect::<Vec<_>>();
             }
             sender
@@ -100,14 +181,9 @@ pub(crate) fn match_main_win(
                 .expect("unable to send version pins");
             conductor.signal(MainWin::GetTransactionChanges.to_event());
         }
-        OMainWin::GetHistoryRevisions => {
-            let results = db
-                .find_all_revisions()
-                .order_by(vec![OrderRevisionBy::Id])
-                .order_direction(OrderDirection::Desc)
-                .query();
-            let revisions = match results {
-                Ok(revisions) => revisions,
+        OMainWin::GetHistoryRevisions { filter, offset } => {
+            let page = match query_revisions(db, filter, offset) {
+                Ok(page) => page,
                 Err(err) => {
                     sender
                         .send(IMsg::Error(format!(
@@ -120,15 +196,99 @@ pub(crate) fn match_main_win(
                 }
             };
             sender
-                .send(IMainWin::HistoryRevisions(revisions).to_imsg())
+                .send(IMainWin::HistoryRevisions(page).to_imsg())
                 .expect("unable to send revisions");
             conductor.signal(MainWin::GetHistoryRevisions.to_event());
         }
+        OMainWin::GetPinHistory { vpin_id, coord } => {
+            let entries = match query_pin_history(db, &coord) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    sender
+                        .send(IMsg::Error(format!(
+                            "Unable to get the history of versionpin {}: {}",
+                            vpin_id, err
+                        )))
+                        .expect("unable to send error msg");
+                    conductor.signal(Event::Error);
+                    return;
+                }
+            };
+            sender
+                .send(IMainWin::PinHistory { vpin_id, entries }.to_imsg())
+                .expect("unable to send pin history");
+            conductor.signal(MainWin::GetPinHistory.to_event());
+        }
+        OMainWin::GetLevelImpact { levels } => {
+            let all_levels = match db.find_all_levels().query() {
+                Ok(all_levels) => all_levels
+                    .into_iter()
+                    .map(|row| row.level)
+                    .collect::<Vec<_>>(),
+                Err(err) => {
+                    sender
+                        .send(IMsg::Error(format!(
+                            "Unable to get levels from db: {}",
+                            err
+                        )))
+                        .expect("unable to send error msg");
+                    conductor.signal(Event::Error);
+                    return;
+                }
+            };
+            let impact = levels
+                .into_iter()
+                .map(|level| {
+                    let cnt = descendant_level_count(level.as_str(), &all_levels);
+                    (level, cnt)
+                })
+                .collect::<HashMap<_, _>>();
+            sender
+                .send(IMainWin::LevelImpact(impact).to_imsg())
+                .expect("unable to send level impact");
+            conductor.signal(MainWin::GetLevelImpact.to_event());
+        }
         OMainWin::SaveVpinChanges {
             changes,
+            new_pins,
             comments,
             user,
         } => {
+            // the saved changes alter the pin counts
+            state.pin_counts = None;
+            // new pins are staged with fake ids, which their withs changes refer to. The
+            // withs are set as each pin is created, once it has an id.
+            let mut new_pin_withs = HashMap::new();
+            for change in changes.iter() {
+                if let Change::ChangeWiths { vpin_id, withs } = change {
+                    if let Some(coord) = new_pins.get(vpin_id) {
+                        new_pin_withs.insert(coord.clone(), withs.clone());
+                    }
+                }
+            }
+            let mut report = |err: String| {
+                sender
+                    .send(IMsg::Error(err))
+                    .expect("unable to send error msg");
+                conductor.signal(Event::Error);
+            };
+            // each withs change was checked as it was staged. Together, they may still
+            // create a with-cycle, which would be saved
+            match find_saved_cycles(db, &changes, &new_pins) {
+                Ok(issues) if issues.is_empty() => (),
+                Ok(issues) => {
+                    report(format!(
+                        "Unable to save the changes, as they create with-cycles:\n{}",
+                        describe(&issues)
+                    ));
+                    return;
+                }
+                Err(err) => {
+                    report(format!("Unable to check the withs for cycles: {}", err));
+                    return;
+                }
+            }
+            // returning before the commit drops the transaction, which rolls it back
             let mut tx = db.transaction();
             let mut tx_cnt = 0;
             for change in changes {
@@ -138,17 +298,34 @@ pub(crate) fn match_main_win(
                         new_dist_id,
                     } => {
                         let change = VersionPinChange::new(vpin_id, Some(new_dist_id), None);
-                        let mut update = PackratDb::update_versionpins(tx)
+                        let mut update = match PackratDb::update_versionpins(tx)
                             .change(change)
                             .update()
-                            .expect("unable to unwrap results of update_versionpins(tx)");
+                        {
+                            Ok(update) => update,
+                            Err(err) => {
+                                report(format!(
+                                    "Unable to change the distribution of versionpin {}: {}",
+                                    vpin_id, err
+                                ));
+                                return;
+                            }
+                        };
                         tx = update.take_tx();
                         tx_cnt += 1;
                     }
+                    Change::ChangeWiths { vpin_id, .. } if new_pins.contains_key(&vpin_id) => (),
                     Change::ChangeWiths { vpin_id, withs } => {
-                        let mut update = PackratDb::add_withs(tx).create(vpin_id, withs).expect(
-                            "Unabe to unwrap results of calling packratdb::add_withs(tx).create(...)",
-                        );
+                        let mut update = match PackratDb::add_withs(tx).create(vpin_id, withs) {
+                            Ok(update) => update,
+                            Err(err) => {
+                                report(format!(
+                                    "Unable to set the withs of versionpin {}: {}",
+                                    vpin_id, err
+                                ));
+                                return;
+                            }
+                        };
                         tx = update.take_tx()
                     }
                     Change::AddDistribution {
@@ -158,12 +335,17 @@ pub(crate) fn match_main_win(
                         platform,
                         site,
                     } => {
-                        let pieces = distribution.split('-').collect::<Vec<_>>();
-
+                        let coord = PinCoord::new(
+                            distribution.package(),
+                            level.as_str(),
+                            role.as_str(),
+                            platform.as_str(),
+                            site.as_str(),
+                        );
                         let mut add_versionpins = PackratDb::add_versionpins(
                             tx,
-                            pieces[0].to_string(),
-                            pieces[1].to_string(),
+                            distribution.package().to_string(),
+                            distribution.version().to_string(),
                         );
                         add_versionpins = add_versionpins.level(level);
                         add_versionpins = add_versionpins.site(site);
@@ -173,19 +355,62 @@ pub(crate) fn match_main_win(
                         add_versionpins = match add_versionpins.create() {
                             Ok(add_versionpins) => add_versionpins,
                             Err(err) => {
-                                sender
-                                    .send(IMsg::Error(format!(
-                                        "Unable to call add_versionpins.create(): {}",
-                                        err
-                                    )))
-                                    .expect("unable to send error msg");
-                                conductor.signal(Event::Error);
+                                report(format!(
+                                    "Unable to call add_versionpins.create(): {}",
+                                    err
+                                ));
                                 return;
                             }
                         };
                         tx = add_versionpins.take_tx();
+                        let withs = match new_pin_withs.remove(&coord) {
+                            Some(withs) => withs,
+                            None => continue,
+                        };
+                        // packybara does not return the id of the pin it creates. The
+                        // insert has just advanced the versionpin id sequence, so its
+                        // current value, within this transaction, is the new pin's id.
+                        let vpin_id = match tx.query(NEW_VERSIONPIN_ID, &[]) {
+                            Ok(rows) if !rows.is_empty() => rows[0].get::<_, i64>(0) as IdType,
+                            Ok(_) => {
+                                report(format!(
+                                    "Unable to find the id of the new pin of {}",
+                                    coord.package
+                                ));
+                                return;
+                            }
+                            Err(err) => {
+                                report(format!(
+                                    "Unable to find the id of the new pin of {}: {}",
+                                    coord.package, err
+                                ));
+                                return;
+                            }
+                        };
+                        let mut update = match PackratDb::add_withs(tx).create(vpin_id, withs) {
+                            Ok(update) => update,
+                            Err(err) => {
+                                report(format!(
+                                    "Unable to set the withs of the new pin of {}: {}",
+                                    coord.package, err
+                                ));
+                                return;
+                            }
+                        };
+                        tx = update.take_tx();
+                    }
+                    Change::ChangePkgCoord { vpin_id, .. } => {
+                        report(format!(
+                            "Unable to save the changes. Moving versionpin {} to another \
+                             coordinate is not supported",
+                            vpin_id
+                        ));
+                        return;
+                    }
+                    Change::Unknown => {
+                        report("Unable to save the changes. They include an unknown change".into());
+                        return;
                     }
-                    _ => panic!("not implemented"),
                 }
             }
             let results = PackratDb::commit(tx, user.as_str(), comments.as_str(), tx_cnt);
@@ -198,16 +423,22 @@ pub(crate) fn match_main_win(
             package,
             version,
             row,
+            site,
         } => {
-            let results = db
-                .find_all_distributions()
-                .package(package.as_str())
-                .query()
-                .expect("unable to unwrap query of distributions");
+            let versions = match find_versions(db, state, package.as_str(), site.as_str()) {
+                Ok(versions) => versions,
+                Err(err) => {
+                    sender
+                        .send(IMsg::Error(err))
+                        .expect("unable to send error msg");
+                    conductor.signal(Event::Error);
+                    return;
+                }
+            };
             sender
                 .send(
                     IMainWin::ChooseDistribution {
-                        distributions: results,
+                        versions,
                         package,
                         version,
                         row,
@@ -217,24 +448,696 @@ pub(crate) fn match_main_win(
                 .expect("unable to send changes");
             conductor.signal(MainWin::ChooseDistribution.to_event());
         }
-        OMainWin::SavePackagesXml { show, output } => {
-            match db.export_packages(show.as_str(), output.as_str()) {
-                Ok(_) => {
+        OMainWin::DropDistribution { distribution, row } => {
+            let package = distribution.package();
+            let dist_id = match find_distribution_id(db, package, distribution.version()) {
+                Ok(dist_id) => dist_id,
+                Err(err) => {
+                    sender
+                        .send(IMsg::Error(err))
+                        .expect("unable to send error msg");
+                    conductor.signal(Event::Error);
+                    return;
+                }
+            };
+            sender
+                .send(
+                    IMainWin::DropDistribution {
+                        distribution: distribution.with_id(dist_id),
+                        row,
+                    }
+                    .to_imsg(),
+                )
+                .expect("unable to send dropped distribution");
+            conductor.signal(MainWin::DropDistribution.to_event());
+        }
+        OMainWin::ImportManifest { entries } => {
+            let mut accepted = Vec::new();
+            let mut rejected = Vec::new();
+            // levels are looked up once per show
+            let mut show_levels = HashMap::new();
+            for entry in entries {
+                match validate_manifest_entry(db, &entry, &mut show_levels) {
+                    Ok(validated) => accepted.push(validated),
+                    Err(reason) => rejected.push(ManifestRejection::new(&entry, reason)),
+                }
+            }
+            sender
+                .send(IMainWin::ImportManifest { accepted, rejected }.to_imsg())
+                .expect("unable to send manifest results");
+            conductor.signal(MainWin::ImportManifest.to_event());
+        }
+        OMainWin::RestoreRevision { revision, entries } => {
+            let mut accepted = Vec::new();
+            let mut rejected = Vec::new();
+            let mut show_levels = HashMap::new();
+            for entry in entries {
+                match validate_manifest_entry(db, &entry, &mut show_levels) {
+                    Ok(validated) => accepted.push(validated),
+                    Err(reason) => rejected.push(ManifestRejection::new(&entry, reason)),
+                }
+            }
+            sender
+                .send(
+                    IMainWin::RestoreRevision {
+                        revision,
+                        accepted,
+                        rejected,
+                    }
+                    .to_imsg(),
+                )
+                .expect("unable to send restore results");
+            conductor.signal(MainWin::RestoreRevision.to_event());
+        }
+        OMainWin::ExportPins {
+            mut rows,
+            path,
+            format,
+            include_pending,
+        } => {
+            let result = expand_withs(db, &mut rows)
+                .and_then(|_| export_pins(path.as_str(), &rows, format, include_pending));
+            if let Err(err) = result {
+                sender
+                    .send(IMsg::Error(format!("Unable to export pins: {}", err)))
+                    .expect("unable to send error msg");
+                conductor.signal(Event::Error);
+                return;
+            }
+            sender
+                .send(
+                    IMainWin::ExportPins {
+                        count: rows.len(),
+                        path,
+                    }
+                    .to_imsg(),
+                )
+                .expect("unable to send export results");
+            conductor.signal(MainWin::ExportPins.to_event());
+        }
+        OMainWin::SavePinTemplate {
+            name,
+            mut rows,
+            library,
+        } => {
+            let result = expand_withs(db, &mut rows)
+                .map_err(|e| e.to_string())
+                .and_then(|_| save_pin_template(name.as_str(), &rows, library.as_str()));
+            if let Err(err) = result {
+                sender
+                    .send(IMsg::Error(format!(
+                        "Unable to save template {}: {}",
+                        name, err
+                    )))
+                    .expect("unable to send error msg");
+                conductor.signal(Event::Error);
+                return;
+            }
+            sender
+                .send(
+                    IMainWin::SavePinTemplate {
+                        name,
+                        count: rows.len(),
+                        library,
+                    }
+                    .to_imsg(),
+                )
+                .expect("unable to send template results");
+            conductor.signal(MainWin::SavePinTemplate.to_event());
+        }
+        OMainWin::ApplyPinTemplate { template, level } => {
+            let mut accepted = Vec::new();
+            let mut rejected = Vec::new();
+            let mut show_levels = HashMap::new();
+            for (idx, pin) in template.pins.iter().enumerate() {
+                validate_template_pin(
+                    db,
+                    pin,
+                    level.as_str(),
+                    idx + 1,
+                    &mut show_levels,
+                    &mut accepted,
+                    &mut rejected,
+                );
+            }
+            sender
+                .send(
+                    IMainWin::ApplyPinTemplate {
+                        name: template.name,
+                        accepted,
+                        rejected,
+                    }
+                    .to_imsg(),
+                )
+                .expect("unable to send template results");
+            conductor.signal(MainWin::ApplyPinTemplate.to_event());
+        }
+        OMainWin::ValidateWiths {
+            vpin_id,
+            coord,
+            withs,
+        } => {
+            let level_withs = match find_level_withs(db, &coord) {
+                Ok(level_withs) => level_withs,
+                Err(err) => {
                     sender
-                        .send(IMainWin::SavePackagesXml(true).to_imsg())
-                        .expect("unable to send changes");
-                    conductor.signal(MainWin::SavePackagesXml.to_event());
+                        .send(IMsg::Error(format!(
+                            "Unable to validate the withs of {}: {}",
+                            coord.package, err
+                        )))
+                        .expect("unable to send error msg");
+                    conductor.signal(Event::Error);
+                    return;
                 }
+            };
+            sender
+                .send(
+                    IMainWin::ValidateWiths {
+                        vpin_id,
+                        coord,
+                        withs,
+                        level_withs,
+                    }
+                    .to_imsg(),
+                )
+                .expect("unable to send level withs");
+            conductor.signal(MainWin::ValidateWiths.to_event());
+        }
+        OMainWin::PreviewPackagesXml { show, output } => {
+            let xml = match export_packages_xml(db, show.as_str()) {
+                Ok(xml) => xml,
                 Err(err) => {
                     sender
                         .send(IMsg::Error(format!(
-                            "Unable to write packages.xml: {}",
-                            err
+                            "Unable to export packages.xml for {}: {}",
+                            show, err
                         )))
                         .expect("unable to send error msg");
                     conductor.signal(Event::Error);
+                    return;
                 }
+            };
+            let existing = match std::fs::read_to_string(output.as_str()) {
+                Ok(existing) => Some(existing),
+                Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(err) => {
+                    sender
+                        .send(IMsg::Error(format!("Unable to read {}: {}", output, err)))
+                        .expect("unable to send error msg");
+                    conductor.signal(Event::Error);
+                    return;
+                }
+            };
+            sender
+                .send(
+                    IMainWin::PreviewPackagesXml {
+                        show,
+                        output,
+                        xml,
+                        existing,
+                    }
+                    .to_imsg(),
+                )
+                .expect("unable to send packages.xml preview");
+            conductor.signal(MainWin::PreviewPackagesXml.to_event());
+        }
+        OMainWin::SavePackagesXml { show, output, xml } => {
+            let result = std::fs::write(output.as_str(), xml).map_err(|e| e.to_string());
+            sender
+                .send(
+                    IMainWin::SavePackagesXml {
+                        show,
+                        output,
+                        result,
+                    }
+                    .to_imsg(),
+                )
+                .expect("unable to send packages.xml result");
+            conductor.signal(MainWin::SavePackagesXml.to_event());
+        }
+    }
+}
+
+// Export the packages.xml of the show with the database's export_packages, by way of a
+// temporary file, so that the file previewed and saved is the one it writes
+fn export_packages_xml(db: &mut PackratDb, show: &str) -> Result<String, String> {
+    let path = std::env::temp_dir().join(format!("pbgui-{}-packages.xml", std::process::id()));
+    db.export_packages(show, path.to_string_lossy().as_ref())
+        .map_err(|e| e.to_string())?;
+    let xml = std::fs::read_to_string(&path)
+        .map_err(|e| format!("unable to read {}: {}", path.display(), e));
+    if let Err(err) = std::fs::remove_file(&path) {
+        log::warn!("unable to remove {}: {}", path.display(), err);
+    }
+    xml
+}
+
+// Reconstruct the pins presented by the query as they stood at a past revision, by
+// undoing the changes recorded since to every pin in the facility
+fn rewind_vpins(db: &mut PackratDb, as_of: &AsOf, query: &PinQuery) -> Result<PastPins, String> {
+    let revision = match as_of {
+        AsOf::Revision(revision) => *revision,
+        AsOf::Timestamp(timestamp) => find_revision_at(db, timestamp)?,
+    };
+    let mut rows = db
+        .find_all_changes()
+        .query()
+        .map_err(|e| format!("unable to look up the changes: {}", e))?;
+    rows.retain(|row| row.transaction_id as IdType > revision);
+    // in the order the changes were made
+    rows.sort_by_key(|row| (row.transaction_id, row.id));
+    let changes = rows.iter().filter_map(past_change).collect();
+    // every pin in the facility, regardless of role, platform or site
+    let pins = db
+        .find_all_versionpins()
+        .level("facility")
+        .role("any")
+        .platform("any")
+        .site("any")
+        .search_mode(LtreeSearchMode::from_str("descendant").expect("unable to find search mode"))
+        .query()
+        .map_err(|e| format!("unable to look up versionpins: {}", e))?;
+    let current = pins
+        .iter()
+        .map(|pin| {
+            let row = VersionPinRow::try_from(pin).map_err(|e| e.to_string())?;
+            Ok((row, pin.withs.clone().unwrap_or_default()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(PastPins::rewind(revision, query, current, changes))
+}
+
+// The transaction id of the newest revision made at or before the local time. Before
+// the first revision, this is 0.
+fn find_revision_at(db: &mut PackratDb, timestamp: &NaiveDateTime) -> Result<IdType, String> {
+    let revisions = db
+        .find_all_revisions()
+        .order_by(vec![OrderRevisionBy::Id])
+        .order_direction(OrderDirection::Desc)
+        .query()
+        .map_err(|e| format!("unable to look up the revisions: {}", e))?;
+    let timestamp = timestamp.format("%F %T").to_string();
+    Ok(revisions
+        .iter()
+        .find(|revision| revision.datetime.format("%F %T").to_string() <= timestamp)
+        .map_or(0, |revision| revision.transaction_id as IdType))
+}
+
+// The change to a versionpin recorded by a row of the change history
+fn past_change(row: &FindAllChangesRow) -> Option<PastChange> {
+    let action = match ChangeAction::from_str(row.action.to_string().as_str()) {
+        Ok(action) => action,
+        Err(err) => {
+            log::warn!("skipping change {}: {}", row.id, err);
+            return None;
+        }
+    };
+    let version = |version: String| Some(version).filter(|v| !v.is_empty());
+    Some(PastChange {
+        tx_id: row.transaction_id as IdType,
+        action,
+        coord: PinCoord::new(
+            row.package.to_string(),
+            row.level.to_string(),
+            row.role.to_string(),
+            row.platform.to_string(),
+            row.site.to_string(),
+        ),
+        old: version(row.old.version().to_string()),
+        new: version(row.new.version().to_string()),
+    })
+}
+
+// Validate a manifest entry against the database, verifying that the level and
+// distribution exist, and that the entry neither duplicates an existing pin nor
+// refers to a pin which does not exist.
+fn validate_manifest_entry(
+    db: &mut PackratDb,
+    entry: &ManifestEntry,
+    show_levels: &mut HashMap<String, Vec<String>>,
+) -> Result<ValidatedEntry, String> {
+    if entry.level != "facility" {
+        let show = entry.level.split('.').next().unwrap_or("");
+        if !show_levels.contains_key(show) {
+            let levels = db
+                .find_all_levels()
+                .show(show)
+                .query()
+                .map_err(|e| format!("unable to look up levels for {}: {}", show, e))?;
+            let levels = levels.into_iter().map(|l| l.level).collect::<Vec<_>>();
+            show_levels.insert(show.to_string(), levels);
+        }
+        if !show_levels[show].iter().any(|l| l == &entry.level) {
+            return Err(format!("level {} does not exist", entry.level));
+        }
+    }
+    let coord = entry.pin_coord();
+    let existing = find_pin_at(db, &coord)?;
+    match entry.op {
+        ManifestOp::AddDistribution => {
+            let version = entry.version.as_ref().ok_or("missing version")?;
+            let dist_id = find_distribution_id(db, entry.package.as_str(), version.as_str())?;
+            let distribution = Distribution::new(entry.package.as_str(), version.as_str())
+                .map_err(|e| e.to_string())?
+                .with_id(dist_id);
+            if let Some(pin) = existing {
+                return Err(format!(
+                    "duplicates existing pin of {}",
+                    pin.distribution.to_string()
+                ));
+            }
+            Ok(ValidatedEntry {
+                line: entry.line,
+                change: Change::AddDistribution {
+                    distribution,
+                    level: entry.level.clone(),
+                    role: entry.role.clone(),
+                    platform: entry.platform.clone(),
+                    site: entry.site.clone(),
+                },
+                level: entry.level.clone(),
+                coord,
+                vpin_id: None,
+                pkgcoord_id: None,
+                context: entry.pkgcoord(),
+                old_value: String::new(),
+                new_value: version.clone(),
+                level_withs: LevelWiths::new(),
+            })
+        }
+        ManifestOp::ChangeDistribution => {
+            let version = entry.version.as_ref().ok_or("missing version")?;
+            let new_dist_id = find_distribution_id(db, entry.package.as_str(), version.as_str())?;
+            let pin = existing.ok_or("no existing pin to change")?;
+            let old_version = pin.distribution.version().to_string();
+            if &old_version == version {
+                return Err(format!("already pinned to {}", version));
             }
+            Ok(ValidatedEntry {
+                line: entry.line,
+                change: Change::ChangeDistribution {
+                    vpin_id: pin.versionpin_id,
+                    new_dist_id,
+                },
+                level: entry.level.clone(),
+                coord,
+                vpin_id: Some(pin.versionpin_id),
+                pkgcoord_id: Some(pin.pkgcoord_id),
+                context: entry.pkgcoord(),
+                old_value: old_version,
+                new_value: version.clone(),
+                level_withs: LevelWiths::new(),
+            })
+        }
+        ManifestOp::ChangeWiths => {
+            // the withs of a pin which does not exist yet are staged against the
+            // pending new pin at the coordinate, if there is one
+            let (vpin_id, pkgcoord_id, old_withs) = match existing {
+                Some(pin) => (
+                    Some(pin.versionpin_id),
+                    Some(pin.pkgcoord_id),
+                    pin.withs.as_ref().map(|w| w.join(",")).unwrap_or_default(),
+                ),
+                None => (None, None, String::new()),
+            };
+            let level_withs = find_level_withs(db, &coord)?;
+            Ok(ValidatedEntry {
+                line: entry.line,
+                change: Change::ChangeWiths {
+                    vpin_id: vpin_id.unwrap_or_default(),
+                    withs: entry.withs.clone(),
+                },
+                level: entry.level.clone(),
+                coord,
+                vpin_id,
+                pkgcoord_id,
+                context: entry.pkgcoord(),
+                old_value: old_withs,
+                new_value: entry.withs.join(","),
+                level_withs,
+            })
         }
     }
 }
+
+// Add the rows to the template library as a template, replacing any template of
+// the same name
+fn save_pin_template(name: &str, rows: &[ExportRow], library: &str) -> Result<(), String> {
+    let mut templates = TemplateLibrary::load(library).map_err(|e| e.to_string())?;
+    templates.insert(PinTemplate::new(
+        name,
+        rows.iter().map(TemplatePin::from).collect(),
+    ));
+    templates.save(library).map_err(|e| e.to_string())
+}
+
+// Validate a template pin against the level it is applied to. A pin which does not
+// exist at the level is added, while an existing pin of the same package is changed to
+// match the template. The withs of an added pin are staged against the new pin.
+fn validate_template_pin(
+    db: &mut PackratDb,
+    pin: &TemplatePin,
+    level: &str,
+    line: usize,
+    show_levels: &mut HashMap<String, Vec<String>>,
+    accepted: &mut Vec<ValidatedEntry>,
+    rejected: &mut Vec<ManifestRejection>,
+) {
+    let add = pin.to_entry(ManifestOp::AddDistribution, level, line);
+    let ops = match find_pin_at(db, &add.pin_coord()) {
+        Ok(None) => pin.additions(),
+        Ok(Some(existing)) => {
+            let withs = existing.withs.clone().unwrap_or_default();
+            let ops = pin.changes_from(&existing.distribution.version().to_string(), &withs);
+            if ops.is_empty() {
+                rejected.push(ManifestRejection::new(&add, "matches the existing pin"));
+            }
+            ops
+        }
+        Err(reason) => {
+            rejected.push(ManifestRejection::new(&add, reason));
+            return;
+        }
+    };
+    for op in ops {
+        let entry = pin.to_entry(op, level, line);
+        match validate_manifest_entry(db, &entry, show_levels) {
+            Ok(validated) => accepted.push(validated),
+            Err(reason) => rejected.push(ManifestRejection::new(&entry, reason)),
+        }
+    }
+}
+
+/// Look up the existing pin of a package at exactly the supplied coordinate
+///
+/// # Arguments
+/// * `db` - The PackratDb instance
+/// * `coord` - The package and coordinate of the pin
+///
+/// # Returns
+/// * Ok(Some(FindAllVersionPinsRow)) if the pin exists
+/// * Ok(None) if it does not
+/// * Err(String) if the lookup fails
+pub(crate) fn find_pin_at(
+    db: &mut PackratDb,
+    coord: &PinCoord,
+) -> Result<Option<FindAllVersionPinsRow>, String> {
+    let pins = db
+        .find_all_versionpins()
+        .level(coord.level.as_str())
+        .role(coord.role.as_str())
+        .platform(coord.platform.as_str())
+        .site(coord.site.as_str())
+        .search_mode(LtreeSearchMode::from_str("exact").expect("unable to find search mode"))
+        .query()
+        .map_err(|e| format!("unable to look up versionpins: {}", e))?;
+    Ok(pins.into_iter().find(|pin| {
+        pin.distribution.package() == coord.package.as_str()
+            && pin.coords.level.to_string() == coord.level
+            && pin.coords.role.to_string() == coord.role
+            && pin.coords.platform.to_string() == coord.platform
+            && pin.coords.site.to_string() == coord.site
+    }))
+}
+
+/// Find the with-cycles the staged withs changes create together, along with the
+/// stored withs of the other pins at their coordinates
+///
+/// # Arguments
+/// * `db` - The PackratDb instance
+/// * `changes` - The changes being saved
+/// * `new_pins` - The coordinates of the new pins, by the fake ids changes refer to
+///
+/// # Returns
+/// * Ok(Vec<WithsIssue>) if successful. Empty if there are no cycles
+/// * Err(String) otherwise
+fn find_saved_cycles(
+    db: &mut PackratDb,
+    changes: &[Change],
+    new_pins: &HashMap<IdType, PinCoord>,
+) -> Result<Vec<WithsIssue>, String> {
+    let staged_withs = changes
+        .iter()
+        .filter_map(|change| match change {
+            Change::ChangeWiths { vpin_id, withs } => Some((*vpin_id, withs)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if staged_withs.is_empty() {
+        return Ok(Vec::new());
+    }
+    // every pin in the facility, regardless of role, platform or site
+    let pins = db
+        .find_all_versionpins()
+        .level("facility")
+        .role("any")
+        .platform("any")
+        .site("any")
+        .search_mode(LtreeSearchMode::from_str("descendant").expect("unable to find search mode"))
+        .query()
+        .map_err(|e| format!("unable to look up versionpins: {}", e))?;
+    let mut coords = HashMap::new();
+    let mut stored = HashMap::new();
+    for pin in pins {
+        let coord = PinCoord::new(
+            pin.distribution.package(),
+            pin.coords.level.to_string(),
+            pin.coords.role.to_string(),
+            pin.coords.platform.to_string(),
+            pin.coords.site.to_string(),
+        );
+        coords.insert(pin.versionpin_id, coord.clone());
+        stored.insert(coord, pin.withs.unwrap_or_default());
+    }
+    let mut staged = HashMap::new();
+    for (vpin_id, withs) in staged_withs {
+        let coord = new_pins
+            .get(&vpin_id)
+            .or_else(|| coords.get(&vpin_id))
+            .ok_or_else(|| format!("unable to find versionpin {}", vpin_id))?;
+        staged.insert(coord.clone(), withs.clone());
+    }
+    Ok(find_staged_cycles(&stored, &staged))
+}
+
+/// Look up the withs of the other pins sharing the coordinate of a pin
+///
+/// # Arguments
+/// * `db` - The PackratDb instance
+/// * `coord` - The coordinate of the pin, whose package is excluded
+///
+/// # Returns
+/// * Ok(LevelWiths) if successful
+/// * Err(String) otherwise
+fn find_level_withs(db: &mut PackratDb, coord: &PinCoord) -> Result<LevelWiths, String> {
+    let pins = db
+        .find_all_versionpins()
+        .level(coord.level.as_str())
+        .role(coord.role.as_str())
+        .platform(coord.platform.as_str())
+        .site(coord.site.as_str())
+        .search_mode(LtreeSearchMode::from_str("exact").expect("unable to find search mode"))
+        .query()
+        .map_err(|e| format!("unable to look up versionpins: {}", e))?;
+    Ok(pins
+        .into_iter()
+        .filter(|pin| {
+            pin.distribution.package() != coord.package.as_str()
+                && pin.coords.level.to_string() == coord.level
+                && pin.coords.role.to_string() == coord.role
+                && pin.coords.platform.to_string() == coord.platform
+                && pin.coords.site.to_string() == coord.site
+        })
+        .map(|pin| {
+            let package = pin.distribution.package().to_string();
+            (package, pin.withs.unwrap_or_default())
+        })
+        .collect())
+}
+
+/// Look up the versions of a package, along with the number of versionpins
+/// using each version, and whether each version is installed at the site
+///
+/// # Arguments
+/// * `db` - The PackratDb instance
+/// * `state` - The thread's state, holding the install roots and cached pin counts
+/// * `package` - The package name
+/// * `site` - The site whose install root is inspected
+///
+/// # Returns
+/// * Ok(Vec<VersionInfo>) if successful
+/// * Err(String) otherwise
+fn find_versions(
+    db: &mut PackratDb,
+    state: &mut MainWinState,
+    package: &str,
+    site: &str,
+) -> Result<Vec<VersionInfo>, String> {
+    let dists = db
+        .find_all_distributions()
+        .package(package)
+        .query()
+        .map_err(|e| format!("unable to look up distributions of {}: {}", package, e))?;
+    if state.pin_counts.is_none() {
+        state.pin_counts = Some(count_pins(db)?);
+    }
+    let pin_counts = state.pin_counts.as_ref().and_then(|counts| counts.get(package));
+    Ok(dists
+        .into_iter()
+        .map(|dist| {
+            let pin_count = pin_counts
+                .and_then(|counts| counts.get(&dist.version))
+                .copied()
+                .unwrap_or(0);
+            let mut version = VersionInfo::new(dist.id, dist.version, pin_count);
+            version.inspect_install(package, site, &state.install_roots);
+            version
+        })
+        .collect())
+}
+
+// count the versionpins using each version of each package, across every pin in the
+// facility regardless of role, platform or site
+fn count_pins(db: &mut PackratDb) -> Result<HashMap<String, HashMap<String, usize>>, String> {
+    let pins = db
+        .find_all_versionpins()
+        .level("facility")
+        .role("any")
+        .platform("any")
+        .site("any")
+        .search_mode(LtreeSearchMode::from_str("descendant").expect("unable to find search mode"))
+        .query()
+        .map_err(|e| format!("unable to look up versionpins: {}", e))?;
+    let mut pin_counts = HashMap::new();
+    for pin in pins.iter() {
+        *pin_counts
+            .entry(pin.distribution.package().to_string())
+            .or_insert_with(HashMap::new)
+            .entry(pin.distribution.version().to_string())
+            .or_insert(0) += 1;
+    }
+    Ok(pin_counts)
+}
+
+/// Look up the id of the distribution matching the package and version
+///
+/// # Arguments
+/// * `db` - The PackratDb instance
+/// * `package` - The package name
+/// * `version` - The version of the package
+///
+/// # Returns
+/// * Ok(IdType) if the distribution exists
+/// * Err(String) otherwise
+pub(crate) fn find_distribution_id(db: &mut PackratDb, package: &str, version: &str) -> Result<IdType, String> {
+    let dists = db
+        .find_all_distributions()
+        .package(package)
+        .query()
+        .map_err(|e| format!("unable to look up distributions of {}: {}", package, e))?;
+    dists
+        .into_iter()
+        .find(|dist| dist.version == version)
+        .map(|dist| dist.id)
+        .ok_or_else(|| format!("distribution {}-{} does not exist", package, version))
+}
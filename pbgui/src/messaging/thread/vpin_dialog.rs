@@ -1,5 +1,7 @@
 use super::*;
+use super::main_win::{find_distribution_id, find_pin_at};
 use crate::change_type::Change;
+use crate::messaging::incoming::ivpin_dialog::PinConflict;
 
 /// perform a submatch against the OVpinDialog msg
 pub(crate) fn match_vpin_dialog(
@@ -53,6 +55,27 @@ pub(crate) fn match_vpin_dialog(
             conductor.signal(VpinDialog::UpdateSites.to_event());
         }
 
+        OVpinDialog::GetPlatforms => {
+            let platforms = match db.find_all_platforms().query() {
+                Ok(platforms) => platforms,
+                Err(e) => {
+                    sender
+                        .send(IMsg::Error(format!("Unable to get platforms from db: {}", e)))
+                        .expect("unable to send error msg");
+                    conductor.signal(Event::Error);
+                    return;
+                }
+            };
+            let platforms = platforms
+                .into_iter()
+                .map(|mut x| std::mem::replace(&mut x.name, String::new()))
+                .collect::<Vec<_>>();
+            sender
+                .send(IVpinDialog::Platforms(platforms).to_imsg())
+                .expect("unable to send platforms");
+            conductor.signal(VpinDialog::UpdatePlatforms.to_event());
+        }
+
         OVpinDialog::GetLevels(ref show) => {
             let levels = match db.find_all_levels().show(show).query() {
                 Ok(levels) => levels,
@@ -126,26 +149,67 @@ pub(crate) fn match_vpin_dialog(
             conductor.signal(VpinDialog::UpdateLevels.to_event());
         }
 
-        OVpinDialog::SetVpin {
-            dist,
-            roles,
-            level,
-            site,
-            platform,
-        } => {
-            // now we create the change
-            let changes = roles.into_iter().fold(Vec::new(), |mut acc, role| {
-                acc.push(Change::AddDistribution {
+        OVpinDialog::SetVpin { dist, combinations } => {
+            let version = dist.version().to_string();
+            // every change and conflict records the distribution by its database id
+            let dist_id = match find_distribution_id(db, dist.package(), &version) {
+                Ok(id) => id,
+                Err(err) => {
+                    sender
+                        .send(IMsg::Error(err))
+                        .expect("unable to send error msg");
+                    conductor.signal(Event::Error);
+                    return;
+                }
+            };
+            let dist = dist.with_id(dist_id);
+            let mut changes = Vec::new();
+            let mut conflicts = Vec::new();
+            for combination in combinations {
+                let change = Change::AddDistribution {
                     distribution: dist.clone(),
-                    level: level.clone(),
-                    role,
-                    platform: platform.clone(),
-                    site: site.clone(),
+                    level: combination.level,
+                    role: combination.role,
+                    platform: combination.platform,
+                    site: combination.site,
+                };
+                let coord = change
+                    .pin_coord()
+                    .expect("unable to get pin coordinate of AddDistribution");
+                // reject pins of the same package at the same coordinate as an existing pin
+                let existing = match find_pin_at(db, &coord) {
+                    Ok(existing) => existing,
+                    Err(err) => {
+                        sender
+                            .send(IMsg::Error(err))
+                            .expect("unable to send error msg");
+                        conductor.signal(Event::Error);
+                        return;
+                    }
+                };
+                let pin = match existing {
+                    Some(pin) => pin,
+                    None => {
+                        changes.push(change);
+                        continue;
+                    }
+                };
+                let existing_version = pin.distribution.version().to_string();
+                if existing_version == version {
+                    log::info!("{} is already pinned at {:?}. skipping", dist, coord);
+                    continue;
+                }
+                conflicts.push(PinConflict {
+                    coord,
+                    vpin_id: pin.versionpin_id,
+                    pkgcoord_id: pin.pkgcoord_id,
+                    existing_version,
+                    version: version.clone(),
+                    new_dist_id: dist_id,
                 });
-                acc
-            });
+            }
             sender
-                .send(IVpinDialog::SetVpin(changes).to_imsg())
+                .send(IVpinDialog::SetVpin { changes, conflicts }.to_imsg())
                 .expect("Unable to send Changes via SetVpin");
             conductor.signal(VpinDialog::SetVpin.to_event());
         }
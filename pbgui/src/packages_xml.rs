@@ -0,0 +1,155 @@
+//! Compares the packages.xml of a show, as exported by the database, against the file
+//! it would replace.
+//!
+//! The file is not rendered here. The database's `export_packages` resolves the pins
+//! of the show and writes the file; previewing its output means the file confirmed is
+//! the file written, down to its layout.
+//!
+//! Files are compared line by line with the `similar` crate, whose diff runs in space
+//! linear in the lines compared.
+use similar::{ChangeTag, TextDiff};
+
+/// A line of the difference between the file on disk and the exported file
+#[derive(Debug, PartialEq, Clone)]
+pub enum DiffLine {
+    /// A line found in both
+    Same(String),
+    /// A line only found in the exported file
+    Added(String),
+    /// A line only found in the file on disk
+    Removed(String),
+    /// A run of the supplied number of unchanged lines, left out of the presentation
+    Skipped(usize),
+}
+
+impl DiffLine {
+    /// Determine whether the line is found in both files
+    pub fn is_same(&self) -> bool {
+        matches!(self, Self::Same(_))
+    }
+}
+
+/// Compare the file on disk against the exported file, line by line
+///
+/// # Arguments
+/// * `old` - The contents of the file on disk
+/// * `new` - The exported contents
+///
+/// # Returns
+/// * The lines of both, in order, each marked as shared, added or removed
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| {
+            let line = change
+                .value()
+                .trim_end_matches(&['\r', '\n'][..])
+                .to_string();
+            match change.tag() {
+                ChangeTag::Equal => DiffLine::Same(line),
+                ChangeTag::Insert => DiffLine::Added(line),
+                ChangeTag::Delete => DiffLine::Removed(line),
+            }
+        })
+        .collect()
+}
+
+/// Leave out the unchanged lines further than `context` lines from a change, replacing
+/// each run of them with a single `DiffLine::Skipped`
+///
+/// # Arguments
+/// * `diff` - The result of `diff_lines`
+/// * `context` - The number of unchanged lines kept on either side of a change
+///
+/// # Returns
+/// * The condensed difference
+pub fn condense(diff: Vec<DiffLine>, context: usize) -> Vec<DiffLine> {
+    let changed = diff
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.is_same())
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+    let near_change = |idx: usize| {
+        changed
+            .iter()
+            .any(|&c| idx + context >= c && idx <= c + context)
+    };
+    let mut condensed = Vec::new();
+    let mut skipped = 0;
+    for (idx, line) in diff.into_iter().enumerate() {
+        if line.is_same() && !near_change(idx) {
+            skipped += 1;
+            continue;
+        }
+        if skipped > 0 {
+            condensed.push(DiffLine::Skipped(skipped));
+            skipped = 0;
+        }
+        condensed.push(line);
+    }
+    if skipped > 0 {
+        condensed.push(DiffLine::Skipped(skipped));
+    }
+    condensed
+}
+
+/// Count the lines added and removed
+///
+/// # Arguments
+/// * `diff` - The difference to summarize
+///
+/// # Returns
+/// * Tuple of the number of lines added, and the number removed
+pub fn diff_stats(diff: &[DiffLine]) -> (usize, usize) {
+    diff.iter()
+        .fold((0, 0), |(added, removed), line| match line {
+            DiffLine::Added(_) => (added + 1, removed),
+            DiffLine::Removed(_) => (added, removed + 1),
+            _ => (added, removed),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_marks_changed_lines() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nc\nx\nd\n";
+        assert_eq!(
+            diff_lines(old, new),
+            vec![
+                DiffLine::Same("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Same("c".to_string()),
+                DiffLine::Added("x".to_string()),
+                DiffLine::Same("d".to_string()),
+            ]
+        );
+        assert_eq!(diff_stats(&diff_lines(old, new)), (1, 1));
+        assert_eq!(diff_stats(&diff_lines(old, old)), (0, 0));
+        assert_eq!(diff_stats(&diff_lines("", new)), (4, 0));
+    }
+
+    #[test]
+    fn condense_keeps_context() {
+        let old = (0..10)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let new = old.replace("5", "five");
+        assert_eq!(
+            condense(diff_lines(old.as_str(), new.as_str()), 1),
+            vec![
+                DiffLine::Skipped(4),
+                DiffLine::Same("4".to_string()),
+                DiffLine::Removed("5".to_string()),
+                DiffLine::Added("five".to_string()),
+                DiffLine::Same("6".to_string()),
+                DiffLine::Skipped(3),
+            ]
+        );
+    }
+}
@@ -0,0 +1,335 @@
+//! Models which shows and levels the current user is allowed to modify. Permissions
+//! are configured in the `permissions` section of the pbgui preferences, and are
+//! either listed there directly, or looked up in the `pbgui_permissions` table of the
+//! packrat database.
+//!
+//! When no permissions section is present, everyone may edit everything. A user who
+//! is granted nothing, or who starts pbgui with `--readonly`, is placed in read-only mode.
+use crate::cache::PinChangesCache;
+use crate::change_type::Change;
+use crate::messaging::client_proxy::{ClientProxy, ConnectParams};
+use crate::prefs::PbguiPrefs;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/* Example preferences
+permissions:
+    source: prefs    # or database
+    users:
+        jgerber:
+            levels: [facility]
+        fflintstone:
+            shows: [dev01]
+            levels: [dev02.rd]
+    default:
+        shows: []
+*/
+
+/// The level name which denotes the facility
+const FACILITY: &str = "facility";
+/// Wildcard entry granting access to all shows or levels
+const WILDCARD: &str = "*";
+
+/// Where the allow lists are loaded from
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionsSource {
+    Prefs,
+    Database,
+}
+
+impl Default for PermissionsSource {
+    fn default() -> Self {
+        Self::Prefs
+    }
+}
+
+/// The shows and levels a user may modify. Access to a level implies access
+/// to its descendants (eg `dev01.rd` grants `dev01.rd.0001`), and access to a
+/// show grants every level within it. Neither grants the facility, which must
+/// be listed explicitly as a level.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize)]
+pub struct AllowList {
+    #[serde(default)]
+    pub shows: Vec<String>,
+    #[serde(default)]
+    pub levels: Vec<String>,
+}
+
+impl AllowList {
+    /// Determine whether the allow list grants access to the supplied level
+    ///
+    /// # Arguments
+    /// * `level` - The level name (eg facility, dev01, dev01.rd.0001)
+    ///
+    /// # Returns
+    /// * bool indicating whether the level may be modified
+    pub fn allows(&self, level: &str) -> bool {
+        let level_allowed = self.levels.iter().any(|allowed| {
+            allowed == WILDCARD
+                || allowed == level
+                || (level.starts_with(allowed.as_str())
+                    && level[allowed.len()..].starts_with('.'))
+        });
+        if level_allowed {
+            return true;
+        }
+        if level == FACILITY {
+            return false;
+        }
+        let show = level.split('.').next().unwrap_or(level);
+        self.shows
+            .iter()
+            .any(|allowed| allowed == WILDCARD || allowed == show)
+    }
+
+    /// Determine whether the allow list grants nothing at all
+    pub fn is_empty(&self) -> bool {
+        self.shows.is_empty() && self.levels.is_empty()
+    }
+}
+
+/// Models the permissions section of the PbguiPrefs
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct PbguiPermPrefs {
+    #[serde(default)]
+    pub source: PermissionsSource,
+    /// Allow lists keyed by user name. Only consulted when the source is `prefs`
+    #[serde(default)]
+    pub users: HashMap<String, AllowList>,
+    /// Allow list for users not found in `users`
+    #[serde(default)]
+    pub default: AllowList,
+}
+
+impl PbguiPermPrefs {
+    /// Retrieve the allow list for the supplied user, falling back on the default
+    pub fn allow_list_for(&self, user: &str) -> AllowList {
+        self.users
+            .get(user)
+            .cloned()
+            .unwrap_or_else(|| self.default.clone())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Access {
+    Unrestricted,
+    Restricted(AllowList),
+    ReadOnly(String),
+}
+
+/// The permissions granted to the current user
+#[derive(Debug, PartialEq)]
+pub struct Permissions {
+    user: String,
+    access: Access,
+}
+
+impl Permissions {
+    /// New up a Permissions instance which allows the user to edit anything
+    pub fn unrestricted<I: Into<String>>(user: I) -> Self {
+        Self {
+            user: user.into(),
+            access: Access::Unrestricted,
+        }
+    }
+
+    /// New up a read-only Permissions instance
+    ///
+    /// # Arguments
+    /// * `user` - The user name
+    /// * `reason` - Why editing is disabled. This is presented to the user.
+    pub fn readonly<I: Into<String>, R: Into<String>>(user: I, reason: R) -> Self {
+        Self {
+            user: user.into(),
+            access: Access::ReadOnly(reason.into()),
+        }
+    }
+
+    /// New up a Permissions instance restricted to the supplied allow list.
+    /// An empty allow list results in read-only permissions.
+    pub fn restricted<I: Into<String>>(user: I, allow_list: AllowList) -> Self {
+        let user = user.into();
+        if allow_list.is_empty() {
+            let reason = format!("{} has not been granted permission to edit pins", user);
+            return Self::readonly(user, reason);
+        }
+        Self {
+            user,
+            access: Access::Restricted(allow_list),
+        }
+    }
+
+    /// Build the permissions for the user from the preferences, consulting
+    /// the database if so configured. Failure to look up the permissions
+    /// results in read-only mode.
+    ///
+    /// # Arguments
+    /// * `prefs` - Reference to the PbguiPrefs
+    /// * `user` - The user name
+    ///
+    /// # Returns
+    /// * Permissions instance
+    pub fn from_prefs(prefs: &PbguiPrefs, user: &str) -> Self {
+        let perm_prefs = match prefs.permissions {
+            Some(ref perm_prefs) => perm_prefs,
+            None => return Self::unrestricted(user),
+        };
+        match perm_prefs.source {
+            PermissionsSource::Prefs => Self::restricted(user, perm_prefs.allow_list_for(user)),
+            PermissionsSource::Database => {
+                match load_allow_list_from_db(prefs.as_connectparams(), user) {
+                    Ok(allow_list) => Self::restricted(user, allow_list),
+                    Err(err) => Self::readonly(
+                        user,
+                        format!("Unable to load permissions from database: {}", err),
+                    ),
+                }
+            }
+        }
+    }
+
+    /// The user the permissions apply to
+    pub fn user(&self) -> &str {
+        self.user.as_str()
+    }
+
+    /// Determine whether editing is disabled entirely
+    pub fn is_readonly(&self) -> bool {
+        match self.access {
+            Access::ReadOnly(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Retrieve the reason editing is disabled, if it is
+    pub fn readonly_reason(&self) -> Option<&str> {
+        match self.access {
+            Access::ReadOnly(ref reason) => Some(reason.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Determine whether the user may modify pins at the supplied level
+    ///
+    /// # Arguments
+    /// * `level` - The level name
+    ///
+    /// # Returns
+    /// * bool
+    pub fn can_edit_level(&self, level: &str) -> bool {
+        match self.access {
+            Access::Unrestricted => true,
+            Access::Restricted(ref allow_list) => allow_list.allows(level),
+            Access::ReadOnly(_) => false,
+        }
+    }
+
+    /// Verify that the user is allowed to make all of the supplied changes.
+    ///
+    /// # Arguments
+    /// * `changes` - The changes to verify
+    /// * `cache` - The PinChangesCache, used to look up the level of existing pins
+    ///
+    /// # Returns
+    /// * Ok(()) if all changes are permitted
+    /// * Err(String) describing the offending changes otherwise
+    pub fn check_changes(
+        &self,
+        changes: &[Change],
+        cache: &PinChangesCache,
+    ) -> Result<(), String> {
+        if let Some(reason) = self.readonly_reason() {
+            return Err(reason.to_string());
+        }
+        let mut denied = Vec::new();
+        for change in changes {
+            match cache.level_for_change(change) {
+                Some(level) if self.can_edit_level(level.as_str()) => (),
+                Some(level) => denied.push(level),
+                None => match change {
+                    Change::Unknown => denied.push("unknown change".to_string()),
+                    _ => denied.push(format!("unknown level (change id {})", change.id())),
+                },
+            }
+        }
+        if denied.is_empty() {
+            return Ok(());
+        }
+        denied.sort();
+        denied.dedup();
+        Err(format!(
+            "{} is not permitted to modify pins at: {}",
+            self.user,
+            denied.join(", ")
+        ))
+    }
+}
+
+// Look up the allow list for the user in the pbgui_permissions table. Each row
+// grants either a show or a level; null columns are ignored.
+fn load_allow_list_from_db(
+    params: ConnectParams,
+    user: &str,
+) -> Result<AllowList, Box<dyn std::error::Error>> {
+    let mut client = ClientProxy::connect(params)?;
+    let rows = client.query(
+        "SELECT show, level FROM pbgui_permissions WHERE username = $1",
+        &[&user],
+    )?;
+    let mut allow_list = AllowList::default();
+    for row in rows {
+        if let Some(show) = row.get::<_, Option<String>>(0) {
+            allow_list.shows.push(show);
+        }
+        if let Some(level) = row.get::<_, Option<String>>(1) {
+            allow_list.levels.push(level);
+        }
+    }
+    Ok(allow_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allow_list() -> AllowList {
+        AllowList {
+            shows: vec!["dev01".to_string()],
+            levels: vec!["dev02.rd".to_string()],
+        }
+    }
+
+    #[test]
+    fn show_grants_its_levels_but_not_facility() {
+        let allow = allow_list();
+        assert!(allow.allows("dev01"));
+        assert!(allow.allows("dev01.rd.0001"));
+        assert!(!allow.allows("facility"));
+        assert!(!allow.allows("dev03"));
+    }
+
+    #[test]
+    fn level_grants_descendants_only() {
+        let allow = allow_list();
+        assert!(allow.allows("dev02.rd"));
+        assert!(allow.allows("dev02.rd.0001"));
+        assert!(!allow.allows("dev02.rdx"));
+        assert!(!allow.allows("dev02"));
+    }
+
+    #[test]
+    fn empty_allow_list_is_readonly() {
+        let perms = Permissions::restricted("fred", AllowList::default());
+        assert!(perms.is_readonly());
+        assert!(!perms.can_edit_level("dev01"));
+    }
+
+    #[test]
+    fn unknown_change_is_denied() {
+        let perms = Permissions::restricted("fred", allow_list());
+        let cache = PinChangesCache::new();
+        assert!(perms.check_changes(&[Change::Unknown], &cache).is_err());
+    }
+}
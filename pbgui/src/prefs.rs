@@ -1,16 +1,57 @@
 //! This module provides the implementation of the pbgui preferences. pbgui preferences are
 //! written in yaml.
+use crate::components::save_review_dialog::DEFAULT_CONFIRM_THRESHOLD;
+use crate::credentials::{self, CredentialError, PassfileKey};
+use crate::messaging::client_proxy::REDACTED;
+pub use crate::permissions::PbguiPermPrefs;
 pub use preferences::{traits::*, DDContext, DDPathProvider, DDPreferenceFinder, PreferenceName};
 use serde::Deserialize;
+use std::collections::HashMap;
 
 /* Example document
 ---
 database:
     host:
     user:
-    password:
     dbname:
     port:
+    # one of the following password sources (checked in this order)
+    password_env: PBGUI_DB_PASSWORD
+    password_command: pass show packrat
+    passfile: ~/.pgpass
+    password:
+    # optional tls settings
+    sslmode: verify-full  # disable | prefer | require | verify-ca | verify-full
+    ca_cert: /etc/ssl/certs/packrat-ca.pem
+# optional. see the permissions module
+permissions:
+    source: prefs
+    users:
+        jgerber:
+            levels: [facility]
+# optional. review of pending changes prior to saving
+save_review:
+    # number of inheriting levels above which the show name must be typed to save
+    confirm_threshold: 50
+# optional. log retention
+log:
+    # maximum number of rows kept by the log window. 0 keeps every row
+    max_rows: 10000
+    # write logs to a rotating file in the directory
+    file: true
+    directory: ~/.pbgui/logs
+    # size in bytes at which the log file is rotated, and the number of rotated files to keep
+    max_file_size: 5242880
+    max_files: 5
+# optional. where distributions are installed, used by the version picker
+install:
+    # per site directory holding <package>/<version>
+    roots:
+        portland: /dd/facility/packages
+        playa: /dd/playa/packages
+# optional. where pin templates are stored
+templates:
+    library: ~/.pbgui/templates.yaml
 */
 
 /// Struct which models the pbgui preference. It implements serde::Deserialize so as
@@ -18,16 +59,186 @@ database:
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct PbguiPrefs {
     pub database: PbguiDbPrefs,
+    #[serde(default)]
+    pub permissions: Option<PbguiPermPrefs>,
+    #[serde(default)]
+    pub save_review: PbguiSaveReviewPrefs,
+    #[serde(default)]
+    pub log: PbguiLogPrefs,
+    #[serde(default)]
+    pub install: PbguiInstallPrefs,
+    #[serde(default)]
+    pub templates: PbguiTemplatePrefs,
 }
-pub use crate::messaging::client_proxy::ConnectParams;
-/// Models the database section of the PbguiPrefs
+
+/// Models the templates section of the PbguiPrefs
 #[derive(Debug, PartialEq, Deserialize)]
+pub struct PbguiTemplatePrefs {
+    /// Path to the yaml file holding the pin templates
+    #[serde(default = "default_template_library")]
+    pub library: String,
+}
+
+impl std::default::Default for PbguiTemplatePrefs {
+    fn default() -> Self {
+        Self {
+            library: default_template_library(),
+        }
+    }
+}
+
+impl PbguiTemplatePrefs {
+    /// The template library, with a leading ~ expanded to the user's home directory
+    pub fn library(&self) -> String {
+        expand_home(self.library.as_str())
+    }
+}
+
+fn default_template_library() -> String {
+    "~/.pbgui/templates.yaml".to_string()
+}
+
+/// Models the install section of the PbguiPrefs
+#[derive(Debug, PartialEq, Deserialize, Default)]
+pub struct PbguiInstallPrefs {
+    /// Map of site name to the directory distributions are installed under,
+    /// as `<root>/<package>/<version>`
+    #[serde(default)]
+    pub roots: HashMap<String, String>,
+}
+
+impl PbguiInstallPrefs {
+    /// The install roots, with a leading ~ expanded to the user's home directory
+    pub fn roots(&self) -> HashMap<String, String> {
+        self.roots
+            .iter()
+            .map(|(site, root)| (site.clone(), expand_home(root.as_str())))
+            .collect()
+    }
+}
+
+/// Models the log section of the PbguiPrefs
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct PbguiLogPrefs {
+    /// Maximum number of rows retained by the log window. Once exceeded, the
+    /// oldest rows are dropped. 0 retains every row.
+    #[serde(default = "default_max_rows")]
+    pub max_rows: usize,
+    /// Whether to write logs to disk
+    #[serde(default = "default_log_file")]
+    pub file: bool,
+    /// Directory the log file is written to
+    #[serde(default = "default_log_directory")]
+    pub directory: String,
+    /// Size in bytes at which the log file is rotated
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+    /// Number of rotated log files to keep
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+}
+
+impl std::default::Default for PbguiLogPrefs {
+    fn default() -> Self {
+        Self {
+            max_rows: default_max_rows(),
+            file: default_log_file(),
+            directory: default_log_directory(),
+            max_file_size: default_max_file_size(),
+            max_files: default_max_files(),
+        }
+    }
+}
+
+impl PbguiLogPrefs {
+    /// The log directory, with a leading ~ expanded to the user's home directory
+    pub fn directory(&self) -> String {
+        expand_home(self.directory.as_str())
+    }
+}
+
+fn default_max_rows() -> usize {
+    10000
+}
+
+fn default_log_file() -> bool {
+    true
+}
+
+fn default_log_directory() -> String {
+    "~/.pbgui/logs".to_string()
+}
+
+fn default_max_file_size() -> u64 {
+    5 * 1024 * 1024
+}
+
+fn default_max_files() -> usize {
+    5
+}
+
+/// Models the save_review section of the PbguiPrefs
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct PbguiSaveReviewPrefs {
+    /// Number of inheriting levels at or above which the user must type the
+    /// name of the show in order to save. Facility changes always require it.
+    #[serde(default = "default_confirm_threshold")]
+    pub confirm_threshold: usize,
+}
+
+impl std::default::Default for PbguiSaveReviewPrefs {
+    fn default() -> Self {
+        Self {
+            confirm_threshold: default_confirm_threshold(),
+        }
+    }
+}
+
+fn default_confirm_threshold() -> usize {
+    DEFAULT_CONFIRM_THRESHOLD
+}
+pub use crate::messaging::client_proxy::{ConnectParams, SslMode};
+/// Models the database section of the PbguiPrefs
+#[derive(PartialEq, Deserialize)]
 pub struct PbguiDbPrefs {
     pub host: String,
     pub user: String,
+    /// Inline password. Prefer one of the other password sources.
+    #[serde(default)]
     pub password: String,
+    /// Name of an environment variable holding the password
+    #[serde(default)]
+    pub password_env: Option<String>,
+    /// Shell command whose output is the password
+    #[serde(default)]
+    pub password_command: Option<String>,
+    /// Path to a .pgpass-style file
+    #[serde(default)]
+    pub passfile: Option<String>,
     pub dbname: String,
     pub port: u64,
+    #[serde(default)]
+    pub sslmode: SslMode,
+    /// Path to a PEM encoded CA certificate used to verify the server
+    #[serde(default)]
+    pub ca_cert: Option<String>,
+}
+
+impl std::fmt::Debug for PbguiDbPrefs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PbguiDbPrefs")
+            .field("host", &self.host)
+            .field("user", &self.user)
+            .field("password", &REDACTED)
+            .field("password_env", &self.password_env)
+            .field("password_command", &self.password_command)
+            .field("passfile", &self.passfile)
+            .field("dbname", &self.dbname)
+            .field("port", &self.port)
+            .field("sslmode", &self.sslmode)
+            .field("ca_cert", &self.ca_cert)
+            .finish()
+    }
 }
 
 impl std::default::Default for PbguiDbPrefs {
@@ -37,11 +248,57 @@ impl std::default::Default for PbguiDbPrefs {
             host: cp.host.to_string(),
             user: cp.user.to_string(),
             password: cp.password.to_string(),
+            password_env: None,
+            password_command: None,
+            passfile: None,
             dbname: cp.dbname.to_string(),
             port: cp.port,
+            sslmode: cp.sslmode,
+            ca_cert: None,
         }
     }
 }
+
+impl PbguiDbPrefs {
+    /// Resolve the password from the configured source, replacing the inline
+    /// password with the result. If no source is configured, the inline
+    /// password is left untouched.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Ok(()) if successful
+    /// * Err(CredentialError) if the configured source fails
+    pub fn resolve_password(&mut self) -> Result<(), CredentialError> {
+        let password = if let Some(ref var) = self.password_env {
+            credentials::password_from_env(var)?
+        } else if let Some(ref cmd) = self.password_command {
+            credentials::password_from_command(cmd)?
+        } else if let Some(ref passfile) = self.passfile {
+            let key = PassfileKey {
+                host: self.host.as_str(),
+                port: self.port,
+                dbname: self.dbname.as_str(),
+                user: self.user.as_str(),
+            };
+            credentials::password_from_passfile(expand_home(passfile).as_str(), &key)?
+        } else {
+            return Ok(());
+        };
+        self.password = password;
+        Ok(())
+    }
+}
+
+// expand a leading ~ to the user's home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
 // This gives us two functions -- load and load_file
 impl Preference for PbguiPrefs {
     type PreferenceStruct = PbguiPrefs;
@@ -52,6 +309,9 @@ impl PbguiPrefs {
     /// Construct a ConectParams instance from a config. Note that the
     /// lifetime of the ConnectParams is intrinsicly tied to that of
     /// the prefs, as ConnectParams is non-owning.
+    ///
+    /// The password should be resolved via `PbguiDbPrefs::resolve_password`
+    /// before calling this.
     pub fn as_connectparams(&self) -> ConnectParams {
         ConnectParams::new(
             self.database.host.as_str(),
@@ -60,5 +320,6 @@ impl PbguiPrefs {
             self.database.dbname.as_str(),
             self.database.port,
         )
+        .tls(self.database.sslmode, self.database.ca_cert.as_deref())
     }
 }
@@ -1,14 +1,21 @@
-use crate::constants::*;
 use crate::messaging::outgoing::omain_win::OMainWin;
 use crate::messaging::OMsg;
 use crate::messaging::Sender;
+use crate::traits::RowTrait;
 pub use crate::utility::qs;
+use crate::versionpin_row::VersionPinRow;
 use log;
-use qt_widgets::{cpp_core::MutPtr, QTableWidget, QWidget};
+use qt_core::QString;
+use qt_widgets::{
+    cpp_core::{CppBox, MutPtr},
+    QTableWidget, QWidget,
+};
 
 // Choose an alternative distribution from a list of distributions in a popup dialog.
+// The site is the current site, whose installs are reported alongside the versions.
 pub fn choose_alternative_distribution(
     row: i32,
+    site: String,
     versionpin_table: MutPtr<QTableWidget>,
     root_widget: MutPtr<QWidget>,
     versionpin_changes_table: MutPtr<QTableWidget>,
@@ -18,25 +25,21 @@ pub fn choose_alternative_distribution(
         if !validate_ptrs(versionpin_table, root_widget, versionpin_changes_table) {
             return;
         }
-        // all we need is the package... perhaps we should change the vtable
-        // TODO: show package & version as separate columns in versionpin table.
-        let distribution = versionpin_table.item(row, COL_DISTRIBUTION);
-        let orig_vpin_table_distribution = distribution.text().to_std_string();
-        // split up the distribution into the package name
-        // and the version
-        let (package, version) = if let [package, version] =
-            *orig_vpin_table_distribution.split('-').collect::<Vec<_>>()
-        {
-            (package, version)
-        } else {
-            panic!("unable to extract package and version from row");
-        };
+        let distribution =
+            match VersionPinRow::<CppBox<QString>>::from_table_at_row(&versionpin_table, row) {
+                Some(vpin_row) => vpin_row.distribution,
+                None => {
+                    log::error!("unable to read the versionpin at row {}", row);
+                    return;
+                }
+            };
         log::debug!("signaling ChooseDistribution");
         to_thread_sender
             .send(OMsg::MainWin(OMainWin::ChooseDistribution {
-                package: package.to_string(),
-                version: version.to_string(),
+                package: distribution.package().to_string(),
+                version: distribution.version().to_string(),
                 row,
+                site,
             }))
             .expect("unable to get history revisions");
     }
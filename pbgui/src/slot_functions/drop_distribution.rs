@@ -0,0 +1,282 @@
+//! Handles the distributions dragged from the packages tree onto the versionpin table.
+//!
+//! The versionpin table is in overwrite mode, so Qt applies a distribution dropped on
+//! a pin by overwriting the cell it lands on with the roles of the tree's item, and
+//! emits itemChanged for that cell. The row of the cell is the row dropped on. The
+//! cell is then restored from the copy of its roles kept in the BACKUP_ROLEs, which
+//! `backup_item` refreshes as the table changes. Dropped below the last pin, the
+//! distribution is inserted as a new row instead, which is removed again.
+//!
+//! A distribution dropped on a pin of its own package changes the version of that pin,
+//! while one dropped on a pin of another package is rejected. Dropped below the pins,
+//! it is left to the caller to pin via the vpin dialog.
+use crate::constants::*;
+use crate::distribution::Distribution;
+use crate::main_window::InnerMainWindow;
+use crate::messaging::outgoing::omain_win::OMainWin;
+use crate::messaging::OMsg;
+use crate::messaging::Sender;
+use crate::traits::RowTrait;
+use crate::versionpin_row::VersionPinRow;
+use pbgui_vpin::vpin_dialog::PinCombination;
+use qt_core::{QString, QVariant};
+use qt_widgets::{
+    cpp_core::{CppBox, MutPtr},
+    QTableWidgetItem,
+};
+use std::rc::Rc;
+
+// the roles of a versionpin table cell which the item of a dropped distribution may
+// overwrite. Each is backed up in the role following BACKUP_ROLE by its index.
+const BACKED_UP_ROLES: [i32; 3] = [
+    0, // DisplayRole
+    3, // ToolTipRole
+    9, // ForegroundRole
+];
+
+/// What becomes of a distribution dropped onto the versionpin table
+#[derive(Debug, PartialEq, Clone)]
+pub enum DropTarget {
+    /// Dropped below the pins. The distribution is pinned via the vpin dialog
+    NewPin,
+    /// Dropped on a pin of its package at the row, whose version it changes
+    ChangePin(i32),
+    /// Dropped on a pin at the row, which is already pinned to the distribution
+    AlreadyPinned(i32),
+    /// Dropped on a pin of another package at the row
+    OtherPackage(i32),
+}
+
+/// Determine what becomes of a dropped distribution, given the pin it was dropped on
+///
+/// # Arguments
+/// * `distribution` - The dropped distribution
+/// * `pinned` - The row and distribution of the pin dropped on, if any
+///
+/// # Returns
+/// * DropTarget instance
+pub fn drop_target(
+    distribution: &Distribution,
+    pinned: Option<(i32, &Distribution)>,
+) -> DropTarget {
+    match pinned {
+        None => DropTarget::NewPin,
+        Some((row, pin)) if pin.package() != distribution.package() => {
+            DropTarget::OtherPackage(row)
+        }
+        Some((row, pin)) if pin == distribution => DropTarget::AlreadyPinned(row),
+        Some((row, _)) => DropTarget::ChangePin(row),
+    }
+}
+
+/// The distribution of a dropped item, from the package and version it carries
+///
+/// # Arguments
+/// * `package` - The value of the DIST_PACKAGE_ROLE
+/// * `version` - The value of the DIST_VERSION_ROLE
+///
+/// # Returns
+/// * Some(Distribution) if the package and version are valid
+/// * None otherwise
+pub fn dropped_distribution(package: &str, version: &str) -> Option<Distribution> {
+    match Distribution::new(package, version) {
+        Ok(distribution) => Some(distribution),
+        Err(err) => {
+            log::error!("dropped item: {}", err);
+            None
+        }
+    }
+}
+
+/// Determine whether an item of the versionpin table was dropped from the packages
+/// tree
+///
+/// # Arguments
+/// * `item` - The item of the versionpin table
+///
+/// # Returns
+/// * bool
+pub fn is_dropped_item(item: MutPtr<QTableWidgetItem>) -> bool {
+    unsafe { !item.is_null() && item.data(DIST_PACKAGE_ROLE).is_valid() }
+}
+
+/// Copy the roles of a changed versionpin table cell, which a dropped distribution
+/// may overwrite, to its BACKUP_ROLEs
+///
+/// # Arguments
+/// * `item` - The changed item of the versionpin table
+///
+/// # Returns
+/// * None
+pub fn backup_item(mut item: MutPtr<QTableWidgetItem>) {
+    unsafe {
+        if item.is_null() {
+            return;
+        }
+        let mut table = item.table_widget();
+        // the backup is not a change of the cell
+        let blocked = table.block_signals(true);
+        for (idx, role) in BACKED_UP_ROLES.iter().enumerate() {
+            let value = item.data(*role);
+            item.set_data(BACKUP_ROLE + idx as i32, &value);
+        }
+        table.block_signals(blocked);
+    }
+}
+
+/// Handle a distribution dropped onto the versionpin table, restoring the cell it
+/// overwrote, or removing the row Qt inserted for it. If it was dropped on a pin of
+/// its package, which the user may edit, the secondary thread is asked to look up the
+/// distribution, so that the version of the pin may be changed.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+/// * `item` - The item of the versionpin table the distribution was dropped into
+///
+/// # Returns
+/// * Some(Distribution) if the distribution was dropped below the pins, and should be
+/// pinned via the vpin dialog
+/// * None otherwise
+pub fn drop_distribution(
+    main_win: Rc<InnerMainWindow>,
+    to_thread_sender: Sender<OMsg>,
+    item: MutPtr<QTableWidgetItem>,
+) -> Option<Distribution> {
+    unsafe {
+        let vpin_table = main_win.vpin_table();
+        let package = item.data(DIST_PACKAGE_ROLE).to_string().to_std_string();
+        let version = item.data(DIST_VERSION_ROLE).to_string().to_std_string();
+        let row = item.row();
+        // the rows of pins carry a pkgcoord id, unlike the row inserted for a drop
+        let on_pin = !vpin_table.item(row, COL_PKGCOORD_ID).is_null();
+        if on_pin {
+            restore_item(item);
+        } else {
+            let mut vpin_table = vpin_table;
+            vpin_table.remove_row(row);
+        }
+        let distribution = dropped_distribution(package.as_str(), version.as_str())?;
+        let permissions = main_win.permissions();
+        if permissions.is_readonly() {
+            log::warn!("pbgui is read-only. unable to pin {}", distribution);
+            return None;
+        }
+        if main_win.is_time_travelling() {
+            log::warn!(
+                "unable to pin {} while viewing a past revision",
+                distribution
+            );
+            return None;
+        }
+        let vpin_row = if on_pin {
+            Some(VersionPinRow::<CppBox<QString>>::from_table_at_row(
+                &vpin_table,
+                row,
+            )?)
+        } else {
+            None
+        };
+        let pinned = vpin_row
+            .as_ref()
+            .map(|vpin_row| (row, &vpin_row.distribution));
+        match drop_target(&distribution, pinned) {
+            DropTarget::NewPin => Some(distribution),
+            DropTarget::OtherPackage(row) => {
+                log::warn!(
+                    "unable to pin {} in place of the pin of another package at row {}",
+                    distribution,
+                    row
+                );
+                None
+            }
+            DropTarget::AlreadyPinned(row) => {
+                log::info!("{} is already pinned at row {}", distribution, row);
+                None
+            }
+            DropTarget::ChangePin(row) => {
+                let level = vpin_table.item(row, COL_LEVEL).text().to_std_string();
+                if !permissions.can_edit_level(level.as_str()) {
+                    log::warn!("{} may not edit pins at {}", permissions.user(), level);
+                    return None;
+                }
+                to_thread_sender
+                    .send(OMsg::MainWin(OMainWin::DropDistribution {
+                        distribution,
+                        row,
+                    }))
+                    .expect("unable to look up dropped distribution");
+                None
+            }
+        }
+    }
+}
+
+/// The level, role, site and platform currently chosen in the main toolbar, used to
+/// preset the vpin dialog
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+///
+/// # Returns
+/// * PinCombination instance
+pub fn toolbar_preset(main_win: &InnerMainWindow) -> PinCombination {
+    unsafe {
+        let toolbar = main_win.main_toolbar();
+        PinCombination::new(
+            toolbar.level().current_text().to_std_string(),
+            toolbar.role().current_text().to_std_string(),
+            toolbar.site().current_text().to_std_string(),
+            toolbar.platform().current_text().to_std_string(),
+        )
+    }
+}
+
+// restore the roles of a cell overwritten by a drop from its backup, and drop the
+// distribution it was overwritten with
+unsafe fn restore_item(mut item: MutPtr<QTableWidgetItem>) {
+    let mut table = item.table_widget();
+    let blocked = table.block_signals(true);
+    for (idx, role) in BACKED_UP_ROLES.iter().enumerate() {
+        let value = item.data(BACKUP_ROLE + idx as i32);
+        item.set_data(*role, &value);
+    }
+    item.set_data(DIST_PACKAGE_ROLE, &QVariant::new());
+    item.set_data(DIST_VERSION_ROLE, &QVariant::new());
+    table.block_signals(blocked);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropped_distribution_keeps_package_and_version() {
+        let dist = dropped_distribution("ocio-2-config", "1.0.0-beta.2").unwrap();
+        assert_eq!(dist.package(), "ocio-2-config");
+        assert_eq!(dist.version(), "1.0.0-beta.2");
+        let dist = dropped_distribution("foo-2d", "3").unwrap();
+        assert_eq!(dist.package(), "foo-2d");
+        assert_eq!(dropped_distribution("maya", ""), None);
+    }
+
+    #[test]
+    fn drop_target_depends_on_the_pin_dropped_on() {
+        let dropped = Distribution::new("maya", "2020.1").unwrap();
+        let pinned = Distribution::new("maya", "2019.3").unwrap();
+        let other = Distribution::new("houdini", "18.0.1").unwrap();
+        assert_eq!(drop_target(&dropped, None), DropTarget::NewPin);
+        assert_eq!(
+            drop_target(&dropped, Some((2, &pinned))),
+            DropTarget::ChangePin(2)
+        );
+        assert_eq!(
+            drop_target(&dropped, Some((2, &dropped.clone().with_id(7)))),
+            DropTarget::AlreadyPinned(2)
+        );
+        assert_eq!(
+            drop_target(&dropped, Some((3, &other))),
+            DropTarget::OtherPackage(3)
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Exports the versionpins currently presented in the versionpin table. The rows are
+//! gathered from the table, optionally annotated with their pending changes, and sent
+//! to the secondary thread, which looks up the withs of each pin and writes the file.
+use crate::change_type::Change;
+use crate::export::{ExportFormat, ExportRow};
+use crate::main_window::InnerMainWindow;
+use crate::messaging::outgoing::omain_win::OMainWin;
+use crate::messaging::OMsg;
+use crate::messaging::Sender;
+use crate::traits::RowTrait;
+use crate::utility::qs;
+use crate::{versionpin_changes_row::VersionPinChangesRow, versionpin_row::VersionPinRow};
+use log;
+use packybara::types::IdType;
+use qt_core::QString;
+use qt_widgets::{cpp_core::CppBox, q_message_box::StandardButton, QFileDialog, QMessageBox};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Prompt the user for the export path and format, and request the export
+/// of the pins in the versionpin table.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn export_pins(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    unsafe {
+        let vpin_table = main_win.vpin_table();
+        if vpin_table.row_count() == 0 {
+            let mut mb = QMessageBox::new();
+            mb.set_text(&qs("There are no pins to export. Run a query first."));
+            mb.exec();
+            return;
+        }
+        let output_path = QFileDialog::get_save_file_name_4a(
+            main_win.main(),
+            &qs("export pins"),
+            &qs(""),
+            &qs("CSV (*.csv);;JSON (*.json);;Markdown (*.md)"),
+        );
+        if output_path.is_null() || output_path.is_empty() {
+            log::debug!("pin export cancelled by user");
+            return;
+        }
+        let mut path = output_path.to_std_string();
+        // default to csv when the user does not supply a recognized extension
+        let format = match ExportFormat::from_path(path.as_str()) {
+            Some(format) => format,
+            None => {
+                path.push_str(".csv");
+                ExportFormat::Csv
+            }
+        };
+        let cache = main_win.cache();
+        let include_pending = cache.row_count() > 0 && {
+            let mut mb = QMessageBox::new();
+            mb.set_text(&qs("Include pending changes in the export?"));
+            mb.set_standard_buttons(StandardButton::Yes | StandardButton::No);
+            mb.exec() == StandardButton::Yes.to_int()
+        };
+        let rows = export_rows(main_win.clone(), include_pending);
+        log::info!("exporting {} pins to {}", rows.len(), path);
+        to_thread_sender
+            .send(OMsg::MainWin(OMainWin::ExportPins {
+                rows,
+                path,
+                format,
+                include_pending,
+            }))
+            .expect("unable to export pins");
+    }
+}
+
+// gather the visible rows of the versionpin table, along with their pending changes
+unsafe fn export_rows(main_win: Rc<InnerMainWindow>, include_pending: bool) -> Vec<ExportRow> {
+    let vpin_table = main_win.vpin_table();
+    let pending = if include_pending {
+        pending_changes(main_win.clone())
+    } else {
+        HashMap::new()
+    };
+    let mut rows = Vec::new();
+    for row in 0..vpin_table.row_count() {
+        if vpin_table.is_row_hidden(row) {
+            continue;
+        }
+        let vpin_row = match VersionPinRow::<CppBox<QString>>::from_table_at_row(&vpin_table, row)
+        {
+            Some(vpin_row) => vpin_row,
+            None => continue,
+        };
+        let (pending_change, pending_value) = match pending.get(&vpin_row.id) {
+            Some((change, value)) => (Some(change.clone()), Some(value.clone())),
+            None => (None, None),
+        };
+        rows.push(ExportRow {
+            vpin_id: vpin_row.id,
+            distribution: vpin_row.distribution,
+            level: vpin_row.level.to_std_string(),
+            role: vpin_row.role.to_std_string(),
+            platform: vpin_row.platform.to_std_string(),
+            site: vpin_row.site.to_std_string(),
+            withs: Vec::new(),
+            pending_change,
+            pending_value,
+        });
+    }
+    rows
+}
+
+// Map each versionpin id to a description of its pending changes. New pins are
+// keyed by the fake id assigned to their row in the versionpin table.
+unsafe fn pending_changes(main_win: Rc<InnerMainWindow>) -> HashMap<IdType, (String, String)> {
+    let cache = main_win.cache();
+    let changes_table = main_win.vpin_requested_changes_table();
+    let mut rows_by_vpin: HashMap<IdType, Vec<i32>> = HashMap::new();
+    for idx in cache.change_indexes() {
+        match cache.change_at(idx) {
+            Some(Change::ChangeDistribution { vpin_id, .. })
+            | Some(Change::ChangeWiths { vpin_id, .. })
+            | Some(Change::ChangePkgCoord { vpin_id, .. }) => {
+                rows_by_vpin.entry(vpin_id).or_insert_with(Vec::new).push(idx)
+            }
+            _ => (),
+        }
+    }
+    // new pins are tracked by their fake id
+    let vpin_table = main_win.vpin_table();
+    for row in 0..vpin_table.row_count() {
+        let vpin_row = VersionPinRow::<CppBox<QString>>::from_table_at_row(&vpin_table, row);
+        if let Some(vpin_row) = vpin_row.filter(|vpin_row| vpin_row.id < 0) {
+            if let Some(idx) = cache.index(vpin_row.id) {
+                rows_by_vpin.insert(vpin_row.id, vec![idx]);
+            }
+        }
+    }
+    rows_by_vpin
+        .into_iter()
+        .map(|(vpin_id, rows)| {
+            let (changes, values): (Vec<_>, Vec<_>) = rows
+                .into_iter()
+                .filter_map(|row| {
+                    VersionPinChangesRow::<String>::from_table_at_row(&changes_table, row)
+                })
+                .map(|row| (row.change_type.as_ref().to_string(), row.new_value))
+                .unzip();
+            (vpin_id, (changes.join("; "), values.join("; ")))
+        })
+        .collect()
+}
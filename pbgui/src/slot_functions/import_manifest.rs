@@ -0,0 +1,278 @@
+//! Imports versionpin changes from a csv or yaml manifest. The manifest is read and
+//! sent to the secondary thread for validation against the database, after which
+//! `stage_manifest_changes` loads the valid entries into the PinChangesCache as
+//! pending changes and reports on the rejected ones.
+use crate::change_type::{Change, ChangeType};
+use crate::main_window::InnerMainWindow;
+use crate::manifest::{read_manifest, ManifestEntry, ManifestRejection, ValidatedEntry};
+use crate::messaging::outgoing::omain_win::OMainWin;
+use crate::messaging::OMsg;
+use crate::messaging::Sender;
+use crate::slot_functions::store_withpackage_changes::stage_withs_change;
+use crate::utility::qs;
+use crate::withs_check::{describe, WithsIssue};
+use crate::{
+    versionpin_changes_row::{RowSetterTrait, VersionPinChangesRow},
+    versionpin_row::VersionPinRow,
+};
+use log;
+use qt_core::QString;
+use qt_widgets::{
+    cpp_core::{CppBox, MutPtr},
+    QFileDialog, QMainWindow, QMessageBox,
+};
+use rustqt_utils::ToQString;
+use std::rc::Rc;
+
+/// Prompt the user for a manifest, read it, and request that its entries be validated
+///
+/// # Arguments
+/// * `main_window` - The QMainWindow, used to parent the file dialog
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn import_manifest(main_window: MutPtr<QMainWindow>, to_thread_sender: Sender<OMsg>) {
+    unsafe {
+        let input_path = QFileDialog::get_open_file_name_4a(
+            main_window,
+            &qs("import manifest"),
+            &qs(""),
+            &qs("Manifests (*.csv *.yaml *.yml)"),
+        );
+        if input_path.is_null() || input_path.is_empty() {
+            log::debug!("manifest import cancelled by user");
+            return;
+        }
+        let input = input_path.to_std_string();
+        match read_manifest(input.as_str()) {
+            Ok(entries) => send_manifest_entries(entries, &to_thread_sender),
+            Err(err) => {
+                log::error!("{}", err);
+                let mut mb = QMessageBox::new();
+                mb.set_text(&qs("Unable to import manifest"));
+                mb.set_informative_text(&qs(err.to_string()));
+                mb.exec();
+            }
+        }
+    }
+}
+
+/// Request that the secondary thread validate the supplied manifest entries
+///
+/// # Arguments
+/// * `entries` - The entries read from the manifest
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn send_manifest_entries(entries: Vec<ManifestEntry>, to_thread_sender: &Sender<OMsg>) {
+    log::info!("validating {} manifest entries", entries.len());
+    to_thread_sender
+        .send(OMsg::MainWin(OMainWin::ImportManifest { entries }))
+        .expect("unable to import manifest");
+}
+
+/// Load the validated manifest entries into the PinChangesCache and pin changes table,
+/// reporting on any rejected entries. Entries which duplicate a pending change
+/// are rejected as well, as are withs which create a with-cycle. Withs of a pin which
+/// does not exist yet are staged against its pending new pin. Applied pin templates
+/// and restored revisions are staged the same way.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `accepted` - The entries which passed validation
+/// * `rejected` - The entries which failed validation
+/// * `source` - Describes where the entries came from, eg `manifest`
+///
+/// # Returns
+/// * None
+pub fn stage_manifest_changes(
+    main_win: Rc<InnerMainWindow>,
+    accepted: Vec<ValidatedEntry>,
+    mut rejected: Vec<ManifestRejection>,
+    source: &str,
+) {
+    unsafe {
+        let cache = main_win.cache();
+        let mut versionpin_table = main_win.vpin_table();
+        let mut pinchanges_ptr = main_win.vpin_requested_changes_table();
+        let mut staged = 0;
+        versionpin_table.set_sorting_enabled(false);
+        for entry in accepted {
+            let ValidatedEntry {
+                line,
+                change,
+                level,
+                coord,
+                vpin_id,
+                pkgcoord_id,
+                context,
+                old_value,
+                new_value,
+                level_withs,
+            } = entry;
+            let ctype = ChangeType::from(&change);
+            // the withs of a pin which does not exist yet are staged against its
+            // pending new pin
+            let (change, vpin_id) = match (change, vpin_id) {
+                (Change::ChangeWiths { withs, .. }, None) => match cache.pending_pin_id(&coord) {
+                    Some(id) => (Change::ChangeWiths { vpin_id: id, withs }, Some(id)),
+                    None => {
+                        rejected.push(ManifestRejection {
+                            line,
+                            entry: format!("{:?} {}", ctype, context),
+                            reason: "no existing or pending pin to change the withs of"
+                                .to_string(),
+                        });
+                        continue;
+                    }
+                },
+                (change, vpin_id) => (change, vpin_id),
+            };
+            let pending = change
+                .pin_coord()
+                .map_or(false, |coord| cache.pending_pin_at(&coord).is_some());
+            if pending {
+                rejected.push(ManifestRejection {
+                    line,
+                    entry: format!("{:?} {}", ctype, context),
+                    reason: "duplicates a pending change".to_string(),
+                });
+                continue;
+            }
+            if let Some(vpin_id) = vpin_id {
+                cache.cache_level(vpin_id, level.as_str());
+                if ctype == ChangeType::ChangeDistribution
+                    && cache.orig_version_for(vpin_id).is_none()
+                {
+                    cache.cache_original_version(vpin_id, old_value.as_str());
+                } else if ctype == ChangeType::ChangeWiths
+                    && cache.orig_withs_for(vpin_id).is_none()
+                {
+                    let withs = old_value
+                        .split(',')
+                        .filter(|with| !with.is_empty())
+                        .map(String::from)
+                        .collect();
+                    cache.cache_original_withs(vpin_id, withs);
+                }
+            }
+            // withs are checked and staged as they are when edited in the withs list
+            if let Change::ChangeWiths { vpin_id, withs } = change {
+                let staged_withs = stage_withs_change(
+                    &cache,
+                    &mut pinchanges_ptr,
+                    vpin_id,
+                    coord,
+                    context.as_str(),
+                    withs,
+                    level_withs,
+                );
+                match staged_withs {
+                    Ok(warnings) => {
+                        for warning in warnings {
+                            log::warn!("{} line {}: {}", source, line, warning);
+                        }
+                        staged += 1;
+                    }
+                    Err(issues) => {
+                        let errors = issues
+                            .into_iter()
+                            .filter(WithsIssue::is_error)
+                            .collect::<Vec<_>>();
+                        rejected.push(ManifestRejection {
+                            line,
+                            entry: format!("{:?} {}", ctype, context),
+                            reason: describe(&errors),
+                        });
+                    }
+                }
+                continue;
+            }
+            let vpc_row = VersionPinChangesRow::<CppBox<QString>>::new(
+                ctype,
+                qs(context),
+                qs(old_value),
+                qs(new_value),
+            );
+            match cache.change_row_from_id(change.id(), ctype) {
+                // a pending change to the same pin is replaced
+                Some(row) if ctype != ChangeType::AddDistribution => {
+                    cache.cache_change_at(change, row);
+                    vpc_row.set_table_row(&mut pinchanges_ptr, row);
+                }
+                _ => {
+                    let row = pinchanges_ptr.row_count();
+                    if let Change::AddDistribution {
+                        ref distribution,
+                        ref role,
+                        ref platform,
+                        ref site,
+                        ..
+                    } = change
+                    {
+                        // new pins are shown in the versionpin table as well
+                        let id = cache.next_fake_row_id();
+                        let versionpin_row = VersionPinRow::<CppBox<QString>>::new(
+                            id,
+                            id,
+                            id,
+                            distribution.clone(),
+                            level.to_qstring(),
+                            role.to_qstring(),
+                            platform.to_qstring(),
+                            site.to_qstring(),
+                            0,
+                        );
+                        let vpin_row = versionpin_table.row_count();
+                        versionpin_table.set_row_count(vpin_row + 1);
+                        versionpin_row.set_table_row(&mut versionpin_table, vpin_row);
+                        cache.cache_dist(id, cache.row_count());
+                    } else if let Some(pkgcoord_id) = pkgcoord_id {
+                        if ctype == ChangeType::ChangeDistribution {
+                            cache.cache_dist(pkgcoord_id, cache.row_count());
+                        }
+                    }
+                    cache.cache_change(change);
+                    vpc_row.set_table_row(&mut pinchanges_ptr, row);
+                }
+            }
+            staged += 1;
+        }
+        versionpin_table.set_sorting_enabled(true);
+        report_manifest_import(staged, &rejected, source);
+    }
+}
+
+// present the results of the import to the user
+fn report_manifest_import(staged: usize, rejected: &[ManifestRejection], source: &str) {
+    unsafe {
+        log::info!(
+            "imported {} change(s) from {}. {} rejected",
+            staged,
+            source,
+            rejected.len()
+        );
+        let mut mb = QMessageBox::new();
+        mb.set_text(&qs(format!(
+            "Imported {} change(s) from {}. {} rejected.",
+            staged,
+            source,
+            rejected.len()
+        )));
+        if !rejected.is_empty() {
+            let report = rejected
+                .iter()
+                .map(|rejection| {
+                    log::warn!("{} entry rejected. {}", source, rejection);
+                    rejection.to_string()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            mb.set_informative_text(&qs("See details for the rejected entries."));
+            mb.set_detailed_text(&qs(report));
+        }
+        mb.exec();
+    }
+}
@@ -3,6 +3,13 @@
 //! for readability's sake. Thus we define the heavy lifting in terms of a
 //! `slot_function`, found in `pbgui::slot_functions`.
 pub(crate) mod choose_distribution;
+pub mod drop_distribution;
+pub(crate) mod export_pins;
+pub mod import_manifest;
+pub(crate) mod pin_history;
+pub(crate) mod pin_templates;
+pub(crate) mod restore_revision;
+pub(crate) mod review_versionpin_changes;
 pub(crate) mod save_packages_xml;
 pub(crate) mod save_versionpin_changes;
 pub(crate) mod select_history;
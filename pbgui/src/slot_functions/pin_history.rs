@@ -0,0 +1,114 @@
+//! Presents the history of the selected versionpin, and shows the revision of a change
+//! from it in the revisions table.
+use crate::main_window::InnerMainWindow;
+use crate::messaging::outgoing::omain_win::OMainWin;
+use crate::messaging::OMsg;
+use crate::messaging::Sender;
+use crate::traits::RowTrait;
+use crate::versionpin_row::VersionPinRow;
+use qt_core::QString;
+use qt_widgets::cpp_core::CppBox;
+use std::rc::Rc;
+
+/// The index of the pin history page of the bottom stacked widget
+pub const PIN_HISTORY_PAGE: i32 = 3;
+
+/// Present the pin history page, requesting the history of the selected versionpin
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn select_pin_history(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    unsafe {
+        main_win
+            .bottom_stacked_widget()
+            .set_current_index(PIN_HISTORY_PAGE);
+        main_win
+            .bottom_ctrls_stacked_widget()
+            .set_current_index(PIN_HISTORY_PAGE);
+    }
+    update_pin_history(main_win, to_thread_sender);
+}
+
+/// Request the history of the selected versionpin, if the pin history page is
+/// visible. The history is not queried for each selection otherwise.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn update_pin_history(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    unsafe {
+        if main_win.bottom_stacked_widget().current_index() != PIN_HISTORY_PAGE {
+            return;
+        }
+        let pin_history = main_win.pin_history();
+        let vpin_table = main_win.vpin_table();
+        let selected = vpin_table.selection_model().selected_rows_0a();
+        if selected.count_0a() == 0 {
+            pin_history.clear();
+            return;
+        }
+        let row = selected.at(0).row();
+        let vpin = match VersionPinRow::<CppBox<QString>>::from_table_at_row(&vpin_table, row) {
+            Some(vpin) => vpin,
+            None => {
+                log::error!("unable to read the versionpin at row {}", row);
+                pin_history.clear();
+                return;
+            }
+        };
+        if pin_history.vpin_id() == Some(vpin.id) {
+            return;
+        }
+        // a deleted pin restored by time travel has yet to be saved, and has no history
+        if vpin.id == 0 {
+            pin_history.clear();
+            return;
+        }
+        let coord = vpin.pin_coord();
+        pin_history.request(
+            vpin.id,
+            format!(
+                "{} at {} / {} / {} / {}",
+                coord.package, coord.level, coord.role, coord.platform, coord.site
+            )
+            .as_str(),
+        );
+        log::debug!("signaling GetPinHistory for versionpin {}", vpin.id);
+        to_thread_sender
+            .send(OMsg::MainWin(OMainWin::GetPinHistory {
+                vpin_id: vpin.id,
+                coord,
+            }))
+            .expect("unable to get pin history");
+    }
+}
+
+/// Show the revision of the change selected in the pin history, at the top of the
+/// revisions table
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+///
+/// # Returns
+/// * None
+pub fn goto_revision(main_win: Rc<InnerMainWindow>) {
+    let revision = match main_win.pin_history().selected_transaction() {
+        Some(revision) => revision,
+        None => {
+            log::info!("select a change in the pin history to go to its revision");
+            return;
+        }
+    };
+    main_win.revisions_filter().show_revision(revision);
+    // checking the history action presents the history page and searches it
+    unsafe {
+        main_win.history_button().default_action().set_checked(true);
+    }
+}
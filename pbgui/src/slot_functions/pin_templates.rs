@@ -0,0 +1,199 @@
+//! Captures pin templates from the versionpin table and applies them to a level.
+//!
+//! Capturing a template gathers the selected rows of the versionpin table and sends
+//! them to the secondary thread, which looks up their withs and writes the template
+//! to the library. Applying a template sends it to the secondary thread, which
+//! validates each of its pins against the chosen level, after which the resulting
+//! changes are staged via `stage_manifest_changes`.
+use crate::export::ExportRow;
+use crate::main_window::InnerMainWindow;
+use crate::messaging::outgoing::omain_win::OMainWin;
+use crate::messaging::OMsg;
+use crate::messaging::Sender;
+use crate::templates::TemplateLibrary;
+use crate::traits::RowTrait;
+use crate::utility::qs;
+use crate::versionpin_row::VersionPinRow;
+use log;
+use qt_core::{QString, QStringList};
+use qt_widgets::{
+    cpp_core::{CppBox, MutPtr},
+    q_line_edit::EchoMode,
+    q_message_box::StandardButton,
+    QInputDialog, QMessageBox,
+};
+use std::rc::Rc;
+
+/// Prompt the user for a name, and save the pins selected in the versionpin table
+/// to the template library under that name.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn save_pin_template(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    unsafe {
+        let rows = selected_rows(main_win.clone());
+        if rows.is_empty() {
+            message("Select the pins to save as a template first.", None);
+            return;
+        }
+        let library_path = main_win.template_library();
+        let library = match TemplateLibrary::load(library_path.as_str()) {
+            Ok(library) => library,
+            Err(err) => {
+                log::error!("{}", err);
+                message("Unable to read the template library", Some(err.to_string()));
+                return;
+            }
+        };
+        let mut ok = false;
+        let ok_ptr = MutPtr::from_raw(&mut ok as *mut bool);
+        let name = QInputDialog::get_text_6a(
+            main_win.main_widget(),
+            &qs("Save Template"),
+            &qs(format!("Save {} pin(s) as template named", rows.len())),
+            EchoMode::Normal,
+            &qs(""),
+            ok_ptr,
+        )
+        .to_std_string();
+        let name = name.trim().to_string();
+        if !ok || name.is_empty() {
+            log::debug!("save template cancelled by user");
+            return;
+        }
+        if library.get(name.as_str()).is_some() {
+            let mut mb = QMessageBox::new();
+            mb.set_text(&qs(format!("Replace the existing template {}?", name)));
+            mb.set_standard_buttons(StandardButton::Yes | StandardButton::No);
+            if mb.exec() != StandardButton::Yes.to_int() {
+                return;
+            }
+        }
+        log::info!("saving {} pins as template {}", rows.len(), name);
+        to_thread_sender
+            .send(OMsg::MainWin(OMainWin::SavePinTemplate {
+                name,
+                rows,
+                library: library_path,
+            }))
+            .expect("unable to save pin template");
+    }
+}
+
+/// Prompt the user for a template and a level, and request that the template's pins
+/// be validated against the level, so that the resulting changes may be staged.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn apply_pin_template(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    unsafe {
+        let library = match TemplateLibrary::load(main_win.template_library().as_str()) {
+            Ok(library) => library,
+            Err(err) => {
+                log::error!("{}", err);
+                message("Unable to read the template library", Some(err.to_string()));
+                return;
+            }
+        };
+        if library.names().is_empty() {
+            message(
+                "There are no templates. Save pins from the versionpin table as a template first.",
+                None,
+            );
+            return;
+        }
+        let mut names = QStringList::new();
+        for name in library.names() {
+            names.append_q_string(&qs(name));
+        }
+        let mut ok = false;
+        let ok_ptr = MutPtr::from_raw(&mut ok as *mut bool);
+        let name = QInputDialog::get_item_7a(
+            main_win.main_widget(),
+            &qs("Apply Template"),
+            &qs("Template"),
+            names.as_ref(),
+            0,
+            false,
+            ok_ptr,
+        )
+        .to_std_string();
+        if !ok {
+            log::debug!("apply template cancelled by user");
+            return;
+        }
+        let template = match library.get(name.as_str()) {
+            Some(template) => template.clone(),
+            None => return,
+        };
+        let level = QInputDialog::get_text_6a(
+            main_win.main_widget(),
+            &qs("Apply Template"),
+            &qs(format!("Apply the {} pin(s) of {} to level", template.pins.len(), name)),
+            EchoMode::Normal,
+            &qs(main_win.main_toolbar().level_string()),
+            ok_ptr,
+        )
+        .to_std_string();
+        let level = level.trim().to_string();
+        if !ok || level.is_empty() {
+            log::debug!("apply template cancelled by user");
+            return;
+        }
+        if !main_win.permissions().can_edit_level(level.as_str()) {
+            message(&format!("You may not edit pins at {}", level), None);
+            return;
+        }
+        log::info!("applying template {} to {}", name, level);
+        to_thread_sender
+            .send(OMsg::MainWin(OMainWin::ApplyPinTemplate { template, level }))
+            .expect("unable to apply pin template");
+    }
+}
+
+// gather the selected rows of the versionpin table. Their withs are looked up by
+// the secondary thread.
+unsafe fn selected_rows(main_win: Rc<InnerMainWindow>) -> Vec<ExportRow> {
+    let vpin_table = main_win.vpin_table();
+    let selection = vpin_table.selection_model().selected_rows_0a();
+    let mut rows = Vec::new();
+    for idx in 0..selection.count_0a() {
+        let row = selection.at(idx).row();
+        let vpin_row = match VersionPinRow::<CppBox<QString>>::from_table_at_row(&vpin_table, row)
+        {
+            Some(vpin_row) => vpin_row,
+            None => continue,
+        };
+        rows.push(ExportRow {
+            vpin_id: vpin_row.id,
+            distribution: vpin_row.distribution,
+            level: vpin_row.level.to_std_string(),
+            role: vpin_row.role.to_std_string(),
+            platform: vpin_row.platform.to_std_string(),
+            site: vpin_row.site.to_std_string(),
+            withs: Vec::new(),
+            pending_change: None,
+            pending_value: None,
+        });
+    }
+    rows
+}
+
+fn message(text: &str, details: Option<String>) {
+    unsafe {
+        let mut mb = QMessageBox::new();
+        mb.set_text(&qs(text));
+        if let Some(details) = details {
+            mb.set_informative_text(&qs(details));
+        }
+        mb.exec();
+    }
+}
@@ -0,0 +1,65 @@
+//! Restores the versionpins shown while time travelling. The changes which return
+//! today's pins to their past state are validated by the secondary thread like the
+//! entries of a manifest, and staged as pending changes by `stage_manifest_changes`.
+use crate::main_window::InnerMainWindow;
+use crate::messaging::outgoing::omain_win::OMainWin;
+use crate::messaging::OMsg;
+use crate::messaging::Sender;
+use crate::utility::qs;
+use qt_widgets::{q_message_box::StandardButton, QMessageBox};
+use std::rc::Rc;
+
+/// Confirm with the user, return to the present, and request that the changes
+/// restoring the revision being viewed be validated. Changes to levels the user
+/// may not edit are left out.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn restore_revision(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    unsafe {
+        let (revision, mut entries) = match main_win.restore_entries() {
+            Some(restore) => restore,
+            None => return,
+        };
+        let permissions = main_win.permissions();
+        let count = entries.len();
+        entries.retain(|entry| permissions.can_edit_level(entry.level.as_str()));
+        if count > entries.len() {
+            log::warn!(
+                "{} may not edit {} of the pins to restore. skipping them",
+                permissions.user(),
+                count - entries.len()
+            );
+        }
+        if entries.is_empty() {
+            log::info!("nothing to restore to revision {}", revision);
+            return;
+        }
+        let mut mb = QMessageBox::new();
+        mb.set_text(&qs(format!(
+            "Stage {} change(s) restoring the versionpins to revision {}?",
+            entries.len(),
+            revision
+        )));
+        mb.set_informative_text(&qs(
+            "Pins added since the revision are not removed. The history does not record \
+             changes to withs, so withs are left as they are.",
+        ));
+        mb.set_standard_buttons(StandardButton::Yes | StandardButton::No);
+        if mb.exec() != StandardButton::Yes.to_int() {
+            log::debug!("restore cancelled by user");
+            return;
+        }
+        // leaving time travel queries today's pins, which the secondary thread returns
+        // ahead of the validated changes
+        main_win.main_toolbar().as_of_btn().set_checked(false);
+        log::info!("restoring {} pin(s) to revision {}", entries.len(), revision);
+        to_thread_sender
+            .send(OMsg::MainWin(OMainWin::RestoreRevision { revision, entries }))
+            .expect("unable to restore revision");
+    }
+}
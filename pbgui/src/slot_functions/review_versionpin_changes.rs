@@ -0,0 +1,113 @@
+//! Before pending versionpin changes are saved, they are presented to the user for review.
+//! The review happens in two steps. First, `request_save_review` verifies the user's
+//! permissions and asks the secondary thread how many descendant levels inherit from
+//! each level being changed. Once the answer arrives, `show_save_review` groups the
+//! changes by level and presents the SaveReviewDialog. Accepting the dialog proceeds
+//! with `save_versionpin_changes`.
+use crate::cache::PinChangesCache;
+use crate::components::save_review_dialog::SaveReviewGroup;
+use crate::main_window::InnerMainWindow;
+use crate::messaging::outgoing::omain_win::OMainWin;
+use crate::messaging::OMsg;
+use crate::messaging::Sender;
+use crate::permissions::Permissions;
+use crate::traits::RowTrait;
+use crate::utility::qs;
+use crate::versionpin_changes_row::VersionPinChangesRow;
+use log;
+use qt_widgets::QMessageBox;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+/// Level reported for changes whose level cannot be determined
+const UNKNOWN_LEVEL: &str = "unknown";
+
+/// Verify that the user may save the pending changes and, if so, request the
+/// impact of the changes from the secondary thread.
+///
+/// # Arguments
+/// * `pinchange_cache` - The cache of pending changes
+/// * `permissions` - The user's Permissions
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn request_save_review(
+    pinchange_cache: Rc<PinChangesCache>,
+    permissions: Rc<Permissions>,
+    to_thread_sender: Sender<OMsg>,
+) {
+    unsafe {
+        let changes = pinchange_cache
+            .change_indexes()
+            .into_iter()
+            .filter_map(|idx| pinchange_cache.change_at(idx))
+            .collect::<Vec<_>>();
+        if changes.is_empty() {
+            log::info!("No pending changes to save");
+            return;
+        }
+        //
+        // verify that the user is allowed to make the changes before going any further
+        //
+        if let Err(err) = permissions.check_changes(&changes, &pinchange_cache) {
+            log::warn!("{}", err);
+            let mut mb = QMessageBox::new();
+            mb.set_text(&qs("Permission Denied"));
+            mb.set_informative_text(&qs(err));
+            mb.exec();
+            return;
+        }
+        let mut levels = changes
+            .iter()
+            .filter_map(|change| pinchange_cache.level_for_change(change))
+            .collect::<Vec<_>>();
+        levels.sort();
+        levels.dedup();
+        log::debug!("signaling GetLevelImpact");
+        to_thread_sender
+            .send(OMsg::MainWin(OMainWin::GetLevelImpact { levels }))
+            .expect("unable to get level impact");
+    }
+}
+
+/// Group the pending changes by level and present them in the SaveReviewDialog.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `impact` - Map of level to the number of descendant levels inheriting from it
+///
+/// # Returns
+/// * None
+pub fn show_save_review(main_win: Rc<InnerMainWindow>, impact: HashMap<String, usize>) {
+    let cache = main_win.cache();
+    let changes_table = main_win.vpin_requested_changes_table();
+    let mut grouped: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for idx in cache.change_indexes() {
+        let change = match cache.change_at(idx) {
+            Some(change) => change,
+            None => continue,
+        };
+        let level = cache
+            .level_for_change(&change)
+            .unwrap_or_else(|| UNKNOWN_LEVEL.to_string());
+        let description = match VersionPinChangesRow::<String>::from_table_at_row(&changes_table, idx)
+        {
+            Some(row) => format!(
+                "{:?}  {}  {} -> {}",
+                row.change_type, row.context, row.old_value, row.new_value
+            ),
+            None => format!("{:?}", change),
+        };
+        grouped.entry(level).or_insert_with(Vec::new).push(description);
+    }
+    let groups = grouped
+        .into_iter()
+        .map(|(level, changes)| SaveReviewGroup {
+            inheriting: impact.get(&level).copied().unwrap_or(0),
+            level,
+            changes,
+        })
+        .collect::<Vec<_>>();
+    main_win.save_review_dialog().exec(groups);
+}
@@ -1,10 +1,25 @@
+use crate::main_window::InnerMainWindow;
 use crate::messaging::outgoing::OMainWin;
 use crate::messaging::OMsg;
 use crate::messaging::Sender;
 use crate::utility::qs;
-use qt_widgets::{cpp_core::MutPtr, QComboBox, QFileDialog, QMainWindow};
+use qt_widgets::{
+    cpp_core::{MutPtr, NullPtr},
+    q_file_dialog::Option as FileDialogOption,
+    QComboBox, QFileDialog, QMainWindow,
+};
+use std::rc::Rc;
 
-/// Updates the withpackages in response to versionpin selection in the main view
+/// Choose where to save the packages.xml of the current show, and request a preview of
+/// it. The file is written once the user has reviewed the preview.
+///
+/// # Arguments
+/// * `main_window` - The QMainWindow, which parents the file dialog
+/// * `level_cb` - The level combobox, naming the current show
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
 pub fn save_packages_xml(
     main_window: MutPtr<QMainWindow>,
     level_cb: MutPtr<QComboBox>,
@@ -13,24 +28,55 @@ pub fn save_packages_xml(
     unsafe {
         let level = level_cb.current_text().to_std_string();
         log::info!("current show: {}", &level);
-        // now I need qdialog
-        let output_path = QFileDialog::get_save_file_name_4a(
+        // the preview names the file it replaces, and presents the lines it changes, and
+        // nothing is written until its Save button is pressed. That confirms replacing an
+        // existing file, so the file dialog does not ask as well.
+        let output_path = QFileDialog::get_save_file_name_6a(
             main_window,
             &qs("save packages.xml"),
             &qs(""),
             &qs("*.xml"),
+            NullPtr,
+            FileDialogOption::DontConfirmOverwrite.into(),
         );
-        if output_path.is_null() {
+        if output_path.is_null() || output_path.is_empty() {
             log::debug!("packages.xml save cancelled by user");
             return;
         }
         let output = output_path.to_std_string();
-        log::debug!("saving packages.xml to {}", output);
+        log::debug!("previewing packages.xml for {}", output);
         to_thread_sender
-            .send(OMsg::MainWin(OMainWin::SavePackagesXml {
+            .send(OMsg::MainWin(OMainWin::PreviewPackagesXml {
                 show: level,
                 output,
             }))
-            .expect("unable to get vpins");
+            .expect("unable to preview packages.xml");
     }
 }
+
+/// Write the packages.xml previewed by the PackagesXmlDialog, once the user has
+/// accepted it
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn write_packages_xml(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    let pending = match main_win.packages_xml_dialog().take_pending() {
+        Some(pending) => pending,
+        None => {
+            log::error!("no packages.xml awaiting confirmation");
+            return;
+        }
+    };
+    log::debug!("saving packages.xml to {}", pending.output);
+    to_thread_sender
+        .send(OMsg::MainWin(OMainWin::SavePackagesXml {
+            show: pending.show,
+            output: pending.output,
+            xml: pending.xml,
+        }))
+        .expect("unable to save packages.xml");
+}
@@ -5,6 +5,7 @@ use crate::messaging::OMsg;
 use crate::messaging::Sender;
 use crate::utility::qs;
 use log;
+use std::collections::HashMap;
 use qt_widgets::{cpp_core::MutPtr, QInputDialog, QMessageBox, QWidget};
 use std::rc::Rc;
 use whoami;
@@ -15,6 +16,25 @@ pub fn save_versionpin_changes(
     to_thread_sender: Sender<OMsg>,
 ) {
     unsafe {
+        // We will send change to secondary thread as vec<change>
+        let mut change_vec: Vec<Change> = Vec::new();
+        // the withs of new pins refer to them by fake id, and are set once they exist
+        let mut new_pins = HashMap::new();
+        // Retrieve the indexes of the changes in the cache and look up the changes
+        // from the cache. We introduce this indirection to make it simple to delete
+        // a change from the change table without having to delete an item from the
+        // vector of changes in the cache, which would lead to an O(n) operation.
+        for idx in pinchange_cache.change_indexes() {
+            let change = pinchange_cache
+                .change_at(idx)
+                .expect("unable to unwrap change");
+            if let Change::ChangeWiths { vpin_id, .. } = change {
+                if let Some(coord) = pinchange_cache.pending_pin_coord(vpin_id) {
+                    new_pins.insert(vpin_id, coord);
+                }
+            }
+            change_vec.push(change);
+        }
         // grab all the data from the pin changes
         let mut ok = false;
         let ok_p: *mut bool = &mut ok;
@@ -29,19 +49,6 @@ pub fn save_versionpin_changes(
             }
         };
 
-        // We will send change to secondary thread as vec<change>
-        let mut change_vec: Vec<Change> = Vec::new();
-        // Retrieve the indexes of the changes in the cache and look up the changes
-        // from the cache. We introduce this indirection to make it simple to delete
-        // a change from the change table without having to delete an item from the
-        // vector of changes in the cache, which would lead to an O(n) operation.
-        for idx in pinchange_cache.change_indexes() {
-            let change = pinchange_cache
-                .change_at(idx)
-                .expect("unable to unwrap change");
-
-            change_vec.push(change);
-        }
         let user = whoami::username();
 
         // Now that we have used the cache for its intended purpose, we reset it back to
@@ -51,6 +58,7 @@ pub fn save_versionpin_changes(
         to_thread_sender
             .send(OMsg::MainWin(OMainWin::SaveVpinChanges {
                 changes: change_vec,
+                new_pins,
                 user,
                 comments,
             }))
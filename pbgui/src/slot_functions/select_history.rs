@@ -1,22 +1,84 @@
+use crate::main_window::InnerMainWindow;
 use crate::messaging::outgoing::omain_win::OMainWin;
 use crate::messaging::OMsg;
 use crate::messaging::Sender;
+use std::rc::Rc;
 
-use qt_widgets::{cpp_core::MutPtr, QStackedWidget, QTableWidget};
+/// The number of rows from the bottom of the revisions table at which the next page
+/// of revisions is requested
+const FETCH_MARGIN: i32 = 5;
 
-pub fn select_history(
-    revisions_ptr: &mut MutPtr<QTableWidget>,
-    stacked_ptr: &mut MutPtr<QStackedWidget>,
-    to_thread_sender: Sender<OMsg>,
-) {
+/// Present the history page, searching for the revisions matching the filter bar
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn select_history(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    unsafe {
+        main_win.bottom_stacked_widget().set_current_index(1);
+        main_win.bottom_ctrls_stacked_widget().set_current_index(1);
+    }
+    search_history(main_win, to_thread_sender);
+}
+
+/// Start a new search of the revisions, replacing the contents of the revisions table
+/// with the first page matching the filter bar
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn search_history(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
+    let filter_bar = main_win.revisions_filter();
+    let filter = match filter_bar.filter() {
+        Ok(filter) => filter,
+        Err(e) => {
+            log::error!("unable to search history: {}", e);
+            filter_bar.set_status(e.as_str());
+            return;
+        }
+    };
+    let (filter, offset) = main_win.history_paging().borrow_mut().start(filter);
     unsafe {
-        stacked_ptr.set_current_index(1);
         log::debug!("clearing contents for revisions");
-        revisions_ptr.clear_contents();
-        log::debug!("contents cleared");
-        log::debug!("signaling SaveVpinChanges");
+        main_win.revisions_table().set_row_count(0);
+    }
+    filter_bar.set_status("searching...");
+    log::debug!("signaling GetHistoryRevisions for {}", filter);
+    to_thread_sender
+        .send(OMsg::MainWin(OMainWin::GetHistoryRevisions { filter, offset }))
+        .expect("unable to get history revisions");
+}
+
+/// Request the next page of revisions once the revisions table is scrolled near
+/// its bottom
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+/// * `value` - The position of the vertical scroll bar of the revisions table
+///
+/// # Returns
+/// * None
+pub fn fetch_more_history(
+    main_win: Rc<InnerMainWindow>,
+    to_thread_sender: Sender<OMsg>,
+    value: i32,
+) {
+    let maximum = unsafe { main_win.revisions_table().vertical_scroll_bar().maximum() };
+    if value < maximum - FETCH_MARGIN {
+        return;
+    }
+    let next_page = main_win.history_paging().borrow_mut().next_page();
+    if let Some((filter, offset)) = next_page {
+        log::debug!("fetching revisions from {} of {}", offset, filter);
         to_thread_sender
-            .send(OMsg::MainWin(OMainWin::GetHistoryRevisions))
+            .send(OMsg::MainWin(OMainWin::GetHistoryRevisions { filter, offset }))
             .expect("unable to get history revisions");
     }
 }
@@ -1,63 +1,291 @@
+//! Stores the edits made to the withs of the current pin as a ChangeWiths.
+//!
+//! `check_edited_withs` marks withs naming the pin's own package, or appearing twice,
+//! in the withs list as they are edited. `store_withpackage_changes` asks the secondary
+//! thread for the withs of the other pins at the pin's coordinate. Once they arrive,
+//! `stage_withs_change`, which stages every ChangeWiths, rejects withs which would
+//! create a with-cycle, and otherwise caches the change. The staged changes are
+//! checked for with-cycles together once more when they are saved.
 use crate::cache::PinChangesCache;
-use crate::change_type::{Change, ChangeType};
+use crate::change_type::{Change, ChangeType, PinCoord};
+use crate::main_window::InnerMainWindow;
+use crate::messaging::outgoing::omain_win::OMainWin;
+use crate::messaging::OMsg;
+use crate::messaging::Sender;
+use crate::slot_functions::update_withpackages::active_vpin_row;
 use crate::traits::RowTrait;
 use crate::utility::qs;
+use crate::withs_check::{check_withs, describe, validate_withs, LevelWiths, WithsIssue};
 use crate::{
     versionpin_changes_row::{RowSetterTrait, VersionPinChangesRow},
     versionpin_row::VersionPinRow,
 };
-use pbgui_withs::WithsList;
+use packybara::types::IdType;
+use pbgui_withs::order::{annotated_diff, join, order_diff};
 use qt_core::QString;
 use qt_widgets::{
     cpp_core::{CppBox, MutPtr},
-    QTableWidget,
+    q_message_box::{Icon, StandardButton},
+    QMessageBox, QTableWidget,
 };
-use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 
-/// add the withpackage change to the listx`
-pub fn store_withpackage_changes(
-    item_list: Rc<RefCell<WithsList>>,
-    versionpin_table: MutPtr<QTableWidget>,
-    changes_table: &mut MutPtr<QTableWidget>,
-    cache: Rc<PinChangesCache>,
-) {
+/// Validate the withs of the current pin, and request the withs of the other pins
+/// at its coordinate, so that the change may be checked for with-cycles before it
+/// is staged. The user is asked to confirm withs which only raise warnings.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `to_thread_sender` - Sender of OMsg's to the secondary thread
+///
+/// # Returns
+/// * None
+pub fn store_withpackage_changes(main_win: Rc<InnerMainWindow>, to_thread_sender: Sender<OMsg>) {
     unsafe {
+        let item_list = main_win.package_withs_list();
         let items = item_list.borrow().items();
 
-        // get current versionpin distribution_id
-        let selection_model = versionpin_table.selection_model();
-        if selection_model.has_selection() {
-            let row = selection_model.selected_rows_0a().first().row();
-            let ctype = ChangeType::ChangeWiths;
-            let table_row =
-                VersionPinRow::<CppBox<QString>>::from_table_at_row(&versionpin_table, row);
-            if table_row.is_none() {
+        // the withs list presents the withs of the single selected pin
+        let versionpin_table = main_win.vpin_table();
+        let row = match active_vpin_row(&versionpin_table) {
+            Some(row) => row,
+            None => {
+                log::warn!("select a single versionpin to save its withs");
+                return;
+            }
+        };
+        let table_row = match VersionPinRow::<CppBox<QString>>::from_table_at_row(
+            &versionpin_table,
+            row,
+        ) {
+            Some(table_row) => table_row,
+            None => {
                 log::warn!("Tablerow {} is None", row);
                 return;
             }
-            let table_row = table_row.ok_or(false).expect("unable to unwrap table_row");
-
-            let new_withs = items.join(",");
-            let change = Change::ChangeWiths {
+        };
+        // the list presents the original withs until the pin's first change is stored
+        let cache = main_win.cache();
+        if cache.orig_withs_for(table_row.id).is_none() {
+            cache.cache_original_withs(table_row.id, item_list.borrow().original_items());
+        }
+        let coord = table_row.pin_coord();
+        let issues = check_withs(coord.package.as_str(), &items);
+        item_list.borrow().set_warnings(warnings(&issues));
+        if !issues.is_empty() {
+            let mut mb = QMessageBox::new();
+            mb.set_icon(Icon::Warning);
+            mb.set_text(&qs(format!("Save the withs of {} anyway?", coord.package)));
+            mb.set_informative_text(&qs(describe(&issues)));
+            mb.set_standard_buttons(StandardButton::Yes | StandardButton::No);
+            if mb.exec() != StandardButton::Yes.to_int() {
+                return;
+            }
+        }
+        to_thread_sender
+            .send(OMsg::MainWin(OMainWin::ValidateWiths {
                 vpin_id: table_row.id,
+                coord,
                 withs: items,
-            };
-            // store change
-            let change_row = VersionPinChangesRow::<CppBox<QString>>::new(
-                ChangeType::ChangeWiths,
-                table_row.pkgcoord(),
-                qs(""),
-                qs(new_withs),
-            );
-            if let Some(row) = cache.change_row_from_id(change.id(), ctype) {
-                // we found a row, we will insert in that row
-                cache.cache_change_at(change, row);
-                change_row.set_table_row(changes_table, row);
-            } else {
-                cache.cache_change(change);
-                change_row.set_table_row(changes_table, changes_table.row_count());
+            }))
+            .expect("unable to validate withs");
+    }
+}
+
+/// Stage the withs of a pin edited in the withs list, once the withs of the other pins
+/// at its coordinate have arrived from the database. With-cycles are reported to the
+/// user, and marked in the withs list.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+/// * `vpin_id` - The id of the versionpin
+/// * `coord` - The coordinate of the versionpin
+/// * `withs` - The proposed withs of the versionpin
+/// * `level_withs` - The withs of the other pins at the coordinate, from the database
+///
+/// # Returns
+/// * None
+pub fn stage_withpackage_changes(
+    main_win: Rc<InnerMainWindow>,
+    vpin_id: IdType,
+    coord: PinCoord,
+    withs: Vec<String>,
+    level_withs: LevelWiths,
+) {
+    unsafe {
+        let cache = main_win.cache();
+        let versionpin_table = main_win.vpin_table();
+        let table_row = match find_vpin_row(&versionpin_table, vpin_id) {
+            Some(table_row) => table_row,
+            None => {
+                log::warn!("versionpin {} is no longer in the versionpin table", vpin_id);
+                return;
             }
+        };
+        let mut changes_table = main_win.vpin_requested_changes_table();
+        let context = table_row.pkgcoord().to_std_string();
+        let package = coord.package.clone();
+        let staged = stage_withs_change(
+            &cache,
+            &mut changes_table,
+            vpin_id,
+            coord,
+            context.as_str(),
+            withs,
+            level_withs,
+        );
+        if let Err(issues) = staged {
+            main_win
+                .package_withs_list()
+                .borrow()
+                .set_warnings(warnings(&issues));
+            let errors = issues
+                .into_iter()
+                .filter(WithsIssue::is_error)
+                .collect::<Vec<_>>();
+            let mut mb = QMessageBox::new();
+            mb.set_icon(Icon::Critical);
+            mb.set_text(&qs(format!("Unable to save the withs of {}", package)));
+            mb.set_informative_text(&qs(describe(&errors)));
+            mb.exec();
+        }
+    }
+}
+
+/// Stage the withs of a pin as a ChangeWiths, replacing any pending withs change of the
+/// pin, provided that they do not create a with-cycle through the other pins at its
+/// coordinate, including the pending withs changes of those pins. Every ChangeWiths is
+/// staged through here, whether it is edited in the withs list, imported from a
+/// manifest, applied from a template or restored from a past revision. The change is
+/// presented in the changes table against the original withs of the pin, which the
+/// caller is expected to have cached.
+///
+/// # Arguments
+/// * `cache` - The PinChangesCache
+/// * `changes_table` - The pin changes table
+/// * `vpin_id` - The id of the versionpin. Pending new pins have a fake id
+/// * `coord` - The package and coordinate of the versionpin
+/// * `context` - Describes the versionpin in the changes table
+/// * `withs` - The proposed withs of the versionpin
+/// * `level_withs` - The withs of the other pins at the coordinate, from the database
+///
+/// # Returns
+/// * Ok(Vec<WithsIssue>) holding any warnings, if the change was staged
+/// * Err(Vec<WithsIssue>) holding every issue, if the withs create a with-cycle
+pub fn stage_withs_change(
+    cache: &PinChangesCache,
+    changes_table: &mut MutPtr<QTableWidget>,
+    vpin_id: IdType,
+    coord: PinCoord,
+    context: &str,
+    withs: Vec<String>,
+    mut level_withs: LevelWiths,
+) -> Result<Vec<WithsIssue>, Vec<WithsIssue>> {
+    level_withs.extend(pending_withs(cache, vpin_id, &coord));
+    let issues = validate_withs(coord.package.as_str(), &withs, &level_withs);
+    if issues.iter().any(WithsIssue::is_error) {
+        return Err(issues);
+    }
+    let ctype = ChangeType::ChangeWiths;
+    let original = cache.orig_withs_for(vpin_id).unwrap_or_default();
+    log::info!("withs of {}: {}", context, order_diff(&original, &withs));
+    // the new value notes the withs added, removed and moved, including reordering
+    let change_row = VersionPinChangesRow::<CppBox<QString>>::new(
+        ctype,
+        qs(context),
+        qs(join(&original)),
+        qs(annotated_diff(&original, &withs)),
+    );
+    cache.cache_level(vpin_id, coord.level.as_str());
+    cache.cache_pin_coord(vpin_id, coord);
+    let change = Change::ChangeWiths { vpin_id, withs };
+    unsafe {
+        if let Some(row) = cache.change_row_from_id(change.id(), ctype) {
+            // we found a row, we will insert in that row
+            cache.cache_change_at(change, row);
+            change_row.set_table_row(changes_table, row);
+        } else {
+            let row = changes_table.row_count();
+            cache.cache_change(change);
+            change_row.set_table_row(changes_table, row);
+        }
+    }
+    Ok(issues)
+}
+
+/// Mark the issues with the withs of the selected pin in the withs list as the withs
+/// are added, removed and moved, rather than waiting for them to be saved. With-cycles
+/// involve the withs of the other pins at the coordinate, and are checked on save.
+///
+/// # Arguments
+/// * `main_win` - The InnerMainWindow instance
+///
+/// # Returns
+/// * None
+pub fn check_edited_withs(main_win: Rc<InnerMainWindow>) {
+    unsafe {
+        let versionpin_table = main_win.vpin_table();
+        let table_row = match active_vpin_row(&versionpin_table).and_then(|row| {
+            VersionPinRow::<CppBox<QString>>::from_table_at_row(&versionpin_table, row)
+        }) {
+            Some(table_row) => table_row,
+            None => return,
+        };
+        let item_list = main_win.package_withs_list();
+        let issues = check_withs(table_row.package().as_str(), &item_list.borrow().items());
+        if !issues.is_empty() {
+            item_list.borrow().set_warnings(warnings(&issues));
+        }
+    }
+}
+
+// the withs of the pending ChangeWiths of the other pins at the coordinate, keyed by
+// package. The coordinates of the pins are cached as their changes are staged, so
+// pins which are not presented in the versionpin table are accounted for.
+fn pending_withs(cache: &PinChangesCache, vpin_id: IdType, coord: &PinCoord) -> LevelWiths {
+    let mut pending = LevelWiths::new();
+    for idx in cache.change_indexes() {
+        let (other_id, withs) = match cache.change_at(idx) {
+            Some(Change::ChangeWiths { vpin_id, withs }) => (vpin_id, withs),
+            _ => continue,
+        };
+        if other_id == vpin_id {
+            continue;
+        }
+        if let Some(other) = cache.pin_coord_for(other_id) {
+            if other.level == coord.level
+                && other.role == coord.role
+                && other.platform == coord.platform
+                && other.site == coord.site
+            {
+                pending.insert(other.package, withs);
+            }
+        }
+    }
+    pending
+}
+
+// find the row of the versionpin table presenting the versionpin
+unsafe fn find_vpin_row(
+    versionpin_table: &MutPtr<QTableWidget>,
+    vpin_id: IdType,
+) -> Option<VersionPinRow<CppBox<QString>>> {
+    (0..versionpin_table.row_count())
+        .filter_map(|row| {
+            VersionPinRow::<CppBox<QString>>::from_table_at_row(versionpin_table, row)
+        })
+        .find(|table_row| table_row.id == vpin_id)
+}
+
+// the issues, keyed by the with they apply to, for presentation in the withs list
+fn warnings(issues: &[WithsIssue]) -> HashMap<String, String> {
+    let mut warnings = HashMap::new();
+    for issue in issues {
+        // errors take precedence over warnings about the same with
+        if issue.is_error() || !warnings.contains_key(issue.with()) {
+            warnings.insert(issue.with().to_string(), issue.to_string());
         }
     }
+    warnings
 }
@@ -2,10 +2,14 @@ use crate::main_window::InnerMainWindow;
 use crate::messaging::outgoing::omain_win::OMainWin;
 use crate::messaging::OMsg;
 use crate::messaging::Sender;
+use crate::time_travel::AsOf;
+use crate::utility::qs;
 use std::rc::Rc;
+use std::str::FromStr;
 /// update the main versionpin table by gathering the user's requested query parameters from    
 /// the comboboxes up top, and sending a message to the secondary thread asking to get
-/// version pins.
+/// version pins. While time travel is toggled on in the toolbar, the pins are requested
+/// as of the revision or timestamp entered there.
 ///
 /// # Arguments
 /// * `toolbar` - shared pointer to the MainToolbar
@@ -30,6 +34,19 @@ pub fn update_vpin_table(main_window: Rc<InnerMainWindow>, to_thread_sender: Sen
         } else {
             None
         };
+        // an invalid revision or timestamp is marked in red, rather than queried
+        let mut as_of_line_edit = toolbar.as_of_line_edit();
+        let as_of = match toolbar.as_of_string().map(|as_of| AsOf::from_str(as_of.as_str())) {
+            Some(Err(err)) => {
+                log::error!("{}", err);
+                as_of_line_edit.set_style_sheet(&qs("color: rgb(255,120,120);"));
+                as_of_line_edit.set_tool_tip(&qs(err));
+                return;
+            }
+            as_of => as_of.and_then(Result::ok),
+        };
+        as_of_line_edit.set_style_sheet(&qs(""));
+        as_of_line_edit.set_tool_tip(&qs(""));
         log::debug!("signaling GetVpins");
 
         to_thread_sender
@@ -41,6 +58,7 @@ pub fn update_vpin_table(main_window: Rc<InnerMainWindow>, to_thread_sender: Sen
                 platform: platformtxt,
                 site: sitetxt,
                 dir: dirtxt,
+                as_of,
             }))
             .expect("unable to get vpins");
     }
@@ -14,6 +14,27 @@ use qt_widgets::{
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Retrieve the row of the versionpin whose withs the withs list presents. The withs
+/// of a single pin are edited at a time, so there is no such row unless exactly one
+/// row is selected.
+///
+/// # Arguments
+/// * `vpin_table` - The versionpin table
+///
+/// # Returns
+/// * Some(row) if a single row is selected
+/// * None otherwise
+pub fn active_vpin_row(vpin_table: &MutPtr<QTableWidget>) -> Option<i32> {
+    unsafe {
+        let selected = vpin_table.selection_model().selected_rows_0a();
+        if selected.count_0a() == 1 {
+            Some(selected.at(0).row())
+        } else {
+            None
+        }
+    }
+}
+
 /// Updates the withpackages in response to versionpin selection in the main view
 pub fn update_withpackages(
     row: i32,
@@ -26,11 +47,20 @@ pub fn update_withpackages(
         .ok_or(false)
         .expect("unable to unwrap from_table_at_row");
     let vpin_id = table_row.id;
+    // a deleted pin restored by time travel has yet to be saved, and has no withs
+    if vpin_id == 0 {
+        item_list.borrow_mut().clear();
+        return;
+    }
     if let Some(row) = cache.change_row_from_id(vpin_id as u64, ChangeType::ChangeWiths) {
         if let Some(Change::ChangeWiths { withs, .. }) = cache.change_at(row) {
             {
                 item_list.borrow_mut().clear();
                 item_list.borrow_mut().set_items(withs);
+                // the list presents the edited withs. compare them against the originals
+                if let Some(original) = cache.orig_withs_for(vpin_id) {
+                    item_list.borrow().set_original_items(original);
+                }
             }
         } else {
             log::error!(
@@ -0,0 +1,332 @@
+//! Pin templates are named sets of pins which may be captured from the versionpin table
+//! and applied to another level, saving the user from re-adding the same pins by hand
+//! whenever a new sequence is set up.
+//!
+//! Each pin of a template supplies a distribution, role, platform, site and withs. The
+//! templates are stored together in a yaml library:
+//!
+//! ```yaml
+//! - name: lighting tools
+//!   pins:
+//!     - distribution: maya-2020.1
+//!       role: light
+//!       withs: [mtoa, ocio]
+//!     - distribution: houdini-18.0.1
+//!       role: fx
+//!       platform: cent7_64
+//! ```
+//!
+//! `role`, `platform` and `site` default to `any`.
+use crate::distribution::Distribution;
+use crate::export::ExportRow;
+use crate::manifest::{ManifestEntry, ManifestOp};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const ANY: &str = "any";
+
+fn any() -> String {
+    ANY.to_string()
+}
+
+/// A single pin of a template
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct TemplatePin {
+    /// The distribution, written as `package-version`
+    pub distribution: Distribution,
+    #[serde(default = "any")]
+    pub role: String,
+    #[serde(default = "any")]
+    pub platform: String,
+    #[serde(default = "any")]
+    pub site: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub withs: Vec<String>,
+}
+
+impl TemplatePin {
+    /// The manifest entry requesting the supplied operation for the pin at a level.
+    /// Template pins are validated against the database as manifest entries are.
+    ///
+    /// # Arguments
+    /// * `op` - The requested operation
+    /// * `level` - The level the template is applied to
+    /// * `line` - The position of the pin in the template, starting at 1
+    ///
+    /// # Returns
+    /// * ManifestEntry instance
+    pub fn to_entry(&self, op: ManifestOp, level: &str, line: usize) -> ManifestEntry {
+        ManifestEntry {
+            line,
+            op,
+            package: self.distribution.package().to_string(),
+            version: Some(self.distribution.version().to_string()),
+            level: level.to_string(),
+            role: self.role.clone(),
+            platform: self.platform.clone(),
+            site: self.site.clone(),
+            withs: self.withs.clone(),
+        }
+    }
+
+    /// Determine the changes required to add the template pin where there is no pin
+    /// of the same package at the coordinate. The withs are staged against the new pin.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Vec of ManifestOp
+    pub fn additions(&self) -> Vec<ManifestOp> {
+        let mut ops = vec![ManifestOp::AddDistribution];
+        if !self.withs.is_empty() {
+            ops.push(ManifestOp::ChangeWiths);
+        }
+        ops
+    }
+
+    /// Determine the changes required to bring an existing pin of the same package,
+    /// at the same coordinate, in line with the template pin.
+    ///
+    /// # Arguments
+    /// * `version` - The version of the existing pin
+    /// * `withs` - The withs of the existing pin
+    ///
+    /// # Returns
+    /// * Vec of ManifestOp. Empty if the existing pin matches
+    pub fn changes_from(&self, version: &str, withs: &[String]) -> Vec<ManifestOp> {
+        let mut ops = Vec::new();
+        if self.distribution.version() != version {
+            ops.push(ManifestOp::ChangeDistribution);
+        }
+        if self.withs.as_slice() != withs {
+            ops.push(ManifestOp::ChangeWiths);
+        }
+        ops
+    }
+}
+
+impl From<&ExportRow> for TemplatePin {
+    fn from(row: &ExportRow) -> Self {
+        Self {
+            distribution: row.distribution.clone(),
+            role: row.role.clone(),
+            platform: row.platform.clone(),
+            site: row.site.clone(),
+            withs: row.withs.clone(),
+        }
+    }
+}
+
+/// A named set of pins
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct PinTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub pins: Vec<TemplatePin>,
+}
+
+impl PinTemplate {
+    /// New up a PinTemplate
+    ///
+    /// # Arguments
+    /// * `name` - The name of the template
+    /// * `pins` - The pins of the template
+    ///
+    /// # Returns
+    /// * PinTemplate instance
+    pub fn new<I: Into<String>>(name: I, pins: Vec<TemplatePin>) -> Self {
+        Self {
+            name: name.into(),
+            pins,
+        }
+    }
+}
+
+/// The collection of templates available to the user
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TemplateLibrary {
+    templates: Vec<PinTemplate>,
+}
+
+impl TemplateLibrary {
+    /// New up an empty TemplateLibrary
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the library from disk. A library which does not exist yet is empty.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the yaml library
+    ///
+    /// # Returns
+    /// * Ok(TemplateLibrary) if successful
+    /// * Err(TemplateError) otherwise
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TemplateError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let contents = fs::read_to_string(path).map_err(|e| TemplateError::Io(e.to_string()))?;
+        Self::from_yaml(&contents)
+    }
+
+    /// Write the library to disk, creating its directory if need be
+    ///
+    /// # Arguments
+    /// * `path` - The path to the yaml library
+    ///
+    /// # Returns
+    /// * Ok(()) if successful
+    /// * Err(TemplateError) otherwise
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TemplateError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| TemplateError::Io(e.to_string()))?;
+        }
+        fs::write(path, self.to_yaml()?).map_err(|e| TemplateError::Io(e.to_string()))
+    }
+
+    /// Parse a library from yaml
+    ///
+    /// # Arguments
+    /// * `contents` - The yaml document
+    ///
+    /// # Returns
+    /// * Ok(TemplateLibrary) if successful
+    /// * Err(TemplateError) otherwise
+    pub fn from_yaml(contents: &str) -> Result<Self, TemplateError> {
+        if contents.trim().is_empty() {
+            return Ok(Self::new());
+        }
+        serde_yaml::from_str(contents).map_err(|e| TemplateError::Parse(e.to_string()))
+    }
+
+    /// Serialize the library to yaml
+    pub fn to_yaml(&self) -> Result<String, TemplateError> {
+        serde_yaml::to_string(self).map_err(|e| TemplateError::Parse(e.to_string()))
+    }
+
+    /// The names of the templates, in the order they were added
+    pub fn names(&self) -> Vec<&str> {
+        self.templates.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    /// Look up a template by name
+    pub fn get(&self, name: &str) -> Option<&PinTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    /// Add a template to the library, replacing any template of the same name
+    ///
+    /// # Arguments
+    /// * `template` - The template to add
+    ///
+    /// # Returns
+    /// * Some(PinTemplate) - the template which was replaced
+    /// * None if the name is new
+    pub fn insert(&mut self, template: PinTemplate) -> Option<PinTemplate> {
+        match self.templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => Some(std::mem::replace(existing, template)),
+            None => {
+                self.templates.push(template);
+                None
+            }
+        }
+    }
+
+    /// Remove a template from the library by name
+    pub fn remove(&mut self, name: &str) -> Option<PinTemplate> {
+        let idx = self.templates.iter().position(|t| t.name == name)?;
+        Some(self.templates.remove(idx))
+    }
+}
+
+/// Errors which may arise while reading or writing the template library
+#[derive(Debug)]
+pub enum TemplateError {
+    /// The library could not be read from or written to disk
+    Io(String),
+    /// The library could not be parsed or serialized
+    Parse(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(msg) => write!(f, "unable to access template library: {}", msg),
+            Self::Parse(msg) => write!(f, "unable to parse template library: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY: &str = r#"
+- name: lighting tools
+  pins:
+    - distribution: maya-2020.1
+      role: light
+      withs: [mtoa]
+"#;
+
+    #[test]
+    fn library_round_trips_through_yaml() {
+        let library = TemplateLibrary::from_yaml(LIBRARY).expect("unable to parse library");
+        let template = library.get("lighting tools").expect("missing template");
+        assert_eq!(template.pins[0].site, "any");
+        let entry = template.pins[0].to_entry(ManifestOp::AddDistribution, "dev01.rd", 1);
+        assert_eq!(entry.package, "maya");
+        assert_eq!(entry.version, Some("2020.1".to_string()));
+        let mut copy = TemplateLibrary::from_yaml(&library.to_yaml().unwrap()).unwrap();
+        assert_eq!(copy, library);
+        assert!(copy.insert(PinTemplate::new("lighting tools", Vec::new())).is_some());
+        assert_eq!(copy.names(), vec!["lighting tools"]);
+    }
+
+    #[test]
+    fn existing_pins_are_changed_to_match() {
+        let pin = TemplatePin {
+            distribution: Distribution::new("nuke", "12.0.1").unwrap(),
+            role: any(),
+            platform: any(),
+            site: any(),
+            withs: vec!["ocio".to_string()],
+        };
+        assert!(pin.changes_from("12.0.1", &["ocio".to_string()]).is_empty());
+        assert_eq!(
+            pin.changes_from("11.3", &[]),
+            vec![ManifestOp::ChangeDistribution, ManifestOp::ChangeWiths]
+        );
+    }
+
+    #[test]
+    fn new_pins_are_added_with_their_withs() {
+        let mut pin = TemplatePin {
+            distribution: Distribution::new("maya", "2020.1").unwrap(),
+            role: "light".to_string(),
+            platform: any(),
+            site: any(),
+            withs: vec!["mtoa".to_string(), "ocio".to_string()],
+        };
+        let ops = pin.additions();
+        assert_eq!(ops, vec![ManifestOp::AddDistribution, ManifestOp::ChangeWiths]);
+        // both entries name the pin at the new level, so the withs find the new pin
+        let entries = ops
+            .iter()
+            .map(|op| pin.to_entry(*op, "dev02.seq10", 1))
+            .collect::<Vec<_>>();
+        assert_eq!(entries[0].pin_coord(), entries[1].pin_coord());
+        assert_eq!(entries[1].withs, pin.withs);
+        pin.withs.clear();
+        assert_eq!(pin.additions(), vec![ManifestOp::AddDistribution]);
+    }
+}
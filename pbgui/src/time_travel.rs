@@ -0,0 +1,605 @@
+//! Reconstructs the versionpins as they stood at a past revision.
+//!
+//! The database only stores the current pins, along with the history of changes
+//! made to them, as returned by `find_all_changes`. The pins at a past revision are
+//! recovered by undoing, newest first, every change made by a later transaction to
+//! every pin in the facility:
+//! * a changed distribution is returned to its old version
+//! * an added pin is removed
+//! * a deleted pin is restored
+//!
+//! The changes are undone before inheritance is resolved, so that a pin added since at
+//! a more specific level no longer hides the pin it overrides. The pins of the past are
+//! then resolved for the coordinate and search mode of the query by a PinQuery, as the
+//! database resolves today's pins.
+//!
+//! The history records the changes made to distributions, but not those made to withs.
+//! A pin which has been neither deleted nor re-created since the revision keeps the
+//! withs it has today. The withs of a pin restored from the history are not known.
+use crate::change_type::PinCoord;
+use crate::distribution::Distribution;
+use crate::manifest::{ManifestEntry, ManifestOp};
+use crate::versionpin_row::VersionPinRow;
+use chrono::{NaiveDate, NaiveDateTime};
+use packybara::types::IdType;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The root of the levels
+const FACILITY: &str = "facility";
+/// The root of the roles, platforms and sites
+const ANY: &str = "any";
+
+/// The point in the history of the database to view the pins at
+#[derive(Debug, PartialEq, Clone)]
+pub enum AsOf {
+    /// After the transaction with the supplied id
+    Revision(IdType),
+    /// At the supplied local time
+    Timestamp(NaiveDateTime),
+}
+
+impl FromStr for AsOf {
+    type Err = String;
+
+    /// Parse a revision id (`1234`), or a timestamp (`2020-03-10`,
+    /// `2020-03-10 17:30` or `2020-03-10 17:30:00`). A date alone refers to
+    /// the end of that day.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(revision) = s.parse::<IdType>() {
+            return Ok(Self::Revision(revision));
+        }
+        parse_timestamp(s, true)
+            .map(Self::Timestamp)
+            .map_err(|_| format!("'{}' is neither a revision id nor a timestamp", s))
+    }
+}
+
+/// Parse a timestamp, given as `YYYY-MM-DD`, `YYYY-MM-DD HH:MM` or
+/// `YYYY-MM-DD HH:MM:SS`
+///
+/// # Arguments
+/// * `s` - The text to parse
+/// * `end_of_day` - Whether a date alone refers to the end of the day, rather than
+/// its start
+///
+/// # Returns
+/// * Ok(NaiveDateTime) if the text is a valid timestamp
+/// * Err(String) otherwise
+pub fn parse_timestamp(s: &str, end_of_day: bool) -> Result<NaiveDateTime, String> {
+    let s = s.trim();
+    for format in &["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(timestamp) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(timestamp);
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| {
+            if end_of_day {
+                date.and_hms(23, 59, 59)
+            } else {
+                date.and_hms(0, 0, 0)
+            }
+        })
+        .map_err(|_| format!("'{}' is not a timestamp (YYYY-MM-DD [HH:MM[:SS]])", s))
+}
+
+impl fmt::Display for AsOf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Revision(revision) => write!(f, "revision {}", revision),
+            Self::Timestamp(timestamp) => write!(f, "{}", timestamp.format("%F %T")),
+        }
+    }
+}
+
+/// The action recorded by a change in the history
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChangeAction {
+    Add,
+    Change,
+    Delete,
+}
+
+impl FromStr for ChangeAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "add" => Ok(Self::Add),
+            "change" => Ok(Self::Change),
+            "delete" => Ok(Self::Delete),
+            _ => Err(format!("unknown change action '{}'", s)),
+        }
+    }
+}
+
+/// A change to a versionpin, as recorded in the history
+#[derive(Debug, PartialEq, Clone)]
+pub struct PastChange {
+    /// The transaction which made the change
+    pub tx_id: IdType,
+    pub action: ChangeAction,
+    /// The package coordinate of the pin
+    pub coord: PinCoord,
+    /// The version pinned before the change, if any
+    pub old: Option<String>,
+    /// The version pinned by the change, if any
+    pub new: Option<String>,
+}
+
+/// How a pin of the past differs from the pin today
+#[derive(Debug, PartialEq, Clone)]
+pub enum PinState {
+    /// The pin has not changed since
+    Unchanged,
+    /// The pin has since been changed to the supplied version
+    Changed(String),
+    /// The pin has since been deleted
+    Deleted,
+}
+
+/// A versionpin as it stood at a past revision
+#[derive(Debug, PartialEq)]
+pub struct PastPin {
+    /// The pin, as presented in the versionpin table
+    pub row: VersionPinRow<String>,
+    pub state: PinState,
+    /// The withs of the pin at the revision, if they are known
+    pub withs: Option<Vec<String>>,
+}
+
+impl PastPin {
+    /// The package coordinate of the pin
+    pub fn coord(&self) -> PinCoord {
+        row_coord(&self.row)
+    }
+}
+
+/// The coordinate and search mode of a versionpin query, used to resolve the pins of
+/// the past the way the database resolves today's pins
+#[derive(Debug, PartialEq, Clone)]
+pub struct PinQuery {
+    pub level: String,
+    pub role: String,
+    pub platform: String,
+    pub site: String,
+    /// The search mode: ancestor, exact or descendant
+    pub dir: String,
+    /// Leave out the facility's pins when querying a show
+    pub isolate_facility: bool,
+}
+
+impl PinQuery {
+    /// Select the pins the query presents. In ancestor mode, the pins whose coordinate
+    /// the query's coordinate inherits from are found, and the most specific pin of each
+    /// package wins: the pin at the deepest level, then role, platform and site. In exact
+    /// mode, the pins at the coordinate are found, and in descendant mode, the pins at or
+    /// below it.
+    ///
+    /// # Arguments
+    /// * `coords` - The package coordinates of the pins
+    ///
+    /// # Returns
+    /// * The indices of the selected pins, in ascending order
+    pub fn select(&self, coords: &[PinCoord]) -> Vec<usize> {
+        let query = [
+            (self.level.as_str(), FACILITY),
+            (self.role.as_str(), ANY),
+            (self.platform.as_str(), ANY),
+            (self.site.as_str(), ANY),
+        ];
+        let in_view = |coord: &PinCoord| {
+            if self.isolate_facility && self.level != FACILITY && coord.level == FACILITY {
+                return false;
+            }
+            let dims = [&coord.level, &coord.role, &coord.platform, &coord.site];
+            query
+                .iter()
+                .zip(dims.iter())
+                .all(|((query, root), dim)| match self.dir.as_str() {
+                    "exact" => dim.as_str() == *query,
+                    "descendant" => inherits(dim, query, root),
+                    _ => inherits(query, dim, root),
+                })
+        };
+        let mut selected = coords
+            .iter()
+            .enumerate()
+            .filter(|(_, coord)| in_view(coord))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        if self.dir == "exact" || self.dir == "descendant" {
+            return selected;
+        }
+        let mut winners: HashMap<&str, usize> = HashMap::new();
+        for idx in selected.drain(..) {
+            let package = coords[idx].package.as_str();
+            let wins = match winners.get(package) {
+                Some(&other) => specificity(&coords[idx]) > specificity(&coords[other]),
+                None => true,
+            };
+            if wins {
+                winners.insert(package, idx);
+            }
+        }
+        let mut selected = winners.values().copied().collect::<Vec<_>>();
+        selected.sort();
+        selected
+    }
+}
+
+/// The versionpins as they stood at a past revision
+#[derive(Debug, PartialEq)]
+pub struct PastPins {
+    /// The last transaction applied to the pins
+    pub revision: IdType,
+    pub pins: Vec<PastPin>,
+    /// Pins added since the revision, which did not exist at the time
+    pub added_since: Vec<PinCoord>,
+}
+
+impl PastPins {
+    /// Undo the changes made after the supplied revision to the current pins of the
+    /// facility, and select the pins of the past presented by the query
+    ///
+    /// # Arguments
+    /// * `revision` - The id of the last transaction to keep
+    /// * `query` - The query resolving the pins presented
+    /// * `current` - Every pin in the facility today, along with its withs
+    /// * `changes` - The change history. Changes up to and including the revision
+    /// are ignored.
+    ///
+    /// # Returns
+    /// * PastPins instance
+    pub fn rewind(
+        revision: IdType,
+        query: &PinQuery,
+        current: Vec<(VersionPinRow<String>, Vec<String>)>,
+        mut changes: Vec<PastChange>,
+    ) -> Self {
+        let current_coords = current
+            .iter()
+            .map(|(row, _)| row_coord(row))
+            .collect::<Vec<_>>();
+        let mut pins = current
+            .into_iter()
+            .map(|(row, withs)| PastPin {
+                row,
+                state: PinState::Unchanged,
+                withs: Some(withs),
+            })
+            .collect::<Vec<_>>();
+        let mut added_since = Vec::new();
+        changes.retain(|change| change.tx_id > revision);
+        // changes within a transaction are undone in the reverse of their recorded order
+        changes.reverse();
+        changes.sort_by(|a, b| b.tx_id.cmp(&a.tx_id));
+        for change in changes {
+            let idx = pins.iter().position(|pin| pin.coord() == change.coord);
+            match (change.action, idx) {
+                (ChangeAction::Change, Some(idx)) => {
+                    let old = match change.old {
+                        Some(old) => old,
+                        None => continue,
+                    };
+                    let distribution = match past_distribution(&change.coord, old) {
+                        Some(distribution) => distribution,
+                        None => continue,
+                    };
+                    let pin = &mut pins[idx];
+                    if pin.state == PinState::Unchanged {
+                        let version = pin.row.distribution.version().to_string();
+                        pin.state = PinState::Changed(version);
+                    }
+                    pin.row.distribution = distribution;
+                    // the id of the distribution is not recorded in the history
+                    pin.row.dist_id = 0;
+                }
+                (ChangeAction::Add, Some(idx)) => {
+                    let pin = pins.remove(idx);
+                    if pin.state != PinState::Deleted {
+                        added_since.push(change.coord);
+                    }
+                }
+                (ChangeAction::Delete, None) => {
+                    let version = match change.old {
+                        Some(version) => version,
+                        None => continue,
+                    };
+                    let distribution = match past_distribution(&change.coord, version) {
+                        Some(distribution) => distribution,
+                        None => continue,
+                    };
+                    let row = VersionPinRow {
+                        id: 0,
+                        dist_id: 0,
+                        pkgcoord_id: 0,
+                        distribution,
+                        level: change.coord.level,
+                        role: change.coord.role,
+                        platform: change.coord.platform,
+                        site: change.coord.site,
+                        withs: 0,
+                    };
+                    pins.push(PastPin {
+                        row,
+                        state: PinState::Deleted,
+                        withs: None,
+                    });
+                }
+                // the history does not agree with the pins today
+                _ => log::warn!("unable to undo {:?}", change),
+            }
+        }
+        let coords = pins.iter().map(PastPin::coord).collect::<Vec<_>>();
+        let selected = query.select(&coords);
+        let mut pins = pins
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, _)| selected.binary_search(idx).is_ok())
+            .map(|(_, pin)| pin)
+            .collect::<Vec<_>>();
+        // sorted by package, as the database returns them
+        pins.sort_by(|a, b| {
+            a.row
+                .distribution
+                .package()
+                .cmp(b.row.distribution.package())
+        });
+        // the pins added since which the query presents today
+        let in_view_today = query
+            .select(&current_coords)
+            .into_iter()
+            .map(|idx| &current_coords[idx])
+            .collect::<Vec<_>>();
+        added_since.retain(|coord| {
+            in_view_today.contains(&coord) && !pins.iter().any(|pin| &pin.coord() == coord)
+        });
+        Self {
+            revision,
+            pins,
+            added_since,
+        }
+    }
+
+    /// Determine whether every pin is as it is today
+    pub fn is_current(&self) -> bool {
+        self.added_since.is_empty() && self.pins.iter().all(|p| p.state == PinState::Unchanged)
+    }
+
+    /// The manifest entries which would return today's pins to their past state.
+    /// Pins added since the revision may not be removed, and are left out.
+    ///
+    /// # Arguments
+    /// * None
+    ///
+    /// # Returns
+    /// * Vec of ManifestEntry, numbered from 1
+    pub fn restore_entries(&self) -> Vec<ManifestEntry> {
+        self.pins
+            .iter()
+            .filter_map(|pin| {
+                let op = match pin.state {
+                    PinState::Unchanged => return None,
+                    PinState::Changed(_) => ManifestOp::ChangeDistribution,
+                    PinState::Deleted => ManifestOp::AddDistribution,
+                };
+                let coord = pin.coord();
+                Some(ManifestEntry {
+                    line: 0,
+                    op,
+                    version: Some(pin.row.distribution.version().to_string()),
+                    package: coord.package,
+                    level: coord.level,
+                    role: coord.role,
+                    platform: coord.platform,
+                    site: coord.site,
+                    withs: Vec::new(),
+                })
+            })
+            .enumerate()
+            .map(|(idx, mut entry)| {
+                entry.line = idx + 1;
+                entry
+            })
+            .collect()
+    }
+}
+
+// the package coordinate of a row
+fn row_coord(row: &VersionPinRow<String>) -> PinCoord {
+    PinCoord::new(
+        row.distribution.package(),
+        row.level.as_str(),
+        row.role.as_str(),
+        row.platform.as_str(),
+        row.site.as_str(),
+    )
+}
+
+// determine whether `value` inherits from `ancestor`, in a dimension whose root is
+// `root`. Everything inherits from the root and from itself, and levels inherit from
+// the levels they are nested under, separated by dots (dev01.rd.0001 from dev01.rd).
+fn inherits(value: &str, ancestor: &str, root: &str) -> bool {
+    ancestor == root
+        || value == ancestor
+        || (value.starts_with(ancestor) && value[ancestor.len()..].starts_with('.'))
+}
+
+// how specific a coordinate is, compared level first, then role, platform and site
+fn specificity(coord: &PinCoord) -> [usize; 4] {
+    let depth = |value: &str, root: &str| {
+        if value == root {
+            0
+        } else {
+            value.split('.').count()
+        }
+    };
+    [
+        depth(coord.level.as_str(), FACILITY),
+        depth(coord.role.as_str(), ANY),
+        depth(coord.platform.as_str(), ANY),
+        depth(coord.site.as_str(), ANY),
+    ]
+}
+
+// the distribution of the package at coord, at the supplied version
+fn past_distribution(coord: &PinCoord, version: String) -> Option<Distribution> {
+    match Distribution::new(coord.package.as_str(), version) {
+        Ok(distribution) => Some(distribution),
+        Err(err) => {
+            log::warn!("skipping change to {:?}: {}", coord, err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: IdType, distribution: &str, level: &str) -> VersionPinRow<String> {
+        VersionPinRow {
+            id,
+            dist_id: id,
+            pkgcoord_id: id,
+            distribution: Distribution::from_str(distribution).unwrap(),
+            level: level.to_string(),
+            role: "any".to_string(),
+            platform: "any".to_string(),
+            site: "any".to_string(),
+            withs: 0,
+        }
+    }
+
+    fn change(
+        tx_id: IdType,
+        action: ChangeAction,
+        package: &str,
+        old: &str,
+        new: &str,
+    ) -> PastChange {
+        let version = |v: &str| Some(v.to_string()).filter(|v| !v.is_empty());
+        PastChange {
+            tx_id,
+            action,
+            coord: PinCoord::new(package, "dev01", "any", "any", "any"),
+            old: version(old),
+            new: version(new),
+        }
+    }
+
+    #[test]
+    fn parse_as_of() {
+        assert_eq!(AsOf::from_str(" 42 "), Ok(AsOf::Revision(42)));
+        let date = NaiveDate::from_ymd(2020, 3, 10);
+        assert_eq!(
+            AsOf::from_str("2020-03-10 17:30"),
+            Ok(AsOf::Timestamp(date.and_hms(17, 30, 0)))
+        );
+        assert_eq!(
+            AsOf::from_str("2020-03-10"),
+            Ok(AsOf::Timestamp(date.and_hms(23, 59, 59)))
+        );
+        assert!(AsOf::from_str("last tuesday").is_err());
+    }
+
+    fn query(level: &str, dir: &str) -> PinQuery {
+        PinQuery {
+            level: level.to_string(),
+            role: "any".to_string(),
+            platform: "any".to_string(),
+            site: "any".to_string(),
+            dir: dir.to_string(),
+            isolate_facility: false,
+        }
+    }
+
+    fn with_withs(rows: Vec<VersionPinRow<String>>) -> Vec<(VersionPinRow<String>, Vec<String>)> {
+        rows.into_iter()
+            .map(|row| (row, vec!["ocio".to_string()]))
+            .collect()
+    }
+
+    #[test]
+    fn rewind_changes_after_revision() {
+        let current = with_withs(vec![
+            row(1, "maya-2020.2", "dev01"),
+            row(2, "nuke-12.1", "dev01"),
+            row(3, "houdini-18.0", "dev01"),
+        ]);
+        let changes = vec![
+            change(5, ChangeAction::Change, "maya", "2018.6", "2019.1"),
+            change(6, ChangeAction::Delete, "vray", "4.1", ""),
+            change(7, ChangeAction::Change, "maya", "2019.1", "2020.2"),
+            change(8, ChangeAction::Add, "nuke", "", "12.1"),
+        ];
+        let past = PastPins::rewind(5, &query("dev01", "ancestor"), current, changes);
+        assert_eq!(past.pins.len(), 3);
+        assert_eq!(past.pins[0].state, PinState::Unchanged);
+        assert_eq!(past.pins[0].withs, Some(vec!["ocio".to_string()]));
+        assert_eq!(past.pins[1].row.distribution.to_string(), "maya-2019.1");
+        assert_eq!(past.pins[1].state, PinState::Changed("2020.2".to_string()));
+        assert_eq!(past.pins[2].row.distribution.to_string(), "vray-4.1");
+        assert_eq!(past.pins[2].state, PinState::Deleted);
+        assert_eq!(past.pins[2].withs, None);
+        assert_eq!(past.added_since.len(), 1);
+        let entries = past.restore_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].op, ManifestOp::ChangeDistribution);
+        assert_eq!(entries[0].version, Some("2019.1".to_string()));
+        assert_eq!(entries[1].op, ManifestOp::AddDistribution);
+        assert_eq!(entries[1].line, 2);
+    }
+
+    #[test]
+    fn rewind_before_resolving_inheritance() {
+        let current = with_withs(vec![
+            row(1, "maya-2018.6", "facility"),
+            row(2, "maya-2020.2", "dev01"),
+            row(3, "nuke-12.1", "dev01.rd"),
+        ]);
+        let mut vray = change(7, ChangeAction::Delete, "vray", "4.1", "");
+        vray.coord.level = "facility".to_string();
+        let changes = vec![change(6, ChangeAction::Add, "maya", "", "2020.2"), vray];
+        // today, the pin of maya at dev01 hides the pin at the facility
+        let past = PastPins::rewind(5, &query("dev01.rd.0001", "ancestor"), current, changes);
+        let pins = past
+            .pins
+            .iter()
+            .map(|pin| (pin.row.distribution.to_string(), pin.row.level.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pins,
+            vec![
+                ("maya-2018.6".to_string(), "facility"),
+                ("nuke-12.1".to_string(), "dev01.rd"),
+                ("vray-4.1".to_string(), "facility"),
+            ]
+        );
+        assert_eq!(
+            past.added_since,
+            vec![PinCoord::new("maya", "dev01", "any", "any", "any")]
+        );
+    }
+
+    #[test]
+    fn select_by_search_mode() {
+        let coords = vec![
+            PinCoord::new("maya", "facility", "any", "any", "any"),
+            PinCoord::new("maya", "dev01", "any", "any", "any"),
+            PinCoord::new("maya", "dev01.rd", "model", "any", "any"),
+            PinCoord::new("nuke", "dev01.rd", "any", "any", "any"),
+            PinCoord::new("nuke", "dev02", "any", "any", "any"),
+        ];
+        assert_eq!(query("dev01.rd", "ancestor").select(&coords), vec![1, 3]);
+        assert_eq!(query("dev01.rd", "exact").select(&coords), vec![3]);
+        assert_eq!(query("dev01", "descendant").select(&coords), vec![1, 2, 3]);
+        let mut show = query("dev02", "ancestor");
+        show.isolate_facility = true;
+        assert_eq!(show.select(&coords), vec![4]);
+    }
+}
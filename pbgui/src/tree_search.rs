@@ -0,0 +1,144 @@
+//! Parses the text typed into the package filter of the packages tree.
+//!
+//! The filter takes the form `package[@versions]`. The package is matched as:
+//! * a regular expression, when wrapped in slashes (`/^maya(py)?$/`)
+//! * a glob, when it contains `*`, `?` or `[` (`hou*`)
+//! * a case insensitive substring otherwise (`maya`)
+//!
+//! The optional versions are a comma separated list of constraints, each of which
+//! is matched like the filter of the version picker (`maya@>=2020,<2022`). A filter
+//! with versions, even an empty list (`maya@`), asks the tree to expand the matching
+//! packages into their distributions.
+use crate::versions::matches_filter;
+use pbgui_vpin::levels::glob_match;
+use regex::{Regex, RegexBuilder};
+
+/// How the package part of the filter is matched
+#[derive(Debug)]
+pub enum PackagePattern {
+    /// Matches packages containing the text, ignoring case
+    Plain(String),
+    /// Matches packages against a glob, ignoring case
+    Glob(String),
+    /// Matches packages against a regular expression
+    Regex(Regex),
+}
+
+impl PackagePattern {
+    fn parse(text: &str) -> Result<Self, regex::Error> {
+        if text.len() > 1 && text.starts_with('/') && text.ends_with('/') {
+            let re = RegexBuilder::new(&text[1..text.len() - 1])
+                .case_insensitive(true)
+                .build()?;
+            Ok(Self::Regex(re))
+        } else if text.contains(|c| c == '*' || c == '?' || c == '[') {
+            Ok(Self::Glob(text.to_string()))
+        } else {
+            Ok(Self::Plain(text.to_lowercase()))
+        }
+    }
+
+    fn is_match(&self, package: &str) -> bool {
+        match self {
+            Self::Plain(text) => package.to_lowercase().contains(text.as_str()),
+            Self::Glob(pattern) => glob_match(pattern, package),
+            Self::Regex(re) => re.is_match(package),
+        }
+    }
+}
+
+/// A parsed package filter
+#[derive(Debug)]
+pub struct TreeSearch {
+    package: PackagePattern,
+    versions: Option<Vec<String>>,
+}
+
+impl TreeSearch {
+    /// Parse the text of the package filter
+    ///
+    /// # Arguments
+    /// * `text` - The text of the filter
+    ///
+    /// # Returns
+    /// * Ok(Some(TreeSearch)) if the filter is valid
+    /// * Ok(None) if the filter is empty
+    /// * Err(regex::Error) if the package is not a valid regular expression
+    pub fn parse(text: &str) -> Result<Option<Self>, regex::Error> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        let mut pieces = text.splitn(2, '@');
+        let package = PackagePattern::parse(pieces.next().unwrap_or("").trim())?;
+        let versions = pieces.next().map(|versions| {
+            versions
+                .split(',')
+                .map(|constraint| constraint.trim().to_string())
+                .filter(|constraint| !constraint.is_empty())
+                .collect()
+        });
+        Ok(Some(Self { package, versions }))
+    }
+
+    /// Determine whether the filter constrains versions, in which case the matching
+    /// packages should be expanded into their distributions
+    pub fn has_versions(&self) -> bool {
+        self.versions.is_some()
+    }
+
+    /// Determine whether the package matches the filter
+    ///
+    /// # Arguments
+    /// * `package` - The name of the package
+    ///
+    /// # Returns
+    /// * bool
+    pub fn matches_package(&self, package: &str) -> bool {
+        self.package.is_match(package)
+    }
+
+    /// Determine whether the version of a distribution satisfies every constraint of
+    /// the filter. Any version matches a filter without versions.
+    ///
+    /// # Arguments
+    /// * `version` - The version of the distribution
+    ///
+    /// # Returns
+    /// * bool
+    pub fn matches_version(&self, version: &str) -> bool {
+        match &self.versions {
+            Some(constraints) => constraints.iter().all(|c| matches_filter(version, c)),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_patterns() {
+        let plain = TreeSearch::parse("MAYA").unwrap().unwrap();
+        assert!(plain.matches_package("maya") && plain.matches_package("mayapy"));
+        assert!(!plain.has_versions());
+        let glob = TreeSearch::parse("hou*").unwrap().unwrap();
+        assert!(glob.matches_package("houdini") && !glob.matches_package("gizmos_hou"));
+        let re = TreeSearch::parse("/^maya(py)?$/").unwrap().unwrap();
+        assert!(re.matches_package("mayapy") && !re.matches_package("mayatools"));
+        assert!(TreeSearch::parse("/(/").is_err());
+        assert!(TreeSearch::parse("  ").unwrap().is_none());
+    }
+
+    #[test]
+    fn version_ranges() {
+        let search = TreeSearch::parse("maya@>=2020, <2022").unwrap().unwrap();
+        assert!(search.has_versions());
+        assert!(search.matches_package("maya"));
+        assert!(search.matches_version("2020.4") && search.matches_version("2021"));
+        assert!(!search.matches_version("2019.2") && !search.matches_version("2022.1"));
+        let any = TreeSearch::parse("maya@").unwrap().unwrap();
+        assert!(any.has_versions() && any.matches_version("2018"));
+    }
+}
@@ -1,4 +1,5 @@
 //! Provides free function utilities used throughout the application
+use crate::distribution::Distribution;
 use log;
 use qt_core::{
     q_io_device::OpenModeFlag, QFile, QFlags, QModelIndex, QSize, QString, QTextStream, QVariant,
@@ -171,6 +172,31 @@ pub fn create_hlayout() -> CppBox<QHBoxLayout> {
     }
 }
 
+/// Count the number of levels in `all_levels` which descend from `level`. Every
+/// level descends from the facility.
+///
+/// # Arguments
+///
+/// * `level` - The level whose descendants we wish to count
+/// * `all_levels` - All of the known levels
+///
+/// # Returns
+///
+/// * The number of descendant levels
+pub fn descendant_level_count<S: AsRef<str>>(level: &str, all_levels: &[S]) -> usize {
+    all_levels
+        .iter()
+        .map(|l| l.as_ref())
+        .filter(|l| {
+            if level == "facility" {
+                *l != "facility"
+            } else {
+                l.len() > level.len() && l.starts_with(level) && l[level.len()..].starts_with('.')
+            }
+        })
+        .count()
+}
+
 /// Given a QModelIndex retrieved from the tree, return the distribution name
 ///
 /// # Arguments
@@ -179,8 +205,8 @@ pub fn create_hlayout() -> CppBox<QHBoxLayout> {
 ///
 /// # Returns
 ///
-/// * Option wrapped distribution name String
-pub fn distribution_from_idx(idx: Ref<QModelIndex>) -> Option<String> {
+/// * Option wrapped Distribution
+pub fn distribution_from_idx(idx: Ref<QModelIndex>) -> Option<Distribution> {
     unsafe {
         if !idx.is_valid() {
             log::warn!("distribution_from_idx supplied QModelIndex not valid.");
@@ -215,9 +241,15 @@ pub fn distribution_from_idx(idx: Ref<QModelIndex>) -> Option<String> {
                 .to_string()
                 .to_std_string()
         };
-        let dist = format!("{}-{}", package, version);
-        log::debug!("found dist: {}", dist);
-        // only the distribution is allowed to have a dash in its name
-        Some(dist)
+        match Distribution::new(package, version) {
+            Ok(dist) => {
+                log::debug!("found dist: {}", dist);
+                Some(dist)
+            }
+            Err(err) => {
+                log::error!("{}", err);
+                None
+            }
+        }
     }
 }
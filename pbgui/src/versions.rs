@@ -0,0 +1,212 @@
+//! Ordering, filtering and metadata for the versions of a package, as presented by the
+//! version picker.
+//!
+//! Versions are compared semver style: the release is compared component by component
+//! (numerically where both components are numbers), a missing component counts as 0, and
+//! a pre-release (`1.0.0-beta.2`) sorts before its release. Build metadata (`+build.5`) is
+//! ignored. Versions which are not strictly semver, like `2020.1`, are handled the same way.
+use chrono::{DateTime, Local};
+use packybara::types::IdType;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A version of a package, along with the metadata presented by the version picker
+#[derive(Debug, PartialEq, Clone)]
+pub struct VersionInfo {
+    /// The id of the distribution
+    pub id: IdType,
+    /// The version of the distribution
+    pub version: String,
+    /// The number of versionpins which use the distribution
+    pub pin_count: usize,
+    /// Whether the distribution is installed at the current site, if known
+    pub installed: Option<bool>,
+    /// The date the distribution was installed at the current site, going by the
+    /// modification time of its install directory, if known
+    pub installed_on: Option<String>,
+}
+
+impl VersionInfo {
+    /// New up a VersionInfo, without install metadata
+    ///
+    /// # Arguments
+    /// * `id` - The id of the distribution
+    /// * `version` - The version of the distribution
+    /// * `pin_count` - The number of versionpins using the distribution
+    ///
+    /// # Returns
+    /// * VersionInfo instance
+    pub fn new<I: Into<String>>(id: IdType, version: I, pin_count: usize) -> Self {
+        Self {
+            id,
+            version: version.into(),
+            pin_count,
+            installed: None,
+            installed_on: None,
+        }
+    }
+
+    /// Fill in the install status and install date of the version by inspecting
+    /// `<root>/<package>/<version>`, where root is the install root of the site.
+    /// The metadata is left unknown if the site has no install root. This touches
+    /// the filesystem, so it is called from the secondary thread rather than the ui.
+    ///
+    /// # Arguments
+    /// * `package` - The name of the package
+    /// * `site` - The current site
+    /// * `roots` - Map of site name to install root
+    ///
+    /// # Returns
+    /// * None
+    pub fn inspect_install(
+        &mut self,
+        package: &str,
+        site: &str,
+        roots: &HashMap<String, String>,
+    ) {
+        let root = match roots.get(site) {
+            Some(root) => root,
+            None => return,
+        };
+        let path = Path::new(root).join(package).join(&self.version);
+        match path.metadata() {
+            Ok(metadata) => {
+                self.installed = Some(true);
+                self.installed_on = metadata
+                    .modified()
+                    .ok()
+                    .map(|time| DateTime::<Local>::from(time).format("%F").to_string());
+            }
+            Err(_) => self.installed = Some(false),
+        }
+    }
+}
+
+/// Compare two versions semver style
+///
+/// # Arguments
+/// * `a` - The first version
+/// * `b` - The second version
+///
+/// # Returns
+/// * Ordering of `a` relative to `b`
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_release, a_pre) = split_version(a);
+    let (b_release, b_pre) = split_version(b);
+    compare_identifiers(&a_release, &b_release, true).then_with(|| match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a_pre), Some(b_pre)) => compare_identifiers(&a_pre, &b_pre, false),
+    })
+}
+
+/// Sort versions newest first
+///
+/// # Arguments
+/// * `versions` - The versions to sort
+///
+/// # Returns
+/// * None
+pub fn sort_newest_first(versions: &mut [VersionInfo]) {
+    versions.sort_by(|a, b| compare_versions(&b.version, &a.version));
+}
+
+/// Determine whether a version matches the filter typed into the version picker.
+/// A filter starting with a comparison operator (`>=2.0`, `<1.5`, `=1.2.3`) is
+/// compared semver style. Otherwise, the filter matches versions containing it.
+///
+/// # Arguments
+/// * `version` - The version to test
+/// * `filter` - The filter
+///
+/// # Returns
+/// * bool
+pub fn matches_filter(version: &str, filter: &str) -> bool {
+    let filter = filter.trim();
+    if filter.is_empty() {
+        return true;
+    }
+    for (op, accepts) in &[
+        (">=", &[Ordering::Greater, Ordering::Equal][..]),
+        ("<=", &[Ordering::Less, Ordering::Equal][..]),
+        (">", &[Ordering::Greater][..]),
+        ("<", &[Ordering::Less][..]),
+        ("=", &[Ordering::Equal][..]),
+    ] {
+        if filter.starts_with(op) {
+            let other = filter[op.len()..].trim();
+            return accepts.contains(&compare_versions(version, other));
+        }
+    }
+    version.to_lowercase().contains(&filter.to_lowercase())
+}
+
+// split a version into its release and pre-release identifiers, dropping any build metadata
+fn split_version(version: &str) -> (Vec<&str>, Option<Vec<&str>>) {
+    let version = version.trim().splitn(2, '+').next().unwrap_or("");
+    let mut pieces = version.splitn(2, '-');
+    let release = pieces.next().unwrap_or("").split('.').collect();
+    let pre = pieces.next().map(|pre| pre.split('.').collect());
+    (release, pre)
+}
+
+// compare dot separated identifiers. numeric identifiers compare numerically and sort before
+// alphanumeric ones. When `pad` is set, missing identifiers count as 0; otherwise the shorter
+// list of identifiers sorts first.
+fn compare_identifiers(a: &[&str], b: &[&str], pad: bool) -> Ordering {
+    let len = a.len().max(b.len());
+    for idx in 0..len {
+        let ordering = match (a.get(idx), b.get(idx)) {
+            (Some(a), Some(b)) => compare_identifier(a, b),
+            (Some(a), None) if pad => compare_identifier(a, "0"),
+            (None, Some(b)) if pad => compare_identifier("0", b),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn compare_identifier(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_sort_semver_style() {
+        let mut versions = ["1.10.0", "1.2.0", "1.2", "2.0.0-beta.2", "2.0.0", "2.0.0-beta.10"]
+            .iter()
+            .enumerate()
+            .map(|(id, v)| VersionInfo::new(id as IdType, *v, 0))
+            .collect::<Vec<_>>();
+        sort_newest_first(&mut versions);
+        let versions = versions.iter().map(|v| v.version.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            versions,
+            vec!["2.0.0", "2.0.0-beta.10", "2.0.0-beta.2", "1.10.0", "1.2.0", "1.2"]
+        );
+    }
+
+    #[test]
+    fn filter_supports_comparisons() {
+        assert!(matches_filter("1.10.0", ">=1.9"));
+        assert!(!matches_filter("1.10.0", "<1.9"));
+        assert!(matches_filter("2020.1", "=2020.1.0"));
+        assert!(matches_filter("2.0.0-Beta.1", "beta"));
+        assert!(matches_filter("2.0.0", ""));
+    }
+}
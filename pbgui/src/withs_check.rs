@@ -0,0 +1,272 @@
+//! Validates the with packages of a pin before a `ChangeWiths` is staged.
+//!
+//! A with package may not be the pin's own package, and may not appear twice. These
+//! are reported as warnings. A with package which leads back to the pin's own package,
+//! through the withs of the other pins at the same coordinate, creates a with-cycle,
+//! which is an error.
+//!
+//! Each change is checked as it is staged, against the stored withs and the changes
+//! staged before it. As changes may be staged in any order, the staged changes are
+//! checked together once more when they are saved.
+use crate::change_type::PinCoord;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Maps the package of each pin at a coordinate to its with packages
+pub type LevelWiths = HashMap<String, Vec<String>>;
+
+/// A problem with one of the with packages of a pin
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum WithsIssue {
+    /// The with is the pin's own package
+    OwnPackage(String),
+    /// The with appears more than once
+    Duplicate(String),
+    /// The with leads back to the pin's package. The path starts and ends with
+    /// the pin's package
+    Cycle(Vec<String>),
+}
+
+impl WithsIssue {
+    /// The with package the issue applies to
+    pub fn with(&self) -> &str {
+        match self {
+            Self::OwnPackage(with) | Self::Duplicate(with) => with.as_str(),
+            Self::Cycle(path) => path.get(1).map(|with| with.as_str()).unwrap_or(""),
+        }
+    }
+
+    /// Determine whether the issue prevents the withs from being saved
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Cycle(_))
+    }
+}
+
+impl fmt::Display for WithsIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OwnPackage(with) => write!(f, "{} is the pin's own package", with),
+            Self::Duplicate(with) => write!(f, "{} appears more than once", with),
+            Self::Cycle(path) => write!(f, "with-cycle: {}", path.join(" \u{2192} ")),
+        }
+    }
+}
+
+/// Check the withs of a pin for its own package and duplicates
+///
+/// # Arguments
+/// * `package` - The package of the pin
+/// * `withs` - The with packages of the pin
+///
+/// # Returns
+/// * Vec of WithsIssue. Empty if the withs are fine
+pub fn check_withs(package: &str, withs: &[String]) -> Vec<WithsIssue> {
+    let mut issues = Vec::new();
+    let mut seen = HashSet::new();
+    for with in withs {
+        if with == package {
+            issues.push(WithsIssue::OwnPackage(with.clone()));
+        } else if !seen.insert(with.as_str()) {
+            issues.push(WithsIssue::Duplicate(with.clone()));
+        }
+    }
+    issues
+}
+
+/// Describe the issues, one per line
+///
+/// # Arguments
+/// * `issues` - The issues to describe
+///
+/// # Returns
+/// * The description
+pub fn describe(issues: &[WithsIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| issue.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Check the withs of a pin for every issue: its own package and duplicates, which
+/// are warnings, and with-cycles through the other pins at the coordinate, which
+/// are errors
+///
+/// # Arguments
+/// * `package` - The package of the pin
+/// * `withs` - The proposed with packages of the pin
+/// * `level_withs` - The with packages of the other pins at the coordinate
+///
+/// # Returns
+/// * Vec of WithsIssue. Empty if the withs are fine
+pub fn validate_withs(
+    package: &str,
+    withs: &[String],
+    level_withs: &LevelWiths,
+) -> Vec<WithsIssue> {
+    let mut issues = check_withs(package, withs);
+    issues.extend(find_cycles(package, withs, level_withs));
+    issues
+}
+
+/// Find the withs of a pin which lead back to its package through the withs of the
+/// other pins at the same coordinate
+///
+/// # Arguments
+/// * `package` - The package of the pin
+/// * `withs` - The proposed with packages of the pin
+/// * `level_withs` - The with packages of the other pins at the coordinate
+///
+/// # Returns
+/// * Vec of WithsIssue::Cycle, one per offending with
+pub fn find_cycles(package: &str, withs: &[String], level_withs: &LevelWiths) -> Vec<WithsIssue> {
+    let mut issues = Vec::new();
+    let mut checked = HashSet::new();
+    for with in withs {
+        if with == package || !checked.insert(with.as_str()) {
+            continue;
+        }
+        let mut visited = HashSet::new();
+        if let Some(mut path) = path_to(with, package, level_withs, &mut visited) {
+            path.insert(0, package.to_string());
+            issues.push(WithsIssue::Cycle(path));
+        }
+    }
+    issues
+}
+
+/// Find the with-cycles created by saving several withs changes together. At each
+/// coordinate, the staged withs of a pin replace its stored withs, and each staged pin
+/// is checked against the merged withs of the pins at its coordinate.
+///
+/// # Arguments
+/// * `stored` - The stored withs of the pins at the coordinates of the staged pins
+/// * `staged` - The staged withs of each pin
+///
+/// # Returns
+/// * Vec of WithsIssue::Cycle, one per offending with of each staged pin
+pub fn find_staged_cycles(
+    stored: &HashMap<PinCoord, Vec<String>>,
+    staged: &HashMap<PinCoord, Vec<String>>,
+) -> Vec<WithsIssue> {
+    let mut merged: HashMap<[&str; 4], LevelWiths> = HashMap::new();
+    // staged withs come last, replacing the stored withs of the same pin
+    for (coord, withs) in stored.iter().chain(staged.iter()) {
+        merged
+            .entry(location(coord))
+            .or_default()
+            .insert(coord.package.clone(), withs.clone());
+    }
+    let mut staged = staged.iter().collect::<Vec<_>>();
+    staged.sort_by_key(|(coord, _)| (location(coord), coord.package.as_str()));
+    staged
+        .into_iter()
+        .flat_map(|(coord, withs)| find_cycles(&coord.package, withs, &merged[&location(coord)]))
+        .collect()
+}
+
+// the coordinate of a pin, without its package
+fn location(coord: &PinCoord) -> [&str; 4] {
+    [
+        coord.level.as_str(),
+        coord.role.as_str(),
+        coord.platform.as_str(),
+        coord.site.as_str(),
+    ]
+}
+
+// depth first search for a path from `from` to `to`, following the withs of the pins
+// at the coordinate. Returns the path, starting at `from` and ending at `to`.
+fn path_to<'a>(
+    from: &'a str,
+    to: &str,
+    level_withs: &'a LevelWiths,
+    visited: &mut HashSet<&'a str>,
+) -> Option<Vec<String>> {
+    if !visited.insert(from) {
+        return None;
+    }
+    for next in level_withs.get(from)? {
+        let found = if next == to {
+            Some(vec![to.to_string()])
+        } else {
+            path_to(next.as_str(), to, level_withs, visited)
+        };
+        if let Some(mut path) = found {
+            path.insert(0, from.to_string());
+            return Some(path);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn own_package_and_duplicates_are_warnings() {
+        let issues = check_withs("maya", &strings(&["mtoa", "maya", "mtoa"]));
+        assert_eq!(
+            issues,
+            vec![
+                WithsIssue::OwnPackage("maya".to_string()),
+                WithsIssue::Duplicate("mtoa".to_string())
+            ]
+        );
+        assert!(issues.iter().all(|issue| !issue.is_error()));
+    }
+
+    #[test]
+    fn cycles_through_other_pins_are_errors() {
+        let mut level_withs = LevelWiths::new();
+        level_withs.insert("mtoa".to_string(), strings(&["ocio"]));
+        level_withs.insert("ocio".to_string(), strings(&["maya"]));
+        level_withs.insert("gizmos".to_string(), strings(&["gizmos"]));
+        let issues = find_cycles("maya", &strings(&["gizmos", "mtoa"]), &level_withs);
+        assert_eq!(
+            issues,
+            vec![WithsIssue::Cycle(strings(&["maya", "mtoa", "ocio", "maya"]))]
+        );
+        assert_eq!(issues[0].with(), "mtoa");
+        let all = validate_withs("maya", &strings(&["mtoa", "maya"]), &level_withs);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all.iter().filter(|issue| issue.is_error()).count(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "with-cycle: maya \u{2192} mtoa \u{2192} ocio \u{2192} maya"
+        );
+    }
+
+    #[test]
+    fn cycles_across_staged_changes_are_errors() {
+        let coord = |package| PinCoord::new(package, "facility", "any", "any", "any");
+        let mut stored = HashMap::new();
+        stored.insert(coord("maya"), strings(&["mtoa"]));
+        stored.insert(coord("ocio"), strings(&["maya"]));
+        stored.insert(
+            PinCoord::new("mtoa", "dev01", "any", "any", "any"),
+            strings(&["maya"]),
+        );
+        // the staged withs of ocio replace its stored withs, which would lead mtoa
+        // back to itself. The pins at other coordinates are not followed.
+        let mut staged = HashMap::new();
+        staged.insert(coord("mtoa"), strings(&["ocio"]));
+        staged.insert(coord("ocio"), strings(&["nuke"]));
+        assert!(find_staged_cycles(&stored, &staged).is_empty());
+        // together with a change to nuke, they lead back to mtoa
+        staged.insert(coord("nuke"), strings(&["maya"]));
+        assert_eq!(
+            find_staged_cycles(&stored, &staged),
+            vec![
+                WithsIssue::Cycle(strings(&["mtoa", "ocio", "nuke", "maya", "mtoa"])),
+                WithsIssue::Cycle(strings(&["nuke", "maya", "mtoa", "ocio", "nuke"])),
+                WithsIssue::Cycle(strings(&["ocio", "nuke", "maya", "mtoa", "ocio"])),
+            ]
+        );
+    }
+}